@@ -54,6 +54,9 @@ pub enum CommandType {
     /// Open graph visualizer in default browser
     Visualize(VisualizeCommand),
 
+    /// Rebuild the full-text indices of a running instance from its stored data
+    Reindex(ReindexCommand),
+
     /// Check login credentials or login with github
     Login,
 
@@ -158,3 +161,16 @@ pub struct VisualizeCommand {
     #[clap(help = "Cluster ID to visualize")]
     pub cluster: String,
 }
+
+#[derive(Debug, Args)]
+#[clap(
+    name = "reindex",
+    about = "Rebuild the BM25 indices of a Helix instance"
+)]
+pub struct ReindexCommand {
+    #[clap(help = "Cluster ID to reindex")]
+    pub cluster: String,
+
+    #[clap(long, help = "Items indexed per write transaction")]
+    pub batch_size: Option<usize>,
+}
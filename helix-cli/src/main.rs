@@ -877,6 +877,66 @@ async fn main() -> ExitCode {
             };
         }
 
+        CommandType::Reindex(command) => {
+            let instance_manager = InstanceManager::new().unwrap();
+            let iid = &command.cluster;
+
+            let port = match instance_manager.get_instance(iid) {
+                Ok(Some(instance)) => instance.port,
+                Ok(None) => {
+                    println!(
+                        "{} {}",
+                        "No Helix instance found with id".red().bold(),
+                        iid.red().bold()
+                    );
+                    return ExitCode::FAILURE;
+                }
+                Err(e) => {
+                    println!("{} {}", "Error:".red().bold(), e);
+                    return ExitCode::FAILURE;
+                }
+            };
+
+            let mut url = format!("http://localhost:{port}/reindex");
+            if let Some(batch_size) = command.batch_size {
+                url.push_str(&format!("?batch_size={batch_size}"));
+            }
+
+            // progress is reported in the instance's output
+            let mut sp = Spinner::new(Spinners::Dots9, "Reindexing".into());
+            let res = reqwest::Client::new().post(&url).send().await;
+            sp.stop();
+
+            let res = match res {
+                Ok(r) => r,
+                Err(e) => {
+                    println!("\nError sending request: {e:?}");
+                    return ExitCode::FAILURE;
+                }
+            };
+
+            match res.error_for_status() {
+                Ok(r) => match r.json::<ReindexResponse>().await {
+                    Ok(stats) => println!(
+                        "\n{} {} nodes, {} edges and {} vectors read, {} indexed",
+                        "Reindexed".green().bold(),
+                        stats.nodes,
+                        stats.edges,
+                        stats.vectors,
+                        stats.indexed
+                    ),
+                    Err(e) => {
+                        println!("\nCouldn't decode response: {e:?}");
+                        return ExitCode::FAILURE;
+                    }
+                },
+                Err(e) => {
+                    println!("\n{} {e:?}", "Error reindexing:".red().bold());
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+
         CommandType::Login => {
             let home_dir = std::env::var("HOME").unwrap_or("~/".to_string());
             let config_path = &format!("{home_dir}/.helix");
@@ -1013,3 +1073,11 @@ struct CreateKeyResponse {
     key: String,
     key_id: String,
 }
+
+#[derive(Deserialize)]
+struct ReindexResponse {
+    nodes: usize,
+    edges: usize,
+    vectors: usize,
+    indexed: usize,
+}
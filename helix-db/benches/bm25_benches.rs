@@ -10,7 +10,7 @@ mod tests {
     use heed3::{Env, EnvOpenOptions};
     use rand::seq::SliceRandom;
     use reqwest::blocking::get;
-    use std::collections::HashMap;
+    use std::{collections::HashMap, time::Instant};
     use tempfile::tempdir;
 
     fn setup_test_env() -> (Env, tempfile::TempDir) {
@@ -154,5 +154,44 @@ mod tests {
             );
         }
     }
-}
 
+    /// Times top-k searches over a common and a rare term, where block-max WAND skips most of the
+    /// common term's postings, against the same query forced through exhaustive scoring
+    #[test]
+    fn test_bm25_top_k_pruning() {
+        let (bm25, _temp_dir) = setup_bm25_config();
+        let mut wtxn = bm25.graph_env.write_txn().unwrap();
+
+        let total_docs = 500_000;
+        for i in tqdm::new(0..total_docs, total_docs, None, Some("inserting docs")) {
+            let doc = match i % 1000 {
+                0 => format!("common rare rare document {}", i),
+                n if n % 2 == 0 => format!("common common document {} other words", i),
+                _ => format!("common document {} other words and filler", i),
+            };
+            bm25.insert_doc(&mut wtxn, v6_uuid(), &doc).unwrap();
+        }
+        wtxn.commit().unwrap();
+
+        let rtxn = bm25.graph_env.read_txn().unwrap();
+        for limit in [10, 100, 1000] {
+            let start = Instant::now();
+            let pruned = bm25.search(&rtxn, "common rare", limit).unwrap();
+            let pruned_time = start.elapsed();
+
+            // a required group takes the exhaustive path
+            let start = Instant::now();
+            let exhaustive = bm25.search(&rtxn, "+(common rare)", limit).unwrap();
+            let exhaustive_time = start.elapsed();
+
+            println!(
+                "top {}: wand {:?}, exhaustive {:?}",
+                limit, pruned_time, exhaustive_time
+            );
+            assert_eq!(pruned.len(), exhaustive.len());
+            for (pruned, exhaustive) in pruned.iter().zip(&exhaustive) {
+                assert!((pruned.1 - exhaustive.1).abs() < 1e-4);
+            }
+        }
+    }
+}
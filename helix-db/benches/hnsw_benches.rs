@@ -7,6 +7,7 @@ mod tests {
             hnsw::HNSW,
             vector::HVector,
             vector_core::{HNSWConfig, VectorCore},
+            vector_distance::DistanceMetric,
         },
         utils::tqdm::tqdm,
     };
@@ -83,7 +84,7 @@ mod tests {
                                 .iter()
                                 .filter_map(|base_vec| {
                                     query_hvector
-                                        .distance_to(base_vec)
+                                        .distance_to(base_vec, DistanceMetric::Cosine)
                                         .map(|dist| (base_vec.id.clone(), dist))
                                         .ok()
                                 })
@@ -213,7 +214,7 @@ mod tests {
         let over_all_time = Instant::now();
         for (i, data) in vectors.iter().enumerate() {
             let start_time = Instant::now();
-            let vec = index.insert::<Filter>(&mut txn, &data, None).unwrap();
+            let vec = index.insert::<Filter>(&mut txn, "vector", &data, None).unwrap();
             let time = start_time.elapsed();
             all_vectors.push(vec);
             if i % 1000 == 0 {
@@ -317,7 +318,7 @@ mod tests {
         let over_all_time = Instant::now();
         for (i, data) in base_vectors.iter().enumerate() {
             let start_time = Instant::now();
-            let vec = index.insert::<Filter>(&mut txn, &data, None).unwrap();
+            let vec = index.insert::<Filter>(&mut txn, "vector", &data, None).unwrap();
             let time = start_time.elapsed();
             base_all_vectors.push(vec);
             //println!("{} => inserting in {} ms", i, time.as_millis());
@@ -0,0 +1,383 @@
+//! Analyzers turn the text of a searchable field, or a query, into the terms that are indexed.
+//!
+//! An analyzer is a tokenizer followed by a chain of token filters. The same analyzer is used
+//! when indexing a field and when searching it, so both sides agree on what a term is.
+
+use super::stemmer;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, fmt};
+
+/// The stop words of the English analyzer
+pub const ENGLISH_STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "if", "in", "into", "is", "it",
+    "no", "not", "of", "on", "or", "such", "that", "the", "their", "then", "there", "these",
+    "they", "this", "to", "was", "will", "with",
+];
+
+/// The built-in analyzers a searchable field can use
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum AnalyzerKind {
+    /// Splits on anything but letters and digits, lowercases and folds diacritics.
+    /// Works for any language and keeps short terms like `ai` or `c#`.
+    #[default]
+    Standard,
+    /// The standard analyzer with English stop words removed and terms stemmed
+    English,
+    /// The original tokenizer, which also drops every term of two characters or less
+    Simple,
+}
+
+impl fmt::Display for AnalyzerKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnalyzerKind::Standard => write!(f, "AnalyzerKind::Standard"),
+            AnalyzerKind::English => write!(f, "AnalyzerKind::English"),
+            AnalyzerKind::Simple => write!(f, "AnalyzerKind::Simple"),
+        }
+    }
+}
+
+/// How a searchable field is analyzed, set from the schema or `config.hx.json`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct AnalyzerConfig {
+    #[serde(default)]
+    pub analyzer: AnalyzerKind,
+    /// Replaces the stop words of the analyzer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_words: Option<Vec<String>>,
+    /// Also indexes the prefixes of each term between these lengths, so partial words match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edge_ngrams: Option<(usize, usize)>,
+}
+
+impl fmt::Display for AnalyzerConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "AnalyzerConfig {{ analyzer: {}, stop_words: ",
+            self.analyzer
+        )?;
+        match &self.stop_words {
+            Some(words) => write!(
+                f,
+                "Some(vec![{}])",
+                words
+                    .iter()
+                    .map(|w| format!("{w:?}.to_string()"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )?,
+            None => write!(f, "None")?,
+        }
+        match self.edge_ngrams {
+            Some((min, max)) => write!(f, ", edge_ngrams: Some(({min}, {max})) }}"),
+            None => write!(f, ", edge_ngrams: None }}"),
+        }
+    }
+}
+
+/// A term and its position in the text it came from.
+///
+/// Filters that drop tokens leave a gap in the positions, so a phrase query doesn't match across
+/// a removed stop word.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub text: String,
+    pub position: u32,
+}
+
+fn positioned<'a>(words: impl Iterator<Item = &'a str>) -> Vec<Token> {
+    words
+        .enumerate()
+        .map(|(position, word)| Token {
+            text: word.to_string(),
+            position: position as u32,
+        })
+        .collect()
+}
+
+/// Splits text into tokens
+pub trait Tokenizer: Send + Sync {
+    fn tokenize(&self, text: &str) -> Vec<Token>;
+}
+
+/// Rewrites, drops or adds tokens after tokenization
+pub trait TokenFilter: Send + Sync {
+    fn filter(&self, tokens: Vec<Token>) -> Vec<Token>;
+
+    /// Filters that only apply when indexing, so queries are matched against what they add
+    fn index_only(&self) -> bool {
+        false
+    }
+}
+
+/// Splits on anything that isn't a letter or digit
+pub struct SimpleTokenizer;
+
+impl Tokenizer for SimpleTokenizer {
+    fn tokenize(&self, text: &str) -> Vec<Token> {
+        positioned(
+            text.split(|c: char| !c.is_alphanumeric())
+                .filter(|s| !s.is_empty()),
+        )
+    }
+}
+
+/// Splits on anything that isn't a letter or digit, keeping a trailing `+` or `#` on a word so
+/// terms like `c++` and `c#` survive
+pub struct WordTokenizer;
+
+impl Tokenizer for WordTokenizer {
+    fn tokenize(&self, text: &str) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        let mut current = String::new();
+        for c in text.chars() {
+            let continues_word = match c {
+                '+' | '#' => !current.is_empty(),
+                _ => c.is_alphanumeric() && !current.ends_with(['+', '#']),
+            };
+            if continues_word {
+                current.push(c);
+            } else {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+                if c.is_alphanumeric() {
+                    current.push(c);
+                }
+            }
+        }
+        if !current.is_empty() {
+            tokens.push(current);
+        }
+        positioned(tokens.iter().map(String::as_str))
+    }
+}
+
+pub struct LowercaseFilter;
+
+impl TokenFilter for LowercaseFilter {
+    fn filter(&self, tokens: Vec<Token>) -> Vec<Token> {
+        tokens
+            .into_iter()
+            .map(|t| Token {
+                text: t.text.to_lowercase(),
+                ..t
+            })
+            .collect()
+    }
+}
+
+/// Drops tokens shorter than the given number of bytes
+pub struct MinLengthFilter(pub usize);
+
+impl TokenFilter for MinLengthFilter {
+    fn filter(&self, tokens: Vec<Token>) -> Vec<Token> {
+        tokens
+            .into_iter()
+            .filter(|t| t.text.len() >= self.0)
+            .collect()
+    }
+}
+
+/// Folds Latin letters with diacritics to their ASCII form, `café` to `cafe` and `straße` to
+/// `strasse`, and strips combining marks
+pub struct AsciiFoldingFilter;
+
+impl TokenFilter for AsciiFoldingFilter {
+    fn filter(&self, tokens: Vec<Token>) -> Vec<Token> {
+        tokens
+            .into_iter()
+            .map(|t| {
+                if t.text.is_ascii() {
+                    return t;
+                }
+                let mut folded = String::with_capacity(t.text.len());
+                for c in t.text.chars() {
+                    match fold(c) {
+                        Some(ascii) if c.is_uppercase() => folded.push_str(&ascii.to_uppercase()),
+                        Some(ascii) => folded.push_str(ascii),
+                        None => folded.push(c),
+                    }
+                }
+                Token { text: folded, ..t }
+            })
+            .collect()
+    }
+}
+
+/// The ASCII form of a Latin-1 or Latin Extended-A letter, the empty string for combining marks
+fn fold(c: char) -> Option<&'static str> {
+    let lower = c.to_lowercase().next().unwrap_or(c);
+    Some(match lower {
+        '\u{300}'..='\u{36f}' => "",
+        'à'..='å' | 'ā' | 'ă' | 'ą' => "a",
+        'æ' => "ae",
+        'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => "c",
+        'ð' | 'ď' | 'đ' => "d",
+        'è'..='ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => "e",
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => "g",
+        'ĥ' | 'ħ' => "h",
+        'ì'..='ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => "i",
+        'ĳ' => "ij",
+        'ĵ' => "j",
+        'ķ' | 'ĸ' => "k",
+        'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => "l",
+        'ñ' | 'ń' | 'ņ' | 'ň' | 'ŉ' | 'ŋ' => "n",
+        'ò'..='ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => "o",
+        'œ' => "oe",
+        'ŕ' | 'ŗ' | 'ř' => "r",
+        'ś' | 'ŝ' | 'ş' | 'š' | 'ſ' => "s",
+        'ß' => "ss",
+        'ţ' | 'ť' | 'ŧ' => "t",
+        'þ' => "th",
+        'ù'..='ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => "u",
+        'ŵ' => "w",
+        'ý' | 'ÿ' | 'ŷ' => "y",
+        'ź' | 'ż' | 'ž' => "z",
+        _ => return None,
+    })
+}
+
+pub struct StopWordFilter(pub HashSet<String>);
+
+impl StopWordFilter {
+    pub fn new<S: AsRef<str>>(words: &[S]) -> Self {
+        StopWordFilter(words.iter().map(|w| w.as_ref().to_lowercase()).collect())
+    }
+}
+
+impl TokenFilter for StopWordFilter {
+    fn filter(&self, tokens: Vec<Token>) -> Vec<Token> {
+        tokens
+            .into_iter()
+            .filter(|t| !self.0.contains(&t.text))
+            .collect()
+    }
+}
+
+/// Stems English words with the Snowball (Porter2) algorithm, `running` to `run`
+pub struct EnglishStemmerFilter;
+
+impl TokenFilter for EnglishStemmerFilter {
+    fn filter(&self, tokens: Vec<Token>) -> Vec<Token> {
+        tokens
+            .into_iter()
+            .map(|t| Token {
+                text: stemmer::stem(&t.text),
+                ..t
+            })
+            .collect()
+    }
+}
+
+/// Adds the prefixes of each token between `min` and `max` characters long before the token, at
+/// its position, so a query for `gra` finds `graph`
+pub struct EdgeNGramFilter {
+    min: usize,
+    max: usize,
+}
+
+impl EdgeNGramFilter {
+    pub fn new(min: usize, max: usize) -> Self {
+        let min = min.max(1);
+        EdgeNGramFilter {
+            min,
+            max: max.max(min),
+        }
+    }
+}
+
+impl TokenFilter for EdgeNGramFilter {
+    fn filter(&self, tokens: Vec<Token>) -> Vec<Token> {
+        let mut out = Vec::with_capacity(tokens.len());
+        for token in tokens {
+            // a prefix of n chars ends where the nth char starts
+            let ends = token.text.char_indices().map(|(i, _)| i);
+            for end in ends.skip(self.min).take(self.max + 1 - self.min) {
+                out.push(Token {
+                    text: token.text[..end].to_string(),
+                    position: token.position,
+                });
+            }
+            out.push(token);
+        }
+        out
+    }
+
+    fn index_only(&self) -> bool {
+        true
+    }
+}
+
+/// A tokenizer and the filters its tokens go through, in order
+pub struct Analyzer {
+    tokenizer: Box<dyn Tokenizer>,
+    filters: Vec<Box<dyn TokenFilter>>,
+}
+
+impl Analyzer {
+    pub fn new(tokenizer: impl Tokenizer + 'static) -> Self {
+        Analyzer {
+            tokenizer: Box::new(tokenizer),
+            filters: Vec::new(),
+        }
+    }
+
+    pub fn with_filter(mut self, filter: impl TokenFilter + 'static) -> Self {
+        self.filters.push(Box::new(filter));
+        self
+    }
+
+    pub fn from_config(config: &AnalyzerConfig) -> Self {
+        let mut analyzer = match config.analyzer {
+            AnalyzerKind::Simple => Analyzer::new(SimpleTokenizer)
+                .with_filter(LowercaseFilter)
+                .with_filter(MinLengthFilter(3)),
+            AnalyzerKind::Standard | AnalyzerKind::English => Analyzer::new(WordTokenizer)
+                .with_filter(LowercaseFilter)
+                .with_filter(AsciiFoldingFilter),
+        };
+        match (&config.stop_words, config.analyzer) {
+            (Some(words), _) => analyzer = analyzer.with_filter(StopWordFilter::new(words)),
+            (None, AnalyzerKind::English) => {
+                analyzer = analyzer.with_filter(StopWordFilter::new(ENGLISH_STOP_WORDS))
+            }
+            _ => {}
+        }
+        if config.analyzer == AnalyzerKind::English {
+            analyzer = analyzer.with_filter(EnglishStemmerFilter);
+        }
+        if let Some((min, max)) = config.edge_ngrams {
+            analyzer = analyzer.with_filter(EdgeNGramFilter::new(min, max));
+        }
+        analyzer
+    }
+
+    /// Gets the tokens to index for a field's text
+    pub fn tokens(&self, text: &str) -> Vec<Token> {
+        self.filters
+            .iter()
+            .fold(self.tokenizer.tokenize(text), |tokens, f| f.filter(tokens))
+    }
+
+    /// Gets the tokens to look up for a query, skipping the filters that only apply when indexing
+    pub fn query_tokens(&self, query: &str) -> Vec<Token> {
+        self.filters
+            .iter()
+            .filter(|f| !f.index_only())
+            .fold(self.tokenizer.tokenize(query), |tokens, f| f.filter(tokens))
+    }
+
+    pub fn analyze(&self, text: &str) -> Vec<String> {
+        self.tokens(text).into_iter().map(|t| t.text).collect()
+    }
+
+    pub fn analyze_query(&self, query: &str) -> Vec<String> {
+        self.query_tokens(query)
+            .into_iter()
+            .map(|t| t.text)
+            .collect()
+    }
+}
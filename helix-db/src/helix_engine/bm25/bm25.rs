@@ -1,5 +1,15 @@
 use crate::{
     helix_engine::{
+        bm25::{
+            analyzer::{Analyzer, AnalyzerConfig, AnalyzerKind, Token},
+            postings::{
+                block_key, block_prefix, decode_block, encode_block, find_block, read_postings,
+                split_block_key, BlockHeader, PostingCursor, BLOCK_SIZE,
+            },
+            query::{parse_query, Occur, QueryClause, QueryNode},
+            scoring::ScoringConfig,
+        },
+        graph_core::config::BM25IndexConfig,
         storage_core::storage_core::HelixGraphStorage,
         types::GraphError,
         vector_core::{hnsw::HNSW, vector::HVector},
@@ -10,28 +20,134 @@ use crate::{
 
 use heed3::{types::*, Database, Env, RoTxn, RwTxn};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use tokio::task;
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BinaryHeap, HashMap, HashSet},
+    ops::Bound,
+    sync::LazyLock,
+};
 
-const DB_BM25_INVERTED_INDEX: &str = "bm25_inverted_index"; // term -> list of (doc_id, tf)
-const DB_BM25_DOC_LENGTHS: &str = "bm25_doc_lengths"; // doc_id -> document length
-const DB_BM25_TERM_FREQUENCIES: &str = "bm25_term_frequencies"; // term -> document frequency
-const DB_BM25_METADATA: &str = "bm25_metadata"; // stores total docs, avgdl, etc.
+pub(crate) const DB_BM25_INVERTED_INDEX: &str = "bm25_posting_blocks"; // term | block -> postings in doc id order
+pub(crate) const DB_BM25_LEGACY_INVERTED_INDEX: &str = "bm25_inverted_index"; // term -> (doc_id, tf), migrated on open
+pub(crate) const DB_BM25_DOC_LENGTHS: &str = "bm25_doc_lengths"; // doc_id -> document length
+pub(crate) const DB_BM25_TERM_FREQUENCIES: &str = "bm25_term_frequencies"; // term -> document frequency
+pub(crate) const DB_BM25_METADATA: &str = "bm25_metadata"; // stores total docs, avgdl, etc.
+pub(crate) const DB_BM25_FORWARD_INDEX: &str = "bm25_forward_index"; // doc_id -> list of (term, tf)
 pub const METADATA_KEY: &[u8] = b"metadata";
 
+/// Positions hold the index of the field they are in above this bit, so phrases and field scopes
+/// can be checked from the posting alone
+const FIELD_SHIFT: u32 = 24;
+const POSITION_MASK: u32 = (1 << FIELD_SHIFT) - 1;
+/// How many postings of the legacy inverted index are migrated at once
+const MIGRATION_BATCH: usize = 100_000;
+/// Postings written before positions were stored are just the doc id and term frequency
+const LEGACY_POSTING_SIZE: usize = 20;
+
+fn field_position(field: usize, position: u32) -> u32 {
+    ((field as u32) << FIELD_SHIFT) | position.min(POSITION_MASK)
+}
+
+/// The unlabeled index and labels without configured scoring use plain BM25
+static DEFAULT_SCORING: LazyLock<ScoringConfig> = LazyLock::new(ScoringConfig::default);
+
+/// The unlabeled index keeps the original tokenizer
+static SIMPLE_ANALYZER: LazyLock<Analyzer> = LazyLock::new(|| {
+    Analyzer::from_config(&AnalyzerConfig {
+        analyzer: AnalyzerKind::Simple,
+        ..Default::default()
+    })
+});
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct BM25Metadata {
     pub total_docs: u64,
     pub avgdl: f64,
-    pub k1: f32, // controls term frequency saturation
-    pub b: f32,  // controls document length normalization
+    /// The parameters the index was last written with, searches use the configured ones
+    pub k1: f32,
+    pub b: f32,
 }
 
 /// For inverted index
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct PostingListEntry {
     pub doc_id: u128,
     pub term_frequency: u32,
+    /// Positions of the term in the document, with the field index in the top bits
+    pub positions: Vec<u32>,
+    /// Kept with the posting so scoring doesn't look it up, the legacy layout didn't store it
+    #[serde(skip)]
+    pub doc_length: u32,
+}
+
+impl PostingListEntry {
+    /// Decodes a posting of the legacy inverted index
+    pub fn decode(bytes: &[u8]) -> Result<Self, GraphError> {
+        if bytes.len() == LEGACY_POSTING_SIZE {
+            let (doc_id, term_frequency): (u128, u32) = bincode::deserialize(bytes)?;
+            return Ok(PostingListEntry {
+                doc_id,
+                term_frequency,
+                positions: Vec::new(),
+                doc_length: 0,
+            });
+        }
+        Ok(bincode::deserialize(bytes)?)
+    }
+
+    /// How often the term occurs in the given fields, every occurrence counts when `None`
+    fn frequency_in(&self, fields: Option<&[u32]>) -> u32 {
+        match fields {
+            None => self.term_frequency,
+            Some(fields) => self
+                .positions
+                .iter()
+                .filter(|p| fields.contains(&(*p >> FIELD_SHIFT)))
+                .count() as u32,
+        }
+    }
+}
+
+/// For forward index, lets a document's postings be found without scanning the inverted index
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ForwardIndexEntry {
+    pub term: String,
+    pub term_frequency: u32,
+    pub positions: Vec<u32>,
+}
+
+/// A document kept by the top-k search, ordered by score and then lower doc ids first
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ScoredDoc {
+    score: f32,
+    doc_id: u128,
+}
+
+impl Eq for ScoredDoc {}
+
+impl Ord for ScoredDoc {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score
+            .total_cmp(&other.score)
+            .then_with(|| other.doc_id.cmp(&self.doc_id))
+    }
+}
+
+impl PartialOrd for ScoredDoc {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl ForwardIndexEntry {
+    fn posting(&self, doc_id: u128, doc_length: u32) -> PostingListEntry {
+        PostingListEntry {
+            doc_id,
+            term_frequency: self.term_frequency,
+            positions: self.positions.clone(),
+            doc_length,
+        }
+    }
 }
 
 pub trait BM25 {
@@ -67,8 +183,8 @@ pub struct HBM25Config {
     pub doc_lengths_db: Database<U128<heed3::byteorder::BE>, U32<heed3::byteorder::BE>>,
     pub term_frequencies_db: Database<Bytes, U32<heed3::byteorder::BE>>,
     pub metadata_db: Database<Bytes, Bytes>,
-    k1: f64,
-    b: f64,
+    pub forward_index_db: Database<U128<heed3::byteorder::BE>, Bytes>,
+    pub indices: HashMap<String, BM25Index>, // searchable fields by label
 }
 
 impl HBM25Config {
@@ -76,7 +192,6 @@ impl HBM25Config {
         let inverted_index_db: Database<Bytes, Bytes> = graph_env
             .database_options()
             .types::<Bytes, Bytes>()
-            .flags(heed3::DatabaseFlags::DUP_SORT)
             .name(DB_BM25_INVERTED_INDEX)
             .create(wtxn)?;
 
@@ -99,160 +214,1062 @@ impl HBM25Config {
             .name(DB_BM25_METADATA)
             .create(wtxn)?;
 
-        Ok(HBM25Config {
+        let forward_index_db: Database<U128<heed3::byteorder::BE>, Bytes> = graph_env
+            .database_options()
+            .types::<U128<heed3::byteorder::BE>, Bytes>()
+            .name(DB_BM25_FORWARD_INDEX)
+            .create(wtxn)?;
+
+        let config = HBM25Config {
             graph_env: graph_env.clone(),
             inverted_index_db,
             doc_lengths_db,
             term_frequencies_db,
             metadata_db,
-            k1: 1.2,
-            b: 0.75,
-        })
+            forward_index_db,
+            indices: HashMap::new(),
+        };
+
+        let legacy_index_db: Option<Database<Bytes, Bytes>> = graph_env
+            .database_options()
+            .types::<Bytes, Bytes>()
+            .flags(heed3::DatabaseFlags::DUP_SORT)
+            .name(DB_BM25_LEGACY_INVERTED_INDEX)
+            .open(wtxn)?;
+        if let Some(legacy_index_db) = legacy_index_db
+            && !legacy_index_db.is_empty(wtxn)?
+        {
+            config.migrate_legacy_postings(wtxn, legacy_index_db)?;
+        }
+
+        Ok(config)
+    }
+
+    /// Moves the postings of the inverted index used before postings were stored in blocks, which
+    /// kept one duplicate value per document under each term
+    fn migrate_legacy_postings(
+        &self,
+        wtxn: &mut RwTxn,
+        legacy_index_db: Database<Bytes, Bytes>,
+    ) -> Result<(), GraphError> {
+        let mut last_term: Option<Vec<u8>> = None;
+        loop {
+            // a batch of terms at a time so a large index isn't held in memory at once
+            let mut batch: Vec<(Vec<u8>, Vec<PostingListEntry>)> = Vec::new();
+            let mut count = 0;
+            let range = match &last_term {
+                Some(term) => (Bound::Excluded(term.as_slice()), Bound::Unbounded),
+                None => (Bound::Unbounded, Bound::Unbounded),
+            };
+            let mut iter = legacy_index_db.range(wtxn, &range)?;
+            while let Some((term_key, posting_bytes)) = iter.next().transpose()? {
+                if batch.last().is_none_or(|(last, _)| last.as_slice() != term_key) {
+                    if count >= MIGRATION_BATCH {
+                        break;
+                    }
+                    batch.push((term_key.to_vec(), Vec::new()));
+                }
+                if let Some((_, postings)) = batch.last_mut() {
+                    postings.push(PostingListEntry::decode(posting_bytes)?);
+                }
+                count += 1;
+            }
+            drop(iter);
+
+            let Some((term_key, _)) = batch.last() else {
+                break;
+            };
+            last_term = Some(term_key.clone());
+            for (term_key, mut postings) in batch {
+                for posting in &mut postings {
+                    posting.doc_length = self.doc_lengths_db.get(wtxn, &posting.doc_id)?.unwrap_or(0);
+                }
+                postings.sort_by_key(|p| p.doc_id);
+                postings.dedup_by_key(|p| p.doc_id);
+                let prefix = block_prefix(&term_key);
+                for block in postings.chunks(BLOCK_SIZE) {
+                    let base = block[0].doc_id;
+                    self.inverted_index_db
+                        .put(wtxn, &block_key(&prefix, base), &encode_block(base, block))?;
+                }
+            }
+        }
+
+        debug_println!("migrated legacy bm25 postings into blocks");
+        legacy_index_db.clear(wtxn)?;
+        Ok(())
+    }
+
+    /// Whether the BM25 tables were created yet, a database that never had them may already hold
+    /// items that need indexing
+    pub fn exists(graph_env: &Env, txn: &RoTxn) -> Result<bool, GraphError> {
+        let metadata_db: Option<Database<Bytes, Bytes>> = graph_env
+            .database_options()
+            .types::<Bytes, Bytes>()
+            .name(DB_BM25_METADATA)
+            .open(txn)?;
+        Ok(metadata_db.is_some())
+    }
+
+    /// Empties every index, including the unlabeled one since it isn't built from stored items
+    pub fn clear(&self, txn: &mut RwTxn) -> Result<(), GraphError> {
+        self.inverted_index_db.clear(txn)?;
+        self.doc_lengths_db.clear(txn)?;
+        self.term_frequencies_db.clear(txn)?;
+        self.metadata_db.clear(txn)?;
+        self.forward_index_db.clear(txn)?;
+        Ok(())
+    }
+
+    /// Indexes a stored item while the indices are rebuilt, returning whether it has searchable
+    /// fields. Items already indexed by a write since the rebuild started are left as they are.
+    pub fn reindex_item(
+        &self,
+        txn: &mut RwTxn,
+        label: &str,
+        id: u128,
+        properties: Option<&HashMap<String, Value>>,
+    ) -> Result<bool, GraphError> {
+        let Some(tokens) = properties.and_then(|props| self.searchable_tokens(label, props)) else {
+            return Ok(false);
+        };
+        if self.doc_lengths_db.get(txn, &id)?.is_none() {
+            self.collection(label).insert_tokens(txn, id, tokens)?;
+        }
+        Ok(true)
+    }
+
+    pub fn with_indices(mut self, indices: Vec<BM25IndexConfig>) -> Self {
+        self.indices = indices
+            .into_iter()
+            .map(|i| (i.label.clone(), BM25Index::new(&i)))
+            .collect();
+        self
+    }
+
+    /// Gets the index of a single label, which has its own postings and collection statistics.
+    ///
+    /// The empty label is the unlabeled index the `BM25` methods of `HBM25Config` use.
+    pub fn collection<'a>(&'a self, label: &'a str) -> BM25Collection<'a> {
+        BM25Collection {
+            config: self,
+            label,
+            index: self.indices.get(label),
+        }
+    }
+
+    /// Analyzes each `SEARCHABLE` property of an item with its field's analyzer into the tokens
+    /// indexed for its label, with the field index in their positions.
+    ///
+    /// Returns `None` when the label has no searchable fields or the item has none of them set.
+    pub fn searchable_tokens(
+        &self,
+        label: &str,
+        properties: &HashMap<String, Value>,
+    ) -> Option<Vec<Token>> {
+        let index = self.indices.get(label)?;
+        let mut found = false;
+        let mut tokens = Vec::new();
+        for (i, field) in index.fields.iter().enumerate() {
+            if let Some(value) = properties.get(field) {
+                found = true;
+                let analyzer = &index.analyzers[index.field_analyzers[i]];
+                tokens.extend(analyzer.tokens(&value.to_string()).into_iter().map(|t| Token {
+                    position: field_position(i, t.position),
+                    ..t
+                }));
+            }
+        }
+        found.then_some(tokens)
+    }
+
+    pub fn is_searchable(&self, label: &str, field: &str) -> bool {
+        self.indices
+            .get(label)
+            .is_some_and(|index| index.fields.iter().any(|f| f == field))
+    }
+
+    /// Indexes a new node, edge or vector under its label
+    pub fn insert_item(
+        &self,
+        txn: &mut RwTxn,
+        label: &str,
+        id: u128,
+        properties: Option<&HashMap<String, Value>>,
+    ) -> Result<(), GraphError> {
+        match properties.and_then(|props| self.searchable_tokens(label, props)) {
+            Some(tokens) => self.collection(label).insert_tokens(txn, id, tokens),
+            None => Ok(()),
+        }
+    }
+
+    /// Re-indexes an item after its properties changed, removing it once no searchable field is set
+    pub fn update_item(
+        &self,
+        txn: &mut RwTxn,
+        label: &str,
+        id: u128,
+        properties: Option<&HashMap<String, Value>>,
+    ) -> Result<(), GraphError> {
+        match properties.and_then(|props| self.searchable_tokens(label, props)) {
+            Some(tokens) => self.collection(label).update_tokens(txn, id, tokens),
+            None => self.delete_item(txn, label, id),
+        }
+    }
+
+    pub fn delete_item(&self, txn: &mut RwTxn, label: &str, id: u128) -> Result<(), GraphError> {
+        if !self.indices.contains_key(label) {
+            return Ok(());
+        }
+        self.collection(label).delete_doc(txn, id)
     }
 }
 
-impl BM25 for HBM25Config {
-    /// Converts text to lowercase, removes non-alphanumeric chars, splits into words
-    fn tokenize<const SHOULD_FILTER: bool>(&self, text: &str) -> Vec<String> {
-        text.to_lowercase()
-            .split(|c: char| !c.is_alphanumeric())
-            .filter(|s| !s.is_empty())
-            .filter_map(|s| (!SHOULD_FILTER || s.len() > 2).then_some(s.to_string()))
+/// The searchable fields of a label, the analyzers they are indexed with and how they are scored
+pub struct BM25Index {
+    pub fields: Vec<String>,
+    pub scoring: ScoringConfig,
+    /// The weight of each field, all 1 unless scoring with BM25F
+    field_weights: Vec<f64>,
+    /// The distinct analyzers of the fields, a query is analyzed with each of them
+    analyzers: Vec<Analyzer>,
+    /// The position in `analyzers` of each field's analyzer
+    field_analyzers: Vec<usize>,
+}
+
+impl BM25Index {
+    pub fn new(config: &BM25IndexConfig) -> Self {
+        let mut configs: Vec<&AnalyzerConfig> = Vec::new();
+        let default = AnalyzerConfig::default();
+        let field_analyzers = config
+            .fields
+            .iter()
+            .map(|field| {
+                let analyzer = config.analyzers.get(field).unwrap_or(&default);
+                configs.iter().position(|c| *c == analyzer).unwrap_or_else(|| {
+                    configs.push(analyzer);
+                    configs.len() - 1
+                })
+            })
+            .collect();
+        BM25Index {
+            fields: config.fields.clone(),
+            scoring: config.scoring.clone(),
+            field_weights: config.scoring.weights(&config.fields),
+            analyzers: configs.into_iter().map(Analyzer::from_config).collect(),
+            field_analyzers,
+        }
+    }
+}
+
+/// The BM25 index of one label.
+///
+/// Terms and metadata are keyed by `label | 0x00 | term`, doc lengths and the forward index by
+/// doc id since an id only ever belongs to one label.
+pub struct BM25Collection<'a> {
+    config: &'a HBM25Config,
+    label: &'a str,
+    index: Option<&'a BM25Index>,
+}
+
+impl BM25Collection<'_> {
+    /// The unlabeled index has no prefix so it keeps the original key layout
+    fn key(&self, suffix: &[u8]) -> Vec<u8> {
+        if self.label.is_empty() {
+            return suffix.to_vec();
+        }
+        let mut key = Vec::with_capacity(self.label.len() + 1 + suffix.len());
+        key.extend_from_slice(self.label.as_bytes());
+        key.push(0);
+        key.extend_from_slice(suffix);
+        key
+    }
+
+    pub fn scoring(&self) -> &ScoringConfig {
+        self.index.map_or(&*DEFAULT_SCORING, |index| &index.scoring)
+    }
+
+    /// The field weights when any field weighs other than 1
+    fn field_weights(&self) -> Option<&[f64]> {
+        self.index
+            .map(|index| index.field_weights.as_slice())
+            .filter(|weights| weights.iter().any(|&w| w != 1.0))
+    }
+
+    /// The weight of the field a position is in
+    fn position_weight(&self, position: u32) -> f64 {
+        self.field_weights()
+            .and_then(|weights| weights.get((position >> FIELD_SHIFT) as usize))
+            .copied()
+            .unwrap_or(1.0)
+    }
+
+    /// How often a term occurs in the given fields, each occurrence counting as much as its
+    /// field weighs
+    fn weighted_frequency(&self, posting: &PostingListEntry, fields: Option<&[u32]>) -> f64 {
+        if self.field_weights().is_none() || posting.positions.is_empty() {
+            return posting.frequency_in(fields) as f64;
+        }
+        let (count, weighted) = posting
+            .positions
+            .iter()
+            .filter(|p| fields.is_none_or(|fields| fields.contains(&(*p >> FIELD_SHIFT))))
+            .fold((0, 0.0), |(count, weighted), &p| {
+                (count + 1, weighted + self.position_weight(p))
+            });
+        // postings written while positions were capped only hold the first ones, the rest are
+        // assumed to be spread alike
+        match posting.term_frequency as usize > posting.positions.len() && fields.is_none() {
+            true => weighted * posting.term_frequency as f64 / count.max(1) as f64,
+            false => weighted,
+        }
+    }
+
+    /// The analyzers of the label's fields, the unlabeled index uses the simple analyzer
+    fn analyzers(&self) -> &[Analyzer] {
+        match self.index {
+            Some(index) if !index.analyzers.is_empty() => &index.analyzers,
+            _ => std::slice::from_ref(&*SIMPLE_ANALYZER),
+        }
+    }
+
+    /// Analyzes plain text as the label's first field
+    fn analyze(&self, doc: &str) -> Vec<Token> {
+        self.analyzers()[0].tokens(doc)
+    }
+
+    /// The analyzers a query clause is analyzed with, and the fields each one indexed.
+    ///
+    /// The fields are `None` when every occurrence of a term counts, which is the case when the
+    /// clause isn't scoped and all fields share an analyzer.
+    fn targets(&self, field: Option<&str>) -> Vec<(&Analyzer, Option<Vec<u32>>)> {
+        let Some(index) = self.index.filter(|index| !index.analyzers.is_empty()) else {
+            return vec![(&*SIMPLE_ANALYZER, None)];
+        };
+        if let Some(field) = field {
+            return index
+                .fields
+                .iter()
+                .position(|f| f == field)
+                .map(|i| {
+                    let analyzer = &index.analyzers[index.field_analyzers[i]];
+                    (analyzer, Some(vec![i as u32]))
+                })
+                .into_iter()
+                .collect();
+        }
+        if index.analyzers.len() == 1 {
+            return vec![(&index.analyzers[0], None)];
+        }
+        index
+            .analyzers
+            .iter()
+            .enumerate()
+            .map(|(a, analyzer)| {
+                let fields = (0..index.fields.len() as u32)
+                    .filter(|&i| index.field_analyzers[i as usize] == a)
+                    .collect();
+                (analyzer, Some(fields))
+            })
             .collect()
     }
 
-    /// Inserts needed information into doc_lengths_db, inverted_index_db, term_frequencies_db, and
-    /// metadata_db
-    fn insert_doc(&self, txn: &mut RwTxn, doc_id: u128, doc: &str) -> Result<(), GraphError> {
-        let tokens = self.tokenize::<true>(doc);
+    /// Counts the terms of a document, returned sorted by term so the forward index is stable
+    fn term_counts(&self, tokens: Vec<Token>) -> (Vec<ForwardIndexEntry>, u32) {
         let doc_length = tokens.len() as u32;
 
-        let mut term_counts: HashMap<String, u32> = HashMap::new();
+        let mut term_counts: HashMap<String, (u32, Vec<u32>)> = HashMap::new();
         for token in tokens {
-            *term_counts.entry(token).or_insert(0) += 1;
+            let (count, positions) = term_counts.entry(token.text).or_default();
+            *count += 1;
+            positions.push(token.position);
         }
 
-        self.doc_lengths_db.put(txn, &doc_id, &doc_length)?;
+        let mut entries = term_counts
+            .into_iter()
+            .map(|(term, (term_frequency, mut positions))| {
+                // every position is kept, so phrases match anywhere in the document
+                positions.sort_unstable();
+                positions.dedup();
+                ForwardIndexEntry {
+                    term,
+                    term_frequency,
+                    positions,
+                }
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by(|a, b| a.term.cmp(&b.term));
+        (entries, doc_length)
+    }
 
-        for (term, tf) in term_counts {
-            let term_bytes = term.as_bytes();
+    /// Gets the terms stored for a document in the forward index.
+    ///
+    /// Documents indexed before the forward index existed have no entry, so their terms are
+    /// recovered from the inverted index instead. Those were all indexed without a label, so a
+    /// labeled index without an entry for the doc doesn't hold it.
+    fn doc_terms(
+        &self,
+        txn: &RoTxn,
+        doc_id: u128,
+    ) -> Result<Option<Vec<ForwardIndexEntry>>, GraphError> {
+        if let Some(bytes) = self.config.forward_index_db.get(txn, &doc_id)? {
+            return Ok(Some(bincode::deserialize(bytes)?));
+        }
+        if !self.label.is_empty() {
+            return Ok(None);
+        }
 
-            let posting_entry = PostingListEntry {
-                doc_id,
-                term_frequency: tf,
+        let mut terms = Vec::new();
+        let mut iter = self.config.inverted_index_db.iter(txn)?;
+        while let Some((key, block_bytes)) = iter.next().transpose()? {
+            // unlabeled term keys are the term alone, which never holds a 0 byte
+            let Some((term_bytes, base)) = split_block_key(key) else {
+                continue;
             };
+            if term_bytes.contains(&0) || doc_id < base {
+                continue;
+            }
+            let (header, _) = BlockHeader::decode(base, block_bytes)?;
+            if doc_id > header.last_doc {
+                continue;
+            }
+            let postings = decode_block(base, block_bytes)?;
+            if let Some(posting) = postings.into_iter().find(|p| p.doc_id == doc_id) {
+                terms.push(ForwardIndexEntry {
+                    term: String::from_utf8_lossy(term_bytes).into_owned(),
+                    term_frequency: posting.term_frequency,
+                    positions: posting.positions,
+                });
+            }
+        }
+        Ok(Some(terms))
+    }
 
-            let posting_bytes = bincode::serialize(&posting_entry)?;
-
-            self.inverted_index_db
-                .put(txn, term_bytes, &posting_bytes)?;
+    /// Writes a posting into the block of its term it belongs in, replacing the document's
+    /// previous posting. Returns whether the document wasn't in the term's postings before.
+    fn put_posting(
+        &self,
+        txn: &mut RwTxn,
+        term_key: &[u8],
+        posting: PostingListEntry,
+    ) -> Result<bool, GraphError> {
+        let db = &self.config.inverted_index_db;
+        let prefix = block_prefix(term_key);
+        let doc_id = posting.doc_id;
+        let Some((base, mut postings)) = find_block(db, txn, &prefix, doc_id)? else {
+            db.put(txn, &block_key(&prefix, doc_id), &encode_block(doc_id, &[posting]))?;
+            return Ok(true);
+        };
 
-            let current_df = self.term_frequencies_db.get(txn, term_bytes)?.unwrap_or(0);
-            self.term_frequencies_db
-                .put(txn, term_bytes, &(current_df + 1))?;
+        let index = match postings.binary_search_by_key(&doc_id, |p| p.doc_id) {
+            Ok(index) => {
+                postings[index] = posting;
+                db.put(txn, &block_key(&prefix, base), &encode_block(base, &postings))?;
+                return Ok(false);
             }
+            Err(index) => index,
+        };
+        postings.insert(index, posting);
+
+        // a document before the term's first block becomes its base
+        let new_base = base.min(doc_id);
+        if new_base != base {
+            db.delete(txn, &block_key(&prefix, base))?;
+        }
+        if postings.len() > BLOCK_SIZE {
+            // ids mostly grow, so a block overflowing at its end starts the next one instead of
+            // leaving two half full blocks
+            let split = if index == postings.len() - 1 {
+                BLOCK_SIZE
+            } else {
+                postings.len() / 2
+            };
+            let tail = postings.split_off(split);
+            let tail_base = tail[0].doc_id;
+            db.put(txn, &block_key(&prefix, tail_base), &encode_block(tail_base, &tail))?;
+        }
+        db.put(txn, &block_key(&prefix, new_base), &encode_block(new_base, &postings))?;
+        Ok(true)
+    }
 
-        let mut metadata = if let Some(data) = self.metadata_db.get(txn, METADATA_KEY)? {
-            bincode::deserialize::<BM25Metadata>(data)?
+    /// Removes a document from its block of a term's postings, returning whether it was there
+    fn delete_posting(
+        &self,
+        txn: &mut RwTxn,
+        term_key: &[u8],
+        doc_id: u128,
+    ) -> Result<bool, GraphError> {
+        let db = &self.config.inverted_index_db;
+        let prefix = block_prefix(term_key);
+        let Some((base, mut postings)) = find_block(db, txn, &prefix, doc_id)? else {
+            return Ok(false);
+        };
+        let Ok(index) = postings.binary_search_by_key(&doc_id, |p| p.doc_id) else {
+            return Ok(false);
+        };
+        postings.remove(index);
+
+        let key = block_key(&prefix, base);
+        if postings.is_empty() {
+            db.delete(txn, &key)?;
         } else {
-            BM25Metadata {
-                total_docs: 0,
-                avgdl: 0.0,
-                k1: 1.2,
-                b: 0.75,
-            }
+            db.put(txn, &key, &encode_block(base, &postings))?;
+        }
+        Ok(true)
+    }
+
+    fn add_posting(
+        &self,
+        txn: &mut RwTxn,
+        doc_id: u128,
+        doc_length: u32,
+        entry: &ForwardIndexEntry,
+    ) -> Result<(), GraphError> {
+        let term_key = self.key(entry.term.as_bytes());
+        let term_bytes = term_key.as_slice();
+        if !self.put_posting(txn, term_bytes, entry.posting(doc_id, doc_length))? {
+            return Ok(());
+        }
+
+        let current_df = self.config.term_frequencies_db.get(txn, term_bytes)?.unwrap_or(0);
+        self.config.term_frequencies_db
+            .put(txn, term_bytes, &(current_df + 1))?;
+        Ok(())
+    }
+
+    fn remove_posting(
+        &self,
+        txn: &mut RwTxn,
+        doc_id: u128,
+        entry: &ForwardIndexEntry,
+    ) -> Result<(), GraphError> {
+        let term_key = self.key(entry.term.as_bytes());
+        let term_bytes = term_key.as_slice();
+        if !self.delete_posting(txn, term_bytes, doc_id)? {
+            return Ok(());
+        }
+
+        let current_df = self.config.term_frequencies_db.get(txn, term_bytes)?.unwrap_or(0);
+        if current_df > 1 {
+            self.config.term_frequencies_db
+                .put(txn, term_bytes, &(current_df - 1))?;
+        } else {
+            self.config.term_frequencies_db.delete(txn, term_bytes)?;
+        }
+        Ok(())
+    }
+
+    /// Gets the collection statistics, `None` until a document has been indexed
+    pub fn metadata(&self, txn: &RoTxn) -> Result<Option<BM25Metadata>, GraphError> {
+        match self.config.metadata_db.get(txn, &self.key(METADATA_KEY))? {
+            Some(data) => Ok(Some(bincode::deserialize::<BM25Metadata>(data)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn get_metadata(&self, txn: &RoTxn) -> Result<BM25Metadata, GraphError> {
+        Ok(self.metadata(txn)?.unwrap_or(BM25Metadata {
+            total_docs: 0,
+            avgdl: 0.0,
+            k1: self.scoring().k1 as f32,
+            b: self.scoring().b as f32,
+        }))
+    }
+
+    fn put_metadata(&self, txn: &mut RwTxn, metadata: &BM25Metadata) -> Result<(), GraphError> {
+        let metadata = BM25Metadata {
+            k1: self.scoring().k1 as f32,
+            b: self.scoring().b as f32,
+            ..metadata.clone()
         };
+        let metadata_bytes = bincode::serialize(&metadata)?;
+        self.config
+            .metadata_db
+            .put(txn, &self.key(METADATA_KEY), &metadata_bytes)?;
+        Ok(())
+    }
+
+    /// Inserts needed information into doc_lengths_db, inverted_index_db, term_frequencies_db, and
+    /// metadata_db for terms that are already analyzed
+    pub fn insert_tokens(
+        &self,
+        txn: &mut RwTxn,
+        doc_id: u128,
+        tokens: Vec<Token>,
+    ) -> Result<(), GraphError> {
+        let (terms, doc_length) = self.term_counts(tokens);
+
+        self.config.doc_lengths_db.put(txn, &doc_id, &doc_length)?;
+
+        for entry in &terms {
+            self.add_posting(txn, doc_id, doc_length, entry)?;
+        }
+
+        self.config.forward_index_db
+            .put(txn, &doc_id, &bincode::serialize(&terms)?)?;
 
+        let mut metadata = self.get_metadata(txn)?;
         let old_total_docs = metadata.total_docs;
         metadata.total_docs += 1;
         metadata.avgdl = (metadata.avgdl * old_total_docs as f64 + doc_length as f64)
             / metadata.total_docs as f64;
+        self.put_metadata(txn, &metadata)?;
 
-        let metadata_bytes = bincode::serialize(&metadata)?;
-        self.metadata_db.put(txn, METADATA_KEY, &metadata_bytes)?;
+        Ok(())
+    }
+
+    /// Diffs the old and new term sets so only changed postings are touched
+    pub fn update_tokens(
+        &self,
+        txn: &mut RwTxn,
+        doc_id: u128,
+        tokens: Vec<Token>,
+    ) -> Result<(), GraphError> {
+        let Some(old_length) = self.config.doc_lengths_db.get(txn, &doc_id)? else {
+            return self.insert_tokens(txn, doc_id, tokens);
+        };
+        let Some(old_terms) = self.doc_terms(txn, doc_id)? else {
+            return self.insert_tokens(txn, doc_id, tokens);
+        };
+
+        let old_terms = old_terms
+            .into_iter()
+            .map(|entry| (entry.term.clone(), entry))
+            .collect::<HashMap<_, _>>();
+        let (new_terms, new_length) = self.term_counts(tokens);
+
+        // postings hold the document length, so they all change when it does
+        for entry in &new_terms {
+            match old_terms.get(&entry.term) {
+                Some(old) if old == entry && old_length == new_length => {}
+                _ => self.add_posting(txn, doc_id, new_length, entry)?,
+            }
+        }
+        for (term, old) in &old_terms {
+            if !new_terms.iter().any(|entry| &entry.term == term) {
+                self.remove_posting(txn, doc_id, old)?;
+            }
+        }
+
+        self.config.doc_lengths_db.put(txn, &doc_id, &new_length)?;
+        self.config.forward_index_db
+            .put(txn, &doc_id, &bincode::serialize(&new_terms)?)?;
+
+        let mut metadata = self.get_metadata(txn)?;
+        if metadata.total_docs > 0 {
+            metadata.avgdl += (new_length as f64 - old_length as f64) / metadata.total_docs as f64;
+            self.put_metadata(txn, &metadata)?;
+        }
 
         Ok(())
     }
 
-    fn delete_doc(&self, txn: &mut RwTxn, doc_id: u128) -> Result<(), GraphError> {
-        let terms_to_update = {
-            let mut terms = Vec::new();
-            let mut iter = self.inverted_index_db.iter(txn)?;
-
-            while let Some((term_bytes, posting_bytes)) = iter.next().transpose()? {
-                let posting: PostingListEntry = bincode::deserialize(posting_bytes)?;
-                if posting.doc_id == doc_id {
-                    terms.push(term_bytes.to_vec());
+    /// Gets the document frequency and postings of a term
+    pub fn postings(
+        &self,
+        txn: &RoTxn,
+        term: &str,
+    ) -> Result<(u32, Vec<PostingListEntry>), GraphError> {
+        let term_key = self.key(term.as_bytes());
+        let doc_frequency = self
+            .config
+            .term_frequencies_db
+            .get(txn, &term_key)?
+            .unwrap_or(0);
+        let postings = match doc_frequency {
+            0 => Vec::new(),
+            _ => read_postings(&self.config.inverted_index_db, txn, &term_key)?,
+        };
+        Ok((doc_frequency, postings))
+    }
+
+    fn score(&self, metadata: &BM25Metadata, posting: &PostingListEntry, tf: f64, df: u32) -> f32 {
+        self.score_frequency(tf, posting.doc_length, df, metadata.total_docs, metadata.avgdl)
+    }
+
+    /// `calculate_bm25_score` with the index's scoring and a frequency that may be weighted
+    fn score_frequency(&self, tf: f64, doc_len: u32, df: u32, total_docs: u64, avgdl: f64) -> f32 {
+        // ensure we don't have division by zero
+        let df = df.max(1) as f64;
+        let total_docs = total_docs.max(1) as f64;
+
+        // calculate IDF: ln((N - df + 0.5) / (df + 0.5) + 1)
+        // this can be negative when df is high relative to N, which is mathematically correct
+        let idf = (((total_docs - df + 0.5) / (df + 0.5)) + 1.0).ln();
+
+        // ensure avgdl is not zero
+        let avgdl = if avgdl > 0.0 { avgdl } else { doc_len as f64 };
+
+        (idf * self.scoring().frequency_score(tf, doc_len as f64, avgdl)) as f32
+    }
+
+    /// Scores the documents containing any of the terms the text analyzes to
+    fn match_term(
+        &self,
+        txn: &RoTxn,
+        metadata: &BM25Metadata,
+        field: Option<&str>,
+        text: &str,
+    ) -> Result<HashMap<u128, f32>, GraphError> {
+        let mut doc_scores = HashMap::new();
+        for (analyzer, fields) in self.targets(field) {
+            let mut seen = HashSet::new();
+            for term in analyzer.analyze_query(text) {
+                if seen.insert(term.clone()) {
+                    self.score_term(txn, metadata, &term, fields.as_deref(), &mut doc_scores)?;
                 }
             }
-            terms
-        };
+        }
+        Ok(doc_scores)
+    }
 
-        // remove postings and update term frequencies
-        for term_bytes in terms_to_update {
-            // collect entries to keep
-            let entries_to_keep = {
-                let mut entries = Vec::new();
-                if let Some(duplicates) = self.inverted_index_db.get_duplicates(txn, &term_bytes)? {
-                    for result in duplicates {
-                        let (_, posting_bytes) = result?;
-                        let posting: PostingListEntry = bincode::deserialize(posting_bytes)?;
-                        if posting.doc_id != doc_id {
-                            entries.push(posting_bytes.to_vec());
-                        }
-                    }
+    fn score_term(
+        &self,
+        txn: &RoTxn,
+        metadata: &BM25Metadata,
+        term: &str,
+        fields: Option<&[u32]>,
+        doc_scores: &mut HashMap<u128, f32>,
+    ) -> Result<(), GraphError> {
+        let (doc_frequency, postings) = self.postings(txn, term)?;
+        for posting in postings {
+            let tf = self.weighted_frequency(&posting, fields);
+            if tf > 0.0 {
+                let score = self.score(metadata, &posting, tf, doc_frequency);
+                *doc_scores.entry(posting.doc_id).or_insert(0.0) += score;
+            }
+        }
+        Ok(())
+    }
+
+    /// Scores the documents containing the terms of the text at consecutive positions of a field,
+    /// using how often the phrase occurs as the frequency of each of its terms
+    fn match_phrase(
+        &self,
+        txn: &RoTxn,
+        metadata: &BM25Metadata,
+        field: Option<&str>,
+        text: &str,
+    ) -> Result<HashMap<u128, f32>, GraphError> {
+        let mut doc_scores = HashMap::new();
+        for (analyzer, fields) in self.targets(field) {
+            let tokens = analyzer.query_tokens(text);
+            let first = match tokens.as_slice() {
+                [] => continue,
+                // a single term doesn't need positions, so it also finds legacy postings
+                [only] => {
+                    self.score_term(txn, metadata, &only.text, fields.as_deref(), &mut doc_scores)?;
+                    continue;
                 }
-                entries
+                [first, ..] => first,
             };
 
-            // delete all entries for this term
-            self.inverted_index_db.delete(txn, &term_bytes)?;
+            let mut term_postings = Vec::with_capacity(tokens.len());
+            for token in &tokens {
+                let (doc_frequency, postings) = self.postings(txn, &token.text)?;
+                let by_doc = postings
+                    .into_iter()
+                    .map(|p| (p.doc_id, p))
+                    .collect::<HashMap<_, _>>();
+                term_postings.push((token.position - first.position, doc_frequency, by_doc));
+            }
 
-            // re-add the entries we want to keep
-            for entry_bytes in entries_to_keep {
-                self.inverted_index_db.put(txn, &term_bytes, &entry_bytes)?;
+            let (_, _, first_postings) = &term_postings[0];
+            for (doc_id, posting) in first_postings {
+                let occurrences = posting
+                    .positions
+                    .iter()
+                    .filter(|&&p| {
+                        fields
+                            .as_ref()
+                            .is_none_or(|fields| fields.contains(&(p >> FIELD_SHIFT)))
+                    })
+                    .filter(|&&p| {
+                        term_postings.iter().skip(1).all(|(offset, _, by_doc)| {
+                            by_doc
+                                .get(doc_id)
+                                .is_some_and(|other| other.positions.contains(&(p + offset)))
+                        })
+                    })
+                    .map(|&p| self.position_weight(p))
+                    .sum::<f64>();
+                if occurrences == 0.0 {
+                    continue;
+                }
+                for (_, doc_frequency, _) in &term_postings {
+                    let score = self.score(metadata, posting, occurrences, *doc_frequency);
+                    *doc_scores.entry(*doc_id).or_insert(0.0) += score;
+                }
             }
+        }
+        Ok(doc_scores)
+    }
 
-            let current_df = self.term_frequencies_db.get(txn, &term_bytes)?.unwrap_or(0);
-            if current_df > 0 {
-                self.term_frequencies_db
-                    .put(txn, &term_bytes, &(current_df - 1))?;
+    /// The analyzed terms of a query made only of optional terms, with the fields each may match
+    /// in, or `None` when the query needs the full evaluation
+    fn optional_terms(&self, clauses: &[QueryClause]) -> Option<Vec<(String, Option<Vec<u32>>)>> {
+        let mut terms = Vec::new();
+        for clause in clauses {
+            let QueryNode::Term { field, text } = &clause.node else {
+                return None;
+            };
+            if clause.occur != Occur::Should {
+                return None;
+            }
+            // the same terms as `match_term`, so both paths score documents alike
+            for (analyzer, fields) in self.targets(field.as_deref()) {
+                let mut seen = HashSet::new();
+                for term in analyzer.analyze_query(text) {
+                    if seen.insert(term.clone()) {
+                        terms.push((term, fields.clone()));
+                    }
+                }
             }
         }
+        Some(terms)
+    }
 
-        let doc_length = self.doc_lengths_db.get(txn, &doc_id)?.unwrap_or(0);
+    /// The highest score a term can give a document of a block
+    fn block_bound(&self, metadata: &BM25Metadata, header: &BlockHeader, df: u32) -> f32 {
+        let max_weight = self.field_weights().map_or(1.0, |weights| {
+            weights.iter().copied().fold(1.0, f64::max)
+        });
+        self.score_frequency(
+            header.max_term_frequency as f64 * max_weight,
+            header.min_doc_length,
+            df,
+            metadata.total_docs,
+            metadata.avgdl,
+        )
+    }
 
-        self.doc_lengths_db.delete(txn, &doc_id)?;
+    /// Finds the top documents for optional terms with block-max WAND. Cursors over the terms'
+    /// postings are kept in doc id order, and documents that can't beat the lowest kept score by
+    /// the terms' (and then their current blocks') highest possible scores are skipped unscored.
+    fn top_k(
+        &self,
+        txn: &RoTxn,
+        metadata: &BM25Metadata,
+        terms: Vec<(String, Option<Vec<u32>>)>,
+        limit: usize,
+    ) -> Result<Vec<(u128, f32)>, GraphError> {
+        struct TermCursor<'t> {
+            cursor: PostingCursor<'t>,
+            fields: Option<Vec<u32>>,
+            doc_frequency: u32,
+            max_score: f32,
+        }
 
-        let metadata_data = self
-            .metadata_db
-            .get(txn, METADATA_KEY)?
-            .map(|data| data.to_vec());
-
-        if let Some(data) = metadata_data {
-            let mut metadata: BM25Metadata = bincode::deserialize(&data)?;
-            if metadata.total_docs > 0 {
-                // update average document length
-                metadata.avgdl = if metadata.total_docs > 1 {
-                    (metadata.avgdl * metadata.total_docs as f64 - doc_length as f64)
-                        / (metadata.total_docs - 1) as f64
-                } else {
-                    0.0
-                };
-                metadata.total_docs -= 1;
-
-                let metadata_bytes = bincode::serialize(&metadata)?;
-                self.metadata_db.put(txn, METADATA_KEY, &metadata_bytes)?;
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+
+        let mut cursors = Vec::with_capacity(terms.len());
+        for (term, fields) in terms {
+            let term_key = self.key(term.as_bytes());
+            let doc_frequency = self
+                .config
+                .term_frequencies_db
+                .get(txn, &term_key)?
+                .unwrap_or(0);
+            if doc_frequency == 0 {
+                continue;
+            }
+            let cursor = PostingCursor::new(&self.config.inverted_index_db, txn, &term_key)?;
+            let max_score = cursor
+                .headers()
+                .map(|header| self.block_bound(metadata, header, doc_frequency))
+                .fold(0.0, f32::max);
+            cursors.push(TermCursor {
+                cursor,
+                fields,
+                doc_frequency,
+                max_score,
+            });
+        }
+
+        let mut heap: BinaryHeap<Reverse<ScoredDoc>> = BinaryHeap::with_capacity(limit + 1);
+        loop {
+            cursors.sort_by_key(|c| c.cursor.doc());
+            let full = heap.len() >= limit;
+            let threshold = heap.peek().map_or(0.0, |Reverse(lowest)| lowest.score);
+
+            // the first cursor where the terms up to it could score above the threshold, any
+            // document before it only has the terms before it
+            let mut upper_bound = 0.0;
+            let mut pivot = None;
+            for (i, c) in cursors.iter().enumerate() {
+                if c.cursor.doc() == u128::MAX {
+                    break;
+                }
+                upper_bound += c.max_score;
+                if !full || upper_bound > threshold {
+                    pivot = Some(i);
+                    break;
+                }
+            }
+            let Some(pivot) = pivot else {
+                break;
+            };
+            let pivot_doc = cursors[pivot].cursor.doc();
+
+            if cursors[0].cursor.doc() != pivot_doc {
+                for c in &mut cursors[..pivot] {
+                    c.cursor.seek(pivot_doc)?;
+                }
+                continue;
+            }
+
+            let at_pivot = cursors
+                .iter()
+                .take_while(|c| c.cursor.doc() == pivot_doc)
+                .count();
+            let block_bound: f32 = cursors[..at_pivot]
+                .iter()
+                .filter_map(|c| {
+                    let header = c.cursor.block_header()?;
+                    Some(self.block_bound(metadata, header, c.doc_frequency))
+                })
+                .sum();
+            if !full || block_bound > threshold {
+                let mut score = 0.0;
+                for c in &cursors[..at_pivot] {
+                    let Some(posting) = c.cursor.posting() else {
+                        continue;
+                    };
+                    let tf = self.weighted_frequency(posting, c.fields.as_deref());
+                    if tf > 0.0 {
+                        score += self.score(metadata, posting, tf, c.doc_frequency);
+                    }
+                }
+                if score > 0.0 && (!full || score > threshold) {
+                    heap.push(Reverse(ScoredDoc {
+                        score,
+                        doc_id: pivot_doc,
+                    }));
+                    if heap.len() > limit {
+                        heap.pop();
+                    }
+                }
+            }
+            for c in &mut cursors[..at_pivot] {
+                c.cursor.advance()?;
+            }
+        }
+
+        let mut results = heap.into_vec();
+        results.sort();
+        Ok(results
+            .into_iter()
+            .map(|Reverse(doc)| (doc.doc_id, doc.score))
+            .collect())
+    }
+
+    /// Scores the documents matching a list of clauses. Optional clauses only add to the score
+    /// once there is a required one, and a list without either matches nothing.
+    fn evaluate(
+        &self,
+        txn: &RoTxn,
+        metadata: &BM25Metadata,
+        clauses: &[QueryClause],
+    ) -> Result<HashMap<u128, f32>, GraphError> {
+        let mut optional: HashMap<u128, f32> = HashMap::new();
+        let mut required: Option<HashMap<u128, f32>> = None;
+        let mut excluded = HashSet::new();
+
+        for clause in clauses {
+            let matches = match &clause.node {
+                QueryNode::Term { field, text } => {
+                    self.match_term(txn, metadata, field.as_deref(), text)?
+                }
+                QueryNode::Phrase { field, text } => {
+                    self.match_phrase(txn, metadata, field.as_deref(), text)?
+                }
+                QueryNode::Group(clauses) => self.evaluate(txn, metadata, clauses)?,
+            };
+            match clause.occur {
+                Occur::Should => {
+                    for (doc_id, score) in matches {
+                        *optional.entry(doc_id).or_insert(0.0) += score;
+                    }
+                }
+                Occur::Must => {
+                    required = Some(match required {
+                        None => matches,
+                        Some(required) => required
+                            .into_iter()
+                            .filter_map(|(doc_id, score)| {
+                                matches.get(&doc_id).map(|other| (doc_id, score + other))
+                            })
+                            .collect(),
+                    });
+                }
+                Occur::MustNot => excluded.extend(matches.into_keys()),
             }
         }
 
+        let mut doc_scores = match required {
+            Some(mut required) => {
+                for (doc_id, score) in required.iter_mut() {
+                    *score += optional.get(doc_id).copied().unwrap_or(0.0);
+                }
+                required
+            }
+            None => optional,
+        };
+        doc_scores.retain(|doc_id, _| !excluded.contains(doc_id));
+        Ok(doc_scores)
+    }
+}
+
+impl BM25 for BM25Collection<'_> {
+    /// Converts text to lowercase, removes non-alphanumeric chars, splits into words
+    fn tokenize<const SHOULD_FILTER: bool>(&self, text: &str) -> Vec<String> {
+        text.to_lowercase()
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|s| !s.is_empty())
+            .filter_map(|s| (!SHOULD_FILTER || s.len() > 2).then_some(s.to_string()))
+            .collect()
+    }
+
+    fn insert_doc(&self, txn: &mut RwTxn, doc_id: u128, doc: &str) -> Result<(), GraphError> {
+        self.insert_tokens(txn, doc_id, self.analyze(doc))
+    }
+
+    /// Removes only the document's own postings, using the forward index to find them
+    fn delete_doc(&self, txn: &mut RwTxn, doc_id: u128) -> Result<(), GraphError> {
+        let Some(doc_length) = self.config.doc_lengths_db.get(txn, &doc_id)? else {
+            return Ok(());
+        };
+        let Some(terms) = self.doc_terms(txn, doc_id)? else {
+            return Ok(());
+        };
+
+        for entry in terms {
+            self.remove_posting(txn, doc_id, &entry)?;
+        }
+
+        self.config.doc_lengths_db.delete(txn, &doc_id)?;
+        self.config.forward_index_db.delete(txn, &doc_id)?;
+
+        let mut metadata = self.get_metadata(txn)?;
+        if metadata.total_docs > 0 {
+            // update average document length
+            metadata.avgdl = if metadata.total_docs > 1 {
+                (metadata.avgdl * metadata.total_docs as f64 - doc_length as f64)
+                    / (metadata.total_docs - 1) as f64
+            } else {
+                0.0
+            };
+            metadata.total_docs -= 1;
+            self.put_metadata(txn, &metadata)?;
+        }
+
         Ok(())
     }
 
-    /// Simply delete doc_id and then re-insert new doc with same doc-id
     fn update_doc(&self, txn: &mut RwTxn, doc_id: u128, doc: &str) -> Result<(), GraphError> {
-        self.delete_doc(txn, doc_id)?;
-        self.insert_doc(txn, doc_id, doc)
+        self.update_tokens(txn, doc_id, self.analyze(doc))
     }
 
     fn calculate_bm25_score(
@@ -263,24 +1280,67 @@ impl BM25 for HBM25Config {
         total_docs: u64,
         avgdl: f64,
     ) -> f32 {
-        // ensure we don't have division by zero
-        let df = df.max(1) as f64;
-        let total_docs = total_docs.max(1) as f64;
+        self.score_frequency(tf as f64, doc_len, df, total_docs, avgdl)
+    }
 
-        // calculate IDF: ln((N - df + 0.5) / (df + 0.5) + 1)
-        // this can be negative when df is high relative to N, which is mathematically correct
-        let idf = (((total_docs - df + 0.5) / (df + 0.5)) + 1.0).ln();
+    fn search(
+        &self,
+        txn: &RoTxn,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<(u128, f32)>, GraphError> {
+        let metadata = self
+            .metadata(txn)?
+            .ok_or(GraphError::New("BM25 metadata not found".to_string()))?;
 
-        // ensure avgdl is not zero
-        let avgdl = if avgdl > 0.0 { avgdl } else { doc_len as f64 };
+        let fields = self.index.map(|index| index.fields.as_slice()).unwrap_or(&[]);
+        let clauses = parse_query(query, fields);
+        let results = match self.optional_terms(&clauses) {
+            Some(terms) => self.top_k(txn, &metadata, terms, limit)?,
+            None => {
+                let doc_scores = self.evaluate(txn, &metadata, &clauses)?;
+
+                // Sort by score and return top results
+                let mut results: Vec<(u128, f32)> = doc_scores.into_iter().collect();
+                results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+                results.truncate(limit);
+                results
+            }
+        };
+
+        debug_println!("found {} results in bm25 search", results.len());
+
+        Ok(results)
+    }
+}
+
+impl BM25 for HBM25Config {
+    fn tokenize<const SHOULD_FILTER: bool>(&self, text: &str) -> Vec<String> {
+        self.collection("").tokenize::<SHOULD_FILTER>(text)
+    }
 
-        // calculate BM25 score
-        let tf = tf as f64;
-        let doc_len = doc_len as f64;
-        let tf_component = (tf * (self.k1 + 1.0))
-            / (tf + self.k1 * (1.0 - self.b + self.b * (doc_len.abs() / avgdl)));
+    fn insert_doc(&self, txn: &mut RwTxn, doc_id: u128, doc: &str) -> Result<(), GraphError> {
+        self.collection("").insert_doc(txn, doc_id, doc)
+    }
+
+    fn delete_doc(&self, txn: &mut RwTxn, doc_id: u128) -> Result<(), GraphError> {
+        self.collection("").delete_doc(txn, doc_id)
+    }
 
-        (idf * tf_component) as f32
+    fn update_doc(&self, txn: &mut RwTxn, doc_id: u128, doc: &str) -> Result<(), GraphError> {
+        self.collection("").update_doc(txn, doc_id, doc)
+    }
+
+    fn calculate_bm25_score(
+        &self,
+        tf: u32,
+        doc_len: u32,
+        df: u32,
+        total_docs: u64,
+        avgdl: f64,
+    ) -> f32 {
+        self.collection("")
+            .calculate_bm25_score(tf, doc_len, df, total_docs, avgdl)
     }
 
     fn search(
@@ -289,134 +1349,152 @@ impl BM25 for HBM25Config {
         query: &str,
         limit: usize,
     ) -> Result<Vec<(u128, f32)>, GraphError> {
-        let query_terms = self.tokenize::<true>(query);
-        // (node uuid, score)
-        let mut doc_scores: HashMap<u128, f32> = HashMap::with_capacity(limit);
-
-        let metadata = self
-            .metadata_db
-            .get(txn, METADATA_KEY)?
-            .ok_or(GraphError::New("BM25 metadata not found".to_string()))?;
-        let metadata: BM25Metadata = bincode::deserialize(metadata)?;
+        self.collection("").search(txn, query, limit)
+    }
+}
 
-        // for each query term, calculate scores
-        for term in query_terms {
-            let term_bytes = term.as_bytes();
+/// How the BM25 and vector rankings of a hybrid search are combined
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HybridFusion {
+    /// `alpha * bm25 + (1 - alpha) * similarity`, with BM25 scores divided by the best one and
+    /// similarities scaled from the furthest vector found to the closest, so both sides are
+    /// between 0 and 1
+    Alpha(f32),
+    /// Reciprocal rank fusion, `1 / (k + rank)` summed over both rankings
+    Rrf(f32),
+}
 
-            let doc_frequency = self.term_frequencies_db.get(txn, term_bytes)?.unwrap_or(0);
-            if doc_frequency == 0 {
-                continue;
-            }
+pub const DEFAULT_RRF_K: f32 = 60.0;
 
-            // Get all documents containing this term
-            if let Some(duplicates) = self.inverted_index_db.get_duplicates(txn, term_bytes)? {
-                for result in duplicates {
-                    let (_, posting_bytes) = result?;
-                    let posting: PostingListEntry = bincode::deserialize(posting_bytes)?;
-
-                    // Get document length
-                    let doc_length = self.doc_lengths_db.get(txn, &posting.doc_id)?.unwrap_or(0);
-
-                    // Calculate BM25 score for this term in this document
-                    let score = self.calculate_bm25_score(
-                        posting.term_frequency,
-                        doc_length,
-                        doc_frequency,
-                        metadata.total_docs,
-                        metadata.avgdl,
-                    );
-
-                    *doc_scores.entry(posting.doc_id).or_insert(0.0) += score;
-                }
-            }
-        }
+impl Default for HybridFusion {
+    fn default() -> Self {
+        HybridFusion::Rrf(DEFAULT_RRF_K)
+    }
+}
 
-        // Sort by score and return top results
-        let mut results: Vec<(u128, f32)> = doc_scores.into_iter().collect();
-        results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
-        results.truncate(limit);
+/// A fused result, with the scores it had in each of the searches that found it
+#[derive(Debug, Clone, PartialEq)]
+pub struct HybridResult {
+    pub id: u128,
+    pub score: f32,
+    pub bm25_score: Option<f32>,
+    pub vector_distance: Option<f64>,
+}
 
-        debug_println!("found {} results in bm25 search", results.len());
+/// Fuses BM25 results and vector distances, both ordered best first
+pub fn fuse_results(
+    bm25_results: &[(u128, f32)],
+    vector_results: &[(u128, f64)],
+    fusion: HybridFusion,
+    limit: usize,
+) -> Vec<HybridResult> {
+    let max_bm25 = bm25_results
+        .iter()
+        .map(|(_, score)| *score)
+        .fold(0.0f32, f32::max);
+    // distances are unbounded under some metrics, e.g. `1 - a·b` for dot products, so they are
+    // scaled over the results instead of turned into similarities on their own
+    let (min_distance, max_distance) = vector_results.iter().fold(
+        (f64::INFINITY, f64::NEG_INFINITY),
+        |(min, max), (_, distance)| (min.min(*distance), max.max(*distance)),
+    );
+
+    // kept in the order results were first seen so ties are broken the same way every time
+    let mut results: Vec<HybridResult> =
+        Vec::with_capacity(bm25_results.len() + vector_results.len());
+    let mut positions: HashMap<u128, usize> = HashMap::with_capacity(results.capacity());
+    fn result<'a>(
+        results: &'a mut Vec<HybridResult>,
+        positions: &mut HashMap<u128, usize>,
+        id: u128,
+    ) -> &'a mut HybridResult {
+        let index = *positions.entry(id).or_insert_with(|| {
+            results.push(HybridResult {
+                id,
+                score: 0.0,
+                bm25_score: None,
+                vector_distance: None,
+            });
+            results.len() - 1
+        });
+        &mut results[index]
+    }
 
-        Ok(results)
+    for (rank, (id, score)) in bm25_results.iter().enumerate() {
+        let entry = result(&mut results, &mut positions, *id);
+        entry.bm25_score = Some(*score);
+        entry.score += match fusion {
+            HybridFusion::Alpha(alpha) if max_bm25 > 0.0 => alpha * score / max_bm25,
+            HybridFusion::Alpha(_) => 0.0,
+            HybridFusion::Rrf(k) => 1.0 / (k + rank as f32 + 1.0),
+        };
     }
+    for (rank, (id, distance)) in vector_results.iter().enumerate() {
+        let entry = result(&mut results, &mut positions, *id);
+        entry.vector_distance = Some(*distance);
+        entry.score += match fusion {
+            HybridFusion::Alpha(alpha) if max_distance > min_distance => {
+                (1.0 - alpha) * ((max_distance - distance) / (max_distance - min_distance)) as f32
+            }
+            HybridFusion::Alpha(alpha) => 1.0 - alpha,
+            HybridFusion::Rrf(k) => 1.0 / (k + rank as f32 + 1.0),
+        };
+    }
+
+    results.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    results.truncate(limit);
+    results
 }
 
 pub trait HybridSearch {
-    /// Search both hnsw index and bm25 docs
+    /// Searches the BM25 index and the hnsw index of a label and fuses the results
     fn hybrid_search(
-        self,
+        &self,
+        txn: &RoTxn,
+        label: &str,
         query: &str,
         query_vector: &[f64],
-        alpha: f32,
+        fusion: HybridFusion,
         limit: usize,
-    ) -> impl std::future::Future<Output = Result<Vec<(u128, f32)>, GraphError>> + Send;
+    ) -> Result<Vec<HybridResult>, GraphError>;
 }
 
 impl HybridSearch for HelixGraphStorage {
-    async fn hybrid_search(
-        self,
+    fn hybrid_search(
+        &self,
+        txn: &RoTxn,
+        label: &str,
         query: &str,
         query_vector: &[f64],
-        alpha: f32,
+        fusion: HybridFusion,
         limit: usize,
-    ) -> Result<Vec<(u128, f32)>, GraphError> {
-        let query_owned = query.to_string();
-        let query_vector_owned = query_vector.to_vec();
-
-        let graph_env_bm25 = self.graph_env.clone();
-        let graph_env_vector = self.graph_env.clone();
-
-        let bm25_handle = task::spawn_blocking(move || -> Result<Vec<(u128, f32)>, GraphError> {
-            let txn = graph_env_bm25.read_txn()?;
-            match self.bm25.as_ref() {
-                Some(s) => s.search(&txn, &query_owned, limit * 2),
-                None => Err(GraphError::from("BM25 not enabled!")),
-            }
-        });
+    ) -> Result<Vec<HybridResult>, GraphError> {
+        // more candidates than needed from each side so results ranked lower by one search can
+        // still make it in on the other
+        let bm25_results = match self.bm25.as_ref() {
+            Some(s) => s.collection(label).search(txn, query, limit * 2)?,
+            None => return Err(GraphError::from("BM25 not enabled!")),
+        };
 
-        let vector_handle = task::spawn_blocking(move || -> Result<Option<Vec<HVector>>, GraphError> {
-            let txn = graph_env_vector.read_txn()?;
-            let results = self.vectors.search::<fn(&HVector, &RoTxn) -> bool>(
-                &txn,
-                &query_vector_owned,
+        let vector_results = self
+            .vectors
+            .search::<fn(&HVector, &RoTxn) -> bool>(
+                txn,
+                query_vector,
                 limit * 2,
-                "vector",
+                label,
                 None,
                 false,
-            )?;
-            Ok(Some(results))
-        });
+            )?
+            .into_iter()
+            .map(|vector| (vector.id, vector.get_distance()))
+            .collect::<Vec<_>>();
 
-        let (bm25_results, vector_results) = match tokio::try_join!(bm25_handle, vector_handle) {
-            Ok((a, b)) => (a, b),
-            Err(e) => return Err(GraphError::from(e.to_string())),
-        };
-
-        let mut combined_scores: HashMap<u128, f32> = HashMap::new();
-
-        for (doc_id, score) in bm25_results? {
-            combined_scores.insert(doc_id, alpha * score);
-        }
-
-        // correct_score = alpha * bm25_score + (1.0 - alpha) * vector_score
-        if let Some(vector_results) = vector_results? {
-            for doc in vector_results {
-                let doc_id = doc.id;
-                let score = doc.distance.unwrap_or(0.0);
-                let similarity = (1.0 / (1.0 + score)) as f32;
-                combined_scores
-                    .entry(doc_id)
-                    .and_modify(|existing_score| *existing_score += (1.0 - alpha) * similarity)
-                    .or_insert((1.0 - alpha) * similarity); // correction made here from score as f32 to similarity
-                }
-        }
-
-        let mut results = combined_scores.into_iter().collect::<Vec<(u128, f32)>>();
-        results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
-        results.truncate(limit);
-
-        Ok(results)
+        Ok(fuse_results(&bm25_results, &vector_results, fusion, limit))
     }
 }
 
@@ -2,19 +2,53 @@
 mod tests {
     use crate::{
         helix_engine::{
-            bm25::bm25::{
-                BM25Flatten, BM25Metadata, HBM25Config, HybridSearch, BM25, METADATA_KEY,
+            bm25::{
+                analyzer::{Analyzer, AnalyzerConfig, AnalyzerKind},
+                bm25::{
+                    fuse_results, BM25Flatten, BM25Metadata, ForwardIndexEntry, HBM25Config,
+                    HybridFusion, PostingListEntry, BM25, METADATA_KEY,
+                },
+                postings::{
+                    block_prefix, decode_block, encode_block, split_block_key, BlockHeader,
+                    PostingCursor, BLOCK_SIZE,
+                },
+                query::{parse_query, Occur, QueryClause, QueryNode},
+                scoring::{ScoringConfig, ScoringVariant},
+                stemmer::stem,
+            },
+            graph_core::{
+                config::{BM25IndexConfig, Config, VectorConfig, VectorTypeConfig},
+                ops::{
+                    bm25::{hybrid_search_bm25::SearchHybridAdapter, search_bm25::SearchBM25Adapter},
+                    g::G,
+                    source::{
+                        add_e::{AddEAdapter, EdgeType},
+                        add_n::AddNAdapter,
+                        e_from_id::EFromIdAdapter,
+                        n_from_id::NFromIdAdapter,
+                    },
+                    tr_val::{Traversable, TraversalVal},
+                    util::{drop::Drop, update::UpdateAdapter},
+                    vectors::insert::InsertVAdapter,
+                    version_info::VersionInfo,
+                },
+            },
+            storage_core::{
+                graph_visualization::GraphVisualization, storage_core::HelixGraphStorage,
+            },
+            vector_core::{
+                vector::{HVector, VectorPrecision},
+                vector_distance::DistanceMetric,
             },
-            graph_core::{config::Config, ops::version_info::VersionInfo},
-            storage_core::storage_core::HelixGraphStorage,
-            vector_core::{hnsw::HNSW, vector::HVector},
         },
+        props,
         protocol::value::Value,
     };
 
-    use heed3::{Env, EnvOpenOptions, RoTxn};
-    use rand::Rng;
-    use std::collections::HashMap;
+    use heed3::{types::Bytes, Database, DatabaseFlags, Env, EnvOpenOptions, RoTxn};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use sonic_rs::JsonValueTrait;
+    use std::{collections::HashMap, sync::Arc};
     use tempfile::tempdir;
 
     fn setup_test_env() -> (Env, tempfile::TempDir) {
@@ -40,27 +74,70 @@ mod tests {
         (config, temp_dir)
     }
 
-    fn setup_helix_storage() -> (HelixGraphStorage, tempfile::TempDir) {
+    fn setup_searchable_storage(
+        indices: &[(&str, &[&str])],
+    ) -> (Arc<HelixGraphStorage>, tempfile::TempDir) {
         let temp_dir = tempdir().unwrap();
-        let path = temp_dir.path().to_str().unwrap();
-        let config = Config::default();
-        let storage = HelixGraphStorage::new(path, config, VersionInfo::default()).unwrap();
-        (storage, temp_dir)
+        let config = Config {
+            bm25_indices: Some(
+                indices
+                    .iter()
+                    .map(|(label, fields)| BM25IndexConfig {
+                        label: label.to_string(),
+                        fields: fields.iter().map(|f| f.to_string()).collect(),
+                        analyzers: Default::default(),
+                        scoring: Default::default(),
+                    })
+                    .collect(),
+            ),
+            ..Config::default()
+        };
+        open_storage(config, temp_dir)
     }
 
-    fn generate_random_vectors(n: usize, d: usize) -> Vec<Vec<f64>> {
-        let mut rng = rand::rng();
-        let mut vectors = Vec::with_capacity(n);
+    fn setup_analyzed_storage(
+        label: &str,
+        analyzers: &[(&str, AnalyzerConfig)],
+    ) -> (Arc<HelixGraphStorage>, tempfile::TempDir) {
+        let temp_dir = tempdir().unwrap();
+        let config = Config {
+            bm25_indices: Some(vec![BM25IndexConfig {
+                label: label.to_string(),
+                fields: analyzers.iter().map(|(f, _)| f.to_string()).collect(),
+                analyzers: analyzers
+                    .iter()
+                    .map(|(f, a)| (f.to_string(), a.clone()))
+                    .collect(),
+                scoring: Default::default(),
+            }]),
+            ..Config::default()
+        };
+        open_storage(config, temp_dir)
+    }
 
-        for _ in 0..n {
-            let mut vector = Vec::with_capacity(d);
-            for _ in 0..d {
-                vector.push(rng.random::<f64>());
-            }
-            vectors.push(vector);
-        }
+    fn open_storage(
+        config: Config,
+        temp_dir: tempfile::TempDir,
+    ) -> (Arc<HelixGraphStorage>, tempfile::TempDir) {
+        let storage = HelixGraphStorage::new(
+            temp_dir.path().to_str().unwrap(),
+            config,
+            VersionInfo::default(),
+        )
+        .unwrap();
+        (Arc::new(storage), temp_dir)
+    }
 
-        vectors
+    /// Ids matching a query in the BM25 index of a label, empty before anything was indexed
+    fn bm25_ids(storage: &HelixGraphStorage, label: &str, query: &str) -> Vec<u128> {
+        let rtxn = storage.graph_env.read_txn().unwrap();
+        let bm25 = storage.bm25.as_ref().unwrap();
+        bm25.collection(label)
+            .search(&rtxn, query, 10)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect()
     }
 
     #[test]
@@ -1349,6 +1426,316 @@ mod tests {
         assert_eq!(results.len(), 0);
     }
 
+    #[test]
+    fn test_label_collections_are_separate() {
+        let (bm25, _temp_dir) = setup_bm25_config();
+        let mut wtxn = bm25.graph_env.write_txn().unwrap();
+
+        bm25.collection("Doc")
+            .insert_doc(&mut wtxn, 1u128, "rust graph database")
+            .unwrap();
+        bm25.collection("Doc")
+            .insert_doc(&mut wtxn, 2u128, "python scripting")
+            .unwrap();
+        bm25.collection("Note")
+            .insert_doc(&mut wtxn, 3u128, "rust notes")
+            .unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = bm25.graph_env.read_txn().unwrap();
+        let docs = bm25.collection("Doc").search(&rtxn, "rust", 10).unwrap();
+        assert_eq!(docs.len(), 1);
+        assert_eq!(docs[0].0, 1u128);
+
+        let notes = bm25.collection("Note").search(&rtxn, "rust", 10).unwrap();
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].0, 3u128);
+
+        // each label keeps its own collection statistics
+        let doc_metadata = bm25.collection("Doc").metadata(&rtxn).unwrap().unwrap();
+        assert_eq!(doc_metadata.total_docs, 2);
+        let note_metadata = bm25.collection("Note").metadata(&rtxn).unwrap().unwrap();
+        assert_eq!(note_metadata.total_docs, 1);
+        assert!(bm25.collection("").metadata(&rtxn).unwrap().is_none());
+        assert!(bm25.term_frequencies_db.get(&rtxn, b"rust").unwrap().is_none());
+        drop(rtxn);
+
+        let mut wtxn = bm25.graph_env.write_txn().unwrap();
+        bm25.collection("Doc").delete_doc(&mut wtxn, 1u128).unwrap();
+        // a doc is only removed from the index of its own label
+        bm25.collection("Doc").delete_doc(&mut wtxn, 3u128).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = bm25.graph_env.read_txn().unwrap();
+        assert!(bm25.collection("Doc").search(&rtxn, "rust", 10).unwrap().is_empty());
+        assert_eq!(
+            bm25.collection("Note").search(&rtxn, "rust", 10).unwrap().len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_search_bm25_only_indexes_searchable_fields() {
+        let (storage, _temp_dir) = setup_searchable_storage(&[("Doc", &["title", "body"])]);
+
+        let mut wtxn = storage.graph_env.write_txn().unwrap();
+        let doc = G::new_mut(Arc::clone(&storage), &mut wtxn)
+            .add_n(
+                "Doc",
+                Some(props! { "title" => "Rust", "body" => "a fast graph", "author" => "ferris" }),
+                None,
+            )
+            .collect_to::<Vec<_>>();
+        let doc_id = doc[0].id();
+        G::new_mut(Arc::clone(&storage), &mut wtxn)
+            .add_n("Note", Some(props! { "title" => "rust notes" }), None)
+            .collect_to::<Vec<_>>();
+        wtxn.commit().unwrap();
+
+        let rtxn = storage.graph_env.read_txn().unwrap();
+        let search = |query: &str| {
+            G::new(Arc::clone(&storage), &rtxn)
+                .search_bm25("Doc", query, 10)
+                .unwrap()
+                .collect_to::<Vec<_>>()
+        };
+
+        let results = search("rust graph");
+        assert_eq!(results.len(), 1);
+        assert!(matches!(&results[0], TraversalVal::Node(node) if node.id == doc_id));
+
+        // unmarked fields, property names and labels are not in the corpus
+        assert!(search("ferris").is_empty());
+        assert!(search("author").is_empty());
+        assert!(search("doc").is_empty());
+
+        // labels without searchable fields are not indexed at all
+        assert!(storage.bm25.as_ref().unwrap().collection("Note").metadata(&rtxn).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_bm25_follows_node_update_and_drop() {
+        let (storage, _temp_dir) = setup_searchable_storage(&[("Doc", &["title"])]);
+
+        let mut wtxn = storage.graph_env.write_txn().unwrap();
+        let doc = G::new_mut(Arc::clone(&storage), &mut wtxn)
+            .add_n("Doc", Some(props! { "title" => "rust graphs", "views" => 1 }), None)
+            .collect_to::<Vec<_>>();
+        let doc_id = doc[0].id();
+        wtxn.commit().unwrap();
+        assert_eq!(bm25_ids(&storage, "Doc", "rust"), vec![doc_id]);
+
+        let mut wtxn = storage.graph_env.write_txn().unwrap();
+        let traversal = G::new(Arc::clone(&storage), &wtxn)
+            .n_from_id(&doc_id)
+            .collect_to::<Vec<_>>();
+        G::new_mut_from(Arc::clone(&storage), &mut wtxn, traversal)
+            .update(Some(props! { "title" => "python scripts" }))
+            .collect_to::<Vec<_>>();
+        wtxn.commit().unwrap();
+        assert!(bm25_ids(&storage, "Doc", "rust").is_empty());
+        assert_eq!(bm25_ids(&storage, "Doc", "python"), vec![doc_id]);
+
+        // updating a field that isn't searchable leaves the index alone
+        let mut wtxn = storage.graph_env.write_txn().unwrap();
+        let traversal = G::new(Arc::clone(&storage), &wtxn)
+            .n_from_id(&doc_id)
+            .collect_to::<Vec<_>>();
+        G::new_mut_from(Arc::clone(&storage), &mut wtxn, traversal)
+            .update(Some(props! { "views" => 2 }))
+            .collect_to::<Vec<_>>();
+        wtxn.commit().unwrap();
+        assert_eq!(bm25_ids(&storage, "Doc", "python"), vec![doc_id]);
+
+        let mut wtxn = storage.graph_env.write_txn().unwrap();
+        let traversal = G::new(Arc::clone(&storage), &wtxn)
+            .n_from_id(&doc_id)
+            .collect_to::<Vec<_>>();
+        Drop::<Vec<_>>::drop_traversal(traversal, Arc::clone(&storage), &mut wtxn).unwrap();
+        wtxn.commit().unwrap();
+        assert!(bm25_ids(&storage, "Doc", "python").is_empty());
+
+        let rtxn = storage.graph_env.read_txn().unwrap();
+        let metadata = storage
+            .bm25
+            .as_ref()
+            .unwrap()
+            .collection("Doc")
+            .metadata(&rtxn)
+            .unwrap()
+            .unwrap();
+        assert_eq!(metadata.total_docs, 0);
+    }
+
+    #[test]
+    fn test_bm25_follows_edge_mutations() {
+        let (storage, _temp_dir) = setup_searchable_storage(&[("Cites", &["note"])]);
+
+        let mut wtxn = storage.graph_env.write_txn().unwrap();
+        let nodes = (0..3)
+            .map(|_| {
+                G::new_mut(Arc::clone(&storage), &mut wtxn)
+                    .add_n("Doc", None, None)
+                    .collect_to::<Vec<_>>()[0]
+                    .id()
+            })
+            .collect::<Vec<_>>();
+        let mut add_edge = |from: u128, to: u128, note: &str| {
+            G::new_mut(Arc::clone(&storage), &mut wtxn)
+                .add_e(
+                    "Cites",
+                    Some(props! { "note" => note }),
+                    from,
+                    to,
+                    false,
+                    EdgeType::Node,
+                )
+                .collect_to::<Vec<_>>()[0]
+                .id()
+        };
+        let first = add_edge(nodes[0], nodes[1], "background reading");
+        let second = add_edge(nodes[1], nodes[2], "related reading");
+        wtxn.commit().unwrap();
+
+        let mut found = bm25_ids(&storage, "Cites", "reading");
+        found.sort();
+        let mut expected = vec![first, second];
+        expected.sort();
+        assert_eq!(found, expected);
+
+        let mut wtxn = storage.graph_env.write_txn().unwrap();
+        let traversal = G::new(Arc::clone(&storage), &wtxn)
+            .e_from_id(&first)
+            .collect_to::<Vec<_>>();
+        G::new_mut_from(Arc::clone(&storage), &mut wtxn, traversal)
+            .update(Some(props! { "note" => "counterexample" }))
+            .collect_to::<Vec<_>>();
+        wtxn.commit().unwrap();
+        assert_eq!(bm25_ids(&storage, "Cites", "reading"), vec![second]);
+        assert_eq!(bm25_ids(&storage, "Cites", "counterexample"), vec![first]);
+
+        let mut wtxn = storage.graph_env.write_txn().unwrap();
+        let traversal = G::new(Arc::clone(&storage), &wtxn)
+            .e_from_id(&first)
+            .collect_to::<Vec<_>>();
+        Drop::<Vec<_>>::drop_traversal(traversal, Arc::clone(&storage), &mut wtxn).unwrap();
+        wtxn.commit().unwrap();
+        assert!(bm25_ids(&storage, "Cites", "counterexample").is_empty());
+
+        // edges dropped along with their node leave the index too
+        let mut wtxn = storage.graph_env.write_txn().unwrap();
+        let traversal = G::new(Arc::clone(&storage), &wtxn)
+            .n_from_id(&nodes[2])
+            .collect_to::<Vec<_>>();
+        Drop::<Vec<_>>::drop_traversal(traversal, Arc::clone(&storage), &mut wtxn).unwrap();
+        wtxn.commit().unwrap();
+        assert!(bm25_ids(&storage, "Cites", "reading").is_empty());
+    }
+
+    #[test]
+    fn test_bm25_follows_vector_insert_and_drop() {
+        let (storage, _temp_dir) = setup_searchable_storage(&[("Chunk", &["text"])]);
+
+        let mut wtxn = storage.graph_env.write_txn().unwrap();
+        let chunk = G::new_mut(Arc::clone(&storage), &mut wtxn)
+            .insert_v::<fn(&HVector, &RoTxn) -> bool>(
+                &[0.1, 0.2, 0.3],
+                "Chunk",
+                Some(props! { "text" => "vectors with searchable text" }),
+            )
+            .collect_to::<Vec<_>>();
+        let chunk_id = chunk[0].id();
+        let batch = G::new_mut(Arc::clone(&storage), &mut wtxn)
+            .insert_vs::<fn(&HVector, &RoTxn) -> bool>(
+                &[vec![0.3, 0.2, 0.1], vec![0.2, 0.2, 0.2]],
+                "Chunk",
+                Some(props! { "text" => "batched text" }),
+            )
+            .collect_to::<Vec<_>>();
+        wtxn.commit().unwrap();
+
+        assert_eq!(bm25_ids(&storage, "Chunk", "searchable"), vec![chunk_id]);
+        assert_eq!(bm25_ids(&storage, "Chunk", "batched").len(), batch.len());
+
+        let mut wtxn = storage.graph_env.write_txn().unwrap();
+        Drop::<Vec<_>>::drop_traversal(chunk, Arc::clone(&storage), &mut wtxn).unwrap();
+        wtxn.commit().unwrap();
+        assert!(bm25_ids(&storage, "Chunk", "searchable").is_empty());
+        assert_eq!(bm25_ids(&storage, "Chunk", "text").len(), 2);
+    }
+
+    #[test]
+    fn test_update_document_diffs_terms() {
+        let (bm25, _temp_dir) = setup_bm25_config();
+        let mut wtxn = bm25.graph_env.write_txn().unwrap();
+
+        bm25.insert_doc(&mut wtxn, 1u128, "shared apple apple banana")
+            .unwrap();
+        bm25.insert_doc(&mut wtxn, 2u128, "shared banana").unwrap();
+
+        bm25.update_doc(&mut wtxn, 1u128, "shared apple cherry")
+            .unwrap();
+
+        // banana is only in doc 2 now, cherry only in doc 1
+        let df = |term: &str| bm25.term_frequencies_db.get(&wtxn, term.as_bytes()).unwrap();
+        assert_eq!(df("shared"), Some(2));
+        assert_eq!(df("apple"), Some(1));
+        assert_eq!(df("banana"), Some(1));
+        assert_eq!(df("cherry"), Some(1));
+
+        let postings = |term: &str| {
+            let (_, postings) = bm25.collection("").postings(&wtxn, term).unwrap();
+            postings
+                .into_iter()
+                .map(|p| (p.doc_id, p.term_frequency))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(postings("apple"), vec![(1u128, 1)]);
+        assert_eq!(postings("banana"), vec![(2u128, 1)]);
+
+        let terms: Vec<ForwardIndexEntry> =
+            bincode::deserialize(bm25.forward_index_db.get(&wtxn, &1u128).unwrap().unwrap())
+                .unwrap();
+        assert_eq!(
+            terms.iter().map(|t| t.term.as_str()).collect::<Vec<_>>(),
+            vec!["apple", "cherry", "shared"]
+        );
+
+        let metadata = bincode::deserialize::<BM25Metadata>(
+            bm25.metadata_db.get(&wtxn, METADATA_KEY).unwrap().unwrap(),
+        )
+        .unwrap();
+        assert_eq!(metadata.total_docs, 2);
+        assert!((metadata.avgdl - 2.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_delete_document_without_forward_index() {
+        let (bm25, _temp_dir) = setup_bm25_config();
+        let mut wtxn = bm25.graph_env.write_txn().unwrap();
+
+        bm25.insert_doc(&mut wtxn, 1u128, "legacy document").unwrap();
+        bm25.insert_doc(&mut wtxn, 2u128, "newer document").unwrap();
+
+        // documents indexed before the forward index existed have no entry
+        bm25.forward_index_db.delete(&mut wtxn, &1u128).unwrap();
+        bm25.delete_doc(&mut wtxn, 1u128).unwrap();
+
+        assert!(bm25.term_frequencies_db.get(&wtxn, b"legacy").unwrap().is_none());
+        assert_eq!(
+            bm25.term_frequencies_db.get(&wtxn, b"document").unwrap(),
+            Some(1)
+        );
+
+        // deleting a document that is not indexed leaves the metadata alone
+        bm25.delete_doc(&mut wtxn, 1u128).unwrap();
+        let metadata = bincode::deserialize::<BM25Metadata>(
+            bm25.metadata_db.get(&wtxn, METADATA_KEY).unwrap().unwrap(),
+        )
+        .unwrap();
+        assert_eq!(metadata.total_docs, 1);
+    }
+
     #[test]
     fn test_search_with_limit() {
         let (bm25, _temp_dir) = setup_bm25_config();
@@ -1404,130 +1791,175 @@ mod tests {
         wtxn.commit().unwrap();
     }
 
-    #[tokio::test]
-    async fn test_hybrid_search() {
-        let (storage, _temp_dir) = setup_helix_storage();
-
-        let mut wtxn = storage.graph_env.write_txn().unwrap();
-        let docs = vec![
-            (1u128, "machine learning algorithms"),
-            (2u128, "deep learning neural networks"),
-            (3u128, "data science methods"),
-        ];
-
-        let bm25 = storage.bm25.as_ref().unwrap();
-        for (doc_id, doc) in &docs {
-            bm25.insert_doc(&mut wtxn, *doc_id, doc).unwrap();
-        }
-        wtxn.commit().unwrap();
-
-        let mut wtxn = storage.graph_env.write_txn().unwrap();
-        let vectors = generate_random_vectors(800, 650);
-        for vec in vectors {
-            let _ = storage
-                .vectors
-                .insert::<fn(&HVector, &RoTxn) -> bool>(&mut wtxn, &vec, None);
-        }
-        wtxn.commit().unwrap();
-
-        let query = "machine learning";
-        let query_vector = generate_random_vectors(1, 650);
-        let alpha = 0.5; // equal weight between BM25 and vector
-        let limit = 10;
-
-        let result = storage
-            .hybrid_search(query, &query_vector[0], alpha, limit)
-            .await;
-
-        match result {
-            Ok(results) => assert!(results.len() <= limit),
-            Err(_) => println!("Vector search not available"),
-        }
+    #[test]
+    fn test_fuse_results() {
+        let bm25_results = [(1u128, 5.0f32), (2u128, 3.0f32)];
+        let vector_results = [(2u128, 0.1f64), (3u128, 0.2f64)];
+
+        // found by both searches so ranked first, then by rank in either
+        let rrf = fuse_results(&bm25_results, &vector_results, HybridFusion::default(), 10);
+        assert_eq!(rrf.iter().map(|r| r.id).collect::<Vec<_>>(), vec![2, 1, 3]);
+        assert!((rrf[0].score - (1.0 / 62.0 + 1.0 / 61.0)).abs() < 1e-6);
+        assert_eq!(rrf[0].bm25_score, Some(3.0));
+        assert_eq!(rrf[0].vector_distance, Some(0.1));
+        assert_eq!(rrf[2].bm25_score, None);
+        assert_eq!(rrf[1].vector_distance, None);
+
+        // bm25 scores are divided by the best one
+        let bm25_only = fuse_results(&bm25_results, &vector_results, HybridFusion::Alpha(1.0), 10);
+        assert_eq!(bm25_only[0].id, 1);
+        assert!((bm25_only[0].score - 1.0).abs() < 1e-6);
+        assert!((bm25_only[1].score - 0.6).abs() < 1e-6);
+
+        let vector_only = fuse_results(&bm25_results, &vector_results, HybridFusion::Alpha(0.0), 2);
+        assert_eq!(vector_only.len(), 2);
+        assert_eq!(vector_only[0].id, 2);
+        // distances are scaled from the furthest result, at 0, to the closest, at 1
+        assert!((vector_only[0].score - 1.0).abs() < 1e-6);
+        assert_eq!(vector_only[1].score, 0.0);
     }
 
-    #[tokio::test]
-    async fn test_hybrid_search_alpha_vectors() {
-        let (storage, _temp_dir) = setup_helix_storage();
-
-        // Insert some test documents first
-        let mut wtxn = storage.graph_env.write_txn().unwrap();
-        let docs = vec![
-            (1u128, "machine learning algorithms"),
-            (2u128, "deep learning neural networks"),
-            (3u128, "data science methods"),
-        ];
-
-        let bm25 = storage.bm25.as_ref().unwrap();
-        for (doc_id, doc) in &docs {
-            bm25.insert_doc(&mut wtxn, *doc_id, doc).unwrap();
-        }
-        wtxn.commit().unwrap();
+    #[test]
+    fn test_hybrid_search_alpha_with_dot_product() {
+        let temp_dir = tempdir().unwrap();
+        let config = Config {
+            bm25_indices: Some(vec![BM25IndexConfig {
+                label: "Chunk".to_string(),
+                fields: vec!["text".to_string()],
+                analyzers: Default::default(),
+                scoring: Default::default(),
+            }]),
+            vector_config: Some(VectorConfig {
+                vector_types: Some(vec![VectorTypeConfig {
+                    label: "Chunk".to_string(),
+                    dimensions: Some(2),
+                    metric: DistanceMetric::DotProduct,
+                    precision: VectorPrecision::F64,
+                    rerank: false,
+                }]),
+                ..VectorConfig::default()
+            }),
+            ..Config::default()
+        };
+        let (storage, _temp_dir) = open_storage(config, temp_dir);
 
         let mut wtxn = storage.graph_env.write_txn().unwrap();
-        let vectors = generate_random_vectors(800, 650);
-        for vec in vectors {
-            let _ = storage
-                .vectors
-                .insert::<fn(&HVector, &RoTxn) -> bool>(&mut wtxn, &vec, None);
-        }
+        let mut insert = |vector: &[f64], text: &str| {
+            G::new_mut(Arc::clone(&storage), &mut wtxn)
+                .insert_v::<fn(&HVector, &RoTxn) -> bool>(
+                    vector,
+                    "Chunk",
+                    Some(props! { "text" => text }),
+                )
+                .collect_to_obj()
+                .id()
+        };
+        // distances of `1 - a·b` are -5, -1 and 1 for the query below
+        let closest = insert(&[3.0, 0.0], "graph database");
+        let middle = insert(&[1.0, 0.0], "graph");
+        let furthest = insert(&[0.0, 1.0], "cooking");
         wtxn.commit().unwrap();
 
-        let query = "machine learning";
-        let query_vector = generate_random_vectors(1, 650);
+        let rtxn = storage.graph_env.read_txn().unwrap();
+        let search = |fusion: HybridFusion| {
+            G::new(Arc::clone(&storage), &rtxn)
+                .search_hybrid("Chunk", "graph", &[2.0, 0.0], 3, fusion)
+                .unwrap()
+                .collect_to::<Vec<_>>()
+                .into_iter()
+                .map(|result| {
+                    let TraversalVal::Vector(vector) = result else {
+                        panic!("hybrid search returns vectors");
+                    };
+                    match vector.properties.as_ref().unwrap().get("hybrid_score") {
+                        Some(Value::F32(score)) => (vector.get_id(), *score),
+                        other => panic!("expected a hybrid score, got {other:?}"),
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
 
-        // alpha = 0.0 (Vector only)
-        let results_vector_only = storage
-            .hybrid_search(query, &query_vector[0], 0.0, 10)
-            .await;
+        let vector_only = search(HybridFusion::Alpha(0.0));
+        assert_eq!(
+            vector_only.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            vec![closest, middle, furthest]
+        );
+        assert!((vector_only[0].1 - 1.0).abs() < 1e-6);
+        assert!((vector_only[1].1 - 1.0 / 3.0).abs() < 1e-6);
+        assert_eq!(vector_only[2].1, 0.0);
 
-        match results_vector_only {
-            Ok(results) => assert!(results.len() <= 10),
-            Err(_) => {
-                println!("Vector-only search failed")
-            }
-        }
+        let fused = search(HybridFusion::Alpha(0.5));
+        assert!(fused.iter().all(|(_, score)| (0.0..=1.0).contains(score)));
+        assert_eq!(fused[0].0, closest);
     }
 
-    #[tokio::test]
-    async fn test_hybrid_search_alpha_bm25() {
-        let (storage, _temp_dir) = setup_helix_storage();
+    #[test]
+    fn test_hybrid_search_on_label() {
+        let (storage, _temp_dir) =
+            setup_searchable_storage(&[("Chunk", &["text"]), ("Other", &["text"])]);
 
-        // Insert some test documents first
         let mut wtxn = storage.graph_env.write_txn().unwrap();
-        let docs = vec![
-            (1u128, "machine learning algorithms"),
-            (2u128, "deep learning neural networks"),
-            (3u128, "data science methods"),
-        ];
-
-        let bm25 = storage.bm25.as_ref().unwrap();
-        for (doc_id, doc) in &docs {
-            bm25.insert_doc(&mut wtxn, *doc_id, doc).unwrap();
-        }
+        let mut insert = |vector: &[f64], label: &str, text: &str| {
+            G::new_mut(Arc::clone(&storage), &mut wtxn)
+                .insert_v::<fn(&HVector, &RoTxn) -> bool>(
+                    vector,
+                    label,
+                    Some(props! { "text" => text }),
+                )
+                .collect_to_obj()
+                .id()
+        };
+        let graph = insert(&[1.0, 0.0, 0.0], "Chunk", "rust graph database");
+        let python = insert(&[0.0, 1.0, 0.0], "Chunk", "python scripts");
+        let cooking = insert(&[0.9, 0.1, 0.0], "Chunk", "cooking recipes");
+        let other = insert(&[0.0, 1.0, 0.0], "Other", "graph");
         wtxn.commit().unwrap();
 
-        let mut wtxn = storage.graph_env.write_txn().unwrap();
-        let vectors = generate_random_vectors(800, 650);
-        for vec in vectors {
-            let _ = storage
-                .vectors
-                .insert::<fn(&HVector, &RoTxn) -> bool>(&mut wtxn, &vec, None);
-        }
-        wtxn.commit().unwrap();
+        let rtxn = storage.graph_env.read_txn().unwrap();
+        let search = |fusion: HybridFusion| {
+            G::new(Arc::clone(&storage), &rtxn)
+                .search_hybrid("Chunk", "graph", &[0.0, 1.0, 0.0], 3, fusion)
+                .unwrap()
+                .collect_to::<Vec<_>>()
+        };
 
-        let query = "machine learning";
-        let query_vector = generate_random_vectors(1, 650);
+        let results = search(HybridFusion::default());
+        let ids = results.iter().map(|r| r.id()).collect::<Vec<_>>();
+        assert_eq!(ids, vec![graph, python, cooking]);
+        assert!(!ids.contains(&other));
 
-        // alpha = 1.0 (BM25 only)
-        let results_bm25_only = storage
-            .hybrid_search(query, &query_vector[0], 1.0, 10)
-            .await;
+        // each result carries the scores it got from the searches that found it
+        let TraversalVal::Vector(first) = &results[0] else {
+            panic!("hybrid search returns vectors");
+        };
+        let properties = first.properties.as_ref().unwrap();
+        assert_eq!(
+            properties.get("text"),
+            Some(&Value::from("rust graph database"))
+        );
+        assert!(matches!(
+            properties.get("hybrid_score"),
+            Some(Value::F32(_))
+        ));
+        assert!(matches!(properties.get("bm25_score"), Some(Value::F32(_))));
+        assert!(matches!(
+            properties.get("vector_distance"),
+            Some(Value::F64(_))
+        ));
+        assert_eq!(
+            first.distance.map(Value::F64).as_ref(),
+            properties.get("vector_distance")
+        );
+        let TraversalVal::Vector(second) = &results[1] else {
+            panic!("hybrid search returns vectors");
+        };
+        let properties = second.properties.as_ref().unwrap();
+        assert!(!properties.contains_key("bm25_score"));
 
-        // all should be valid results or acceptable errors
-        match results_bm25_only {
-            Ok(results) => assert!(results.len() <= 10),
-            Err(_) => println!("BM25-only search failed"),
-        }
+        let vector_only = search(HybridFusion::Alpha(0.0));
+        assert_eq!(vector_only[0].id(), python);
+        let bm25_only = search(HybridFusion::Alpha(1.0));
+        assert_eq!(bm25_only[0].id(), graph);
     }
 
     #[test]
@@ -1580,5 +2012,789 @@ mod tests {
 
         wtxn.commit().unwrap();
     }
-}
 
+    fn analyzer(analyzer: AnalyzerKind) -> AnalyzerConfig {
+        AnalyzerConfig {
+            analyzer,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_english_stemmer() {
+        let cases = [
+            ("running", "run"),
+            ("runs", "run"),
+            ("caresses", "caress"),
+            ("ponies", "poni"),
+            ("ties", "tie"),
+            ("cries", "cri"),
+            ("gaps", "gap"),
+            ("gas", "gas"),
+            ("hopping", "hop"),
+            ("hoped", "hope"),
+            ("agreed", "agre"),
+            ("generously", "generous"),
+            ("national", "nation"),
+            ("relational", "relat"),
+            ("connection", "connect"),
+            ("consolidated", "consolid"),
+            ("abilities", "abil"),
+            ("luxuriated", "luxuri"),
+            ("happily", "happili"),
+            ("skies", "sky"),
+            ("dying", "die"),
+            ("news", "news"),
+            ("exceeding", "exceed"),
+            ("go", "go"),
+        ];
+        for (word, stemmed) in cases {
+            assert_eq!(stem(word), stemmed, "stemming {word}");
+        }
+    }
+
+    #[test]
+    fn test_standard_analyzer_keeps_short_terms() {
+        let standard = Analyzer::from_config(&AnalyzerConfig::default());
+        assert_eq!(
+            standard.analyze("AI in C# and C++, go!"),
+            vec!["ai", "in", "c#", "and", "c++", "go"]
+        );
+
+        // the simple analyzer keeps the original behaviour
+        let simple = Analyzer::from_config(&analyzer(AnalyzerKind::Simple));
+        assert_eq!(simple.analyze("AI in C# and C++, go!"), vec!["and"]);
+    }
+
+    #[test]
+    fn test_ascii_folding() {
+        let standard = Analyzer::from_config(&AnalyzerConfig::default());
+        assert_eq!(
+            standard.analyze("Café Straße NAÏVE Łódź cafe\u{301}"),
+            vec!["cafe", "strasse", "naive", "lodz", "cafe"]
+        );
+    }
+
+    #[test]
+    fn test_english_analyzer_stop_words_and_stemming() {
+        let english = Analyzer::from_config(&analyzer(AnalyzerKind::English));
+        assert_eq!(
+            english.analyze("The graphs are running with the indexes"),
+            vec!["graph", "run", "index"]
+        );
+
+        // configured stop words replace the built-in list
+        let custom = Analyzer::from_config(&AnalyzerConfig {
+            analyzer: AnalyzerKind::English,
+            stop_words: Some(vec!["Graphs".to_string()]),
+            edge_ngrams: None,
+        });
+        assert_eq!(custom.analyze("the graphs"), vec!["the"]);
+    }
+
+    #[test]
+    fn test_edge_ngrams_only_apply_when_indexing() {
+        let ngrams = Analyzer::from_config(&AnalyzerConfig {
+            edge_ngrams: Some((2, 4)),
+            ..Default::default()
+        });
+        assert_eq!(
+            ngrams.analyze("Graph db"),
+            vec!["gr", "gra", "grap", "graph", "db"]
+        );
+        assert_eq!(ngrams.analyze_query("Graph db"), vec!["graph", "db"]);
+    }
+
+    #[test]
+    fn test_field_analyzers_are_used_at_index_and_query_time() {
+        let (storage, _temp_dir) = setup_analyzed_storage(
+            "Doc",
+            &[
+                ("title", analyzer(AnalyzerKind::English)),
+                (
+                    "tags",
+                    AnalyzerConfig {
+                        edge_ngrams: Some((2, 10)),
+                        ..Default::default()
+                    },
+                ),
+            ],
+        );
+
+        let mut wtxn = storage.graph_env.write_txn().unwrap();
+        let doc = G::new_mut(Arc::clone(&storage), &mut wtxn)
+            .add_n(
+                "Doc",
+                Some(props! { "title" => "Running databases", "tags" => "AI Kubernetes" }),
+                None,
+            )
+            .collect_to::<Vec<_>>();
+        let doc_id = doc[0].id();
+        wtxn.commit().unwrap();
+
+        // stemmed on both sides
+        assert_eq!(bm25_ids(&storage, "Doc", "runs"), vec![doc_id]);
+        assert_eq!(bm25_ids(&storage, "Doc", "database"), vec![doc_id]);
+        // short terms are kept and prefixes match through the edge n-grams
+        assert_eq!(bm25_ids(&storage, "Doc", "ai"), vec![doc_id]);
+        assert_eq!(bm25_ids(&storage, "Doc", "kube"), vec![doc_id]);
+        // stop words are not indexed
+        assert!(bm25_ids(&storage, "Doc", "the").is_empty());
+    }
+
+    #[test]
+    fn test_bm25_index_config_display_and_parse() {
+        let schema = BM25IndexConfig {
+            label: "Doc".to_string(),
+            fields: vec!["title".to_string()],
+            analyzers: [("title".to_string(), analyzer(AnalyzerKind::English))]
+                .into_iter()
+                .collect(),
+            scoring: Default::default(),
+        };
+        assert_eq!(
+            schema.to_string(),
+            "BM25IndexConfig { label: \"Doc\".to_string(), fields: vec![\"title\".to_string()], \
+             analyzers: [(\"title\".to_string(), AnalyzerConfig { analyzer: AnalyzerKind::English, \
+             stop_words: None, edge_ngrams: None })].into_iter().collect(), scoring: Default::default() }"
+        );
+
+        let parsed: BM25IndexConfig = sonic_rs::from_str(
+            r#"{"label": "Doc", "fields": ["title"], "analyzers": {"title": {"analyzer": "english", "edge_ngrams": [2, 8]}}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            parsed.analyzers["title"],
+            AnalyzerConfig {
+                analyzer: AnalyzerKind::English,
+                stop_words: None,
+                edge_ngrams: Some((2, 8)),
+            }
+        );
+    }
+
+    /// Sorted ids matching a query in the unlabeled index
+    fn search_ids(bm25: &HBM25Config, query: &str) -> Vec<u128> {
+        let rtxn = bm25.graph_env.read_txn().unwrap();
+        let mut ids = bm25
+            .search(&rtxn, query, 10)
+            .unwrap()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn test_parse_query() {
+        let term = |field: Option<&str>, text: &str| QueryNode::Term {
+            field: field.map(str::to_string),
+            text: text.to_string(),
+        };
+        let clause = |occur, node| QueryClause { occur, node };
+
+        let clauses = parse_query(
+            r#"+rust -java "graph db" (zig OR go) title:fast title:"x y" http://a"#,
+            &["title".to_string()],
+        );
+        assert_eq!(
+            clauses,
+            vec![
+                clause(Occur::Must, term(None, "rust")),
+                clause(Occur::MustNot, term(None, "java")),
+                clause(
+                    Occur::Should,
+                    QueryNode::Phrase {
+                        field: None,
+                        text: "graph db".to_string()
+                    }
+                ),
+                clause(
+                    Occur::Should,
+                    QueryNode::Group(vec![
+                        clause(Occur::Should, term(None, "zig")),
+                        clause(Occur::Should, term(None, "go")),
+                    ])
+                ),
+                clause(Occur::Should, term(Some("title"), "fast")),
+                clause(
+                    Occur::Should,
+                    QueryNode::Phrase {
+                        field: Some("title".to_string()),
+                        text: "x y".to_string()
+                    }
+                ),
+                // not a searchable field, so not a scope
+                clause(Occur::Should, term(None, "http://a")),
+            ]
+        );
+
+        // unbalanced quotes and parentheses are closed at the end
+        assert_eq!(
+            parse_query(r#"+(rust "graph db"#, &[]),
+            vec![clause(
+                Occur::Must,
+                QueryNode::Group(vec![
+                    clause(Occur::Should, term(None, "rust")),
+                    clause(
+                        Occur::Should,
+                        QueryNode::Phrase {
+                            field: None,
+                            text: "graph db".to_string()
+                        }
+                    ),
+                ])
+            )]
+        );
+    }
+
+    #[test]
+    fn test_boolean_queries() {
+        let (bm25, _temp_dir) = setup_bm25_config();
+        let mut wtxn = bm25.graph_env.write_txn().unwrap();
+        bm25.insert_doc(&mut wtxn, 1u128, "rust graph engine").unwrap();
+        bm25.insert_doc(&mut wtxn, 2u128, "java graph engine").unwrap();
+        bm25.insert_doc(&mut wtxn, 3u128, "rust java bindings").unwrap();
+        bm25.insert_doc(&mut wtxn, 4u128, "zig graph").unwrap();
+        wtxn.commit().unwrap();
+
+        assert_eq!(search_ids(&bm25, "graph"), vec![1, 2, 4]);
+        assert_eq!(search_ids(&bm25, "+graph -java"), vec![1, 4]);
+        assert_eq!(search_ids(&bm25, "+rust +java"), vec![3]);
+        assert_eq!(search_ids(&bm25, "+graph +(rust OR java)"), vec![1, 2]);
+        assert_eq!(search_ids(&bm25, "+(rust OR zig) -(java OR engine)"), vec![4]);
+        // exclusions alone match nothing
+        assert!(search_ids(&bm25, "-java").is_empty());
+
+        // optional terms rank the required matches
+        let rtxn = bm25.graph_env.read_txn().unwrap();
+        let results = bm25.search(&rtxn, "+graph rust", 10).unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].0, 1u128);
+    }
+
+    #[test]
+    fn test_phrase_queries() {
+        let (bm25, _temp_dir) = setup_bm25_config();
+        let mut wtxn = bm25.graph_env.write_txn().unwrap();
+        bm25.insert_doc(&mut wtxn, 1u128, "the graph database is fast")
+            .unwrap();
+        bm25.insert_doc(&mut wtxn, 2u128, "database of graph things")
+            .unwrap();
+        bm25.insert_doc(&mut wtxn, 3u128, "graph databases, graph database")
+            .unwrap();
+        wtxn.commit().unwrap();
+
+        assert_eq!(search_ids(&bm25, "graph database"), vec![1, 2, 3]);
+        assert_eq!(search_ids(&bm25, "\"graph database\""), vec![1, 3]);
+        assert_eq!(search_ids(&bm25, "\"database graph\""), Vec::<u128>::new());
+        assert_eq!(search_ids(&bm25, "\"Graph Database\" -databases"), vec![1]);
+
+        // positions follow updates
+        let mut wtxn = bm25.graph_env.write_txn().unwrap();
+        bm25.update_doc(&mut wtxn, 2u128, "a graph database of things")
+            .unwrap();
+        wtxn.commit().unwrap();
+        assert_eq!(search_ids(&bm25, "\"graph database\""), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_legacy_postings_are_migrated_into_blocks() {
+        let (env, _temp_dir) = setup_test_env();
+        let mut wtxn = env.write_txn().unwrap();
+        let bm25 = HBM25Config::new(&env, &mut wtxn).unwrap();
+        bm25.insert_doc(&mut wtxn, 2u128, "newer posting").unwrap();
+        bm25.insert_doc(&mut wtxn, 4u128, "other words").unwrap();
+        bm25.insert_doc(&mut wtxn, 5u128, "more words").unwrap();
+
+        // the inverted index postings were kept in before blocks, one duplicate value per
+        // document, with and without positions
+        let legacy_db: Database<Bytes, Bytes> = env
+            .database_options()
+            .types::<Bytes, Bytes>()
+            .flags(DatabaseFlags::DUP_SORT)
+            .name("bm25_inverted_index")
+            .create(&mut wtxn)
+            .unwrap();
+        let without_positions = bincode::serialize(&(1u128, 1u32)).unwrap();
+        let with_positions = bincode::serialize(&(3u128, 2u32, vec![0u32, 4])).unwrap();
+        legacy_db
+            .put(&mut wtxn, b"legacy", &without_positions)
+            .unwrap();
+        legacy_db.put(&mut wtxn, b"legacy", &with_positions).unwrap();
+        bm25.term_frequencies_db
+            .put(&mut wtxn, b"legacy", &2)
+            .unwrap();
+        bm25.doc_lengths_db.put(&mut wtxn, &1u128, &1).unwrap();
+        bm25.doc_lengths_db.put(&mut wtxn, &3u128, &5).unwrap();
+        wtxn.commit().unwrap();
+
+        let mut wtxn = env.write_txn().unwrap();
+        let bm25 = HBM25Config::new(&env, &mut wtxn).unwrap();
+        assert!(legacy_db.is_empty(&wtxn).unwrap());
+        wtxn.commit().unwrap();
+
+        let rtxn = env.read_txn().unwrap();
+        let (df, postings) = bm25.collection("").postings(&rtxn, "legacy").unwrap();
+        assert_eq!(df, 2);
+        assert_eq!(
+            postings
+                .iter()
+                .map(|p| (p.doc_id, p.term_frequency, p.doc_length))
+                .collect::<Vec<_>>(),
+            vec![(1, 1, 1), (3, 2, 5)]
+        );
+        assert_eq!(postings[1].positions, vec![0, 4]);
+        drop(rtxn);
+
+        assert_eq!(search_ids(&bm25, "legacy"), vec![1, 3]);
+        assert_eq!(search_ids(&bm25, "\"legacy\""), vec![1, 3]);
+        assert_eq!(search_ids(&bm25, "posting"), vec![2]);
+
+        let mut wtxn = env.write_txn().unwrap();
+        bm25.delete_doc(&mut wtxn, 1u128).unwrap();
+        bm25.delete_doc(&mut wtxn, 3u128).unwrap();
+        let (_, postings) = bm25.collection("").postings(&wtxn, "legacy").unwrap();
+        assert!(postings.is_empty());
+        assert!(bm25.term_frequencies_db.get(&wtxn, b"legacy").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_posting_block_roundtrip() {
+        let postings = vec![
+            PostingListEntry {
+                doc_id: 10,
+                term_frequency: 2,
+                positions: vec![1, 7, (1 << 24) + 3],
+                doc_length: 12,
+            },
+            PostingListEntry {
+                doc_id: 11,
+                term_frequency: 5,
+                positions: Vec::new(),
+                doc_length: 40,
+            },
+            PostingListEntry {
+                doc_id: u128::MAX - 1,
+                term_frequency: 1,
+                positions: vec![0],
+                doc_length: 3,
+            },
+        ];
+        let bytes = encode_block(7, &postings);
+        assert_eq!(decode_block(7, &bytes).unwrap(), postings);
+
+        let (header, _) = BlockHeader::decode(7, &bytes).unwrap();
+        assert_eq!(
+            header,
+            BlockHeader {
+                len: 3,
+                last_doc: u128::MAX - 1,
+                max_term_frequency: 5,
+                min_doc_length: 3,
+            }
+        );
+    }
+
+    #[test]
+    fn test_postings_split_into_ordered_blocks() {
+        let (bm25, _temp_dir) = setup_bm25_config();
+        let mut wtxn = bm25.graph_env.write_txn().unwrap();
+        let count = BLOCK_SIZE as u128 * 3;
+
+        // ascending ids fill blocks in turn, the rest land between them and split them
+        for doc_id in (0..count).map(|i| i * 2 + 1000) {
+            bm25.insert_doc(&mut wtxn, doc_id, "common").unwrap();
+        }
+        for doc_id in (0..count).rev().map(|i| i * 2 + 1001) {
+            bm25.insert_doc(&mut wtxn, doc_id, "common").unwrap();
+        }
+        bm25.insert_doc(&mut wtxn, 1u128, "common").unwrap();
+
+        let (df, postings) = bm25.collection("").postings(&wtxn, "common").unwrap();
+        assert_eq!(df as u128, count * 2 + 1);
+        let doc_ids = postings.iter().map(|p| p.doc_id).collect::<Vec<_>>();
+        let mut expected = (1000..1000 + count * 2).collect::<Vec<_>>();
+        expected.insert(0, 1);
+        assert_eq!(doc_ids, expected);
+
+        let prefix = block_prefix(b"common");
+        let mut blocks = 0;
+        for result in bm25.inverted_index_db.prefix_iter(&wtxn, &prefix).unwrap() {
+            let (key, bytes) = result.unwrap();
+            let (_, base) = split_block_key(key).unwrap();
+            let (header, _) = BlockHeader::decode(base, bytes).unwrap();
+            assert!(header.len <= BLOCK_SIZE);
+            blocks += 1;
+        }
+        assert!(blocks > 1);
+
+        // a cursor seeking through the blocks sees the same postings
+        let mut cursor = PostingCursor::new(&bm25.inverted_index_db, &wtxn, b"common").unwrap();
+        assert_eq!(cursor.doc(), 1);
+        cursor.seek(1500).unwrap();
+        assert_eq!(cursor.doc(), 1500);
+        cursor.seek(1000 + count * 2 - 1).unwrap();
+        assert_eq!(cursor.doc(), 1000 + count * 2 - 1);
+        cursor.advance().unwrap();
+        assert_eq!(cursor.doc(), u128::MAX);
+
+        for doc_id in 1000..1000 + count * 2 {
+            bm25.delete_doc(&mut wtxn, doc_id).unwrap();
+        }
+        let (_, postings) = bm25.collection("").postings(&wtxn, "common").unwrap();
+        assert_eq!(postings.iter().map(|p| p.doc_id).collect::<Vec<_>>(), vec![1]);
+    }
+
+    #[test]
+    fn test_top_k_matches_exhaustive_scoring() {
+        let (bm25, _temp_dir) = setup_bm25_config();
+        let mut wtxn = bm25.graph_env.write_txn().unwrap();
+        let words = ["alpha", "beta", "gamma", "delta", "epsilon", "zeta"];
+        let mut rng = StdRng::seed_from_u64(42);
+        for doc_id in 0..2000u128 {
+            let length = rng.random_range(1..40);
+            let doc = (0..length)
+                .map(|_| {
+                    // skewed so some terms are common and others rare
+                    let index = rng.random_range(0..words.len() * words.len()).isqrt();
+                    words[index]
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            bm25.insert_doc(&mut wtxn, doc_id, &doc).unwrap();
+        }
+        wtxn.commit().unwrap();
+
+        let rtxn = bm25.graph_env.read_txn().unwrap();
+        for query in ["alpha", "alpha zeta", "epsilon zeta", "beta gamma delta zeta"] {
+            for limit in [1, 10, 100] {
+                let results = bm25.search(&rtxn, query, limit).unwrap();
+
+                // a required clause takes the exhaustive path and scores every match
+                let exhaustive = bm25
+                    .search(&rtxn, &format!("+({query})"), usize::MAX)
+                    .unwrap();
+                assert_eq!(results.len(), limit.min(exhaustive.len()));
+                for (result, expected) in results.iter().zip(&exhaustive) {
+                    assert!((result.1 - expected.1).abs() < 1e-4, "{query} {limit}");
+                }
+                let expected_scores = exhaustive.iter().copied().collect::<HashMap<_, _>>();
+                for (doc_id, score) in &results {
+                    assert!((expected_scores[doc_id] - score).abs() < 1e-4);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_phrase_matches_past_first_positions() {
+        let (bm25, _temp_dir) = setup_bm25_config();
+        let mut wtxn = bm25.graph_env.write_txn().unwrap();
+        let doc = format!("{}database", "graph ".repeat(500));
+        bm25.insert_doc(&mut wtxn, 1u128, &doc).unwrap();
+
+        let (_, postings) = bm25.collection("").postings(&wtxn, "graph").unwrap();
+        assert_eq!(postings[0].term_frequency, 500);
+        assert_eq!(postings[0].positions.len(), 500);
+        assert_eq!(postings[0].doc_length, 501);
+        wtxn.commit().unwrap();
+
+        // the only occurrence followed by `database` is the last one
+        assert_eq!(search_ids(&bm25, "\"graph database\""), vec![1]);
+
+        let mut wtxn = bm25.graph_env.write_txn().unwrap();
+        bm25.delete_doc(&mut wtxn, 1u128).unwrap();
+        let (_, postings) = bm25.collection("").postings(&wtxn, "graph").unwrap();
+        assert!(postings.is_empty());
+    }
+
+    #[test]
+    fn test_field_scoped_and_phrase_queries_on_searchable_fields() {
+        let (storage, _temp_dir) = setup_analyzed_storage(
+            "Doc",
+            &[
+                ("title", analyzer(AnalyzerKind::English)),
+                ("body", AnalyzerConfig::default()),
+            ],
+        );
+
+        let mut wtxn = storage.graph_env.write_txn().unwrap();
+        let mut add = |title: &str, body: &str| {
+            G::new_mut(Arc::clone(&storage), &mut wtxn)
+                .add_n("Doc", Some(props! { "title" => title, "body" => body }), None)
+                .collect_to::<Vec<_>>()[0]
+                .id()
+        };
+        let databases = add("Graph databases", "rust engine");
+        let tips = add("Rust tips", "graph of databases");
+        let stop_word = add("Graph of databases", "notes");
+        wtxn.commit().unwrap();
+
+        let ids = |query: &str| {
+            let mut ids = bm25_ids(&storage, "Doc", query);
+            ids.sort();
+            ids
+        };
+        let sorted = |mut expected: Vec<u128>| {
+            expected.sort();
+            expected
+        };
+
+        assert_eq!(ids("rust"), sorted(vec![databases, tips]));
+        assert_eq!(ids("title:rust"), vec![tips]);
+        assert_eq!(ids("body:rust"), vec![databases]);
+        assert_eq!(ids("rust -title:tips"), vec![databases]);
+        // the stop word removed from the title leaves a gap, and the body keeps "of"
+        assert_eq!(ids("\"graph databases\""), vec![databases]);
+        assert_eq!(ids("title:\"graph database\""), vec![databases]);
+        assert_eq!(ids("body:\"graph of databases\""), vec![tips]);
+        assert_eq!(ids("title:graph"), sorted(vec![databases, stop_word]));
+    }
+
+    #[test]
+    fn test_scoring_config_display_and_parse() {
+        let parsed: BM25IndexConfig = sonic_rs::from_str(
+            r#"{"label": "Doc", "fields": ["title", "body"], "scoring": {"k1": 1.5, "variant": "bm25f", "field_weights": {"title": 3.0}}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            parsed.scoring,
+            ScoringConfig {
+                k1: 1.5,
+                b: 0.75,
+                variant: ScoringVariant::Bm25F,
+                delta: 1.0,
+                field_weights: [("title".to_string(), 3.0)].into_iter().collect(),
+            }
+        );
+        assert_eq!(
+            parsed.scoring.to_string(),
+            "ScoringConfig { k1: 1.5, b: 0.75, variant: ScoringVariant::Bm25F, delta: 1.0, \
+             field_weights: [(\"title\".to_string(), 3.0)].into_iter().collect() }"
+        );
+        assert_eq!(parsed.scoring.weights(&parsed.fields), vec![3.0, 1.0]);
+
+        let plus: ScoringConfig = sonic_rs::from_str(r#"{"variant": "bm25+", "delta": 0.5}"#).unwrap();
+        assert_eq!(plus.variant, ScoringVariant::Bm25Plus);
+        assert_eq!((plus.k1, plus.b, plus.delta), (1.2, 0.75, 0.5));
+        assert!(ScoringConfig::default().is_default());
+    }
+
+    #[test]
+    fn test_scoring_changes_apply_without_reindexing() {
+        let index = |scoring: ScoringConfig| Config {
+            bm25_indices: Some(vec![BM25IndexConfig {
+                label: "Doc".to_string(),
+                fields: vec!["title".to_string(), "body".to_string()],
+                analyzers: Default::default(),
+                scoring,
+            }]),
+            ..Config::default()
+        };
+        let (storage, temp_dir) = open_storage(index(ScoringConfig::default()), tempdir().unwrap());
+
+        let mut wtxn = storage.graph_env.write_txn().unwrap();
+        let mut add = |title: &str, body: &str| {
+            G::new_mut(Arc::clone(&storage), &mut wtxn)
+                .add_n("Doc", Some(props! { "title" => title, "body" => body }), None)
+                .collect_to::<Vec<_>>()[0]
+                .id()
+        };
+        let in_title = add("rust", "graph graph graph graph");
+        let in_body = add("graph", "rust rust rust rust");
+        add("other", "unrelated words here");
+        wtxn.commit().unwrap();
+
+        let scores = |storage: &HelixGraphStorage, query: &str| {
+            let rtxn = storage.graph_env.read_txn().unwrap();
+            storage
+                .bm25
+                .as_ref()
+                .unwrap()
+                .collection("Doc")
+                .search(&rtxn, query, 10)
+                .unwrap()
+        };
+        let bm25 = scores(&storage, "rust");
+        assert_eq!(bm25[0].0, in_body);
+
+        // the title outweighs the body once reopened with BM25F, without touching the index
+        drop(storage);
+        let weighted = ScoringConfig {
+            variant: ScoringVariant::Bm25F,
+            field_weights: [("title".to_string(), 10.0)].into_iter().collect(),
+            ..Default::default()
+        };
+        let (storage, temp_dir) = open_storage(index(weighted), temp_dir);
+        let bm25f = scores(&storage, "rust");
+        assert_eq!(bm25f[0].0, in_title);
+        // the top-k search bounds weighted frequencies like the exhaustive one scores them
+        let exhaustive = scores(&storage, "+(rust graph)");
+        for (doc_id, score) in scores(&storage, "rust graph") {
+            let expected = exhaustive.iter().find(|(id, _)| *id == doc_id).unwrap().1;
+            assert!((score - expected).abs() < 1e-4);
+        }
+
+        // BM25+ adds delta times the idf to every matched term
+        drop(storage);
+        let plus = ScoringConfig {
+            variant: ScoringVariant::Bm25Plus,
+            delta: 1.0,
+            ..Default::default()
+        };
+        let (storage, _temp_dir) = open_storage(index(plus), temp_dir);
+        let bm25_plus = scores(&storage, "rust");
+        let idf = ((3.0f64 - 2.0 + 0.5) / (2.0 + 0.5) + 1.0).ln() as f32;
+        for (doc_id, score) in &bm25_plus {
+            let (_, plain) = bm25.iter().find(|(id, _)| id == doc_id).unwrap();
+            assert!((score - plain - idf).abs() < 1e-4);
+        }
+
+        let rtxn = storage.graph_env.read_txn().unwrap();
+        let stats: sonic_rs::Value =
+            sonic_rs::from_str(&storage.get_db_stats_json(&rtxn).unwrap()).unwrap();
+        let index = &stats["bm25_indices"][0];
+        assert_eq!(index["label"].as_str(), Some("Doc"));
+        assert_eq!(index["scoring"]["variant"].as_str(), Some("bm25+"));
+        assert_eq!(index["scoring"]["k1"].as_f64(), Some(1.2));
+        assert_eq!(index["scoring"]["delta"].as_f64(), Some(1.0));
+        assert_eq!(index["total_docs"].as_u64(), Some(3));
+    }
+
+    #[test]
+    fn test_existing_items_are_indexed_when_bm25_is_enabled() {
+        let index = |bm25: bool| Config {
+            bm25: Some(bm25),
+            bm25_indices: Some(vec![
+                BM25IndexConfig {
+                    label: "Doc".to_string(),
+                    fields: vec!["title".to_string()],
+                    analyzers: Default::default(),
+                    scoring: Default::default(),
+                },
+                BM25IndexConfig {
+                    label: "Cites".to_string(),
+                    fields: vec!["note".to_string()],
+                    analyzers: Default::default(),
+                    scoring: Default::default(),
+                },
+            ]),
+            ..Config::default()
+        };
+        let (storage, temp_dir) = open_storage(index(false), tempdir().unwrap());
+        assert!(storage.bm25.is_none());
+
+        let mut wtxn = storage.graph_env.write_txn().unwrap();
+        let mut add = |title: &str| {
+            G::new_mut(Arc::clone(&storage), &mut wtxn)
+                .add_n("Doc", Some(props! { "title" => title }), None)
+                .collect_to::<Vec<_>>()[0]
+                .id()
+        };
+        let rust = add("rust graphs");
+        let python = add("python graphs");
+        let edge = G::new_mut(Arc::clone(&storage), &mut wtxn)
+            .add_e(
+                "Cites",
+                Some(props! { "note" => "background reading" }),
+                rust,
+                python,
+                false,
+                EdgeType::Node,
+            )
+            .collect_to::<Vec<_>>()[0]
+            .id();
+        wtxn.commit().unwrap();
+
+        drop(storage);
+        let (storage, _temp_dir) = open_storage(index(true), temp_dir);
+        assert_eq!(bm25_ids(&storage, "Doc", "rust"), vec![rust]);
+        assert_eq!(bm25_ids(&storage, "Doc", "graphs").len(), 2);
+        assert_eq!(bm25_ids(&storage, "Cites", "reading"), vec![edge]);
+
+        let rtxn = storage.graph_env.read_txn().unwrap();
+        let metadata = storage
+            .bm25
+            .as_ref()
+            .unwrap()
+            .collection("Doc")
+            .metadata(&rtxn)
+            .unwrap()
+            .unwrap();
+        assert_eq!(metadata.total_docs, 2);
+        assert_eq!(metadata.avgdl, 2.0);
+    }
+
+    #[test]
+    fn test_reindex_rebuilds_indices_in_batches() {
+        let index = |analyzer: AnalyzerKind| Config {
+            bm25_indices: Some(vec![BM25IndexConfig {
+                label: "Doc".to_string(),
+                fields: vec!["title".to_string()],
+                analyzers: [(
+                    "title".to_string(),
+                    AnalyzerConfig {
+                        analyzer,
+                        ..Default::default()
+                    },
+                )]
+                .into_iter()
+                .collect(),
+                scoring: Default::default(),
+            }]),
+            ..Config::default()
+        };
+        let (storage, temp_dir) = open_storage(index(AnalyzerKind::Standard), tempdir().unwrap());
+
+        let mut wtxn = storage.graph_env.write_txn().unwrap();
+        let docs = (0..5)
+            .map(|i| {
+                G::new_mut(Arc::clone(&storage), &mut wtxn)
+                    .add_n(
+                        "Doc",
+                        Some(props! { "title" => format!("running graphs {i}") }),
+                        None,
+                    )
+                    .collect_to::<Vec<_>>()[0]
+                    .id()
+            })
+            .collect::<Vec<_>>();
+        G::new_mut(Arc::clone(&storage), &mut wtxn)
+            .add_n("Person", Some(props! { "name" => "running" }), None)
+            .collect_to::<Vec<_>>();
+        wtxn.commit().unwrap();
+        assert!(bm25_ids(&storage, "Doc", "run").is_empty());
+
+        // the stemmed terms are only found once the stored docs are indexed again
+        drop(storage);
+        let (storage, _temp_dir) = open_storage(index(AnalyzerKind::English), temp_dir);
+        assert!(bm25_ids(&storage, "Doc", "run").is_empty());
+
+        let stats = storage.reindex_bm25(2).unwrap();
+        assert_eq!(stats.nodes, 6);
+        assert_eq!(stats.edges, 0);
+        assert_eq!(stats.vectors, 0);
+        assert_eq!(stats.indexed, 5);
+
+        let mut found = bm25_ids(&storage, "Doc", "run");
+        found.sort();
+        let mut expected = docs.clone();
+        expected.sort();
+        assert_eq!(found, expected);
+        assert_eq!(bm25_ids(&storage, "Doc", "3"), vec![docs[3]]);
+
+        // reindexing again leaves the same statistics
+        let rtxn = storage.graph_env.read_txn().unwrap();
+        let collection = storage.bm25.as_ref().unwrap().collection("Doc");
+        let before = collection.metadata(&rtxn).unwrap().unwrap();
+        drop(rtxn);
+        assert_eq!(storage.reindex_bm25(4).unwrap(), stats);
+        let rtxn = storage.graph_env.read_txn().unwrap();
+        let after = collection.metadata(&rtxn).unwrap().unwrap();
+        assert_eq!(after.total_docs, 5);
+        assert_eq!(before.total_docs, after.total_docs);
+        assert_eq!(before.avgdl, after.avgdl);
+    }
+}
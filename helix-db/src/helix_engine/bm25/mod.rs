@@ -1,4 +1,9 @@
+pub mod analyzer;
 pub mod bm25;
+pub mod postings;
+pub mod query;
+pub mod scoring;
+pub mod stemmer;
 
 #[cfg(test)]
-pub mod bm25_tests;
\ No newline at end of file
+pub mod bm25_tests;
@@ -0,0 +1,308 @@
+//! The on-disk layout of posting lists.
+//!
+//! A term's postings are split into blocks of up to [`BLOCK_SIZE`] documents in doc id order, each
+//! stored under `term key | 0xFF | base doc id` where the base is at most the block's first doc id
+//! and above the last doc id of the block before it. `0xFF` never occurs in UTF-8, so the blocks of
+//! one term can't be confused with those of a longer term or another label.
+//!
+//! A block starts with a header holding its size, last doc id, largest term frequency and shortest
+//! document, which bound the score of any document in it without decoding the postings. Postings
+//! follow as varints, doc ids as deltas from the one before, then the term frequency, the document
+//! length and the positions, also delta encoded.
+
+use crate::helix_engine::{bm25::bm25::PostingListEntry, types::GraphError};
+
+use heed3::{Database, RoTxn, types::Bytes};
+use std::ops::Bound;
+
+pub const BLOCK_SIZE: usize = 128;
+const BLOCK_SEPARATOR: u8 = 0xFF;
+
+/// The key prefix all blocks of a term share
+pub fn block_prefix(term_key: &[u8]) -> Vec<u8> {
+    let mut prefix = Vec::with_capacity(term_key.len() + 1 + 16);
+    prefix.extend_from_slice(term_key);
+    prefix.push(BLOCK_SEPARATOR);
+    prefix
+}
+
+pub fn block_key(prefix: &[u8], base: u128) -> Vec<u8> {
+    let mut key = prefix.to_vec();
+    key.extend_from_slice(&base.to_be_bytes());
+    key
+}
+
+/// The base doc id of a block key, `None` for keys that aren't `prefix` and a doc id
+fn block_base(prefix: &[u8], key: &[u8]) -> Option<u128> {
+    let id = key.strip_prefix(prefix)?;
+    Some(u128::from_be_bytes(id.try_into().ok()?))
+}
+
+/// Splits a block key into its term key and base doc id
+pub fn split_block_key(key: &[u8]) -> Option<(&[u8], u128)> {
+    let split = key.len().checked_sub(1 + 16)?;
+    let (term_key, rest) = key.split_at(split);
+    if rest[0] != BLOCK_SEPARATOR {
+        return None;
+    }
+    Some((term_key, u128::from_be_bytes(rest[1..].try_into().ok()?)))
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u128) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_varint(bytes: &[u8], pos: &mut usize) -> Result<u128, GraphError> {
+    let mut value = 0u128;
+    let mut shift = 0;
+    loop {
+        let byte = *bytes
+            .get(*pos)
+            .ok_or_else(|| GraphError::New("truncated posting block".to_string()))?;
+        *pos += 1;
+        if shift >= 128 {
+            return Err(GraphError::New(
+                "invalid varint in posting block".to_string(),
+            ));
+        }
+        value |= ((byte & 0x7F) as u128) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+        shift += 7;
+    }
+}
+
+fn read_u32(bytes: &[u8], pos: &mut usize) -> Result<u32, GraphError> {
+    u32::try_from(read_varint(bytes, pos)?)
+        .map_err(|_| GraphError::New("invalid varint in posting block".to_string()))
+}
+
+/// What a block holds, readable without decoding its postings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockHeader {
+    pub len: usize,
+    pub last_doc: u128,
+    pub max_term_frequency: u32,
+    pub min_doc_length: u32,
+}
+
+impl BlockHeader {
+    /// Reads the header, returning it and where the postings start
+    pub fn decode(base: u128, bytes: &[u8]) -> Result<(Self, usize), GraphError> {
+        let mut pos = 0;
+        let len = read_varint(bytes, &mut pos)? as usize;
+        let last_doc = base + read_varint(bytes, &mut pos)?;
+        let max_term_frequency = read_u32(bytes, &mut pos)?;
+        let min_doc_length = read_u32(bytes, &mut pos)?;
+        let header = BlockHeader {
+            len,
+            last_doc,
+            max_term_frequency,
+            min_doc_length,
+        };
+        Ok((header, pos))
+    }
+}
+
+/// Encodes postings sorted by doc id, all at least `base`
+pub fn encode_block(base: u128, postings: &[PostingListEntry]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(8 + postings.len() * 8);
+    let last_doc = postings.last().map_or(base, |p| p.doc_id);
+    write_varint(&mut out, postings.len() as u128);
+    write_varint(&mut out, last_doc - base);
+    write_varint(
+        &mut out,
+        postings.iter().map(|p| p.term_frequency).max().unwrap_or(0) as u128,
+    );
+    write_varint(
+        &mut out,
+        postings.iter().map(|p| p.doc_length).min().unwrap_or(0) as u128,
+    );
+
+    let mut previous = base;
+    for posting in postings {
+        write_varint(&mut out, posting.doc_id - previous);
+        previous = posting.doc_id;
+        write_varint(&mut out, posting.term_frequency as u128);
+        write_varint(&mut out, posting.doc_length as u128);
+        write_varint(&mut out, posting.positions.len() as u128);
+        let mut previous_position = 0;
+        for &position in &posting.positions {
+            write_varint(&mut out, (position - previous_position) as u128);
+            previous_position = position;
+        }
+    }
+    out
+}
+
+pub fn decode_block(base: u128, bytes: &[u8]) -> Result<Vec<PostingListEntry>, GraphError> {
+    let (header, mut pos) = BlockHeader::decode(base, bytes)?;
+    let mut postings = Vec::with_capacity(header.len);
+    let mut previous = base;
+    for _ in 0..header.len {
+        let doc_id = previous + read_varint(bytes, &mut pos)?;
+        previous = doc_id;
+        let term_frequency = read_u32(bytes, &mut pos)?;
+        let doc_length = read_u32(bytes, &mut pos)?;
+        let position_count = read_varint(bytes, &mut pos)? as usize;
+        let mut positions = Vec::with_capacity(position_count);
+        let mut position = 0;
+        for _ in 0..position_count {
+            position += read_u32(bytes, &mut pos)?;
+            positions.push(position);
+        }
+        postings.push(PostingListEntry {
+            doc_id,
+            term_frequency,
+            positions,
+            doc_length,
+        });
+    }
+    Ok(postings)
+}
+
+/// The block a document belongs in, the last one whose base is not above it, or the term's first
+/// block when the document comes before all of them
+pub fn find_block(
+    db: &Database<Bytes, Bytes>,
+    txn: &RoTxn,
+    prefix: &[u8],
+    doc_id: u128,
+) -> Result<Option<(u128, Vec<PostingListEntry>)>, GraphError> {
+    let start = block_key(prefix, 0);
+    let end = block_key(prefix, doc_id);
+    let range = (
+        Bound::Included(start.as_slice()),
+        Bound::Included(end.as_slice()),
+    );
+    let found = match db.rev_range(txn, &range)?.next().transpose()? {
+        Some(block) => Some(block),
+        None => db.prefix_iter(txn, prefix)?.next().transpose()?,
+    };
+    match found.and_then(|(key, bytes)| Some((block_base(prefix, key)?, bytes))) {
+        Some((base, bytes)) => Ok(Some((base, decode_block(base, bytes)?))),
+        None => Ok(None),
+    }
+}
+
+/// Every posting of a term in doc id order
+pub fn read_postings(
+    db: &Database<Bytes, Bytes>,
+    txn: &RoTxn,
+    term_key: &[u8],
+) -> Result<Vec<PostingListEntry>, GraphError> {
+    let prefix = block_prefix(term_key);
+    let mut postings = Vec::new();
+    for result in db.prefix_iter(txn, &prefix)? {
+        let (key, bytes) = result?;
+        if let Some(base) = block_base(&prefix, key) {
+            postings.extend(decode_block(base, bytes)?);
+        }
+    }
+    Ok(postings)
+}
+
+/// Walks the postings of a term in doc id order, skipping whole blocks by their headers
+pub struct PostingCursor<'t> {
+    blocks: Vec<(u128, BlockHeader, &'t [u8])>,
+    block: usize,
+    postings: Vec<PostingListEntry>,
+    index: usize,
+    decoded: Option<usize>,
+}
+
+impl<'t> PostingCursor<'t> {
+    pub fn new(
+        db: &Database<Bytes, Bytes>,
+        txn: &'t RoTxn,
+        term_key: &[u8],
+    ) -> Result<Self, GraphError> {
+        let prefix = block_prefix(term_key);
+        let mut blocks = Vec::new();
+        for result in db.prefix_iter(txn, &prefix)? {
+            let (key, bytes) = result?;
+            if let Some(base) = block_base(&prefix, key) {
+                let (header, _) = BlockHeader::decode(base, bytes)?;
+                blocks.push((base, header, bytes));
+            }
+        }
+        let mut cursor = PostingCursor {
+            blocks,
+            block: 0,
+            postings: Vec::new(),
+            index: 0,
+            decoded: None,
+        };
+        cursor.decode_current()?;
+        Ok(cursor)
+    }
+
+    /// The headers of every block of the term
+    pub fn headers(&self) -> impl Iterator<Item = &BlockHeader> {
+        self.blocks.iter().map(|(_, header, _)| header)
+    }
+
+    /// The header of the block the cursor is in, `None` once exhausted
+    pub fn block_header(&self) -> Option<&BlockHeader> {
+        self.blocks.get(self.block).map(|(_, header, _)| header)
+    }
+
+    fn decode_current(&mut self) -> Result<(), GraphError> {
+        if self.decoded == Some(self.block) {
+            return Ok(());
+        }
+        self.postings = match self.blocks.get(self.block) {
+            Some((base, _, bytes)) => decode_block(*base, bytes)?,
+            None => Vec::new(),
+        };
+        self.index = 0;
+        self.decoded = Some(self.block);
+        Ok(())
+    }
+
+    /// The current posting, `None` once exhausted
+    pub fn posting(&self) -> Option<&PostingListEntry> {
+        self.postings.get(self.index)
+    }
+
+    /// The current doc id, `u128::MAX` once exhausted
+    pub fn doc(&self) -> u128 {
+        self.posting().map_or(u128::MAX, |p| p.doc_id)
+    }
+
+    pub fn advance(&mut self) -> Result<(), GraphError> {
+        self.index += 1;
+        if self.index >= self.postings.len() && self.block < self.blocks.len() {
+            self.block += 1;
+            self.decode_current()?;
+        }
+        Ok(())
+    }
+
+    /// Moves to the first posting at or after `target`
+    pub fn seek(&mut self, target: u128) -> Result<(), GraphError> {
+        if self.doc() >= target {
+            return Ok(());
+        }
+        let skipped = self.blocks[self.block..]
+            .iter()
+            .take_while(|(_, header, _)| header.last_doc < target)
+            .count();
+        if skipped > 0 {
+            self.block += skipped;
+            self.decode_current()?;
+        }
+        self.index += self.postings[self.index.min(self.postings.len())..]
+            .partition_point(|p| p.doc_id < target);
+        if self.index >= self.postings.len() && self.block < self.blocks.len() {
+            self.block += 1;
+            self.decode_current()?;
+        }
+        Ok(())
+    }
+}
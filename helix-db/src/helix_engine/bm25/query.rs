@@ -0,0 +1,147 @@
+//! The query syntax of `SearchBM25`.
+//!
+//! - `rust graph` matches either term, scoring documents with both higher
+//! - `"graph database"` matches the exact phrase
+//! - `+rust` requires a term, `-java` excludes documents containing it
+//! - `(rust OR go)` groups clauses, so `+(rust OR go)` requires one of them
+//! - `title:rust` and `title:"graph database"` only match in the `title` field
+//!
+//! Parsing never fails: unbalanced quotes and parentheses are closed at the end of the query,
+//! and `name:` is only a field scope when `name` is a searchable field of the label.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Occur {
+    Should,
+    Must,
+    MustNot,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryNode {
+    Term { field: Option<String>, text: String },
+    Phrase { field: Option<String>, text: String },
+    Group(Vec<QueryClause>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryClause {
+    pub occur: Occur,
+    pub node: QueryNode,
+}
+
+/// Parses a query against the searchable fields of a label
+pub fn parse_query(query: &str, fields: &[String]) -> Vec<QueryClause> {
+    let chars = query.chars().collect::<Vec<_>>();
+    let mut parser = QueryParser {
+        chars: &chars,
+        pos: 0,
+        fields,
+    };
+    parser.clauses(false)
+}
+
+struct QueryParser<'a> {
+    chars: &'a [char],
+    pos: usize,
+    fields: &'a [String],
+}
+
+impl QueryParser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn clauses(&mut self, in_group: bool) -> Vec<QueryClause> {
+        let mut clauses = Vec::new();
+        while let Some(c) = self.peek() {
+            match c {
+                c if c.is_whitespace() => self.pos += 1,
+                ')' if in_group => {
+                    self.pos += 1;
+                    break;
+                }
+                ')' => self.pos += 1,
+                _ => clauses.extend(self.clause()),
+            }
+        }
+        clauses
+    }
+
+    fn clause(&mut self) -> Option<QueryClause> {
+        let occur = match self.peek() {
+            Some('+') => Occur::Must,
+            Some('-') => Occur::MustNot,
+            _ => Occur::Should,
+        };
+        if occur != Occur::Should {
+            self.pos += 1;
+        }
+
+        let node = match self.peek() {
+            Some('"') => {
+                self.pos += 1;
+                QueryNode::Phrase {
+                    field: None,
+                    text: self.phrase(),
+                }
+            }
+            Some('(') => {
+                self.pos += 1;
+                QueryNode::Group(self.clauses(true))
+            }
+            Some(c) if !c.is_whitespace() && c != ')' => {
+                let word = self.word();
+                if word == "OR" && occur == Occur::Should {
+                    return None;
+                }
+                match word.split_once(':') {
+                    Some((field, rest)) if self.fields.iter().any(|f| f == field) => {
+                        match (rest.is_empty(), self.peek()) {
+                            (true, Some('"')) => {
+                                self.pos += 1;
+                                QueryNode::Phrase {
+                                    field: Some(field.to_string()),
+                                    text: self.phrase(),
+                                }
+                            }
+                            _ => QueryNode::Term {
+                                field: Some(field.to_string()),
+                                text: rest.to_string(),
+                            },
+                        }
+                    }
+                    _ => QueryNode::Term {
+                        field: None,
+                        text: word,
+                    },
+                }
+            }
+            _ => return None,
+        };
+        Some(QueryClause { occur, node })
+    }
+
+    /// Reads up to the closing quote, or the end of the query
+    fn phrase(&mut self) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c != '"') {
+            self.pos += 1;
+        }
+        let text = self.chars[start..self.pos].iter().collect();
+        if self.peek().is_some() {
+            self.pos += 1;
+        }
+        text
+    }
+
+    fn word(&mut self) -> String {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| !c.is_whitespace() && !matches!(c, '(' | ')' | '"'))
+        {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+}
@@ -0,0 +1,130 @@
+//! How the BM25 index of a label scores the terms a document matches.
+//!
+//! The parameters only apply at query time, so they can be changed in `config.hx.json` without
+//! reindexing anything.
+
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt};
+
+pub const DEFAULT_K1: f64 = 1.2;
+pub const DEFAULT_B: f64 = 0.75;
+pub const DEFAULT_DELTA: f64 = 1.0;
+
+fn default_k1() -> f64 {
+    DEFAULT_K1
+}
+
+fn default_b() -> f64 {
+    DEFAULT_B
+}
+
+fn default_delta() -> f64 {
+    DEFAULT_DELTA
+}
+
+/// The ranking functions an index can score with
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ScoringVariant {
+    #[default]
+    Bm25,
+    /// Adds `delta` to the frequency part of every matched term, so a long document matching a
+    /// term never scores below one that doesn't match it
+    #[serde(rename = "bm25+")]
+    Bm25Plus,
+    /// Weighs the occurrences of a term by the field they are in before saturating them, so a
+    /// match in a heavier field counts for more
+    Bm25F,
+}
+
+impl fmt::Display for ScoringVariant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScoringVariant::Bm25 => write!(f, "ScoringVariant::Bm25"),
+            ScoringVariant::Bm25Plus => write!(f, "ScoringVariant::Bm25Plus"),
+            ScoringVariant::Bm25F => write!(f, "ScoringVariant::Bm25F"),
+        }
+    }
+}
+
+/// The scoring of a label's index, set per label in `config.hx.json`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ScoringConfig {
+    /// Controls term frequency saturation
+    #[serde(default = "default_k1")]
+    pub k1: f64,
+    /// Controls document length normalization
+    #[serde(default = "default_b")]
+    pub b: f64,
+    #[serde(default)]
+    pub variant: ScoringVariant,
+    /// What BM25+ adds to each matched term
+    #[serde(default = "default_delta")]
+    pub delta: f64,
+    /// The weight of each field for BM25F, fields without one weigh 1
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub field_weights: BTreeMap<String, f64>,
+}
+
+impl Default for ScoringConfig {
+    fn default() -> Self {
+        ScoringConfig {
+            k1: DEFAULT_K1,
+            b: DEFAULT_B,
+            variant: ScoringVariant::default(),
+            delta: DEFAULT_DELTA,
+            field_weights: BTreeMap::new(),
+        }
+    }
+}
+
+impl ScoringConfig {
+    pub fn is_default(&self) -> bool {
+        *self == ScoringConfig::default()
+    }
+
+    /// The saturated, length normalized frequency of a term in a document, which the term's idf
+    /// is multiplied by
+    pub fn frequency_score(&self, tf: f64, doc_len: f64, avgdl: f64) -> f64 {
+        let (k1, b) = (self.k1, self.b);
+        let saturated = (tf * (k1 + 1.0)) / (tf + k1 * (1.0 - b + b * (doc_len.abs() / avgdl)));
+        match self.variant {
+            ScoringVariant::Bm25Plus if tf > 0.0 => saturated + self.delta,
+            _ => saturated,
+        }
+    }
+
+    /// The weight of each of the given fields, all 1 unless scoring with BM25F
+    pub fn weights(&self, fields: &[String]) -> Vec<f64> {
+        fields
+            .iter()
+            .map(|field| match self.variant {
+                ScoringVariant::Bm25F => self.field_weights.get(field).copied().unwrap_or(1.0),
+                _ => 1.0,
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for ScoringConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ScoringConfig {{ k1: {:?}, b: {:?}, variant: {}, delta: {:?}, field_weights: ",
+            self.k1, self.b, self.variant, self.delta
+        )?;
+        if self.field_weights.is_empty() {
+            write!(f, "Default::default() }}")
+        } else {
+            write!(
+                f,
+                "[{}].into_iter().collect() }}",
+                self.field_weights
+                    .iter()
+                    .map(|(field, weight)| format!("(\"{field}\".to_string(), {weight:?})"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        }
+    }
+}
@@ -0,0 +1,337 @@
+//! The Snowball English (Porter2) stemmer, see <https://snowballstem.org/algorithms/english/stemmer.html>.
+//!
+//! Words are expected lowercased and ASCII folded, anything containing other characters is
+//! returned unchanged.
+
+/// Words that are stemmed to a fixed form, or left alone, before the algorithm runs
+const EXCEPTIONS: &[(&str, &str)] = &[
+    ("skis", "ski"),
+    ("skies", "sky"),
+    ("dying", "die"),
+    ("lying", "lie"),
+    ("tying", "tie"),
+    ("idly", "idl"),
+    ("gently", "gentl"),
+    ("ugly", "ugli"),
+    ("early", "earli"),
+    ("only", "onli"),
+    ("singly", "singl"),
+    ("sky", "sky"),
+    ("news", "news"),
+    ("howe", "howe"),
+    ("atlas", "atlas"),
+    ("cosmos", "cosmos"),
+    ("bias", "bias"),
+    ("andes", "andes"),
+];
+
+/// Words left alone once step 1a has run
+const EXCEPTIONS_AFTER_1A: &[&str] = &[
+    "inning", "outing", "canning", "herring", "earring", "proceed", "exceed", "succeed",
+];
+
+const STEP_2: &[(&str, &str)] = &[
+    ("ization", "ize"),
+    ("ational", "ate"),
+    ("fulness", "ful"),
+    ("ousness", "ous"),
+    ("iveness", "ive"),
+    ("tional", "tion"),
+    ("biliti", "ble"),
+    ("lessli", "less"),
+    ("entli", "ent"),
+    ("ation", "ate"),
+    ("alism", "al"),
+    ("aliti", "al"),
+    ("ousli", "ous"),
+    ("iviti", "ive"),
+    ("fulli", "ful"),
+    ("enci", "ence"),
+    ("anci", "ance"),
+    ("abli", "able"),
+    ("izer", "ize"),
+    ("ator", "ate"),
+    ("alli", "al"),
+    ("bli", "ble"),
+    ("ogi", "og"),
+    ("li", ""),
+];
+
+const STEP_3: &[(&str, &str)] = &[
+    ("ational", "ate"),
+    ("tional", "tion"),
+    ("alize", "al"),
+    ("icate", "ic"),
+    ("iciti", "ic"),
+    ("ative", ""),
+    ("ical", "ic"),
+    ("ness", ""),
+    ("ful", ""),
+];
+
+const STEP_4: &[&str] = &[
+    "ement", "ance", "ence", "able", "ible", "ment", "ant", "ent", "ism", "ate", "iti", "ous",
+    "ive", "ize", "ion", "al", "er", "ic",
+];
+
+pub fn stem(word: &str) -> String {
+    if word.len() <= 2 || !word.bytes().all(|b| b.is_ascii_lowercase() || b == b'\'') {
+        return word.to_string();
+    }
+    if let Some((_, stemmed)) = EXCEPTIONS.iter().find(|(w, _)| *w == word) {
+        return stemmed.to_string();
+    }
+
+    let mut w = Word::new(word);
+    w.step_0();
+    w.step_1a();
+    if EXCEPTIONS_AFTER_1A
+        .iter()
+        .any(|e| e.as_bytes() == w.chars.as_slice())
+    {
+        return w.finish();
+    }
+    w.step_1b();
+    w.step_1c();
+    w.step_2();
+    w.step_3();
+    w.step_4();
+    w.step_5();
+    w.finish()
+}
+
+struct Word {
+    /// The word with `y`s that act as consonants marked as `Y`
+    chars: Vec<u8>,
+    r1: usize,
+    r2: usize,
+}
+
+fn is_vowel(c: u8) -> bool {
+    matches!(c, b'a' | b'e' | b'i' | b'o' | b'u' | b'y')
+}
+
+fn is_double(a: u8, b: u8) -> bool {
+    a == b
+        && matches!(
+            a,
+            b'b' | b'd' | b'f' | b'g' | b'm' | b'n' | b'p' | b'r' | b't'
+        )
+}
+
+fn is_li_ending(c: u8) -> bool {
+    matches!(
+        c,
+        b'c' | b'd' | b'e' | b'g' | b'h' | b'k' | b'm' | b'n' | b'r' | b't'
+    )
+}
+
+/// Whether a word ends in a short syllable, a vowel followed by a non-vowel other than `w`, `x`
+/// or `Y` and preceded by a non-vowel, or a vowel and a non-vowel that start the word
+fn ends_with_short_syllable(c: &[u8]) -> bool {
+    match c.len() {
+        0 | 1 => false,
+        2 => is_vowel(c[0]) && !is_vowel(c[1]),
+        n => {
+            !is_vowel(c[n - 3])
+                && is_vowel(c[n - 2])
+                && !is_vowel(c[n - 1])
+                && !matches!(c[n - 1], b'w' | b'x' | b'Y')
+        }
+    }
+}
+
+/// The start of the region after the first non-vowel following a vowel, from `start`
+fn region_after(chars: &[u8], start: usize) -> usize {
+    (start + 1..chars.len())
+        .find(|&i| !is_vowel(chars[i]) && is_vowel(chars[i - 1]))
+        .map_or(chars.len(), |i| i + 1)
+}
+
+impl Word {
+    fn new(word: &str) -> Self {
+        let mut chars = word.as_bytes().to_vec();
+        if chars[0] == b'\'' {
+            chars.remove(0);
+        }
+        for i in 0..chars.len() {
+            if chars[i] == b'y' && (i == 0 || is_vowel(chars[i - 1])) {
+                chars[i] = b'Y';
+            }
+        }
+
+        let r1 = ["gener", "commun", "arsen"]
+            .iter()
+            .find(|prefix| chars.starts_with(prefix.as_bytes()))
+            .map_or_else(|| region_after(&chars, 0), |prefix| prefix.len());
+        let r2 = region_after(&chars, r1);
+        Word { chars, r1, r2 }
+    }
+
+    fn ends_with(&self, suffix: &str) -> bool {
+        self.chars.ends_with(suffix.as_bytes())
+    }
+
+    /// Where the suffix starts, assuming the word ends with it
+    fn suffix_start(&self, suffix: &str) -> usize {
+        self.chars.len() - suffix.len()
+    }
+
+    fn replace_suffix(&mut self, suffix: &str, replacement: &str) {
+        self.chars.truncate(self.suffix_start(suffix));
+        self.chars.extend_from_slice(replacement.as_bytes());
+    }
+
+    fn longest_suffix<'s>(&self, suffixes: &[&'s str]) -> Option<&'s str> {
+        suffixes
+            .iter()
+            .filter(|s| self.ends_with(s))
+            .max_by_key(|s| s.len())
+            .copied()
+    }
+
+    fn has_vowel(&self, end: usize) -> bool {
+        self.chars[..end].iter().any(|&c| is_vowel(c))
+    }
+
+    fn is_short(&self) -> bool {
+        self.r1 >= self.chars.len() && ends_with_short_syllable(&self.chars)
+    }
+
+    fn step_0(&mut self) {
+        if let Some(suffix) = self.longest_suffix(&["'s'", "'s", "'"]) {
+            self.replace_suffix(suffix, "");
+        }
+    }
+
+    fn step_1a(&mut self) {
+        let Some(suffix) = self.longest_suffix(&["sses", "ied", "ies", "us", "ss", "s"]) else {
+            return;
+        };
+        match suffix {
+            "sses" => self.replace_suffix(suffix, "ss"),
+            "ied" | "ies" => {
+                let replacement = if self.suffix_start(suffix) > 1 {
+                    "i"
+                } else {
+                    "ie"
+                };
+                self.replace_suffix(suffix, replacement);
+            }
+            "s" => {
+                // a vowel somewhere before the letter preceding the s
+                let start = self.suffix_start(suffix);
+                if start >= 2 && self.has_vowel(start - 1) {
+                    self.replace_suffix(suffix, "");
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn step_1b(&mut self) {
+        let Some(suffix) = self.longest_suffix(&["eedly", "ingly", "edly", "eed", "ing", "ed"])
+        else {
+            return;
+        };
+        let start = self.suffix_start(suffix);
+        if suffix == "eed" || suffix == "eedly" {
+            if start >= self.r1 {
+                self.replace_suffix(suffix, "ee");
+            }
+            return;
+        }
+        if !self.has_vowel(start) {
+            return;
+        }
+
+        self.replace_suffix(suffix, "");
+        if self.ends_with("at") || self.ends_with("bl") || self.ends_with("iz") {
+            self.chars.push(b'e');
+        } else if self.chars.len() >= 2
+            && is_double(
+                self.chars[self.chars.len() - 2],
+                self.chars[self.chars.len() - 1],
+            )
+        {
+            self.chars.pop();
+        } else if self.is_short() {
+            self.chars.push(b'e');
+        }
+    }
+
+    fn step_1c(&mut self) {
+        let n = self.chars.len();
+        if n > 2 && matches!(self.chars[n - 1], b'y' | b'Y') && !is_vowel(self.chars[n - 2]) {
+            self.chars[n - 1] = b'i';
+        }
+    }
+
+    fn step_2(&mut self) {
+        let suffixes = STEP_2.iter().map(|(s, _)| *s).collect::<Vec<_>>();
+        let Some(suffix) = self.longest_suffix(&suffixes) else {
+            return;
+        };
+        let start = self.suffix_start(suffix);
+        if start < self.r1 {
+            return;
+        }
+        let preceding = start.checked_sub(1).map(|i| self.chars[i]);
+        match suffix {
+            "ogi" if preceding != Some(b'l') => {}
+            "li" if !preceding.is_some_and(is_li_ending) => {}
+            _ => {
+                let (_, replacement) = STEP_2.iter().find(|(s, _)| *s == suffix).unwrap();
+                self.replace_suffix(suffix, replacement);
+            }
+        }
+    }
+
+    fn step_3(&mut self) {
+        let suffixes = STEP_3.iter().map(|(s, _)| *s).collect::<Vec<_>>();
+        let Some(suffix) = self.longest_suffix(&suffixes) else {
+            return;
+        };
+        let start = self.suffix_start(suffix);
+        if start < self.r1 || (suffix == "ative" && start < self.r2) {
+            return;
+        }
+        let (_, replacement) = STEP_3.iter().find(|(s, _)| *s == suffix).unwrap();
+        self.replace_suffix(suffix, replacement);
+    }
+
+    fn step_4(&mut self) {
+        let Some(suffix) = self.longest_suffix(STEP_4) else {
+            return;
+        };
+        let start = self.suffix_start(suffix);
+        if start < self.r2 {
+            return;
+        }
+        if suffix == "ion" && !(start > 0 && matches!(self.chars[start - 1], b's' | b't')) {
+            return;
+        }
+        self.replace_suffix(suffix, "");
+    }
+
+    fn step_5(&mut self) {
+        let n = self.chars.len();
+        if self.ends_with("e") {
+            let start = n - 1;
+            if start >= self.r2
+                || (start >= self.r1 && !ends_with_short_syllable(&self.chars[..start]))
+            {
+                self.chars.pop();
+            }
+        } else if self.ends_with("l") && n > self.r2 && n >= 2 && self.chars[n - 2] == b'l' {
+            self.chars.pop();
+        }
+    }
+
+    fn finish(self) -> String {
+        self.chars
+            .into_iter()
+            .map(|c| if c == b'Y' { 'y' } else { c as char })
+            .collect()
+    }
+}
@@ -1,15 +1,38 @@
 use crate::{
-    helix_engine::types::GraphError,
-    helixc::analyzer::analyzer::{INTROSPECTION_DATA, SECONDARY_INDICES},
+    helix_engine::{
+        bm25::{analyzer::AnalyzerConfig, scoring::ScoringConfig},
+        types::GraphError,
+        vector_core::{vector::VectorPrecision, vector_distance::DistanceMetric},
+    },
+    helixc::analyzer::analyzer::{
+        BM25_INDICES, EDGE_SECONDARY_INDICES, INTROSPECTION_DATA, SECONDARY_INDICES,
+        UNIQUE_INDICES, VECTOR_TYPES,
+    },
 };
 use serde::{Deserialize, Serialize};
-use std::{fmt, path::PathBuf};
+use std::{collections::BTreeMap, fmt, path::PathBuf};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct VectorConfig {
     pub m: Option<usize>,
     pub ef_construction: Option<usize>,
     pub ef_search: Option<usize>,
+    /// Dimensions, distance metric and storage precision of each vector type declared in the schema
+    pub vector_types: Option<Vec<VectorTypeConfig>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct VectorTypeConfig {
+    pub label: String,
+    /// Vectors of another length are rejected on insert when set
+    pub dimensions: Option<usize>,
+    #[serde(default)]
+    pub metric: DistanceMetric,
+    #[serde(default)]
+    pub precision: VectorPrecision,
+    /// Keeps a full precision copy of compact vectors to rerank search candidates with
+    #[serde(default)]
+    pub rerank: bool,
 }
 
 impl Default for VectorConfig {
@@ -18,6 +41,69 @@ impl Default for VectorConfig {
             m: Some(16),
             ef_construction: Some(128),
             ef_search: Some(768),
+            vector_types: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BM25IndexConfig {
+    pub label: String,
+    /// String properties that are tokenized into the label's full-text index
+    pub fields: Vec<String>,
+    /// Analyzer of each field, fields without one use the standard analyzer.
+    ///
+    /// Changing a field's analyzer only affects items indexed afterwards.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub analyzers: BTreeMap<String, AnalyzerConfig>,
+    /// Parameters and variant of the ranking function, only used at query time
+    #[serde(default, skip_serializing_if = "ScoringConfig::is_default")]
+    pub scoring: ScoringConfig,
+}
+
+impl BM25IndexConfig {
+    /// Overrides the analyzers of the fields set in `other` and takes its scoring, used to apply
+    /// `config.hx.json` on top of the schema
+    fn with_settings_from(mut self, other: &BM25IndexConfig) -> Self {
+        for (field, analyzer) in &other.analyzers {
+            if self.fields.contains(field) {
+                self.analyzers.insert(field.clone(), analyzer.clone());
+            }
+        }
+        self.scoring = other.scoring.clone();
+        self
+    }
+}
+
+impl fmt::Display for BM25IndexConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "BM25IndexConfig {{ label: \"{}\".to_string(), fields: vec![{}], analyzers: ",
+            self.label,
+            self.fields
+                .iter()
+                .map(|f| format!("\"{f}\".to_string()"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )?;
+        if self.analyzers.is_empty() {
+            write!(f, "Default::default()")?;
+        } else {
+            write!(
+                f,
+                "[{}].into_iter().collect()",
+                self.analyzers
+                    .iter()
+                    .map(|(field, analyzer)| format!("(\"{field}\".to_string(), {analyzer})"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )?;
+        }
+        if self.scoring.is_default() {
+            write!(f, ", scoring: Default::default() }}")
+        } else {
+            write!(f, ", scoring: {} }}", self.scoring)
         }
     }
 }
@@ -25,6 +111,10 @@ impl Default for VectorConfig {
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct GraphConfig {
     pub secondary_indices: Option<Vec<String>>,
+    /// Secondary indices over edge properties, qualified by the edge label
+    pub edge_secondary_indices: Option<Vec<String>>,
+    /// Secondary indices that reject a second node with the same key
+    pub unique_indices: Option<Vec<String>>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -34,6 +124,8 @@ pub struct Config {
     pub db_max_size_gb: Option<usize>,
     pub mcp: Option<bool>,
     pub bm25: Option<bool>,
+    /// Labels with `SEARCHABLE` fields, each label gets its own BM25 index
+    pub bm25_indices: Option<Vec<BM25IndexConfig>>,
     pub schema: Option<String>,
     pub embedding_model: Option<String>,
     pub graphvis_node_label: Option<String>,
@@ -56,13 +148,17 @@ impl Config {
                 m: Some(m),
                 ef_construction: Some(ef_construction),
                 ef_search: Some(ef_search),
+                vector_types: None,
             }),
             graph_config: Some(GraphConfig {
                 secondary_indices: None,
+                edge_secondary_indices: None,
+                unique_indices: None,
             }),
             db_max_size_gb: Some(db_max_size_gb),
             mcp: Some(mcp),
             bm25: Some(bm25),
+            bm25_indices: None,
             schema,
             embedding_model,
             graphvis_node_label,
@@ -94,14 +190,18 @@ impl Config {
         "vector_config": {
             "m": 16,
             "ef_construction": 128,
-            "ef_search": 768
+            "ef_search": 768,
+            "vector_types": []
         },
         "graph_config": {
-            "secondary_indices": []
+            "secondary_indices": [],
+            "edge_secondary_indices": [],
+            "unique_indices": []
         },
         "db_max_size_gb": 10,
         "mcp": true,
         "bm25": true,
+        "bm25_indices": [],
         "embedding_model": "text-embedding-ada-002",
         "graphvis_node_label": ""
     }
@@ -133,6 +233,10 @@ impl Config {
         self.bm25.unwrap_or(true)
     }
 
+    pub fn get_bm25_indices(&self) -> Vec<BM25IndexConfig> {
+        self.bm25_indices.clone().unwrap_or_default()
+    }
+
     pub fn get_schema(&self) -> Option<String> {
         self.schema.clone()
     }
@@ -145,13 +249,17 @@ impl Default for Config {
                 m: Some(16),
                 ef_construction: Some(128),
                 ef_search: Some(768),
+                vector_types: None,
             }),
             graph_config: Some(GraphConfig {
                 secondary_indices: None,
+                edge_secondary_indices: None,
+                unique_indices: None,
             }),
             db_max_size_gb: Some(10),
             mcp: Some(true),
             bm25: Some(true),
+            bm25_indices: None,
             schema: None,
             embedding_model: Some("text-embedding-ada-002".to_string()),
             graphvis_node_label: None,
@@ -191,6 +299,26 @@ impl fmt::Display for Config {
                 .ef_search
                 .unwrap_or(768)
         )?;
+        writeln!(
+            f,
+            "vector_types: {},",
+            match VECTOR_TYPES.get() {
+                Some(types) => {
+                    format!(
+                        "Some(vec![{}])",
+                        types
+                            .iter()
+                            .map(|t| format!(
+                                "VectorTypeConfig {{ label: \"{}\".to_string(), dimensions: {:?}, metric: {}, precision: {}, rerank: {} }}",
+                                t.label, t.dimensions, t.metric, t.precision, t.rerank
+                            ))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                }
+                None => "None".to_string(),
+            }
+        )?;
         writeln!(f, "}}),")?;
         writeln!(f, "graph_config: Some(GraphConfig {{")?;
         writeln!(
@@ -203,6 +331,26 @@ impl fmt::Display for Config {
                 None => "None".to_string(),
             }
         )?;
+        writeln!(
+            f,
+            "edge_secondary_indices: {},",
+            match EDGE_SECONDARY_INDICES.get() {
+                Some(indices) => {
+                    format!("Some(vec![{}])", indices.iter().map(|i| format!("\"{i}\".to_string()")).collect::<Vec<_>>().join(", "))
+                }
+                None => "None".to_string(),
+            }
+        )?;
+        writeln!(
+            f,
+            "unique_indices: {},",
+            match UNIQUE_INDICES.get() {
+                Some(indices) => {
+                    format!("Some(vec![{}])", indices.iter().map(|i| format!("\"{i}\".to_string()")).collect::<Vec<_>>().join(", "))
+                }
+                None => "None".to_string(),
+            }
+        )?;
         writeln!(f, "}}),")?;
         writeln!(
             f,
@@ -211,6 +359,33 @@ impl fmt::Display for Config {
         )?;
         writeln!(f, "mcp: Some({}),", self.mcp.unwrap_or(true))?;
         writeln!(f, "bm25: Some({}),", self.bm25.unwrap_or(true))?;
+        writeln!(
+            f,
+            "bm25_indices: {},",
+            match BM25_INDICES.get() {
+                Some(indices) => {
+                    format!(
+                        "Some(vec![{}])",
+                        indices
+                            .iter()
+                            .map(|i| {
+                                let configured = self
+                                    .bm25_indices
+                                    .iter()
+                                    .flatten()
+                                    .find(|c| c.label == i.label);
+                                match configured {
+                                    Some(c) => i.clone().with_settings_from(c).to_string(),
+                                    None => i.to_string(),
+                                }
+                            })
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                }
+                None => "None".to_string(),
+            }
+        )?;
         if let Some(data) = INTROSPECTION_DATA.get()
             && let Ok(stringified) = sonic_rs::to_string_pretty(data)
         {
@@ -1,82 +1,101 @@
-/*
 use heed3::RoTxn;
 
 use super::super::tr_val::TraversalVal;
-use crate::helix_engine::{
-    bm25::bm25::{BM25, HybridSearch},
-    graph_core::traversal_iter::RoTraversalIterator,
-    storage_core::{storage_core::HelixGraphStorage, storage_methods::StorageMethods},
-    types::GraphError,
+use crate::{
+    helix_engine::{
+        bm25::bm25::{HybridFusion, HybridResult, HybridSearch},
+        graph_core::traversal_iter::RoTraversalIterator,
+        storage_core::storage_core::HelixGraphStorage,
+        types::GraphError,
+        vector_core::hnsw::HNSW,
+    },
+    protocol::value::Value,
 };
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
-pub struct HybridSearchBM25<'scope, 'inner> {
+pub struct SearchHybrid<'scope> {
     txn: &'scope RoTxn<'scope>,
-    iter: std::vec::IntoIter<(u128, f32)>,
+    iter: std::vec::IntoIter<HybridResult>,
     storage: Arc<HelixGraphStorage>,
-    label: &'inner str,
 }
 
-// implementing iterator for HybridSearchBM25
-impl<'scope, 'inner> Iterator for HybridSearchBM25<'scope, 'inner> {
+// implementing iterator for SearchHybrid
+impl<'scope> Iterator for SearchHybrid<'scope> {
     type Item = Result<TraversalVal, GraphError>;
 
     fn next(&mut self) -> Option<Self::Item> {
         let next = self.iter.next()?;
-        match self.storage.get_node(self.txn, &next.0) {
-            Ok(node) => {
-                if node.label == self.label {
-                    Some(Ok(TraversalVal::Node(node)))
-                } else {
-                    return None;
-                }
-            }
-            Err(e) => Some(Err(e)),
+        let mut vector = match self.storage.vectors.get_vector(self.txn, next.id, 0, true) {
+            Ok(vector) => vector,
+            Err(e) => return Some(Err(GraphError::from(e))),
+        };
+        vector.distance = next.vector_distance;
+
+        // the scores each search gave the result are returned alongside its properties
+        let properties = vector.properties.get_or_insert_with(HashMap::new);
+        properties.insert("hybrid_score".to_string(), Value::F32(next.score));
+        if let Some(score) = next.bm25_score {
+            properties.insert("bm25_score".to_string(), Value::F32(score));
+        }
+        if let Some(distance) = next.vector_distance {
+            properties.insert("vector_distance".to_string(), Value::F64(distance));
         }
+        Some(Ok(TraversalVal::Vector(vector)))
     }
 }
 
-pub trait HybridSearchBM25Adapter<'a>: Iterator<Item = Result<TraversalVal, GraphError>> {
-    fn hybrid_search_bm25(
+pub trait SearchHybridAdapter<'a>: Iterator<Item = Result<TraversalVal, GraphError>> {
+    /// Searches the BM25 and hnsw indices of a vector type and fuses the two rankings
+    fn search_hybrid<K>(
         self,
         label: &str,
         query: &str,
-        query_vector: Vec<f64>,
-        k: usize,
-    ) -> RoTraversalIterator<'a, impl Iterator<Item = Result<TraversalVal, GraphError>>>;
+        query_vector: &[f64],
+        k: K,
+        fusion: HybridFusion,
+    ) -> Result<
+        RoTraversalIterator<'a, impl Iterator<Item = Result<TraversalVal, GraphError>>>,
+        GraphError,
+    >
+    where
+        K: TryInto<usize>,
+        K::Error: std::fmt::Debug;
 }
 
-impl<'a, I: Iterator<Item = Result<TraversalVal, GraphError>>> HybridSearchBM25Adapter<'a>
+impl<'a, I: Iterator<Item = Result<TraversalVal, GraphError>>> SearchHybridAdapter<'a>
     for RoTraversalIterator<'a, I>
 {
-    fn hybrid_search_bm25(
+    fn search_hybrid<K>(
         self,
         label: &str,
         query: &str,
-        query_vector: &Vec<f64>,
-        k: usize,
-    ) -> RoTraversalIterator<'a, impl Iterator<Item = Result<TraversalVal, GraphError>>> {
-        // check bm25 enabled
+        query_vector: &[f64],
+        k: K,
+        fusion: HybridFusion,
+    ) -> Result<
+        RoTraversalIterator<'a, impl Iterator<Item = Result<TraversalVal, GraphError>>>,
+        GraphError,
+    >
+    where
+        K: TryInto<usize>,
+        K::Error: std::fmt::Debug,
+    {
+        let k = k
+            .try_into()
+            .map_err(|e| GraphError::from(format!("invalid k for hybrid search: {e:?}")))?;
+        let results =
+            self.storage
+                .hybrid_search(self.txn, label, query, query_vector, fusion, k)?;
 
-        let results = self.storage.hybrid_search(
-            query,
-            query_vector,
-            0.5,
-            k,
-        );
-
-        let iter = HybridSearchBM25 {
+        let iter = SearchHybrid {
             txn: self.txn,
-            iter: results,
+            iter: results.into_iter(),
             storage: Arc::clone(&self.storage),
-            label,
         };
-        RoTraversalIterator {
+        Ok(RoTraversalIterator {
             inner: iter,
             storage: self.storage,
             txn: self.txn,
-        }
+        })
     }
 }
-*/
-
@@ -9,27 +9,20 @@ use crate::helix_engine::{
 };
 use std::sync::Arc;
 
-pub struct SearchBM25<'scope, 'inner> {
+pub struct SearchBM25<'scope> {
     txn: &'scope RoTxn<'scope>,
     iter: std::vec::IntoIter<(u128, f32)>,
     storage: Arc<HelixGraphStorage>,
-    label: &'inner str,
 }
 
 // implementing iterator for SearchBM25
-impl<'scope, 'inner> Iterator for SearchBM25<'scope, 'inner> {
+impl<'scope> Iterator for SearchBM25<'scope> {
     type Item = Result<TraversalVal, GraphError>;
 
     fn next(&mut self) -> Option<Self::Item> {
         let next = self.iter.next()?;
         match self.storage.get_node(self.txn, &next.0) {
-            Ok(node) => {
-                if node.label == self.label {
-                    Some(Ok(TraversalVal::Node(node)))
-                } else {
-                    None
-                }
-            }
+            Ok(node) => Some(Ok(TraversalVal::Node(node))),
             Err(e) => Some(Err(e)),
         }
     }
@@ -59,8 +52,9 @@ impl<'a, I: Iterator<Item = Result<TraversalVal, GraphError>>> SearchBM25Adapter
         RoTraversalIterator<'a, impl Iterator<Item = Result<TraversalVal, GraphError>>>,
         GraphError,
     > {
+        // each label has its own index so only nodes of the label are scored
         let results = match self.storage.bm25.as_ref() {
-            Some(s) => s.search(self.txn, query, k)?,
+            Some(s) => s.collection(label).search(self.txn, query, k)?,
             None => return Err(GraphError::from("BM25 not enabled!")),
         };
 
@@ -68,7 +62,6 @@ impl<'a, I: Iterator<Item = Result<TraversalVal, GraphError>>> SearchBM25Adapter
             txn: self.txn,
             iter: results.into_iter(),
             storage: Arc::clone(&self.storage),
-            label,
         };
         Ok(RoTraversalIterator {
             inner: iter,
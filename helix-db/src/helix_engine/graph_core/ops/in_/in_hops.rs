@@ -0,0 +1,50 @@
+use crate::helix_engine::{
+    graph_core::{
+        ops::{out::out_hops::HopsIterator, tr_val::TraversalVal},
+        traversal_iter::RoTraversalIterator,
+    },
+    types::GraphError,
+};
+use std::sync::Arc;
+
+pub trait InHopsAdapter<'a>: Iterator<Item = Result<TraversalVal, GraphError>> {
+    /// Returns the nodes that reach the nodes of the traversal over `min_depth` to `max_depth`
+    /// incoming edges with the given label, both inclusive.
+    ///
+    /// Like [`out_hops`](crate::helix_engine::graph_core::ops::out::out_hops::OutHopsAdapter::out_hops),
+    /// each starting node is walked on its own and each node is returned once, if its shortest
+    /// path to any starting node is in range.
+    fn in_hops(
+        self,
+        edge_label: &'a str,
+        min_depth: usize,
+        max_depth: usize,
+    ) -> RoTraversalIterator<'a, impl Iterator<Item = Result<TraversalVal, GraphError>>>;
+}
+
+impl<'a, I: Iterator<Item = Result<TraversalVal, GraphError>>> InHopsAdapter<'a>
+    for RoTraversalIterator<'a, I>
+{
+    fn in_hops(
+        self,
+        edge_label: &'a str,
+        min_depth: usize,
+        max_depth: usize,
+    ) -> RoTraversalIterator<'a, impl Iterator<Item = Result<TraversalVal, GraphError>>> {
+        let storage = Arc::clone(&self.storage);
+        let edges_db = storage.in_edges_db;
+        RoTraversalIterator {
+            inner: HopsIterator::new(
+                self.inner,
+                Arc::clone(&storage),
+                self.txn,
+                edges_db,
+                edge_label,
+                min_depth,
+                max_depth,
+            ),
+            storage,
+            txn: self.txn,
+        }
+    }
+}
@@ -1,4 +1,5 @@
 pub mod in_;
 pub mod in_e;
+pub mod in_hops;
 pub mod to_n;
 pub mod to_v;
\ No newline at end of file
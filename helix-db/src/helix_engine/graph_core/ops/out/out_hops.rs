@@ -0,0 +1,192 @@
+use crate::{
+    helix_engine::{
+        graph_core::{
+            ops::tr_val::{Traversable, TraversalVal},
+            traversal_iter::RoTraversalIterator,
+        },
+        storage_core::{storage_core::HelixGraphStorage, storage_methods::StorageMethods},
+        types::GraphError,
+    },
+    utils::label_hash::hash_label,
+};
+use heed3::{Database, RoTxn, types::Bytes};
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::Arc,
+};
+
+/// Walks the edges of one label breadth first from the nodes of a traversal, over `out_edges_db`
+/// for `Out*` or `in_edges_db` for `In*`, and returns the nodes reached within a range of depths.
+///
+/// The walk from each starting node visits every node once, at the first depth it is reached at,
+/// so cycles end the walk and a node reachable over paths of different lengths is only returned
+/// for the shortest one. Starting nodes are walked one after the other, each with its own visited
+/// nodes, so a starting node is reached from the others like any node. A node reached from several
+/// starting nodes is returned once.
+pub struct HopsIterator<'a, I> {
+    pub iter: I,
+    pub storage: Arc<HelixGraphStorage>,
+    pub txn: &'a RoTxn<'a>,
+    /// `out_edges_db` or `in_edges_db`, both are keyed by node id and label hash
+    pub edges_db: Database<Bytes, Bytes>,
+    pub label_hash: [u8; 4],
+    pub min_depth: usize,
+    pub max_depth: usize,
+    /// Nodes visited by the walk from the current starting node
+    visited: HashSet<u128>,
+    /// Nodes returned by any walk
+    returned: HashSet<u128>,
+    /// Nodes still to expand and the depth they were reached at
+    queue: VecDeque<(u128, usize)>,
+    /// Items to return before expanding the next node
+    pending: VecDeque<Result<TraversalVal, GraphError>>,
+}
+
+impl<'a, I> HopsIterator<'a, I> {
+    pub fn new(
+        iter: I,
+        storage: Arc<HelixGraphStorage>,
+        txn: &'a RoTxn<'a>,
+        edges_db: Database<Bytes, Bytes>,
+        edge_label: &str,
+        min_depth: usize,
+        max_depth: usize,
+    ) -> Self {
+        Self {
+            iter,
+            storage,
+            txn,
+            edges_db,
+            label_hash: hash_label(edge_label, None),
+            min_depth,
+            max_depth,
+            visited: HashSet::new(),
+            returned: HashSet::new(),
+            queue: VecDeque::new(),
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl<'a, I: Iterator<Item = Result<TraversalVal, GraphError>>> HopsIterator<'a, I> {
+    /// Starts the walk from the next starting node, forgetting the nodes the previous walk visited.
+    /// Returns `false` once there are no starting nodes left.
+    fn start_next(&mut self) -> bool {
+        match self.iter.next() {
+            Some(Ok(item)) => {
+                self.visited.clear();
+                self.visited.insert(item.id());
+                self.queue.push_back((item.id(), 0));
+                if self.min_depth == 0 && self.returned.insert(item.id()) {
+                    self.pending.push_back(Ok(item));
+                }
+                true
+            }
+            Some(Err(e)) => {
+                self.pending.push_back(Err(e));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Visits the unvisited neighbours of a node, queuing the ones within the depth range
+    fn expand(&mut self, id: u128, depth: usize) -> Result<(), GraphError> {
+        let key = HelixGraphStorage::out_edge_key(&id, &self.label_hash);
+        let neighbours = match self
+            .edges_db
+            .lazily_decode_data()
+            .get_duplicates(self.txn, &key)?
+        {
+            Some(iter) => iter,
+            None => return Ok(()),
+        };
+        for result in neighbours {
+            let (_, data) = result?;
+            let data = data
+                .decode()
+                .map_err(|e| GraphError::DecodeError(e.to_string()))?;
+            let (_, node_id) = HelixGraphStorage::unpack_adj_edge_data(data)?;
+            if !self.visited.insert(node_id) {
+                continue;
+            }
+            if depth + 1 < self.max_depth {
+                self.queue.push_back((node_id, depth + 1));
+            }
+            if depth + 1 >= self.min_depth && self.returned.insert(node_id) {
+                self.pending.push_back(
+                    self.storage
+                        .get_node(self.txn, &node_id)
+                        .map(TraversalVal::Node),
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a, I: Iterator<Item = Result<TraversalVal, GraphError>>> Iterator for HopsIterator<'a, I> {
+    type Item = Result<TraversalVal, GraphError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.pending.pop_front() {
+                return Some(item);
+            }
+            let Some((id, depth)) = self.queue.pop_front() else {
+                if !self.start_next() {
+                    return None;
+                }
+                continue;
+            };
+            if depth < self.max_depth
+                && let Err(e) = self.expand(id, depth)
+            {
+                return Some(Err(e));
+            }
+        }
+    }
+}
+
+pub trait OutHopsAdapter<'a>: Iterator<Item = Result<TraversalVal, GraphError>> {
+    /// Returns the nodes reachable from the nodes of the traversal over `min_depth` to
+    /// `max_depth` outgoing edges with the given label, both inclusive.
+    ///
+    /// Each node is returned once, if its shortest path from any starting node is in range. A starting
+    /// node is returned from its own walk only when `min_depth` is `0`, but like any node when
+    /// another starting node reaches it. Nodes closer than `min_depth` are walked through but not
+    /// returned.
+    fn out_hops(
+        self,
+        edge_label: &'a str,
+        min_depth: usize,
+        max_depth: usize,
+    ) -> RoTraversalIterator<'a, impl Iterator<Item = Result<TraversalVal, GraphError>>>;
+}
+
+impl<'a, I: Iterator<Item = Result<TraversalVal, GraphError>>> OutHopsAdapter<'a>
+    for RoTraversalIterator<'a, I>
+{
+    fn out_hops(
+        self,
+        edge_label: &'a str,
+        min_depth: usize,
+        max_depth: usize,
+    ) -> RoTraversalIterator<'a, impl Iterator<Item = Result<TraversalVal, GraphError>>> {
+        let storage = Arc::clone(&self.storage);
+        let edges_db = storage.out_edges_db;
+        RoTraversalIterator {
+            inner: HopsIterator::new(
+                self.inner,
+                Arc::clone(&storage),
+                self.txn,
+                edges_db,
+                edge_label,
+                min_depth,
+                max_depth,
+            ),
+            storage,
+            txn: self.txn,
+        }
+    }
+}
@@ -4,7 +4,9 @@ use super::super::tr_val::TraversalVal;
 use crate::{
     helix_engine::{
         graph_core::traversal_iter::RwTraversalIterator,
-        storage_core::storage_core::HelixGraphStorage, types::GraphError, vector_core::hnsw::HNSW,
+        storage_core::{index_key::encode_item_index_key, storage_core::HelixGraphStorage},
+        types::GraphError,
+        vector_core::hnsw::HNSW,
     },
     protocol::value::Value,
     utils::{id::v6_uuid, items::Edge, label_hash::hash_label},
@@ -132,6 +134,14 @@ impl<'a, 'b, I: Iterator<Item = Result<TraversalVal, GraphError>>> AddEAdapter<'
 
         let label_hash = hash_label(edge.label.as_str(), None);
 
+        if let Err(e) = self
+            .storage
+            .edge_labels_db
+            .put(self.txn, &label_hash, &edge.id)
+        {
+            result = Err(GraphError::from(e));
+        }
+
         match self.storage.out_edges_db.put_with_flags(
             self.txn,
             PutFlags::APPEND_DUP,
@@ -162,6 +172,22 @@ impl<'a, 'b, I: Iterator<Item = Result<TraversalVal, GraphError>>> AddEAdapter<'
             }
         }
 
+        for (_, index, db) in self.storage.label_edge_secondary_indices(label) {
+            // edges missing an indexed field are left out of that index
+            let Ok(key) = encode_item_index_key(&edge, index) else {
+                continue;
+            };
+            if let Err(e) = db.put_with_flags(self.txn, PutFlags::APPEND_DUP, &key, &edge.id) {
+                result = Err(GraphError::from(e));
+            }
+        }
+
+        if let Some(bm25) = &self.storage.bm25
+            && let Err(e) = bm25.insert_item(self.txn, label, edge.id, edge.properties.as_ref())
+        {
+            result = Err(e);
+        }
+
         let result = match result {
             Ok(_) => Ok(TraversalVal::Edge(edge)),
             Err(_) => Err(GraphError::EdgeNotFound),
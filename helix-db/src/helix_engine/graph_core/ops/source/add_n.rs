@@ -1,12 +1,15 @@
 use super::super::tr_val::TraversalVal;
 use crate::{
     helix_engine::{
-        bm25::bm25::{BM25, BM25Flatten},
         graph_core::traversal_iter::RwTraversalIterator,
+        storage_core::{
+            index_key::{encode_item_index_key, label_index_name},
+            storage_core::HelixGraphStorage,
+        },
         types::GraphError,
     },
     protocol::value::Value,
-    utils::{filterable::Filterable, id::v6_uuid, items::Node},
+    utils::{id::v6_uuid, items::Node},
 };
 use heed3::PutFlags;
 
@@ -49,6 +52,26 @@ impl<'a, 'b, I: Iterator<Item = Result<TraversalVal, GraphError>>> AddNAdapter<'
         let secondary_indices = secondary_indices.unwrap_or(&[]).to_vec();
         let mut result: Result<TraversalVal, GraphError> = Ok(TraversalVal::Empty);
 
+        // unique indices are checked before anything is written so a duplicate leaves no trace
+        for index in secondary_indices.iter() {
+            let unique = match encode_item_index_key(&node, index) {
+                Ok(key) => self.storage.check_unique(
+                    self.txn,
+                    &label_index_name(label, index),
+                    &key,
+                    &node.id,
+                ),
+                Err(_) => Ok(()),
+            };
+            if let Err(e) = unique {
+                return RwTraversalIterator {
+                    inner: std::iter::once(Err(e)),
+                    storage: self.storage,
+                    txn: self.txn,
+                };
+            }
+        }
+
         match node.encode_node() {
             Ok(bytes) => {
                 if let Err(e) = self.storage.nodes_db.put_with_flags(
@@ -63,56 +86,47 @@ impl<'a, 'b, I: Iterator<Item = Result<TraversalVal, GraphError>>> AddNAdapter<'
             Err(e) => result = Err(e),
         }
 
+        if let Err(e) = self.storage.node_labels_db.put(
+            self.txn,
+            &HelixGraphStorage::label_key(&node.label),
+            &node.id,
+        ) {
+            result = Err(GraphError::from(e));
+        }
+
         for index in secondary_indices {
-            match self.storage.secondary_indices.get(index) {
-                Some(db) => {
-                    let key = match node.check_property(index) {
-                        Ok(value) => value,
+            match self.storage.secondary_index(label, index) {
+                Ok(db) => {
+                    let key = match encode_item_index_key(&node, index) {
+                        Ok(key) => key,
                         Err(e) => {
                             result = Err(e);
                             continue;
                         }
                     };
-                    // look into if there is a way to serialize to a slice
-                    match bincode::serialize(&key) {
-                        Ok(serialized) => {
-                            // possibly append dup
-
-                            if let Err(e) = db.put(self.txn, &serialized, &node.id) {
-                                println!(
-                                    "{} Error adding node to secondary index: {:?}",
-                                    line!(),
-                                    e
-                                );
-                                result = Err(GraphError::from(e));
-                            }
-                        }
-                        Err(e) => result = Err(GraphError::from(e)),
+                    if let Err(e) = db.put(self.txn, &key, &node.id) {
+                        println!(
+                            "{} Error adding node to secondary index: {:?}",
+                            line!(),
+                            e
+                        );
+                        result = Err(GraphError::from(e));
                     }
                 }
-                None => {
-                    result = Err(GraphError::New(format!(
-                        "Secondary Index {index} not found"
-                    )));
-                }
+                Err(e) => result = Err(e),
             }
         }
 
         if let Some(bm25) = &self.storage.bm25
-            && let Some(props) = node.properties.as_ref() {
-                let mut data = props.flatten_bm25();
-                data.push_str(&node.label);
-                if let Err(e) = bm25.insert_doc(self.txn, node.id, &data) {
-                    result = Err(e);
-                }
+            && let Err(e) =
+                bm25.insert_item(self.txn, &node.label, node.id, node.properties.as_ref())
+        {
+            result = Err(e);
         }
 
+        // a failed write is returned as is, e.g. a missing index or an LMDB error
         if result.is_ok() {
             result = Ok(TraversalVal::Node(node.clone()));
-        } else {
-            result = Err(GraphError::New(
-                "Failed to add node to secondary indices".to_string(),
-            ));
         }
 
         RwTraversalIterator {
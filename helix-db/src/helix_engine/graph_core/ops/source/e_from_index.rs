@@ -0,0 +1,133 @@
+use crate::{
+    helix_engine::{
+        graph_core::{ops::tr_val::TraversalVal, traversal_iter::RoTraversalIterator},
+        storage_core::{
+            index_key::encode_index_key, storage_core::HelixGraphStorage,
+            storage_methods::StorageMethods,
+        },
+        types::GraphError,
+    },
+    protocol::value::Value,
+};
+use heed3::{RoTxn, byteorder::BE};
+use helix_macros::debug_trace;
+use serde::Serialize;
+use std::{ops::Bound, sync::Arc};
+
+pub struct EFromIndex<'a> {
+    iter: heed3::RoRange<'a, heed3::types::Bytes, heed3::types::LazyDecode<heed3::types::U128<BE>>>,
+    txn: &'a RoTxn<'a>,
+    storage: Arc<HelixGraphStorage>,
+}
+
+impl<'a> Iterator for EFromIndex<'a> {
+    type Item = Result<TraversalVal, GraphError>;
+
+    #[debug_trace("E_FROM_INDEX")]
+    fn next(&mut self) -> Option<Self::Item> {
+        let (_, value) = match self.iter.next()? {
+            Ok(value) => value,
+            Err(e) => return Some(Err(GraphError::from(e))),
+        };
+        match value.decode() {
+            Ok(value) => match self.storage.get_edge(self.txn, &value) {
+                Ok(edge) => Some(Ok(TraversalVal::Edge(edge))),
+                Err(e) => Some(Err(e)),
+            },
+            Err(e) => Some(Err(GraphError::ConversionError(e.to_string()))),
+        }
+    }
+}
+
+pub trait EFromIndexAdapter<'a, K: Into<Value> + Serialize>:
+    Iterator<Item = Result<TraversalVal, GraphError>>
+{
+    type OutputIter: Iterator<Item = Result<TraversalVal, GraphError>>;
+
+    /// Returns a new iterator that will return the edges with the given key in an edge secondary index.
+    ///
+    /// # Arguments
+    ///
+    /// * `label` - The label of the edges.
+    /// * `index` - The name of the secondary index.
+    /// * `key` - The key to search for in the secondary index.
+    ///
+    /// An index that doesn't exist is an error.
+    fn e_from_index(
+        self,
+        label: &'a str,
+        index: &'a str,
+        key: &'a K,
+    ) -> Result<Self::OutputIter, GraphError>
+    where
+        K: Into<Value> + Serialize + Clone;
+
+    /// Returns a new iterator that will return the edges whose indexed value falls between the two bounds.
+    ///
+    /// Edges are returned in ascending order of the indexed value.
+    fn e_from_index_range(
+        self,
+        label: &'a str,
+        index: &'a str,
+        start: Bound<&'a K>,
+        end: Bound<&'a K>,
+    ) -> Result<Self::OutputIter, GraphError>
+    where
+        K: Into<Value> + Serialize + Clone;
+}
+
+impl<'a, I: Iterator<Item = Result<TraversalVal, GraphError>>, K: Into<Value> + Serialize + 'a>
+    EFromIndexAdapter<'a, K> for RoTraversalIterator<'a, I>
+{
+    type OutputIter = RoTraversalIterator<'a, EFromIndex<'a>>;
+
+    #[inline]
+    fn e_from_index(
+        self,
+        label: &'a str,
+        index: &'a str,
+        key: &'a K,
+    ) -> Result<Self::OutputIter, GraphError>
+    where
+        K: Into<Value> + Serialize + Clone,
+    {
+        self.e_from_index_range(label, index, Bound::Included(key), Bound::Included(key))
+    }
+
+    #[inline]
+    fn e_from_index_range(
+        self,
+        label: &'a str,
+        index: &'a str,
+        start: Bound<&'a K>,
+        end: Bound<&'a K>,
+    ) -> Result<Self::OutputIter, GraphError>
+    where
+        K: Into<Value> + Serialize + Clone,
+    {
+        let db = self.storage.edge_secondary_index(label, index)?;
+        let start = start.map(|key| encode_index_key(&Value::from(key)));
+        let end = end.map(|key| encode_index_key(&Value::from(key)));
+        let res = db
+            .lazily_decode_data()
+            .range(
+                self.txn,
+                &(
+                    start.as_ref().map(Vec::as_slice),
+                    end.as_ref().map(Vec::as_slice),
+                ),
+            )?;
+
+        let e_from_index = EFromIndex {
+            iter: res,
+            txn: self.txn,
+            storage: Arc::clone(&self.storage),
+        };
+
+        Ok(RoTraversalIterator {
+            inner: e_from_index,
+            storage: self.storage,
+            txn: self.txn,
+        })
+    }
+}
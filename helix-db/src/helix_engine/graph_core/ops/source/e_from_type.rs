@@ -1,18 +1,23 @@
 use crate::{
     helix_engine::{
         graph_core::{ops::tr_val::TraversalVal, traversal_iter::RoTraversalIterator},
+        storage_core::storage_core::HelixGraphStorage,
         types::GraphError,
     },
     utils::items::Edge,
 };
 use heed3::{
+    RoTxn,
     byteorder::BE,
-    types::{Bytes, U128},
+    types::{Bytes, LazyDecode, U128},
 };
 use helix_macros::debug_trace;
+use std::sync::Arc;
 
 pub struct EFromType<'a> {
-    pub iter: heed3::RoIter<'a, U128<BE>, heed3::types::LazyDecode<Bytes>>,
+    pub iter: heed3::RoPrefix<'a, Bytes, LazyDecode<U128<BE>>>,
+    pub txn: &'a RoTxn<'a>,
+    pub storage: Arc<HelixGraphStorage>,
     pub label: &'a str,
 }
 
@@ -22,14 +27,24 @@ impl<'a> Iterator for EFromType<'a> {
     #[debug_trace("E_FROM_TYPE")]
     fn next(&mut self) -> Option<Self::Item> {
         for value in self.iter.by_ref() {
-            let (key, value) = value.unwrap();
-            match value.decode() {
-                Ok(value) => match Edge::decode_edge(value, key) {
-                    Ok(edge) => match &edge.label {
-                        label if label == self.label => return Some(Ok(TraversalVal::Edge(edge))),
-                        _ => continue,
-                    },
-                    Err(e) => return Some(Err(GraphError::ConversionError(e.to_string()))),
+            let (_, value) = match value {
+                Ok(value) => value,
+                Err(e) => return Some(Err(GraphError::from(e))),
+            };
+            let id = match value.decode() {
+                Ok(id) => id,
+                Err(e) => return Some(Err(GraphError::ConversionError(e.to_string()))),
+            };
+            let data = match self.storage.edges_db.get(self.txn, HelixGraphStorage::edge_key(&id)) {
+                Ok(Some(data)) => data,
+                Ok(None) => return Some(Err(GraphError::EdgeNotFound)),
+                Err(e) => return Some(Err(GraphError::from(e))),
+            };
+            match Edge::decode_edge(data, id) {
+                // label hashes can collide so the label is still checked
+                Ok(edge) => match &edge.label {
+                    label if label == self.label => return Some(Ok(TraversalVal::Edge(edge))),
+                    _ => continue,
                 },
                 Err(e) => return Some(Err(GraphError::ConversionError(e.to_string()))),
             }
@@ -53,12 +68,17 @@ impl<'a, I: Iterator<Item = Result<TraversalVal, GraphError>>> EFromTypeAdapter<
     ) -> RoTraversalIterator<'a, impl Iterator<Item = Result<TraversalVal, GraphError>>> {
         let iter = self
             .storage
-            .edges_db
+            .edge_labels_db
             .lazily_decode_data()
-            .iter(self.txn)
+            .prefix_iter(self.txn, &HelixGraphStorage::label_key(label))
             .unwrap();
         RoTraversalIterator {
-            inner: EFromType { iter, label },
+            inner: EFromType {
+                iter,
+                txn: self.txn,
+                storage: Arc::clone(&self.storage),
+                label,
+            },
             storage: self.storage,
             txn: self.txn,
         }
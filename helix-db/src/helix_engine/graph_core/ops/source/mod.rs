@@ -2,8 +2,11 @@ pub mod add_e;
 pub mod add_n;
 
 pub mod e_from_id;
+pub mod e_from_index;
 pub mod e_from_type;
 pub mod n_from_id;
 pub mod n_from_index;
 pub mod n_from_type;
+pub mod upsert_e;
+pub mod upsert_n;
 pub mod v_from_id;
\ No newline at end of file
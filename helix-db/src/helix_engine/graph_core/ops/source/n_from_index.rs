@@ -1,7 +1,11 @@
 use crate::{
     helix_engine::{
         graph_core::{ops::tr_val::TraversalVal, traversal_iter::RoTraversalIterator},
-        storage_core::{storage_core::HelixGraphStorage, storage_methods::StorageMethods},
+        storage_core::{
+            index_key::{encode_composite_index_key, encode_index_key, prefix_successor},
+            storage_core::HelixGraphStorage,
+            storage_methods::StorageMethods,
+        },
         types::GraphError,
     },
     protocol::value::Value,
@@ -9,14 +13,12 @@ use crate::{
 use heed3::{RoTxn, byteorder::BE};
 use helix_macros::debug_trace;
 use serde::Serialize;
-use std::sync::Arc;
+use std::{ops::Bound, sync::Arc};
 
 pub struct NFromIndex<'a> {
-    iter:
-        heed3::RoPrefix<'a, heed3::types::Bytes, heed3::types::LazyDecode<heed3::types::U128<BE>>>,
+    iter: heed3::RoRange<'a, heed3::types::Bytes, heed3::types::LazyDecode<heed3::types::U128<BE>>>,
     txn: &'a RoTxn<'a>,
     storage: Arc<HelixGraphStorage>,
-    label: &'a str,
 }
 
 impl<'a> Iterator for NFromIndex<'a> {
@@ -24,27 +26,21 @@ impl<'a> Iterator for NFromIndex<'a> {
 
     #[debug_trace("N_FROM_INDEX")]
     fn next(&mut self) -> Option<Self::Item> {
-        for value in self.iter.by_ref() {
-            let (_, value) = value.unwrap();
-            match value.decode() {
-                Ok(value) => match self.storage.get_node(self.txn, &value) {
-                    Ok(node) => {
-                        if node.label == self.label {
-                            return Some(Ok(TraversalVal::Node(node)));
-                        } else {
-                            continue;
-                        }
-                    }
-                    Err(e) => {
-                        println!("{} Error getting node: {:?}", line!(), e);
-                        return Some(Err(GraphError::ConversionError(e.to_string())));
-                    }
-                },
-
-                Err(e) => return Some(Err(GraphError::ConversionError(e.to_string()))),
-            }
+        // the index only holds nodes of its own label so every hit is returned
+        let (_, value) = match self.iter.next()? {
+            Ok(value) => value,
+            Err(e) => return Some(Err(GraphError::from(e))),
+        };
+        match value.decode() {
+            Ok(value) => match self.storage.get_node(self.txn, &value) {
+                Ok(node) => Some(Ok(TraversalVal::Node(node))),
+                Err(e) => {
+                    println!("{} Error getting node: {:?}", line!(), e);
+                    Some(Err(GraphError::ConversionError(e.to_string())))
+                }
+            },
+            Err(e) => Some(Err(GraphError::ConversionError(e.to_string()))),
         }
-        None
     }
 }
 
@@ -61,8 +57,33 @@ pub trait NFromIndexAdapter<'a, K: Into<Value> + Serialize>:
     /// * `key` - The key to search for in the secondary index.
     ///
     /// Note that both the `index` and `key` must be provided.
-    /// The index must be a valid and existing secondary index and the key should match the type of the index.
-    fn n_from_index(self, label: &'a str, index: &'a str, key: &'a K) -> Self::OutputIter
+    /// The index must be a valid and existing secondary index and the key should match the type of the index,
+    /// an index that doesn't exist is an error.
+    fn n_from_index(
+        self,
+        label: &'a str,
+        index: &'a str,
+        key: &'a K,
+    ) -> Result<Self::OutputIter, GraphError>
+    where
+        K: Into<Value> + Serialize + Clone;
+
+    /// Returns a new iterator that will return the nodes whose indexed value falls between the two bounds.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The name of the secondary index.
+    /// * `start` - The lower bound of the range, `Bound::Unbounded` for no lower bound.
+    /// * `end` - The upper bound of the range, `Bound::Unbounded` for no upper bound.
+    ///
+    /// Nodes are returned in ascending order of the indexed value.
+    fn n_from_index_range(
+        self,
+        label: &'a str,
+        index: &'a str,
+        start: Bound<&'a K>,
+        end: Bound<&'a K>,
+    ) -> Result<Self::OutputIter, GraphError>
     where
         K: Into<Value> + Serialize + Clone;
 }
@@ -73,35 +94,139 @@ impl<'a, I: Iterator<Item = Result<TraversalVal, GraphError>>, K: Into<Value> +
     type OutputIter = RoTraversalIterator<'a, NFromIndex<'a>>;
 
     #[inline]
-    fn n_from_index(self, label: &'a str, index: &'a str, key: &'a K) -> Self::OutputIter
+    fn n_from_index(
+        self,
+        label: &'a str,
+        index: &'a str,
+        key: &'a K,
+    ) -> Result<Self::OutputIter, GraphError>
     where
         K: Into<Value> + Serialize + Clone,
     {
-        let db = self
-            .storage
-            .secondary_indices
-            .get(index)
-            // TODO: this
-            .ok_or(GraphError::New(format!(
-                "Secondary Index {index} not found"
-            )))
-            .unwrap();
+        self.n_from_index_range(label, index, Bound::Included(key), Bound::Included(key))
+    }
+
+    #[inline]
+    fn n_from_index_range(
+        self,
+        label: &'a str,
+        index: &'a str,
+        start: Bound<&'a K>,
+        end: Bound<&'a K>,
+    ) -> Result<Self::OutputIter, GraphError>
+    where
+        K: Into<Value> + Serialize + Clone,
+    {
+        let db = self.storage.secondary_index(label, index)?;
+        let start = start.map(|key| encode_index_key(&Value::from(key)));
+        let end = end.map(|key| encode_index_key(&Value::from(key)));
         let res = db
             .lazily_decode_data()
-            .prefix_iter(self.txn, &bincode::serialize(&Value::from(key)).unwrap())
-            .unwrap();
+            .range(
+                self.txn,
+                &(
+                    start.as_ref().map(Vec::as_slice),
+                    end.as_ref().map(Vec::as_slice),
+                ),
+            )?;
 
         let n_from_index = NFromIndex {
             iter: res,
             txn: self.txn,
             storage: Arc::clone(&self.storage),
-            label,
         };
 
-        RoTraversalIterator {
+        Ok(RoTraversalIterator {
             inner: n_from_index,
             storage: self.storage,
             txn: self.txn,
-        }
+        })
+    }
+}
+
+pub trait NFromCompositeIndexAdapter<'a>:
+    Iterator<Item = Result<TraversalVal, GraphError>>
+{
+    type OutputIter: Iterator<Item = Result<TraversalVal, GraphError>>;
+
+    /// Returns a new iterator over a composite secondary index that matches the leading fields
+    /// exactly and the next field within a range, in a single scan.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The name of the composite index, e.g. `user_id,created_at`.
+    /// * `prefix` - The values of the leading fields of the index, in field order.
+    /// * `start` - The lower bound of the field after the prefix, `Bound::Unbounded` for no lower bound.
+    /// * `end` - The upper bound of the field after the prefix, `Bound::Unbounded` for no upper bound.
+    ///
+    /// Nodes are returned in ascending order of the remaining indexed fields.
+    fn n_from_composite_index(
+        self,
+        label: &'a str,
+        index: &'a str,
+        prefix: &[Value],
+        start: Bound<Value>,
+        end: Bound<Value>,
+    ) -> Result<Self::OutputIter, GraphError>;
+}
+
+impl<'a, I: Iterator<Item = Result<TraversalVal, GraphError>>> NFromCompositeIndexAdapter<'a>
+    for RoTraversalIterator<'a, I>
+{
+    type OutputIter = RoTraversalIterator<'a, NFromIndex<'a>>;
+
+    fn n_from_composite_index(
+        self,
+        label: &'a str,
+        index: &'a str,
+        prefix: &[Value],
+        start: Bound<Value>,
+        end: Bound<Value>,
+    ) -> Result<Self::OutputIter, GraphError> {
+        let db = self.storage.secondary_index(label, index)?;
+
+        // keys continue after the bounded field, so a bound must include or exclude
+        // every key that starts with it rather than the bound key alone
+        let prefix = encode_composite_index_key(prefix);
+        let with_prefix = |value: &Value| [prefix.as_slice(), &encode_index_key(value)].concat();
+        let after = |key: &[u8]| match prefix_successor(key) {
+            Some(key) => Bound::Included(key),
+            None => Bound::Unbounded,
+        };
+        let before = |key: &[u8]| match prefix_successor(key) {
+            Some(key) => Bound::Excluded(key),
+            None => Bound::Unbounded,
+        };
+        let start = match start {
+            Bound::Included(value) => Bound::Included(with_prefix(&value)),
+            Bound::Excluded(value) => after(&with_prefix(&value)),
+            Bound::Unbounded => Bound::Included(prefix.clone()),
+        };
+        let end = match end {
+            Bound::Included(value) => before(&with_prefix(&value)),
+            Bound::Excluded(value) => Bound::Excluded(with_prefix(&value)),
+            Bound::Unbounded => before(&prefix),
+        };
+        let res = db
+            .lazily_decode_data()
+            .range(
+                self.txn,
+                &(
+                    start.as_ref().map(Vec::as_slice),
+                    end.as_ref().map(Vec::as_slice),
+                ),
+            )?;
+
+        let n_from_index = NFromIndex {
+            iter: res,
+            txn: self.txn,
+            storage: Arc::clone(&self.storage),
+        };
+
+        Ok(RoTraversalIterator {
+            inner: n_from_index,
+            storage: self.storage,
+            txn: self.txn,
+        })
     }
 }
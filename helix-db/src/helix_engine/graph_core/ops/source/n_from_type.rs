@@ -1,18 +1,23 @@
 use crate::{
     helix_engine::{
         graph_core::{ops::tr_val::TraversalVal, traversal_iter::RoTraversalIterator},
+        storage_core::storage_core::HelixGraphStorage,
         types::GraphError,
     },
     utils::items::Node,
 };
 use helix_macros::debug_trace;
 use heed3::{
+    RoTxn,
     byteorder::BE,
-    types::{Bytes, U128},
+    types::{Bytes, LazyDecode, U128},
 };
+use std::sync::Arc;
 
 pub struct NFromType<'a> {
-    pub iter: heed3::RoIter<'a, U128<BE>, heed3::types::LazyDecode<Bytes>>,
+    pub iter: heed3::RoPrefix<'a, Bytes, LazyDecode<U128<BE>>>,
+    pub txn: &'a RoTxn<'a>,
+    pub storage: Arc<HelixGraphStorage>,
     pub label: &'a str,
 }
 
@@ -22,19 +27,29 @@ impl<'a> Iterator for NFromType<'a> {
     #[debug_trace("N_FROM_TYPE")]
     fn next(&mut self) -> Option<Self::Item> {
         for value in self.iter.by_ref() {
-            let (key_, value) = value.unwrap();
-            match value.decode() {
-                Ok(value) => match Node::decode_node(value, key_) {
-                    Ok(node) => match &node.label {
-                        label if label == self.label => return Some(Ok(TraversalVal::Node(node))),
-                        _ => continue,
-                    },
-                    Err(e) => {
-                        println!("{} Error decoding node: {:?}", line!(), e);
-                        return Some(Err(GraphError::ConversionError(e.to_string())));
-                    }
-                },
+            let (_, value) = match value {
+                Ok(value) => value,
+                Err(e) => return Some(Err(GraphError::from(e))),
+            };
+            let id = match value.decode() {
+                Ok(id) => id,
                 Err(e) => return Some(Err(GraphError::ConversionError(e.to_string()))),
+            };
+            let data = match self.storage.nodes_db.get(self.txn, HelixGraphStorage::node_key(&id)) {
+                Ok(Some(data)) => data,
+                Ok(None) => return Some(Err(GraphError::NodeNotFound)),
+                Err(e) => return Some(Err(GraphError::from(e))),
+            };
+            match Node::decode_node(data, id) {
+                // label hashes can collide so the label is still checked
+                Ok(node) => match &node.label {
+                    label if label == self.label => return Some(Ok(TraversalVal::Node(node))),
+                    _ => continue,
+                },
+                Err(e) => {
+                    println!("{} Error decoding node: {:?}", line!(), e);
+                    return Some(Err(GraphError::ConversionError(e.to_string())));
+                }
             }
         }
         None
@@ -59,12 +74,17 @@ impl<'a, I: Iterator<Item = Result<TraversalVal, GraphError>>> NFromTypeAdapter<
     ) -> RoTraversalIterator<'a, impl Iterator<Item = Result<TraversalVal, GraphError>>> {
         let iter = self
             .storage
-            .nodes_db
+            .node_labels_db
             .lazily_decode_data()
-            .iter(self.txn)
+            .prefix_iter(self.txn, &HelixGraphStorage::label_key(label))
             .unwrap();
         RoTraversalIterator {
-            inner: NFromType { iter, label },
+            inner: NFromType {
+                iter,
+                txn: self.txn,
+                storage: Arc::clone(&self.storage),
+                label,
+            },
             storage: self.storage,
             txn: self.txn,
         }
@@ -0,0 +1,86 @@
+use super::{
+    super::tr_val::TraversalVal,
+    add_e::{AddEAdapter, EdgeType},
+};
+use crate::{
+    helix_engine::{
+        graph_core::{ops::util::update::UpdateAdapter, traversal_iter::RwTraversalIterator},
+        storage_core::{storage_core::HelixGraphStorage, storage_methods::StorageMethods},
+        types::GraphError,
+    },
+    protocol::value::Value,
+    utils::{items::Edge, label_hash::hash_label},
+};
+use heed3::RoTxn;
+
+pub trait UpsertEAdapter<'a, 'b>: Iterator<Item = Result<TraversalVal, GraphError>> {
+    /// Updates the edge of the given label from `from_node` to `to_node` with `properties`,
+    /// or adds it if the two nodes aren't connected by an edge of that label yet.
+    ///
+    /// Both paths keep the secondary and BM25 indices in step with the stored edge, like
+    /// [`add_e`](AddEAdapter::add_e) and [`update`](UpdateAdapter::update) do.
+    fn upsert_e(
+        self,
+        label: &'a str,
+        properties: Option<Vec<(String, Value)>>,
+        from_node: u128,
+        to_node: u128,
+    ) -> RwTraversalIterator<'a, 'b, impl Iterator<Item = Result<TraversalVal, GraphError>>>;
+}
+
+impl<'a, 'b, I: Iterator<Item = Result<TraversalVal, GraphError>>> UpsertEAdapter<'a, 'b>
+    for RwTraversalIterator<'a, 'b, I>
+{
+    fn upsert_e(
+        self,
+        label: &'a str,
+        properties: Option<Vec<(String, Value)>>,
+        from_node: u128,
+        to_node: u128,
+    ) -> RwTraversalIterator<'a, 'b, impl Iterator<Item = Result<TraversalVal, GraphError>>> {
+        let storage = self.storage;
+        let txn = self.txn;
+
+        let result = match find_edge(&storage, txn, label, from_node, to_node) {
+            Ok(Some(edge)) => RwTraversalIterator::new(
+                storage.clone(),
+                &mut *txn,
+                std::iter::once(Ok(TraversalVal::Edge(edge))),
+            )
+            .update(properties)
+            .collect::<Vec<_>>(),
+            Ok(None) => RwTraversalIterator::new(storage.clone(), &mut *txn, std::iter::empty())
+                .add_e(label, properties, from_node, to_node, true, EdgeType::Node)
+                .collect::<Vec<_>>(),
+            Err(e) => vec![Err(e)],
+        };
+
+        RwTraversalIterator {
+            inner: result.into_iter(),
+            storage,
+            txn,
+        }
+    }
+}
+
+/// Looks up the edge of `label` from `from_node` to `to_node` through the out edges of `from_node`.
+fn find_edge(
+    storage: &HelixGraphStorage,
+    txn: &RoTxn,
+    label: &str,
+    from_node: u128,
+    to_node: u128,
+) -> Result<Option<Edge>, GraphError> {
+    let key = HelixGraphStorage::out_edge_key(&from_node, &hash_label(label, None));
+    let Some(edges) = storage.out_edges_db.get_duplicates(txn, &key)? else {
+        return Ok(None);
+    };
+    for data in edges {
+        let (_, data) = data?;
+        let (edge_id, node_id) = HelixGraphStorage::unpack_adj_edge_data(data)?;
+        if node_id == to_node {
+            return Ok(Some(storage.get_edge(txn, &edge_id)?));
+        }
+    }
+    Ok(None)
+}
@@ -0,0 +1,103 @@
+use super::{super::tr_val::TraversalVal, add_n::AddNAdapter};
+use crate::{
+    helix_engine::{
+        graph_core::{ops::util::update::UpdateAdapter, traversal_iter::RwTraversalIterator},
+        storage_core::{
+            index_key::encode_item_index_key, storage_core::HelixGraphStorage,
+            storage_methods::StorageMethods,
+        },
+        types::GraphError,
+    },
+    protocol::value::Value,
+    utils::items::Node,
+};
+use heed3::RoTxn;
+
+pub trait UpsertNAdapter<'a, 'b>: Iterator<Item = Result<TraversalVal, GraphError>> {
+    /// Updates the node of the given label whose `key` index holds the key of `properties`,
+    /// or adds a new node with `properties` and `defaults` if there is none.
+    ///
+    /// `defaults` are only written when the node is added, so an update never overwrites a
+    /// value with its default. Both paths keep the secondary and BM25 indices in step with the
+    /// stored node, like [`add_n`](AddNAdapter::add_n) and
+    /// [`update`](UpdateAdapter::update) do.
+    fn upsert_n(
+        self,
+        label: &'a str,
+        key: &'a str,
+        properties: Option<Vec<(String, Value)>>,
+        defaults: Option<Vec<(String, Value)>>,
+        secondary_indices: Option<&'a [&str]>,
+    ) -> RwTraversalIterator<'a, 'b, impl Iterator<Item = Result<TraversalVal, GraphError>>>;
+}
+
+impl<'a, 'b, I: Iterator<Item = Result<TraversalVal, GraphError>>> UpsertNAdapter<'a, 'b>
+    for RwTraversalIterator<'a, 'b, I>
+{
+    fn upsert_n(
+        self,
+        label: &'a str,
+        key: &'a str,
+        properties: Option<Vec<(String, Value)>>,
+        defaults: Option<Vec<(String, Value)>>,
+        secondary_indices: Option<&'a [&str]>,
+    ) -> RwTraversalIterator<'a, 'b, impl Iterator<Item = Result<TraversalVal, GraphError>>> {
+        let storage = self.storage;
+        let txn = self.txn;
+
+        let existing = find_node(&storage, txn, label, key, properties.as_deref());
+        let result = match existing {
+            Ok(Some(node)) => RwTraversalIterator::new(
+                storage.clone(),
+                &mut *txn,
+                std::iter::once(Ok(TraversalVal::Node(node))),
+            )
+            .update(properties)
+            .collect::<Vec<_>>(),
+            Ok(None) => {
+                let mut properties = properties.unwrap_or_default();
+                for (field, value) in defaults.unwrap_or_default() {
+                    if !properties.iter().any(|(k, _)| *k == field) {
+                        properties.push((field, value));
+                    }
+                }
+                RwTraversalIterator::new(storage.clone(), &mut *txn, std::iter::empty())
+                    .add_n(label, Some(properties), secondary_indices)
+                    .collect::<Vec<_>>()
+            }
+            Err(e) => vec![Err(e)],
+        };
+
+        RwTraversalIterator {
+            inner: result.into_iter(),
+            storage,
+            txn,
+        }
+    }
+}
+
+/// Looks up the node of `label` that holds the key of `properties` in the `key` index.
+fn find_node(
+    storage: &HelixGraphStorage,
+    txn: &RoTxn,
+    label: &str,
+    key: &str,
+    properties: Option<&[(String, Value)]>,
+) -> Result<Option<Node>, GraphError> {
+    // a node that isn't stored, only used to encode the key the same way inserts do
+    let probe = Node {
+        id: 0,
+        label: label.to_string(),
+        version: 1,
+        properties: properties.map(|props| props.iter().cloned().collect()),
+    };
+    let index_key = encode_item_index_key(&probe, key).map_err(|_| {
+        GraphError::New(format!(
+            "UpsertN<{label}> must be given a value for each field of the `{key}` index"
+        ))
+    })?;
+    match storage.secondary_index(label, key)?.get(txn, &index_key)? {
+        Some(id) => Ok(Some(storage.get_node(txn, &id)?)),
+        None => Ok(None),
+    }
+}
@@ -7,7 +7,7 @@ use crate::{
         items::{Edge, Node},
     },
 };
-use std::{borrow::Cow, hash::Hash};
+use std::{borrow::Cow, collections::HashMap, hash::Hash};
 
 #[derive(Clone, Debug)]
 pub enum TraversalVal {
@@ -23,6 +23,10 @@ pub enum TraversalVal {
     Path((Vec<Node>, Vec<Edge>)),
     /// A value in the graph
     Value(Value),
+    /// Aggregates of all the items of a traversal, keyed by their names
+    Aggregate(HashMap<String, Value>),
+    /// Aggregates of the items of a traversal per value of a property, keyed by that value
+    Group(HashMap<String, HashMap<String, Value>>),
     /// An empty traversal value
     Empty,
 }
@@ -0,0 +1,284 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    helix_engine::{
+        graph_core::{
+            ops::tr_val::{Traversable, TraversalVal},
+            traversal_iter::RoTraversalIterator,
+        },
+        types::GraphError,
+    },
+    protocol::value::Value,
+};
+
+/// An aggregate computed over the items of a traversal, or of each group of them
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Aggregation<'a> {
+    /// The number of items, or of items with a value for the property
+    Count(Option<&'a str>),
+    Sum(&'a str),
+    Avg(&'a str),
+    Min(&'a str),
+    Max(&'a str),
+}
+
+impl Aggregation<'_> {
+    /// The key the aggregate is returned under, e.g. `count` or `sum_age`
+    pub fn name(&self) -> String {
+        match self {
+            Aggregation::Count(None) => "count".to_string(),
+            Aggregation::Count(Some(property)) => format!("count_{property}"),
+            Aggregation::Sum(property) => format!("sum_{property}"),
+            Aggregation::Avg(property) => format!("avg_{property}"),
+            Aggregation::Min(property) => format!("min_{property}"),
+            Aggregation::Max(property) => format!("max_{property}"),
+        }
+    }
+
+    fn property(&self) -> Option<&str> {
+        match self {
+            Aggregation::Count(property) => *property,
+            Aggregation::Sum(property)
+            | Aggregation::Avg(property)
+            | Aggregation::Min(property)
+            | Aggregation::Max(property) => Some(property),
+        }
+    }
+}
+
+/// A number summed by `SUM` and `AVG`, integers stay integers so large ids and counters are exact
+#[derive(Debug, Clone, Copy)]
+enum Number {
+    Int(i128),
+    Float(f64),
+}
+
+impl Number {
+    fn from_value(value: &Value) -> Option<Number> {
+        match value {
+            Value::I8(i) => Some(Number::Int(*i as i128)),
+            Value::I16(i) => Some(Number::Int(*i as i128)),
+            Value::I32(i) => Some(Number::Int(*i as i128)),
+            Value::I64(i) => Some(Number::Int(*i as i128)),
+            Value::U8(u) => Some(Number::Int(*u as i128)),
+            Value::U16(u) => Some(Number::Int(*u as i128)),
+            Value::U32(u) => Some(Number::Int(*u as i128)),
+            Value::U64(u) => Some(Number::Int(*u as i128)),
+            Value::U128(u) => {
+                Some(i128::try_from(*u).map_or(Number::Float(*u as f64), Number::Int))
+            }
+            Value::F32(f) => Some(Number::Float(*f as f64)),
+            Value::F64(f) => Some(Number::Float(*f)),
+            _ => None,
+        }
+    }
+
+    fn add(self, other: Number) -> Number {
+        match (self, other) {
+            // sums past `i128` carry on as floats rather than sticking at the bound
+            (Number::Int(a), Number::Int(b)) => a
+                .checked_add(b)
+                .map_or(Number::Float(a as f64 + b as f64), Number::Int),
+            (a, b) => Number::Float(a.as_f64() + b.as_f64()),
+        }
+    }
+
+    fn as_f64(self) -> f64 {
+        match self {
+            Number::Int(i) => i as f64,
+            Number::Float(f) => f,
+        }
+    }
+
+    fn into_value(self) -> Value {
+        match self {
+            Number::Int(i) => match i64::try_from(i) {
+                Ok(i) => Value::I64(i),
+                Err(_) => Value::F64(i as f64),
+            },
+            Number::Float(f) => Value::F64(f),
+        }
+    }
+}
+
+/// The running state of one aggregate
+#[derive(Debug, Clone)]
+enum Accumulator {
+    Count(usize),
+    Sum(Option<Number>),
+    Avg(Option<Number>, usize),
+    Min(Option<Value>),
+    Max(Option<Value>),
+}
+
+impl Accumulator {
+    fn new(aggregation: &Aggregation) -> Self {
+        match aggregation {
+            Aggregation::Count(_) => Accumulator::Count(0),
+            Aggregation::Sum(_) => Accumulator::Sum(None),
+            Aggregation::Avg(_) => Accumulator::Avg(None, 0),
+            Aggregation::Min(_) => Accumulator::Min(None),
+            Aggregation::Max(_) => Accumulator::Max(None),
+        }
+    }
+
+    /// Adds an item's value for the aggregated property, `None` when counting items
+    fn push(&mut self, value: Option<&Value>) {
+        match (self, value) {
+            (Accumulator::Count(count), _) => *count += 1,
+            (Accumulator::Sum(sum), Some(value)) => {
+                if let Some(n) = Number::from_value(value) {
+                    *sum = Some(sum.map_or(n, |sum| sum.add(n)));
+                }
+            }
+            (Accumulator::Avg(sum, count), Some(value)) => {
+                if let Some(n) = Number::from_value(value) {
+                    *sum = Some(sum.map_or(n, |sum| sum.add(n)));
+                    *count += 1;
+                }
+            }
+            (Accumulator::Min(min), Some(value)) if min.as_ref().is_none_or(|min| value < min) => {
+                *min = Some(value.clone());
+            }
+            (Accumulator::Max(max), Some(value)) if max.as_ref().is_none_or(|max| value > max) => {
+                *max = Some(value.clone());
+            }
+            _ => {}
+        }
+    }
+
+    fn finish(self) -> Value {
+        match self {
+            Accumulator::Count(count) => Value::from(count),
+            Accumulator::Sum(sum) => sum.map_or(Value::I64(0), Number::into_value),
+            Accumulator::Avg(Some(sum), count) => Value::F64(sum.as_f64() / count as f64),
+            Accumulator::Avg(None, _) => Value::Empty,
+            Accumulator::Min(value) | Accumulator::Max(value) => value.unwrap_or(Value::Empty),
+        }
+    }
+}
+
+/// The accumulators of each aggregate of a group
+fn accumulators(aggregations: &[Aggregation]) -> Vec<Accumulator> {
+    aggregations.iter().map(Accumulator::new).collect()
+}
+
+fn push_item(
+    accumulators: &mut [Accumulator],
+    aggregations: &[Aggregation],
+    item: &TraversalVal,
+) -> Result<(), GraphError> {
+    for (accumulator, aggregation) in accumulators.iter_mut().zip(aggregations) {
+        match aggregation.property() {
+            None => accumulator.push(None),
+            Some(property) => match item.check_property(property) {
+                Ok(value) if !matches!(value.as_ref(), Value::Empty) => {
+                    accumulator.push(Some(value.as_ref()))
+                }
+                // items without the property are left out of its aggregates
+                Ok(_) | Err(GraphError::ConversionError(_)) => {}
+                Err(err) => return Err(err),
+            },
+        }
+    }
+    Ok(())
+}
+
+fn finish(accumulators: Vec<Accumulator>, aggregations: &[Aggregation]) -> HashMap<String, Value> {
+    aggregations
+        .iter()
+        .zip(accumulators)
+        .map(|(aggregation, accumulator)| (aggregation.name(), accumulator.finish()))
+        .collect()
+}
+
+/// Aggregates the items of a traversal, grouped by a property when one is given.
+///
+/// Returns a single `TraversalVal::Aggregate` with the aggregates of all items, or a single
+/// `TraversalVal::Group` with the aggregates of the items of each value of the property, keyed by
+/// that value. Items without the property are grouped under `null`.
+///
+/// Values of different types are grouped apart, and when they print the same, like `"1"` and `1`,
+/// their keys are qualified by the type, e.g. `String(1)` and `I64(1)`.
+fn aggregate<I: Iterator<Item = Result<TraversalVal, GraphError>>>(
+    iter: I,
+    group_by: Option<&str>,
+    aggregations: &[Aggregation],
+) -> Result<TraversalVal, GraphError> {
+    match group_by {
+        None => {
+            let mut group = accumulators(aggregations);
+            for item in iter {
+                push_item(&mut group, aggregations, &item?)?;
+            }
+            Ok(TraversalVal::Aggregate(finish(group, aggregations)))
+        }
+        Some(property) => {
+            // keyed by the type, which is all `Display` writes, and the value itself
+            let mut groups: HashMap<(String, String), Vec<Accumulator>> = HashMap::new();
+            for item in iter {
+                let item = item?;
+                let key = match item.check_property(property) {
+                    Ok(value) if !matches!(value.as_ref(), Value::Empty) => (
+                        format!("{}", value.as_ref()),
+                        Value::to_string(value.as_ref()),
+                    ),
+                    Ok(_) | Err(GraphError::ConversionError(_)) => {
+                        (format!("{}", Value::Empty), "null".to_string())
+                    }
+                    Err(err) => return Err(err),
+                };
+                let group = groups
+                    .entry(key)
+                    .or_insert_with(|| accumulators(aggregations));
+                push_item(group, aggregations, &item)?;
+            }
+            let mut types_per_key: HashMap<String, usize> = HashMap::new();
+            for (_, key) in groups.keys() {
+                *types_per_key.entry(key.clone()).or_default() += 1;
+            }
+            Ok(TraversalVal::Group(
+                groups
+                    .into_iter()
+                    .map(|((ty, key), group)| {
+                        let key = if types_per_key[&key] > 1 {
+                            format!("{ty}({key})")
+                        } else {
+                            key
+                        };
+                        (key, finish(group, aggregations))
+                    })
+                    .collect(),
+            ))
+        }
+    }
+}
+
+pub trait AggregateAdapter<'a>: Iterator {
+    /// Aggregates the items of the traversal into a single value, per value of `group_by` if set
+    ///
+    /// `SUM` and `AVG` skip values that aren't numbers, and `MIN` and `MAX` compare values of the
+    /// same type. Aggregates of properties no item has are `0` for `SUM` and `null` otherwise.
+    fn aggregate_by(
+        self,
+        group_by: Option<&str>,
+        aggregations: &[Aggregation],
+    ) -> RoTraversalIterator<'a, impl Iterator<Item = Result<TraversalVal, GraphError>>>;
+}
+
+impl<'a, I: Iterator<Item = Result<TraversalVal, GraphError>>> AggregateAdapter<'a>
+    for RoTraversalIterator<'a, I>
+{
+    fn aggregate_by(
+        self,
+        group_by: Option<&str>,
+        aggregations: &[Aggregation],
+    ) -> RoTraversalIterator<'a, impl Iterator<Item = Result<TraversalVal, GraphError>>> {
+        let result = aggregate(self.inner, group_by, aggregations);
+        RoTraversalIterator {
+            inner: std::iter::once(result),
+            storage: Arc::clone(&self.storage),
+            txn: self.txn,
+        }
+    }
+}
@@ -1,5 +1,4 @@
 use crate::helix_engine::{
-    bm25::bm25::BM25,
     graph_core::ops::tr_val::TraversalVal,
     storage_core::{storage_core::HelixGraphStorage, storage_methods::StorageMethods},
     types::GraphError,
@@ -25,10 +24,6 @@ where
                 match item {
                     TraversalVal::Node(node) => match storage.drop_node(txn, &node.id) {
                         Ok(_) => {
-                            if let Some(bm25) = &storage.bm25
-                                && let Err(e) = bm25.delete_doc(txn, node.id) {
-                                    println!("failed to delete doc from bm25: {e}");
-                            }
                             println!("Dropped node: {:?}", node.id);
                             Ok(())
                         }
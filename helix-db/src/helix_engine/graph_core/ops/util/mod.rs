@@ -1,3 +1,4 @@
+pub mod aggregate;
 pub mod dedup;
 pub mod drop;
 pub mod exist;
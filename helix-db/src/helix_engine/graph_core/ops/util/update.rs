@@ -1,9 +1,11 @@
-use heed3::PutFlags;
-
 use crate::{
     helix_engine::{
         graph_core::traversal_iter::RwTraversalIterator,
-        storage_core::{storage_core::HelixGraphStorage, storage_methods::StorageMethods},
+        storage_core::{
+            index_key::{encode_item_index_key, index_fields},
+            storage_core::HelixGraphStorage,
+            storage_methods::StorageMethods,
+        },
         types::GraphError,
     },
     protocol::value::Value,
@@ -53,69 +55,87 @@ impl<'scope, 'env, I: Iterator<Item = Result<TraversalVal, GraphError>>> UpdateA
         for item in self.inner {
             match item {
                 Ok(TraversalVal::Node(node)) => match storage.get_node(self.txn, &node.id) {
-                    Ok(mut old_node) => {
-                        let mut properties = old_node.properties.unwrap_or_default();
-
-                        if let Some(ref props) = props {
-                            for (key, _new_value) in props.iter() {
-                                if let Some(db) = storage.secondary_indices.get(key)
-                                    && let Some(old_value) = properties.get(key) {
-                                        match bincode::serialize(old_value) {
-                                            Ok(old_serialized) => {
-                                                if let Err(e) = db.delete_one_duplicate(
-                                                    self.txn,
-                                                    &old_serialized,
-                                                    &node.id,
-                                                ) {
-                                                    vec.push(Err(GraphError::from(e)));
-                                                }
-                                            }
-                                            Err(e) => vec.push(Err(GraphError::from(e))),
-                                        }
-                                    }
-                            }
-                        }
-
+                    Ok(old_node) => {
+                        let mut new_node = old_node.clone();
+                        let mut properties = new_node.properties.take().unwrap_or_default();
                         if let Some(ref props) = props {
                             for (k, v) in props.iter() {
                                 properties.insert(k.clone(), v.clone());
                             }
                         }
+                        if !properties.is_empty() {
+                            new_node.properties = Some(properties);
+                        }
 
-                        if let Some(ref props) = props {
-                            for (key, new_value) in props.iter() {
-                                if let Some(db) = storage.secondary_indices.get(key) {
-                                    match bincode::serialize(new_value) {
-                                        Ok(new_serialized) => {
-                                            if let Err(e) = db.put_with_flags(
-                                                self.txn,
-                                                PutFlags::APPEND_DUP,
-                                                &new_serialized,
-                                                &node.id,
-                                            ) {
-                                                vec.push(Err(GraphError::from(e)));
-                                            }
-                                        }
-                                        Err(e) => vec.push(Err(GraphError::from(e))),
-                                    }
-                                }
+                        // only indices covering an updated field need their keys moved
+                        let changed_indices = storage
+                            .label_secondary_indices(&old_node.label)
+                            .filter(|(_, index, _)| {
+                                props.as_ref().is_some_and(|props| {
+                                    index_fields(index)
+                                        .any(|field| props.iter().any(|(k, _)| k == field))
+                                })
+                            })
+                            .map(|(name, index, db)| {
+                                (
+                                    name,
+                                    db,
+                                    encode_item_index_key(&old_node, index).ok(),
+                                    encode_item_index_key(&new_node, index).ok(),
+                                )
+                            })
+                            .collect::<Vec<_>>();
+
+                        // reject the whole update if any new key is already held by another node
+                        if let Some(e) =
+                            changed_indices.iter().find_map(|(index, _, _, new_key)| {
+                                new_key.as_ref().and_then(|key| {
+                                    storage.check_unique(self.txn, index, key, &node.id).err()
+                                })
+                            })
+                        {
+                            vec.push(Err(e));
+                            continue;
+                        }
+
+                        for (_, db, old_key, new_key) in changed_indices.iter() {
+                            if let Some(old_key) = old_key
+                                && let Err(e) = db.delete_one_duplicate(self.txn, old_key, &node.id)
+                            {
+                                vec.push(Err(GraphError::from(e)));
+                            }
+                            if let Some(new_key) = new_key
+                                && let Err(e) = db.put(self.txn, new_key, &node.id)
+                            {
+                                vec.push(Err(GraphError::from(e)));
                             }
                         }
 
-                        if properties.is_empty() {
-                            old_node.properties = None;
-                        } else {
-                            old_node.properties = Some(properties);
+                        // only updates that touch a searchable field change the indexed text
+                        if let Some(bm25) = &storage.bm25
+                            && props.as_ref().is_some_and(|props| {
+                                props
+                                    .iter()
+                                    .any(|(k, _)| bm25.is_searchable(&old_node.label, k))
+                            })
+                            && let Err(e) = bm25.update_item(
+                                self.txn,
+                                &old_node.label,
+                                node.id,
+                                new_node.properties.as_ref(),
+                            )
+                        {
+                            vec.push(Err(e));
                         }
 
-                        match old_node.encode_node() {
+                        match new_node.encode_node() {
                             Ok(serialized) => {
                                 match storage.nodes_db.put(
                                     self.txn,
                                     HelixGraphStorage::node_key(&node.id),
                                     &serialized,
                                 ) {
-                                    Ok(_) => vec.push(Ok(TraversalVal::Node(old_node))),
+                                    Ok(_) => vec.push(Ok(TraversalVal::Node(new_node))),
                                     Err(e) => vec.push(Err(GraphError::from(e))),
                                 }
                             }
@@ -126,22 +146,62 @@ impl<'scope, 'env, I: Iterator<Item = Result<TraversalVal, GraphError>>> UpdateA
                 },
                 Ok(TraversalVal::Edge(edge)) => match storage.get_edge(self.txn, &edge.id) {
                     Ok(old_edge) => {
-                        let mut old_edge = old_edge.clone();
-                        if let Some(mut properties) = old_edge.properties.clone()
-                            && let Some(ref props) = props {
-                                for (k, v) in props.iter() {
-                                    properties.insert(k.clone(), v.clone());
-                                }
-                                old_edge.properties = Some(properties);
+                        let mut new_edge = old_edge.clone();
+                        let mut properties = new_edge.properties.take().unwrap_or_default();
+                        if let Some(ref props) = props {
+                            for (k, v) in props.iter() {
+                                properties.insert(k.clone(), v.clone());
+                            }
+                        }
+                        if !properties.is_empty() {
+                            new_edge.properties = Some(properties);
+                        }
+
+                        for (_, index, db) in storage.label_edge_secondary_indices(&old_edge.label)
+                        {
+                            if !props.as_ref().is_some_and(|props| {
+                                index_fields(index)
+                                    .any(|field| props.iter().any(|(k, _)| k == field))
+                            }) {
+                                continue;
+                            }
+                            if let Ok(old_key) = encode_item_index_key(&old_edge, index)
+                                && let Err(e) =
+                                    db.delete_one_duplicate(self.txn, &old_key, &edge.id)
+                            {
+                                vec.push(Err(GraphError::from(e)));
+                            }
+                            if let Ok(new_key) = encode_item_index_key(&new_edge, index)
+                                && let Err(e) = db.put(self.txn, &new_key, &edge.id)
+                            {
+                                vec.push(Err(GraphError::from(e)));
+                            }
                         }
-                        match old_edge.encode_edge() {
+
+                        if let Some(bm25) = &storage.bm25
+                            && props.as_ref().is_some_and(|props| {
+                                props
+                                    .iter()
+                                    .any(|(k, _)| bm25.is_searchable(&old_edge.label, k))
+                            })
+                            && let Err(e) = bm25.update_item(
+                                self.txn,
+                                &old_edge.label,
+                                edge.id,
+                                new_edge.properties.as_ref(),
+                            )
+                        {
+                            vec.push(Err(e));
+                        }
+
+                        match new_edge.encode_edge() {
                             Ok(serialized) => {
-                                match storage.nodes_db.put(
+                                match storage.edges_db.put(
                                     self.txn,
                                     HelixGraphStorage::edge_key(&edge.id),
                                     &serialized,
                                 ) {
-                                    Ok(_) => vec.push(Ok(TraversalVal::Edge(old_edge))),
+                                    Ok(_) => vec.push(Ok(TraversalVal::Edge(new_edge))),
                                     Err(e) => vec.push(Err(GraphError::from(e))),
                                 }
                             }
@@ -1,10 +1,10 @@
-use std::sync::Arc;
+use std::{cmp::Ordering, sync::Arc};
 
 use super::super::tr_val::TraversalVal;
 use crate::{
     helix_engine::{
         graph_core::traversal_iter::RoTraversalIterator, types::GraphError,
-        vector_core::vector_distance::cosine_similarity,
+        vector_core::vector::HVector,
     },
     protocol::value::Value,
     utils::filterable::Filterable,
@@ -49,12 +49,16 @@ impl<'a, I: Iterator<Item = Result<TraversalVal, GraphError>> + 'a> BruteForceSe
         K: TryInto<usize>,
         K::Error: std::fmt::Debug,
     {
-        let iter = self.inner.map(|v| match v {
+        let query = HVector::from_slice(0, query.to_vec());
+        let storage = Arc::clone(&self.storage);
+        let iter = self.inner.map(move |v| match v {
             Ok(TraversalVal::Vector(mut v)) => {
-                let d = cosine_similarity(v.get_data(), query).unwrap();
-                v.set_distance(d);
-                v
+                // each vector is compared under the metric of its own vector type
+                let metric = storage.vectors.metric(v.label());
+                v.set_distance(v.distance_to(&query, metric)?);
+                Ok(v)
             }
+            Err(e) => Err(e),
             other => {
                 println!("expected vector traversal values, got: {other:?}");
                 panic!("expected vector traversal values")
@@ -64,10 +68,20 @@ impl<'a, I: Iterator<Item = Result<TraversalVal, GraphError>> + 'a> BruteForceSe
         let storage = Arc::clone(&self.storage);
         let txn = self.txn;
 
+        // closest first, errors ahead of every vector so they are not cut off by `k`
         let iter = iter
-            .sorted_by(|v1, v2| v1.partial_cmp(v2).unwrap())
+            .sorted_by(|v1, v2| match (v1, v2) {
+                (Ok(v1), Ok(v2)) => v1.get_distance().total_cmp(&v2.get_distance()),
+                (Err(_), Ok(_)) => Ordering::Less,
+                (Ok(_), Err(_)) => Ordering::Greater,
+                (Err(_), Err(_)) => Ordering::Equal,
+            })
             .take(k.try_into().unwrap())
-            .filter_map(move |mut item| {
+            .filter_map(move |item| {
+                let mut item = match item {
+                    Ok(item) => item,
+                    Err(e) => return Some(Err(e)),
+                };
                 item.properties = match storage
                 .vectors
                 .vector_data_db
@@ -92,7 +106,7 @@ impl<'a, I: Iterator<Item = Result<TraversalVal, GraphError>> + 'a> BruteForceSe
                         if *is_deleted {
                             None
                         } else {
-                            Some(item)
+                            Some(Ok(item))
                         }
                     } else {
                         None
@@ -103,7 +117,7 @@ impl<'a, I: Iterator<Item = Result<TraversalVal, GraphError>> + 'a> BruteForceSe
 
                 // get properties
             })
-            .map(|v| Ok(TraversalVal::Vector(v)));
+            .map(|v| v.map(TraversalVal::Vector));
 
         RoTraversalIterator {
             inner: iter.into_iter(),
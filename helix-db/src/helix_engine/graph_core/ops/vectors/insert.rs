@@ -1,15 +1,16 @@
-use heed3::RoTxn;
+use heed3::{RoTxn, RwTxn};
 
 use super::super::tr_val::TraversalVal;
 use crate::{
     helix_engine::{
         graph_core::traversal_iter::RwTraversalIterator,
+        storage_core::storage_core::HelixGraphStorage,
         types::GraphError,
         vector_core::{hnsw::HNSW, vector::HVector},
     },
     protocol::value::Value,
 };
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 pub struct InsertVIterator {
     inner: std::iter::Once<Result<TraversalVal, GraphError>>,
@@ -36,6 +37,7 @@ pub trait InsertVAdapter<'a, 'b>: Iterator<Item = Result<TraversalVal, GraphErro
     fn insert_vs<F>(
         self,
         queries: &[Vec<f64>],
+        label: &str,
         fields: Option<Vec<(String, Value)>>,
     ) -> RwTraversalIterator<'a, 'b, impl Iterator<Item = Result<TraversalVal, GraphError>>>
     where
@@ -65,10 +67,20 @@ impl<'a, 'b, I: Iterator<Item = Result<TraversalVal, GraphError>>> InsertVAdapte
                 (String::from("is_deleted"), Value::Boolean(false)),
             ]),
         };
-        let vector = self.storage.vectors.insert::<F>(self.txn, query, fields);
+        let properties = searchable_properties(&self.storage, label, fields.as_deref());
+        let vector = self
+            .storage
+            .vectors
+            .insert::<F>(self.txn, label, query, fields);
 
         let result = match vector {
-            Ok(vector) => Ok(TraversalVal::Vector(vector)),
+            Ok(vector) => {
+                match insert_bm25_doc(&self.storage, self.txn, label, &vector, properties.as_ref())
+                {
+                    Ok(()) => Ok(TraversalVal::Vector(vector)),
+                    Err(e) => Err(e),
+                }
+            }
             Err(e) => Err(GraphError::from(e)),
         };
 
@@ -82,19 +94,28 @@ impl<'a, 'b, I: Iterator<Item = Result<TraversalVal, GraphError>>> InsertVAdapte
     fn insert_vs<F>(
         self,
         queries: &[Vec<f64>],
+        label: &str,
         fields: Option<Vec<(String, Value)>>,
     ) -> RwTraversalIterator<'a, 'b, impl Iterator<Item = Result<TraversalVal, GraphError>>>
     where
         F: Fn(&HVector, &RoTxn) -> bool,
     {
+        let mut fields = fields.unwrap_or_default();
+        fields.push((String::from("label"), Value::String(label.to_string())));
+        fields.push((String::from("is_deleted"), Value::Boolean(false)));
+        let properties = searchable_properties(&self.storage, label, Some(&fields));
+        let fields = Some(fields);
         let txn = self.txn;
         let storage = Arc::clone(&self.storage);
         let iter = queries
             .iter()
             .map(|vec| {
-                let vector = storage.vectors.insert::<F>(txn, vec, fields.clone()); // TODO: remove clone
+                let vector = storage.vectors.insert::<F>(txn, label, vec, fields.clone()); // TODO: remove clone
                 match vector {
-                    Ok(vector) => Ok(TraversalVal::Vector(vector)),
+                    Ok(vector) => {
+                        insert_bm25_doc(&storage, txn, label, &vector, properties.as_ref())?;
+                        Ok(TraversalVal::Vector(vector))
+                    }
                     Err(e) => Err(GraphError::from(e)),
                 }
             })
@@ -107,3 +128,30 @@ impl<'a, 'b, I: Iterator<Item = Result<TraversalVal, GraphError>>> InsertVAdapte
         }
     }
 }
+
+/// Collects the fields of new vectors when their label has a BM25 index
+fn searchable_properties(
+    storage: &HelixGraphStorage,
+    label: &str,
+    fields: Option<&[(String, Value)]>,
+) -> Option<HashMap<String, Value>> {
+    match (&storage.bm25, fields) {
+        (Some(bm25), Some(fields)) if bm25.indices.contains_key(label) => {
+            Some(fields.iter().cloned().collect())
+        }
+        _ => None,
+    }
+}
+
+fn insert_bm25_doc(
+    storage: &HelixGraphStorage,
+    txn: &mut RwTxn,
+    label: &str,
+    vector: &HVector,
+    properties: Option<&HashMap<String, Value>>,
+) -> Result<(), GraphError> {
+    match &storage.bm25 {
+        Some(bm25) => bm25.insert_item(txn, label, vector.id, properties),
+        None => Ok(()),
+    }
+}
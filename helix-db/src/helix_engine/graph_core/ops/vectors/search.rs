@@ -55,7 +55,7 @@ impl<'a, I: Iterator<Item = Result<TraversalVal, GraphError>> + 'a> SearchVAdapt
         let vectors =
             self.storage
                 .vectors
-                .search(self.txn, query, k.try_into().unwrap(), label, filter, false);
+                .search(self.txn, query, k.try_into().unwrap(), label, filter, true);
 
         let iter = match vectors {
             Ok(vectors) => vectors
@@ -88,6 +88,10 @@ impl<'a, I: Iterator<Item = Result<TraversalVal, GraphError>> + 'a> SearchVAdapt
                 let error = GraphError::VectorError("invalid vector dimensions!".to_string());
                 once(Err(error)).collect::<Vec<_>>().into_iter()
             }
+            Err(e @ VectorError::DimensionMismatch { .. }) => {
+                let error = GraphError::VectorError(e.to_string());
+                once(Err(error)).collect::<Vec<_>>().into_iter()
+            }
             Err(id) => {
                 let error = GraphError::VectorError(format!("vector already deleted for id {id}"));
                 once(Err(error)).collect::<Vec<_>>().into_iter()
@@ -53,6 +53,27 @@ impl<'a, I: Iterator<Item = Result<TraversalVal, GraphError>>> RoTraversalIterat
         }
     }
 
+    /// Like [`take_and_collect_to`](Self::take_and_collect_to) but returns the first error
+    /// instead of dropping it, so a query fails rather than returning partial results.
+    pub fn try_take_and_collect_to<B: FromIterator<TraversalVal>>(
+        self,
+        n: usize,
+    ) -> Result<B, GraphError> {
+        self.inner.take(n).collect::<Result<B, _>>()
+    }
+
+    /// Like [`collect_to`](Self::collect_to) but returns the first error instead of dropping it,
+    /// so a query fails rather than returning partial results.
+    pub fn try_collect_to<B: FromIterator<TraversalVal>>(self) -> Result<B, GraphError> {
+        self.inner.collect::<Result<B, _>>()
+    }
+
+    /// Like [`collect_to_obj`](Self::collect_to_obj) but returns the error of the first item
+    /// instead of skipping it, e.g. `NodeNotFound` for an id that isn't stored.
+    pub fn try_collect_to_obj(mut self) -> Result<TraversalVal, GraphError> {
+        self.inner.next().unwrap_or(Ok(TraversalVal::Empty))
+    }
+
     pub fn count_to_val(self) -> Value {
         Value::from(self.inner.count())
     }
@@ -113,6 +134,12 @@ impl<'scope, 'env, I: Iterator<Item = Result<TraversalVal, GraphError>>>
         self.inner.filter_map(|item| item.ok()).collect::<B>()
     }
 
+    /// Like [`collect_to`](Self::collect_to) but returns the first error instead of dropping it,
+    /// so the caller can abort the write transaction.
+    pub fn try_collect_to<B: FromIterator<TraversalVal>>(self) -> Result<B, GraphError> {
+        self.inner.collect::<Result<B, _>>()
+    }
+
     pub fn collect_to_val(self) -> TraversalVal
     where
         I: Iterator<Item = Result<TraversalVal, GraphError>>,
@@ -133,6 +160,19 @@ impl<'scope, 'env, I: Iterator<Item = Result<TraversalVal, GraphError>>>
             None => TraversalVal::Empty,
         }
     }
+
+    /// Like [`collect_to_obj`](Self::collect_to_obj) but returns the first error instead of
+    /// dropping it, so the caller can abort the write transaction.
+    pub fn try_collect_to_obj(self) -> Result<TraversalVal, GraphError> {
+        let mut first = None;
+        for item in self.inner {
+            let val = item?;
+            if first.is_none() {
+                first = Some(val);
+            }
+        }
+        Ok(first.unwrap_or(TraversalVal::Empty))
+    }
 }
 // pub trait TraversalIteratorMut<'a> {
 //     type Inner: Iterator<Item = Result<TraversalVal, GraphError>>;
@@ -1,27 +1,42 @@
-use std::{collections::HashMap, sync::Arc, time::Instant};
+use std::{collections::HashMap, ops::Bound, sync::Arc, time::Instant};
 
 use crate::{
     exclude_field,
     helix_engine::{
+        bm25::bm25::BM25,
         graph_core::ops::{
             g::G,
-            in_::{in_e::InEdgesAdapter, to_n::ToNAdapter, to_v::ToVAdapter},
-            out::{from_n::FromNAdapter, from_v::FromVAdapter, out::OutAdapter},
+            in_::{
+                in_e::InEdgesAdapter, in_hops::InHopsAdapter, to_n::ToNAdapter, to_v::ToVAdapter,
+            },
+            out::{
+                from_n::FromNAdapter, from_v::FromVAdapter, out::OutAdapter,
+                out_hops::OutHopsAdapter,
+            },
             source::{
-                add_n::AddNAdapter, e_from_id::EFromIdAdapter, n_from_id::NFromIdAdapter,
-                n_from_index::NFromIndexAdapter,
+                add_n::AddNAdapter, e_from_id::EFromIdAdapter, e_from_index::EFromIndexAdapter,
+                n_from_id::NFromIdAdapter,
+                n_from_index::{NFromCompositeIndexAdapter, NFromIndexAdapter},
             },
             tr_val::{Traversable, TraversalVal},
             util::{
-                dedup::DedupAdapter, map::MapAdapter, order::OrderByAdapter, props::PropsAdapter,
+                aggregate::{AggregateAdapter, Aggregation},
+                dedup::DedupAdapter,
+                map::MapAdapter,
+                order::OrderByAdapter,
+                props::PropsAdapter,
                 range::RangeAdapter,
             },
             vectors::brute_force_search::BruteForceSearchVAdapter,
         },
-        storage_core::storage_core::HelixGraphStorage,
+        storage_core::{
+            index_key::encode_index_key, storage_core::HelixGraphStorage,
+            storage_methods::StorageMethods,
+        },
         types::GraphError,
     },
     protocol::{
+        date::Date,
         remapping::{Remapping, RemappingMap, ResponseRemapping},
         return_values::ReturnValue,
     },
@@ -31,7 +46,7 @@ use crate::{
         source::n_from_type::NFromTypeAdapter, util::paths::ShortestPathAdapter,
     },
     protocol::value::Value,
-    utils::{filterable::Filterable, id::ID},
+    utils::{filterable::Filterable, id::ID, items::Node},
 };
 use crate::{
     helix_engine::{
@@ -40,7 +55,10 @@ use crate::{
             util::drop::Drop,
             vectors::{insert::InsertVAdapter, search::SearchVAdapter},
         },
-        vector_core::vector::HVector,
+        vector_core::{
+            vector::{HVector, VectorPrecision},
+            vector_distance::DistanceMetric,
+        },
     },
     props,
 };
@@ -49,11 +67,16 @@ use rand::Rng;
 use serde::{Deserialize, Serialize};
 use tempfile::TempDir;
 
+use super::config::VectorTypeConfig;
 use super::ops::{
     in_::in_::InAdapter,
     out::out_e::OutEdgesAdapter,
-    source::add_e::{AddEAdapter, EdgeType},
-    util::{filter_ref::FilterRefAdapter, update::UpdateAdapter},
+    source::{
+        add_e::{AddEAdapter, EdgeType},
+        upsert_e::UpsertEAdapter,
+        upsert_n::UpsertNAdapter,
+    },
+    util::{exist::Exist, filter_ref::FilterRefAdapter, update::UpdateAdapter},
 };
 
 fn setup_test_db() -> (Arc<HelixGraphStorage>, TempDir) {
@@ -199,6 +222,92 @@ fn test_out() {
     assert_eq!(nodes[0].id(), person2.id());
 }
 
+#[test]
+fn test_out_hops() {
+    let (storage, _temp_dir) = setup_test_db();
+    let mut txn = storage.graph_env.write_txn().unwrap();
+
+    // Create graph: (p0)-[knows]->(p1)-[knows]->(p2)-[knows]->(p3)-[knows]->(p0)
+    // with a shortcut (p1)-[knows]->(p3)
+    let people = (0..4)
+        .map(|_| {
+            G::new_mut(Arc::clone(&storage), &mut txn)
+                .add_n("person", Some(props!()), None)
+                .collect_to_obj()
+                .id()
+        })
+        .collect::<Vec<_>>();
+    for (from, to) in [(0, 1), (1, 2), (2, 3), (3, 0), (1, 3)] {
+        G::new_mut(Arc::clone(&storage), &mut txn)
+            .add_e(
+                "knows",
+                Some(props!()),
+                people[from],
+                people[to],
+                false,
+                EdgeType::Node,
+            )
+            .collect_to::<Vec<_>>();
+    }
+    txn.commit().unwrap();
+
+    let txn = storage.graph_env.read_txn().unwrap();
+    let hops = |min_depth, max_depth, out: bool| {
+        let start = G::new(Arc::clone(&storage), &txn).n_from_id(&people[0]);
+        let mut ids = match out {
+            true => start
+                .out_hops("knows", min_depth, max_depth)
+                .map(|node| node.unwrap().id())
+                .collect::<Vec<_>>(),
+            false => start
+                .in_hops("knows", min_depth, max_depth)
+                .map(|node| node.unwrap().id())
+                .collect::<Vec<_>>(),
+        };
+        ids.sort();
+        ids
+    };
+    let sorted = |indices: &[usize]| {
+        let mut ids = indices.iter().map(|i| people[*i]).collect::<Vec<_>>();
+        ids.sort();
+        ids
+    };
+
+    // the cycle back to p0 doesn't visit it again, p3 is only returned for the shortcut
+    assert_eq!(hops(1, 3, true), sorted(&[1, 2, 3]));
+    assert_eq!(hops(1, 10, true), sorted(&[1, 2, 3]));
+    // only the given level, and the starting node for a depth of 0
+    assert_eq!(hops(2, 2, true), sorted(&[2, 3]));
+    assert_eq!(hops(3, 3, true), sorted(&[]));
+    assert_eq!(hops(0, 1, true), sorted(&[0, 1]));
+
+    assert_eq!(hops(1, 1, false), sorted(&[3]));
+    assert_eq!(hops(2, 2, false), sorted(&[1, 2]));
+    assert_eq!(hops(1, 3, false), sorted(&[1, 2, 3]));
+
+    // every starting node is walked on its own, so starting nodes reach each other, and a node
+    // reached from several of them is returned once
+    let starts = [0, 1, 3]
+        .iter()
+        .map(|i| TraversalVal::Node(storage.get_node(&txn, &people[*i]).unwrap()))
+        .collect::<Vec<_>>();
+    for out in [true, false] {
+        let start = G::new_from(Arc::clone(&storage), &txn, starts.clone());
+        let mut ids = match out {
+            true => start
+                .out_hops("knows", 1, 1)
+                .map(|node| node.unwrap().id())
+                .collect::<Vec<_>>(),
+            false => start
+                .in_hops("knows", 1, 1)
+                .map(|node| node.unwrap().id())
+                .collect::<Vec<_>>(),
+        };
+        ids.sort();
+        assert_eq!(ids, sorted(&[0, 1, 2, 3]));
+    }
+}
+
 #[test]
 fn test_out_e() {
     let (storage, _temp_dir) = setup_test_db();
@@ -609,6 +718,163 @@ fn test_count_empty() {
     assert_eq!(count, 0);
 }
 
+#[test]
+fn test_aggregate_by() {
+    let (storage, _temp_dir) = setup_test_db();
+    let mut txn = storage.graph_env.write_txn().unwrap();
+    for (name, age, country) in [("alice", 25, "UK"), ("bob", 30, "UK"), ("carol", 41, "US")] {
+        G::new_mut(Arc::clone(&storage), &mut txn)
+            .add_n(
+                "person",
+                Some(props! { "name" => name, "age" => age, "country" => country }),
+                None,
+            )
+            .collect_to::<Vec<_>>();
+    }
+    G::new_mut(Arc::clone(&storage), &mut txn)
+        .add_n("person", Some(props! { "name" => "dave" }), None)
+        .collect_to::<Vec<_>>();
+    txn.commit().unwrap();
+
+    let aggregations = [
+        Aggregation::Count(None),
+        Aggregation::Sum("age"),
+        Aggregation::Avg("age"),
+        Aggregation::Min("name"),
+        Aggregation::Max("age"),
+    ];
+    let txn = storage.graph_env.read_txn().unwrap();
+    let aggregate = G::new(Arc::clone(&storage), &txn)
+        .n_from_type("person")
+        .aggregate_by(None, &aggregations)
+        .collect_to_obj();
+    let TraversalVal::Aggregate(aggregate) = aggregate else {
+        panic!("expected an aggregate, got {aggregate:?}");
+    };
+    assert_eq!(aggregate.get("count"), Some(&Value::U64(4)));
+    assert_eq!(aggregate.get("sum_age"), Some(&Value::I64(96)));
+    assert_eq!(aggregate.get("avg_age"), Some(&Value::F64(32.0)));
+    assert_eq!(aggregate.get("min_name"), Some(&Value::from("alice")));
+    assert_eq!(aggregate.get("max_age"), Some(&Value::I32(41)));
+
+    let groups = G::new(Arc::clone(&storage), &txn)
+        .n_from_type("person")
+        .aggregate_by(Some("country"), &aggregations)
+        .collect_to_obj();
+    let TraversalVal::Group(groups) = groups else {
+        panic!("expected groups, got {groups:?}");
+    };
+    assert_eq!(groups.len(), 3);
+    assert_eq!(groups["UK"]["count"], Value::U64(2));
+    assert_eq!(groups["UK"]["sum_age"], Value::I64(55));
+    assert_eq!(groups["UK"]["avg_age"], Value::F64(27.5));
+    assert_eq!(groups["US"]["min_name"], Value::from("carol"));
+    // items without the grouped property are grouped under null
+    assert_eq!(groups["null"]["count"], Value::U64(1));
+    assert_eq!(groups["null"]["sum_age"], Value::I64(0));
+    assert_eq!(groups["null"]["avg_age"], Value::Empty);
+
+    let json = sonic_rs::to_string(&ReturnValue::from(TraversalVal::Group(groups))).unwrap();
+    let json: HashMap<String, HashMap<String, sonic_rs::Value>> =
+        sonic_rs::from_str(&json).unwrap();
+    assert_eq!(json["UK"]["count"], sonic_rs::json!(2));
+    assert_eq!(json["US"]["max_age"], sonic_rs::json!(41));
+}
+
+#[test]
+fn test_aggregate_by_empty() {
+    let (storage, _temp_dir) = setup_test_db();
+    let txn = storage.graph_env.read_txn().unwrap();
+    let aggregate = G::new(Arc::clone(&storage), &txn)
+        .n_from_type("person")
+        .aggregate_by(None, &[Aggregation::Count(None), Aggregation::Sum("age")])
+        .collect_to_obj();
+    let TraversalVal::Aggregate(aggregate) = aggregate else {
+        panic!("expected an aggregate, got {aggregate:?}");
+    };
+    assert_eq!(aggregate.get("count"), Some(&Value::U64(0)));
+    assert_eq!(aggregate.get("sum_age"), Some(&Value::I64(0)));
+}
+
+#[test]
+fn test_aggregate_by_sum_beyond_i128() {
+    let (storage, _temp_dir) = setup_test_db();
+    let mut txn = storage.graph_env.write_txn().unwrap();
+    for balance in [u128::MAX, 1] {
+        G::new_mut(Arc::clone(&storage), &mut txn)
+            .add_n("account", Some(props! { "balance" => balance }), None)
+            .collect_to::<Vec<_>>();
+    }
+    txn.commit().unwrap();
+
+    let txn = storage.graph_env.read_txn().unwrap();
+    let aggregate = G::new(Arc::clone(&storage), &txn)
+        .n_from_type("account")
+        .aggregate_by(None, &[Aggregation::Sum("balance")])
+        .collect_to_obj();
+    let TraversalVal::Aggregate(aggregate) = aggregate else {
+        panic!("expected an aggregate, got {aggregate:?}");
+    };
+    // too large for an integer sum, so summed as a float instead of wrapping negative
+    assert_eq!(
+        aggregate.get("sum_balance"),
+        Some(&Value::F64(u128::MAX as f64 + 1.0))
+    );
+    drop(txn);
+
+    // integers that only overflow once summed carry on as a float too
+    let mut txn = storage.graph_env.write_txn().unwrap();
+    for balance in [i128::MAX as u128, i128::MAX as u128] {
+        G::new_mut(Arc::clone(&storage), &mut txn)
+            .add_n("ledger", Some(props! { "balance" => balance }), None)
+            .collect_to::<Vec<_>>();
+    }
+    txn.commit().unwrap();
+    let txn = storage.graph_env.read_txn().unwrap();
+    let aggregate = G::new(Arc::clone(&storage), &txn)
+        .n_from_type("ledger")
+        .aggregate_by(None, &[Aggregation::Sum("balance")])
+        .collect_to_obj();
+    let TraversalVal::Aggregate(aggregate) = aggregate else {
+        panic!("expected an aggregate, got {aggregate:?}");
+    };
+    assert_eq!(
+        aggregate.get("sum_balance"),
+        Some(&Value::F64(i128::MAX as f64 * 2.0))
+    );
+}
+
+#[test]
+fn test_aggregate_by_groups_values_by_type() {
+    let (storage, _temp_dir) = setup_test_db();
+    let mut txn = storage.graph_env.write_txn().unwrap();
+    for code in [
+        Value::from("1"),
+        Value::I64(1),
+        Value::I64(1),
+        Value::I64(2),
+    ] {
+        G::new_mut(Arc::clone(&storage), &mut txn)
+            .add_n("item", Some(vec![("code".to_string(), code)]), None)
+            .collect_to::<Vec<_>>();
+    }
+    txn.commit().unwrap();
+
+    let txn = storage.graph_env.read_txn().unwrap();
+    let groups = G::new(Arc::clone(&storage), &txn)
+        .n_from_type("item")
+        .aggregate_by(Some("code"), &[Aggregation::Count(None)])
+        .collect_to_obj();
+    let TraversalVal::Group(groups) = groups else {
+        panic!("expected groups, got {groups:?}");
+    };
+    assert_eq!(groups.len(), 3);
+    assert_eq!(groups["String(1)"]["count"], Value::U64(1));
+    assert_eq!(groups["I64(1)"]["count"], Value::U64(2));
+    // values no other type shares keep their plain key
+    assert_eq!(groups["2"]["count"], Value::U64(1));
+}
+
 #[test]
 fn test_n_from_id() {
     let (storage, _temp_dir) = setup_test_db();
@@ -725,6 +991,44 @@ fn test_e_from_id_nonexistent() {
     assert!(edges.is_empty());
 }
 
+#[test]
+fn test_try_collect_to_nonexistent() {
+    let (storage, _temp_dir) = setup_test_db();
+    let mut txn = storage.graph_env.write_txn().unwrap();
+    let person = G::new_mut(Arc::clone(&storage), &mut txn)
+        .add_n("person", Some(props!()), None)
+        .try_collect_to_obj()
+        .unwrap();
+    txn.commit().unwrap();
+
+    let txn = storage.graph_env.read_txn().unwrap();
+    let nodes = G::new(Arc::clone(&storage), &txn)
+        .n_from_id(&person.id())
+        .try_collect_to::<Vec<_>>()
+        .unwrap();
+    assert_eq!(nodes.len(), 1);
+
+    // unlike `collect_to`, the error isn't dropped from the results
+    let nodes = G::new(Arc::clone(&storage), &txn)
+        .n_from_id(&100)
+        .try_collect_to::<Vec<_>>();
+    assert!(matches!(nodes, Err(GraphError::NodeNotFound)));
+    let node = G::new(Arc::clone(&storage), &txn)
+        .n_from_id(&100)
+        .try_collect_to_obj();
+    assert!(matches!(node, Err(GraphError::NodeNotFound)));
+    let edges = G::new(Arc::clone(&storage), &txn)
+        .e_from_id(&100)
+        .try_take_and_collect_to::<Vec<_>>(1);
+    assert!(matches!(edges, Err(GraphError::EdgeNotFound)));
+
+    let edges = G::new(Arc::clone(&storage), &txn)
+        .e_from_id(&100)
+        .try_take_and_collect_to::<Vec<_>>(0)
+        .unwrap();
+    assert!(edges.is_empty());
+}
+
 #[test]
 fn test_n_from_id_chain_operations() {
     let (storage, _temp_dir) = setup_test_db();
@@ -1853,6 +2157,72 @@ fn test_brute_force_vector_search() {
     assert_eq!(traversal[2].id(), vector_ids[2]);
 }
 
+/// Ranks `[10, 1]`, `[1, 0]` and `[0.5, 1]` against `[1, 0]` with a vector type of `metric`,
+/// returning the positions of the vectors from closest to furthest
+fn brute_force_order(metric: DistanceMetric) -> Vec<usize> {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().to_str().unwrap();
+    let mut config = super::config::Config::default();
+    config.vector_config.as_mut().unwrap().vector_types = Some(vec![VectorTypeConfig {
+        label: "embedding".to_string(),
+        dimensions: Some(2),
+        metric,
+        precision: VectorPrecision::F64,
+        rerank: false,
+    }]);
+    let storage = Arc::new(HelixGraphStorage::new(db_path, config, Default::default()).unwrap());
+    let mut txn = storage.graph_env.write_txn().unwrap();
+
+    let node = G::new_mut(Arc::clone(&storage), &mut txn)
+        .add_n("person", None, None)
+        .collect_to_val();
+    let mut vector_ids = Vec::new();
+    for vector in [[10.0, 1.0], [1.0, 0.0], [0.5, 1.0]] {
+        let vector_id = G::new_mut(Arc::clone(&storage), &mut txn)
+            .insert_v::<fn(&HVector, &RoTxn) -> bool>(&vector, "embedding", None)
+            .collect_to_val()
+            .id();
+        G::new_mut(Arc::clone(&storage), &mut txn)
+            .add_e(
+                "embedding",
+                None,
+                node.id(),
+                vector_id,
+                false,
+                EdgeType::Vec,
+            )
+            .collect_to_val();
+        vector_ids.push(vector_id);
+    }
+    txn.commit().unwrap();
+
+    let txn = storage.graph_env.read_txn().unwrap();
+    G::new(Arc::clone(&storage), &txn)
+        .n_from_id(&node.id())
+        .out_e("embedding")
+        .to_v()
+        .brute_force_search_v(&[1.0, 0.0], 10)
+        .collect_to::<Vec<_>>()
+        .iter()
+        .map(|v| vector_ids.iter().position(|id| *id == v.id()).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_brute_force_vector_search_cosine() {
+    assert_eq!(brute_force_order(DistanceMetric::Cosine), vec![1, 0, 2]);
+}
+
+#[test]
+fn test_brute_force_vector_search_dot_product() {
+    assert_eq!(brute_force_order(DistanceMetric::DotProduct), vec![0, 1, 2]);
+}
+
+#[test]
+fn test_brute_force_vector_search_euclidean() {
+    assert_eq!(brute_force_order(DistanceMetric::Euclidean), vec![1, 2, 0]);
+}
+
 #[test]
 fn test_order_by_desc() {
     let (storage, _temp_dir) = setup_test_db();
@@ -2250,6 +2620,62 @@ fn test_delete_vector() {
     assert_eq!(traversal.len(), 0);
 }
 
+#[test]
+fn test_search_v_prefilter() {
+    let (db, _temp_dir) = setup_test_db();
+    let mut txn = db.graph_env.write_txn().unwrap();
+
+    let mut rng = rand::rng();
+    let mut tenant_docs = Vec::new();
+    for i in 0..200 {
+        let vector: Vec<f64> = (0..16).map(|_| rng.random::<f64>()).collect();
+        let tenant_id = if i % 40 == 0 { "a" } else { "b" };
+        let doc = G::new_mut(Arc::clone(&db), &mut txn)
+            .insert_v::<fn(&HVector, &RoTxn) -> bool>(
+                &vector,
+                "doc",
+                Some(vec![("tenant_id".to_string(), Value::from(tenant_id))]),
+            )
+            .collect_to_val();
+        if tenant_id == "a" {
+            tenant_docs.push(doc.id());
+        }
+    }
+    txn.commit().unwrap();
+
+    let txn = db.graph_env.read_txn().unwrap();
+    let tenant_id = "a".to_string();
+    let query: Vec<f64> = (0..16).map(|_| rng.random::<f64>()).collect();
+    // as generated for `SearchV<doc>(query, 10)::PREFILTER(_::{tenant_id}::EQ(tenant_id))`
+    let docs = G::new(Arc::clone(&db), &txn)
+        .search_v::<_, _>(
+            &query,
+            10,
+            "doc",
+            Some(&[|v: &HVector, txn: &RoTxn| {
+                let val = TraversalVal::Vector(v.clone());
+                Exist::exists(
+                    &mut G::new_from(Arc::clone(&db), txn, vec![val.clone()]).filter_ref(
+                        |val, txn| {
+                            if let Ok(val) = val {
+                                Ok(G::new_from(Arc::clone(&db), txn, val.clone())
+                                    .check_property("tenant_id")
+                                    .map_value_or(false, |v| *v == tenant_id.clone())?)
+                            } else {
+                                Ok(false)
+                            }
+                        },
+                    ),
+                )
+            }]),
+        )
+        .collect_to::<Vec<_>>();
+
+    // fewer vectors match than were asked for, so the whole graph is searched for them
+    assert_eq!(docs.len(), tenant_docs.len());
+    assert!(docs.iter().all(|doc| tenant_docs.contains(&doc.id())));
+}
+
 /*
 QUERY updateEntity (entity_id: ID, name: String, name_embedding: [F64], group_id: String, summary: String, created_at: Date, labels: [String], attributes: String) =>
     entity <- N<Entity>(entity_id)::UPDATE({name: name, group_id: group_id, summary: summary, created_at: created_at, labels: labels, attributes: attributes})
@@ -2689,7 +3115,7 @@ fn test_update_of_secondary_indices() {
         let temp_dir = TempDir::new().unwrap();
         let db_path = temp_dir.path().to_str().unwrap();
         let mut config = super::config::Config::default();
-        config.graph_config.as_mut().unwrap().secondary_indices = Some(vec!["name".to_string()]);
+        config.graph_config.as_mut().unwrap().secondary_indices = Some(vec!["person.name".to_string()]);
         let storage = HelixGraphStorage::new(db_path, config, Default::default()).unwrap();
         (Arc::new(storage), temp_dir)
     };
@@ -2709,6 +3135,7 @@ fn test_update_of_secondary_indices() {
 
     let node = G::new(Arc::clone(&storage), &txn)
         .n_from_index("person", "name", &"Jane".to_string())
+        .unwrap()
         .collect_to::<Vec<_>>();
     assert_eq!(node.len(), 1);
     assert_eq!(node[0].id(), node.id());
@@ -2723,19 +3150,66 @@ fn test_update_of_secondary_indices() {
 
     let node = G::new(Arc::clone(&storage), &txn)
         .n_from_index("person", "name", &"John".to_string())
+        .unwrap()
         .collect_to::<Vec<_>>();
     assert_eq!(node.len(), 0);
 
     txn.commit().unwrap();
 }
 
+#[test]
+fn test_add_n_returns_index_error() {
+    let (storage, _temp_dir) = setup_test_db();
+    let mut txn = storage.graph_env.write_txn().unwrap();
+    let result = G::new_mut(Arc::clone(&storage), &mut txn)
+        .add_n("person", Some(props! { "name" => "John" }), Some(&["name"]))
+        .next()
+        .unwrap();
+    assert!(
+        matches!(result, Err(GraphError::New(ref msg)) if msg.contains("not found for label person"))
+    );
+}
+
+#[test]
+fn test_update_onto_key_of_newer_node() {
+    let (storage, _temp_dir) = {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().to_str().unwrap();
+        let mut config = super::config::Config::default();
+        config.graph_config.as_mut().unwrap().secondary_indices =
+            Some(vec!["person.name".to_string()]);
+        let storage = HelixGraphStorage::new(db_path, config, Default::default()).unwrap();
+        (Arc::new(storage), temp_dir)
+    };
+    let mut txn = storage.graph_env.write_txn().unwrap();
+    let older = G::new_mut(Arc::clone(&storage), &mut txn)
+        .add_n("person", Some(props! { "name" => "John" }), Some(&["name"]))
+        .collect_to_val();
+    G::new_mut(Arc::clone(&storage), &mut txn)
+        .add_n("person", Some(props! { "name" => "Jane" }), Some(&["name"]))
+        .collect_to_val();
+
+    // the updated node's id sorts before the one already stored under the key
+    G::new_mut_from(Arc::clone(&storage), &mut txn, older)
+        .update(Some(props! { "name" => "Jane" }))
+        .collect_to_val();
+    txn.commit().unwrap();
+
+    let txn = storage.graph_env.read_txn().unwrap();
+    let janes = G::new(Arc::clone(&storage), &txn)
+        .n_from_index("person", "name", &"Jane".to_string())
+        .unwrap()
+        .collect_to::<Vec<_>>();
+    assert_eq!(janes.len(), 2);
+}
+
 #[test]
 fn test_delete_node_with_secondary_index() {
     let (storage, _) = {
         let temp_dir = TempDir::new().unwrap();
         let db_path = temp_dir.path().to_str().unwrap();
         let mut config = super::config::Config::default();
-        config.graph_config.as_mut().unwrap().secondary_indices = Some(vec!["name".to_string()]);
+        config.graph_config.as_mut().unwrap().secondary_indices = Some(vec!["person.name".to_string()]);
         let storage = HelixGraphStorage::new(db_path, config, Default::default()).unwrap();
         (Arc::new(storage), temp_dir)
   
@@ -2758,12 +3232,14 @@ fn test_delete_node_with_secondary_index() {
 
     let jane_nodes = G::new(Arc::clone(&storage), &txn)
         .n_from_index("person", "name", &"Jane".to_string())
+        .unwrap()
         .collect_to::<Vec<_>>();
     assert_eq!(jane_nodes.len(), 1);
     assert_eq!(jane_nodes[0].id(), node_id);  // Compare with original node id
 
     let john_nodes = G::new(Arc::clone(&storage), &txn)
         .n_from_index("person", "name", &"John".to_string())
+        .unwrap()
         .collect_to::<Vec<_>>();
     assert_eq!(john_nodes.len(), 0);
 
@@ -2785,7 +3261,1120 @@ fn test_delete_node_with_secondary_index() {
 
     let node = G::new(Arc::clone(&storage), &txn)
         .n_from_index("person", "name", &"Jane".to_string())
+        .unwrap()
         .collect_to::<Vec<_>>();
     assert_eq!(node.len(), 0);
 
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_n_from_index_range() {
+    let (storage, _) = {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().to_str().unwrap();
+        let mut config = super::config::Config::default();
+        config.graph_config.as_mut().unwrap().secondary_indices = Some(vec!["person.age".to_string()]);
+        let storage = HelixGraphStorage::new(db_path, config, Default::default()).unwrap();
+        (Arc::new(storage), temp_dir)
+    };
+    let mut txn = storage.graph_env.write_txn().unwrap();
+
+    // mixed integer widths and signs must share one ordering
+    for age in [30i64, -5, 18, 200, 17, 0] {
+        G::new_mut(Arc::clone(&storage), &mut txn)
+            .add_n("person", Some(props! { "age" => age }), Some(&["age"]))
+            .collect_to_val();
+    }
+    G::new_mut(Arc::clone(&storage), &mut txn)
+        .add_n("person", Some(props! { "age" => 18u8 }), Some(&["age"]))
+        .collect_to_val();
+    txn.commit().unwrap();
+
+    let txn = storage.graph_env.read_txn().unwrap();
+    let ages = |nodes: Vec<TraversalVal>| {
+        nodes
+            .iter()
+            .map(|n| match n.check_property("age").unwrap().into_owned() {
+                Value::I64(i) => i,
+                Value::U8(u) => u as i64,
+                other => panic!("unexpected age {other:?}"),
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let adults = G::new(Arc::clone(&storage), &txn)
+        .n_from_index_range("person", "age", Bound::Included(&18), Bound::Unbounded)
+        .unwrap()
+        .collect_to::<Vec<_>>();
+    assert_eq!(ages(adults), vec![18, 18, 30, 200]);
+
+    let minors = G::new(Arc::clone(&storage), &txn)
+        .n_from_index_range("person", "age", Bound::Unbounded, Bound::Excluded(&18))
+        .unwrap()
+        .collect_to::<Vec<_>>();
+    assert_eq!(ages(minors), vec![-5, 0, 17]);
+
+    let between = G::new(Arc::clone(&storage), &txn)
+        .n_from_index_range("person", "age", Bound::Excluded(&0), Bound::Included(&30))
+        .unwrap()
+        .collect_to::<Vec<_>>();
+    assert_eq!(ages(between), vec![17, 18, 18, 30]);
+
+    let exact = G::new(Arc::clone(&storage), &txn)
+        .n_from_index("person", "age", &18u64)
+        .unwrap()
+        .collect_to::<Vec<_>>();
+    assert_eq!(exact.len(), 2);
+
+    // an index that was never declared is an error rather than a panic
+    assert!(matches!(
+        G::new(Arc::clone(&storage), &txn).n_from_index("person", "height", &18u64),
+        Err(GraphError::New(_))
+    ));
+    assert!(matches!(
+        G::new(Arc::clone(&storage), &txn).e_from_index("follows", "height", &18u64),
+        Err(GraphError::New(_))
+    ));
+}
+
+#[test]
+fn test_n_from_index_range_dates() {
+    let (storage, _temp_dir) = {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().to_str().unwrap();
+        let mut config = super::config::Config::default();
+        config.graph_config.as_mut().unwrap().secondary_indices =
+            Some(vec!["person.created_at".to_string()]);
+        let storage = HelixGraphStorage::new(db_path, config, Default::default()).unwrap();
+        (Arc::new(storage), temp_dir)
+    };
+    let date = |s: &str| Value::from(s.parse::<chrono::DateTime<chrono::Utc>>().unwrap());
+    let mut txn = storage.graph_env.write_txn().unwrap();
+
+    // ordered by instant: 09:00Z, 10:00Z, 11:00Z, 12:00Z, which is not their string order
+    for created_at in [
+        "2024-01-01T12:00:00+02:00",
+        "2024-01-01T06:00:00-05:00",
+        "2024-01-01T09:00:00Z",
+        "2024-01-01T12:00:00+00:00",
+    ] {
+        G::new_mut(Arc::clone(&storage), &mut txn)
+            .add_n(
+                "person",
+                Some(props! { "created_at" => date(created_at) }),
+                Some(&["created_at"]),
+            )
+            .collect_to_val();
+    }
+    txn.commit().unwrap();
+
+    let txn = storage.graph_env.read_txn().unwrap();
+    let hours = |nodes: Vec<TraversalVal>| {
+        nodes
+            .iter()
+            .map(
+                |n| match n.check_property("created_at").unwrap().into_owned() {
+                    Value::Date(d) => d.format("%H").to_string(),
+                    other => panic!("unexpected created_at {other:?}"),
+                },
+            )
+            .collect::<Vec<_>>()
+    };
+
+    let after = G::new(Arc::clone(&storage), &txn)
+        .n_from_index_range(
+            "person",
+            "created_at",
+            Bound::Excluded(&date("2024-01-01T11:00:00+02:00")),
+            Bound::Unbounded,
+        )
+        .unwrap()
+        .collect_to::<Vec<_>>();
+    assert_eq!(hours(after), vec!["10", "11", "12"]);
+
+    let between = G::new(Arc::clone(&storage), &txn)
+        .n_from_index_range(
+            "person",
+            "created_at",
+            Bound::Included(&date("2024-01-01T04:00:00-05:00")),
+            Bound::Excluded(&date("2024-01-01T13:00:00+02:00")),
+        )
+        .unwrap()
+        .collect_to::<Vec<_>>();
+    assert_eq!(hours(between), vec!["09", "10"]);
+}
+
+#[test]
+fn test_index_key_ordering() {
+    let ordered = [
+        vec![
+            Value::I64(i64::MIN),
+            Value::I32(-1),
+            Value::U8(0),
+            Value::I16(1),
+            Value::U64(u64::MAX),
+            Value::U128(u128::MAX),
+        ],
+        vec![
+            Value::F64(f64::NEG_INFINITY),
+            Value::F64(-2.5),
+            Value::F32(-0.5),
+            Value::F64(0.0),
+            Value::F32(0.25),
+            Value::F64(1e10),
+        ],
+        vec![
+            Value::String("".to_string()),
+            Value::String("a".to_string()),
+            Value::String("a\0".to_string()),
+            Value::String("ab".to_string()),
+            Value::String("b".to_string()),
+        ],
+        vec![
+            Value::Date(Date::new(&Value::I64(-86_400)).unwrap()),
+            Value::Date(Date::new(&Value::String("2021-01-01".to_string())).unwrap()),
+            Value::Date(Date::new(&Value::String("2021-01-01T00:00:00.5Z".to_string())).unwrap()),
+            Value::Date(Date::new(&Value::String("2024-06-30T12:00:00Z".to_string())).unwrap()),
+        ],
+    ];
+    for values in ordered {
+        for pair in values.windows(2) {
+            assert!(
+                encode_index_key(&pair[0]) < encode_index_key(&pair[1]),
+                "{:?} should sort before {:?}",
+                pair[0],
+                pair[1]
+            );
+        }
+    }
+    assert_eq!(
+        encode_index_key(&Value::I8(42)),
+        encode_index_key(&Value::U64(42))
+    );
+}
+
+#[test]
+fn test_secondary_index_in_older_key_format_rebuilt_on_open() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().to_str().unwrap();
+    let mut config = super::config::Config::default();
+    config.graph_config.as_mut().unwrap().secondary_indices = Some(vec!["person.name".to_string()]);
+    {
+        let storage =
+            Arc::new(HelixGraphStorage::new(db_path, config.clone(), Default::default()).unwrap());
+        let mut txn = storage.graph_env.write_txn().unwrap();
+        let names = ["Alice", "Bob", "Carol"];
+        let ids = names
+            .iter()
+            .map(|name| {
+                G::new_mut(Arc::clone(&storage), &mut txn)
+                    .add_n("person", Some(props! { "name" => *name }), Some(&["name"]))
+                    .collect_to_val()
+                    .id()
+            })
+            .collect::<Vec<_>>();
+
+        // simulate an index written with the `bincode` keys used before the current format
+        let db = storage.secondary_index("person", "name").unwrap();
+        db.clear(&mut txn).unwrap();
+        for (name, id) in names.iter().zip(ids) {
+            let key = bincode::serialize(&Value::from(*name)).unwrap();
+            db.put(&mut txn, &key, &id).unwrap();
+        }
+        let formats = storage
+            .graph_env
+            .open_database::<heed3::types::Str, heed3::types::Bytes>(&txn, Some("index_formats"))
+            .unwrap()
+            .unwrap();
+        formats.delete(&mut txn, "person.name").unwrap();
+        txn.commit().unwrap();
+    }
+
+    let storage = Arc::new(HelixGraphStorage::new(db_path, config, Default::default()).unwrap());
+    let txn = storage.graph_env.read_txn().unwrap();
+    let db = storage.secondary_index("person", "name").unwrap();
+    assert_eq!(db.len(&txn).unwrap(), 3);
+    let bob = G::new(Arc::clone(&storage), &txn)
+        .n_from_index("person", "name", &"Bob".to_string())
+        .unwrap()
+        .collect_to::<Vec<_>>();
+    assert_eq!(bob.len(), 1);
+}
+
+#[test]
+fn test_unqualified_secondary_index_qualified_from_schema() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().to_str().unwrap();
+    {
+        let storage =
+            HelixGraphStorage::new(db_path, Default::default(), Default::default()).unwrap();
+        let storage = Arc::new(storage);
+        let mut txn = storage.graph_env.write_txn().unwrap();
+        for label in ["person", "company"] {
+            G::new_mut(Arc::clone(&storage), &mut txn)
+                .add_n(label, Some(props! { "name" => "John" }), None)
+                .collect_to_val();
+        }
+        txn.commit().unwrap();
+    }
+
+    let schema = r#"{"schema": {"nodes": [
+        {"name": "person", "properties": {"id": "ID", "name": "String"}},
+        {"name": "company", "properties": {"id": "ID", "employees": "I64"}}
+    ], "vectors": [], "edges": []}, "queries": []}"#;
+    let mut config = super::config::Config::default();
+    config.schema = Some(schema.to_string());
+    config.graph_config.as_mut().unwrap().secondary_indices = Some(vec!["name".to_string()]);
+    let storage = Arc::new(HelixGraphStorage::new(db_path, config, Default::default()).unwrap());
+
+    // the index is only built for the labels that declare the field
+    assert!(storage.secondary_index("company", "name").is_err());
+    let txn = storage.graph_env.read_txn().unwrap();
+    let people = G::new(Arc::clone(&storage), &txn)
+        .n_from_index("person", "name", &"John".to_string())
+        .unwrap()
+        .collect_to::<Vec<_>>();
+    assert_eq!(people.len(), 1);
+    assert_eq!(people[0].label(), "person");
+    drop(txn);
+    drop(storage);
+
+    // without a label to place it on the index can't be opened
+    let mut config = super::config::Config::default();
+    config.schema = Some(schema.to_string());
+    config.graph_config.as_mut().unwrap().secondary_indices = Some(vec!["age".to_string()]);
+    let result = HelixGraphStorage::new(db_path, config, Default::default());
+    assert!(matches!(result, Err(GraphError::New(ref msg)) if msg.contains("helix compile")));
+}
+
+#[test]
+fn test_open_with_many_secondary_indices() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().to_str().unwrap();
+    let mut config = super::config::Config::default();
+    let graph_config = config.graph_config.as_mut().unwrap();
+    graph_config.secondary_indices = Some((0..40).map(|i| format!("person.field_{i}")).collect());
+    graph_config.edge_secondary_indices =
+        Some((0..20).map(|i| format!("knows.field_{i}")).collect());
+    let storage = HelixGraphStorage::new(db_path, config, Default::default()).unwrap();
+    assert_eq!(storage.secondary_indices.len(), 40);
+    assert_eq!(storage.edge_secondary_indices.len(), 20);
+}
+
+#[test]
+fn test_per_field_index_cleared_once_superseded() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().to_str().unwrap();
+    {
+        let storage =
+            HelixGraphStorage::new(db_path, Default::default(), Default::default()).unwrap();
+        // simulate the index over `name` for every label, from before indices had a label
+        let mut txn = storage.graph_env.write_txn().unwrap();
+        let db: heed3::Database<heed3::types::Bytes, heed3::types::Bytes> = storage
+            .graph_env
+            .create_database(&mut txn, Some("name"))
+            .unwrap();
+        db.put(&mut txn, b"John", &[0; 16]).unwrap();
+        txn.commit().unwrap();
+    }
+
+    let mut config = super::config::Config::default();
+    config.graph_config.as_mut().unwrap().secondary_indices = Some(vec!["person.name".to_string()]);
+    let storage = Arc::new(HelixGraphStorage::new(db_path, config, Default::default()).unwrap());
+    let txn = storage.graph_env.read_txn().unwrap();
+    let db = storage
+        .graph_env
+        .open_database::<heed3::types::Bytes, heed3::types::Bytes>(&txn, Some("name"))
+        .unwrap()
+        .unwrap();
+    assert!(db.is_empty(&txn).unwrap());
+}
+
+#[test]
+fn test_n_from_type_uses_label_index() {
+    let (storage, _temp_dir) = setup_test_db();
+    let mut txn = storage.graph_env.write_txn().unwrap();
+
+    let person = G::new_mut(Arc::clone(&storage), &mut txn)
+        .add_n("person", Some(props! { "name" => "John" }), None)
+        .collect_to_val();
+    let _ = G::new_mut(Arc::clone(&storage), &mut txn)
+        .add_n("company", Some(props! { "name" => "Helix" }), None)
+        .collect_to_val();
+    let edge = G::new_mut(Arc::clone(&storage), &mut txn)
+        .add_e("knows", None, person.id(), person.id(), false, EdgeType::Node)
+        .collect_to_val();
+    txn.commit().unwrap();
+
+    let txn = storage.graph_env.read_txn().unwrap();
+    let people = G::new(Arc::clone(&storage), &txn)
+        .n_from_type("person")
+        .collect_to::<Vec<_>>();
+    assert_eq!(people.len(), 1);
+    assert_eq!(people[0].id(), person.id());
+    let edges = G::new(Arc::clone(&storage), &txn)
+        .e_from_type("knows")
+        .collect_to::<Vec<_>>();
+    assert_eq!(edges.len(), 1);
+    assert_eq!(edges[0].id(), edge.id());
+    assert_eq!(storage.node_labels_db.len(&txn).unwrap(), 2);
+    txn.commit().unwrap();
+
+    let mut txn = storage.graph_env.write_txn().unwrap();
+    storage.drop_node(&mut txn, &person.id()).unwrap();
+    txn.commit().unwrap();
+
+    let txn = storage.graph_env.read_txn().unwrap();
+    let people = G::new(Arc::clone(&storage), &txn)
+        .n_from_type("person")
+        .collect_to::<Vec<_>>();
+    assert!(people.is_empty());
+    let edges = G::new(Arc::clone(&storage), &txn)
+        .e_from_type("knows")
+        .collect_to::<Vec<_>>();
+    assert!(edges.is_empty());
+    assert_eq!(storage.node_labels_db.len(&txn).unwrap(), 1);
+    assert_eq!(storage.edge_labels_db.len(&txn).unwrap(), 0);
+}
+
+#[test]
+fn test_label_index_built_on_open() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().to_str().unwrap();
+    {
+        let storage = Arc::new(
+            HelixGraphStorage::new(db_path, super::config::Config::default(), Default::default())
+                .unwrap(),
+        );
+        let mut txn = storage.graph_env.write_txn().unwrap();
+        let from = G::new_mut(Arc::clone(&storage), &mut txn)
+            .add_n("person", None, None)
+            .collect_to_val();
+        let to = G::new_mut(Arc::clone(&storage), &mut txn)
+            .add_n("person", None, None)
+            .collect_to_val();
+        let _ = G::new_mut(Arc::clone(&storage), &mut txn)
+            .add_e("knows", None, from.id(), to.id(), false, EdgeType::Node)
+            .collect_to_val();
+        // simulate a database written before the label indices existed
+        storage.node_labels_db.clear(&mut txn).unwrap();
+        storage.edge_labels_db.clear(&mut txn).unwrap();
+        txn.commit().unwrap();
+    }
+
+    let storage = Arc::new(
+        HelixGraphStorage::new(db_path, super::config::Config::default(), Default::default())
+            .unwrap(),
+    );
+    let txn = storage.graph_env.read_txn().unwrap();
+    let people = G::new(Arc::clone(&storage), &txn)
+        .n_from_type("person")
+        .collect_to::<Vec<_>>();
+    assert_eq!(people.len(), 2);
+    let edges = G::new(Arc::clone(&storage), &txn)
+        .e_from_type("knows")
+        .collect_to::<Vec<_>>();
+    assert_eq!(edges.len(), 1);
+}
+
+#[test]
+fn test_label_index_build_resumes_in_batches() {
+    let (storage, _temp_dir) = setup_test_db();
+    let mut txn = storage.graph_env.write_txn().unwrap();
+    let people = (0..5)
+        .map(|_| {
+            G::new_mut(Arc::clone(&storage), &mut txn)
+                .add_n("person", None, None)
+                .collect_to_val()
+                .id()
+        })
+        .collect::<Vec<_>>();
+    // simulate a build that was interrupted after the first two nodes
+    storage.node_labels_db.clear(&mut txn).unwrap();
+    for id in &people[..2] {
+        storage
+            .node_labels_db
+            .put(&mut txn, &HelixGraphStorage::label_key("person"), id)
+            .unwrap();
+    }
+    txn.commit().unwrap();
+
+    HelixGraphStorage::build_label_index(
+        &storage.graph_env,
+        storage.nodes_db,
+        storage.node_labels_db,
+        2,
+        |id, data| Ok(Node::decode_node(data, id)?.label),
+    )
+    .unwrap();
+
+    let txn = storage.graph_env.read_txn().unwrap();
+    assert_eq!(storage.node_labels_db.len(&txn).unwrap(), 5);
+    let found = G::new(Arc::clone(&storage), &txn)
+        .n_from_type("person")
+        .collect_to::<Vec<_>>();
+    assert_eq!(found.len(), 5);
+}
+
+fn setup_unique_test_db() -> (Arc<HelixGraphStorage>, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().to_str().unwrap();
+    let mut config = super::config::Config::default();
+    config.graph_config.as_mut().unwrap().unique_indices = Some(vec!["user.email".to_string()]);
+    let storage = HelixGraphStorage::new(db_path, config, Default::default()).unwrap();
+    (Arc::new(storage), temp_dir)
+}
+
+#[test]
+fn test_add_n_unique_index_rejects_duplicate() {
+    let (storage, _temp_dir) = setup_unique_test_db();
+
+    let mut txn = storage.graph_env.write_txn().unwrap();
+    G::new_mut(Arc::clone(&storage), &mut txn)
+        .add_n("user", Some(props! { "email" => "a@x.com" }), Some(&["email"]))
+        .try_collect_to_obj()
+        .unwrap();
+    txn.commit().unwrap();
+
+    let mut txn = storage.graph_env.write_txn().unwrap();
+    G::new_mut(Arc::clone(&storage), &mut txn)
+        .add_n("user", Some(props! { "email" => "b@x.com" }), Some(&["email"]))
+        .try_collect_to_obj()
+        .unwrap();
+    let result = G::new_mut(Arc::clone(&storage), &mut txn)
+        .add_n("user", Some(props! { "email" => "a@x.com" }), Some(&["email"]))
+        .try_collect_to_obj();
+    assert!(matches!(result, Err(GraphError::DuplicateKey(ref index)) if index == "user.email"));
+    // generated handlers return early on the error, which aborts the transaction
+    drop(txn);
+
+    let txn = storage.graph_env.read_txn().unwrap();
+    let users = G::new(Arc::clone(&storage), &txn)
+        .n_from_type("user")
+        .collect_to::<Vec<_>>();
+    assert_eq!(users.len(), 1);
+    let users = G::new(Arc::clone(&storage), &txn)
+        .n_from_index("user", "email", &"a@x.com".to_string())
+        .unwrap()
+        .collect_to::<Vec<_>>();
+    assert_eq!(users.len(), 1);
+    let users = G::new(Arc::clone(&storage), &txn)
+        .n_from_index("user", "email", &"b@x.com".to_string())
+        .unwrap()
+        .collect_to::<Vec<_>>();
+    assert!(users.is_empty());
+}
+
+#[test]
+fn test_update_unique_index_rejects_duplicate() {
+    let (storage, _temp_dir) = setup_unique_test_db();
+
+    let mut txn = storage.graph_env.write_txn().unwrap();
+    let alice = G::new_mut(Arc::clone(&storage), &mut txn)
+        .add_n("user", Some(props! { "email" => "a@x.com" }), Some(&["email"]))
+        .try_collect_to_obj()
+        .unwrap();
+    let bob = G::new_mut(Arc::clone(&storage), &mut txn)
+        .add_n("user", Some(props! { "email" => "b@x.com" }), Some(&["email"]))
+        .try_collect_to_obj()
+        .unwrap();
+
+    let result = G::new_mut_from(Arc::clone(&storage), &mut txn, bob.clone())
+        .update(Some(props! { "email" => "a@x.com" }))
+        .try_collect_to_obj();
+    assert!(matches!(result, Err(GraphError::DuplicateKey(ref index)) if index == "user.email"));
+
+    // writing a node's own value back is not a conflict
+    G::new_mut_from(Arc::clone(&storage), &mut txn, alice.clone())
+        .update(Some(props! { "email" => "a@x.com" }))
+        .try_collect_to_obj()
+        .unwrap();
+    txn.commit().unwrap();
+
+    let txn = storage.graph_env.read_txn().unwrap();
+    let users = G::new(Arc::clone(&storage), &txn)
+        .n_from_index("user", "email", &"a@x.com".to_string())
+        .unwrap()
+        .collect_to::<Vec<_>>();
+    assert_eq!(users.len(), 1);
+    assert_eq!(users[0].id(), alice.id());
+    let users = G::new(Arc::clone(&storage), &txn)
+        .n_from_index("user", "email", &"b@x.com".to_string())
+        .unwrap()
+        .collect_to::<Vec<_>>();
+    assert_eq!(users.len(), 1);
+    assert_eq!(users[0].id(), bob.id());
+}
+
+#[test]
+fn test_unique_index_on_existing_duplicates_fails_open() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().to_str().unwrap();
+    let mut config = super::config::Config::default();
+    config.graph_config.as_mut().unwrap().secondary_indices = Some(vec!["user.email".to_string()]);
+    {
+        let storage =
+            Arc::new(HelixGraphStorage::new(db_path, config.clone(), Default::default()).unwrap());
+        let mut txn = storage.graph_env.write_txn().unwrap();
+        for email in ["a@x.com", "b@x.com", "a@x.com"] {
+            G::new_mut(Arc::clone(&storage), &mut txn)
+                .add_n("user", Some(props! { "email" => email }), Some(&["email"]))
+                .try_collect_to_obj()
+                .unwrap();
+        }
+        txn.commit().unwrap();
+    }
+
+    // the existing index holds the duplicates, it is checked once it becomes unique
+    config.graph_config.as_mut().unwrap().unique_indices = Some(vec!["user.email".to_string()]);
+    let result = HelixGraphStorage::new(db_path, config, Default::default());
+    assert!(matches!(
+        result,
+        Err(GraphError::DuplicateIndexKey(ref index, ref key))
+            if index == "user.email" && key.contains("a@x.com")
+    ));
+}
+
+#[test]
+fn test_n_from_composite_index() {
+    let (storage, _temp_dir) = {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().to_str().unwrap();
+        let mut config = super::config::Config::default();
+        config.graph_config.as_mut().unwrap().secondary_indices =
+            Some(vec!["post.user_id,created_at".to_string()]);
+        let storage = HelixGraphStorage::new(db_path, config, Default::default()).unwrap();
+        (Arc::new(storage), temp_dir)
+    };
+    let mut txn = storage.graph_env.write_txn().unwrap();
+
+    // the second field of one user must never leak into the range of another
+    for (user_id, created_at) in [(1i64, 30i64), (2, 5), (1, 10), (1, 20), (0, 15), (1, -1)] {
+        G::new_mut(Arc::clone(&storage), &mut txn)
+            .add_n(
+                "post",
+                Some(props! { "user_id" => user_id, "created_at" => created_at }),
+                Some(&["user_id,created_at"]),
+            )
+            .collect_to_val();
+    }
+    txn.commit().unwrap();
+
+    let txn = storage.graph_env.read_txn().unwrap();
+    let created = |nodes: Vec<TraversalVal>| {
+        nodes
+            .iter()
+            .map(|n| match n.check_property("created_at").unwrap().into_owned() {
+                Value::I64(i) => i,
+                other => panic!("unexpected created_at {other:?}"),
+            })
+            .collect::<Vec<_>>()
+    };
+    let scan = |prefix: &[Value], start: Bound<Value>, end: Bound<Value>| {
+        G::new(Arc::clone(&storage), &txn)
+            .n_from_composite_index("post", "user_id,created_at", prefix, start, end)
+            .unwrap()
+            .collect_to::<Vec<_>>()
+    };
+
+    let all = scan(&[Value::I64(1)], Bound::Unbounded, Bound::Unbounded);
+    assert_eq!(created(all), vec![-1, 10, 20, 30]);
+
+    let since = scan(&[Value::I64(1)], Bound::Included(Value::I64(10)), Bound::Unbounded);
+    assert_eq!(created(since), vec![10, 20, 30]);
+
+    let after = scan(&[Value::I64(1)], Bound::Excluded(Value::I64(10)), Bound::Unbounded);
+    assert_eq!(created(after), vec![20, 30]);
+
+    let until = scan(&[Value::I64(1)], Bound::Unbounded, Bound::Included(Value::I64(20)));
+    assert_eq!(created(until), vec![-1, 10, 20]);
+
+    let before = scan(&[Value::I64(1)], Bound::Unbounded, Bound::Excluded(Value::I64(20)));
+    assert_eq!(created(before), vec![-1, 10]);
+
+    let exact = scan(&[Value::I64(1), Value::I64(20)], Bound::Unbounded, Bound::Unbounded);
+    assert_eq!(created(exact), vec![20]);
+
+    let by_first_field = scan(&[], Bound::Included(Value::I64(1)), Bound::Included(Value::I64(2)));
+    assert_eq!(created(by_first_field), vec![-1, 10, 20, 30, 5]);
+}
+
+#[test]
+fn test_update_and_drop_composite_index() {
+    let (storage, _temp_dir) = {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().to_str().unwrap();
+        let mut config = super::config::Config::default();
+        config.graph_config.as_mut().unwrap().secondary_indices =
+            Some(vec!["account.tenant_id,external_id".to_string()]);
+        let storage = HelixGraphStorage::new(db_path, config, Default::default()).unwrap();
+        (Arc::new(storage), temp_dir)
+    };
+    let lookup = |txn: &RoTxn, tenant_id: &str, external_id: &str| {
+        G::new(Arc::clone(&storage), txn)
+            .n_from_composite_index(
+                "account",
+                "tenant_id,external_id",
+                &[Value::from(tenant_id), Value::from(external_id)],
+                Bound::Unbounded,
+                Bound::Unbounded,
+            )
+            .unwrap()
+            .collect_to::<Vec<_>>()
+    };
+
+    let mut txn = storage.graph_env.write_txn().unwrap();
+    let account = G::new_mut(Arc::clone(&storage), &mut txn)
+        .add_n(
+            "account",
+            Some(props! { "tenant_id" => "acme", "external_id" => "a-1" }),
+            Some(&["tenant_id,external_id"]),
+        )
+        .collect_to_val();
+    // changing either field moves the composite key
+    G::new_mut_from(Arc::clone(&storage), &mut txn, account.clone())
+        .update(Some(props! { "external_id" => "a-2" }))
+        .collect_to_val();
+    txn.commit().unwrap();
+
+    let txn = storage.graph_env.read_txn().unwrap();
+    assert!(lookup(&txn, "acme", "a-1").is_empty());
+    let found = lookup(&txn, "acme", "a-2");
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].id(), account.id());
+    txn.commit().unwrap();
+
+    let mut txn = storage.graph_env.write_txn().unwrap();
+    storage.drop_node(&mut txn, &account.id()).unwrap();
+    txn.commit().unwrap();
+
+    let txn = storage.graph_env.read_txn().unwrap();
+    assert!(lookup(&txn, "acme", "a-2").is_empty());
+}
+
+#[test]
+fn test_secondary_indices_are_per_label() {
+    let (storage, _temp_dir) = {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().to_str().unwrap();
+        let mut config = super::config::Config::default();
+        let graph_config = config.graph_config.as_mut().unwrap();
+        graph_config.secondary_indices = Some(vec!["company.name".to_string()]);
+        graph_config.unique_indices = Some(vec!["user.name".to_string()]);
+        let storage = HelixGraphStorage::new(db_path, config, Default::default()).unwrap();
+        (Arc::new(storage), temp_dir)
+    };
+
+    let mut txn = storage.graph_env.write_txn().unwrap();
+    let user = G::new_mut(Arc::clone(&storage), &mut txn)
+        .add_n("user", Some(props! { "name" => "acme" }), Some(&["name"]))
+        .try_collect_to_obj()
+        .unwrap();
+    // the same value under another label is neither a duplicate nor a hit
+    let company = G::new_mut(Arc::clone(&storage), &mut txn)
+        .add_n("company", Some(props! { "name" => "acme" }), Some(&["name"]))
+        .try_collect_to_obj()
+        .unwrap();
+    txn.commit().unwrap();
+
+    let txn = storage.graph_env.read_txn().unwrap();
+    let users = G::new(Arc::clone(&storage), &txn)
+        .n_from_index("user", "name", &"acme".to_string())
+        .unwrap()
+        .collect_to::<Vec<_>>();
+    assert_eq!(users.len(), 1);
+    assert_eq!(users[0].id(), user.id());
+    let companies = G::new(Arc::clone(&storage), &txn)
+        .n_from_index("company", "name", &"acme".to_string())
+        .unwrap()
+        .collect_to::<Vec<_>>();
+    assert_eq!(companies.len(), 1);
+    assert_eq!(companies[0].id(), company.id());
+    assert!(storage.secondary_index("person", "name").is_err());
+}
+
+#[test]
+fn test_secondary_index_built_on_open() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().to_str().unwrap();
+    {
+        let storage = Arc::new(
+            HelixGraphStorage::new(db_path, super::config::Config::default(), Default::default())
+                .unwrap(),
+        );
+        let mut txn = storage.graph_env.write_txn().unwrap();
+        for (label, name) in [("person", "alice"), ("person", "bob"), ("pet", "alice")] {
+            G::new_mut(Arc::clone(&storage), &mut txn)
+                .add_n(label, Some(props! { "name" => name }), None)
+                .collect_to_val();
+        }
+        G::new_mut(Arc::clone(&storage), &mut txn)
+            .add_n("person", None, None)
+            .collect_to_val();
+        txn.commit().unwrap();
+    }
+
+    let mut config = super::config::Config::default();
+    config.graph_config.as_mut().unwrap().secondary_indices = Some(vec!["person.name".to_string()]);
+    let storage = Arc::new(HelixGraphStorage::new(db_path, config, Default::default()).unwrap());
+    let txn = storage.graph_env.read_txn().unwrap();
+    let people = G::new(Arc::clone(&storage), &txn)
+        .n_from_index_range("person", "name", Bound::<&String>::Unbounded, Bound::Unbounded)
+        .unwrap()
+        .collect_to::<Vec<_>>();
+    assert_eq!(people.len(), 2);
+    assert!(people.iter().all(|node| node.label() == "person"));
+}
+
+#[test]
+fn test_unqualified_secondary_index_rejected() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().to_str().unwrap();
+    let mut config = super::config::Config::default();
+    config.graph_config.as_mut().unwrap().secondary_indices = Some(vec!["name".to_string()]);
+    assert!(HelixGraphStorage::new(db_path, config, Default::default()).is_err());
+}
+
+fn setup_edge_index_test_db() -> (Arc<HelixGraphStorage>, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().to_str().unwrap();
+    let mut config = super::config::Config::default();
+    config.graph_config.as_mut().unwrap().edge_secondary_indices =
+        Some(vec!["follows.external_ref".to_string()]);
+    let storage = HelixGraphStorage::new(db_path, config, Default::default()).unwrap();
+    (Arc::new(storage), temp_dir)
+}
+
+#[test]
+fn test_update_onto_key_of_newer_edge() {
+    let (storage, _temp_dir) = setup_edge_index_test_db();
+    let mut txn = storage.graph_env.write_txn().unwrap();
+    let alice = G::new_mut(Arc::clone(&storage), &mut txn)
+        .add_n("user", None, None)
+        .collect_to_val();
+    let bob = G::new_mut(Arc::clone(&storage), &mut txn)
+        .add_n("user", None, None)
+        .collect_to_val();
+    let mut follows = Vec::new();
+    for external_ref in ["ref-1", "ref-2"] {
+        follows.push(
+            G::new_mut(Arc::clone(&storage), &mut txn)
+                .add_e(
+                    "follows",
+                    Some(props! { "external_ref" => external_ref }),
+                    alice.id(),
+                    bob.id(),
+                    false,
+                    EdgeType::Node,
+                )
+                .collect_to_val(),
+        );
+    }
+
+    // the updated edge's id sorts before the one already stored under the key
+    G::new_mut_from(Arc::clone(&storage), &mut txn, follows[0].clone())
+        .update(Some(props! { "external_ref" => "ref-2" }))
+        .collect_to_val();
+    txn.commit().unwrap();
+
+    let txn = storage.graph_env.read_txn().unwrap();
+    let found = G::new(Arc::clone(&storage), &txn)
+        .e_from_index("follows", "external_ref", &"ref-2".to_string())
+        .unwrap()
+        .collect_to::<Vec<_>>();
+    assert_eq!(found.len(), 2);
+}
+
+#[test]
+fn test_e_from_index() {
+    let (storage, _temp_dir) = setup_edge_index_test_db();
+    let lookup = |txn: &RoTxn, external_ref: &str| {
+        G::new(Arc::clone(&storage), txn)
+            .e_from_index("follows", "external_ref", &external_ref.to_string())
+            .unwrap()
+            .collect_to::<Vec<_>>()
+    };
+
+    let mut txn = storage.graph_env.write_txn().unwrap();
+    let alice = G::new_mut(Arc::clone(&storage), &mut txn)
+        .add_n("user", None, None)
+        .collect_to_val();
+    let bob = G::new_mut(Arc::clone(&storage), &mut txn)
+        .add_n("user", None, None)
+        .collect_to_val();
+    let follows = G::new_mut(Arc::clone(&storage), &mut txn)
+        .add_e(
+            "follows",
+            Some(props! { "external_ref" => "ref-1" }),
+            alice.id(),
+            bob.id(),
+            false,
+            EdgeType::Node,
+        )
+        .collect_to_val();
+    // edges of another label or without the field are not indexed
+    G::new_mut(Arc::clone(&storage), &mut txn)
+        .add_e(
+            "likes",
+            Some(props! { "external_ref" => "ref-1" }),
+            alice.id(),
+            bob.id(),
+            false,
+            EdgeType::Node,
+        )
+        .collect_to_val();
+    G::new_mut(Arc::clone(&storage), &mut txn)
+        .add_e("follows", None, bob.id(), alice.id(), false, EdgeType::Node)
+        .collect_to_val();
+    txn.commit().unwrap();
+
+    let txn = storage.graph_env.read_txn().unwrap();
+    let found = lookup(&txn, "ref-1");
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].id(), follows.id());
+    let all = G::new(Arc::clone(&storage), &txn)
+        .e_from_index_range("follows", "external_ref", Bound::<&String>::Unbounded, Bound::Unbounded)
+        .unwrap()
+        .collect_to::<Vec<_>>();
+    assert_eq!(all.len(), 1);
+    txn.commit().unwrap();
+
+    let mut txn = storage.graph_env.write_txn().unwrap();
+    G::new_mut_from(Arc::clone(&storage), &mut txn, follows.clone())
+        .update(Some(props! { "external_ref" => "ref-2" }))
+        .collect_to_val();
+    txn.commit().unwrap();
+
+    let txn = storage.graph_env.read_txn().unwrap();
+    assert!(lookup(&txn, "ref-1").is_empty());
+    let found = lookup(&txn, "ref-2");
+    assert_eq!(found.len(), 1);
+    // the update is stored with the edge itself
+    assert_eq!(
+        found[0].check_property("external_ref").unwrap().as_ref(),
+        &Value::from("ref-2")
+    );
+    txn.commit().unwrap();
+
+    let mut txn = storage.graph_env.write_txn().unwrap();
+    storage.drop_edge(&mut txn, &follows.id()).unwrap();
+    let other = G::new_mut(Arc::clone(&storage), &mut txn)
+        .add_e(
+            "follows",
+            Some(props! { "external_ref" => "ref-3" }),
+            alice.id(),
+            bob.id(),
+            false,
+            EdgeType::Node,
+        )
+        .collect_to_val();
+    txn.commit().unwrap();
+
+    let txn = storage.graph_env.read_txn().unwrap();
+    assert!(lookup(&txn, "ref-2").is_empty());
+    assert_eq!(lookup(&txn, "ref-3")[0].id(), other.id());
+    txn.commit().unwrap();
+
+    // dropping a node drops the index keys of its edges
+    let mut txn = storage.graph_env.write_txn().unwrap();
+    storage.drop_node(&mut txn, &alice.id()).unwrap();
+    txn.commit().unwrap();
+
+    let txn = storage.graph_env.read_txn().unwrap();
+    assert!(lookup(&txn, "ref-3").is_empty());
+}
+
+#[test]
+fn test_edge_secondary_index_built_on_open() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().to_str().unwrap();
+    {
+        let storage = Arc::new(
+            HelixGraphStorage::new(db_path, super::config::Config::default(), Default::default())
+                .unwrap(),
+        );
+        let mut txn = storage.graph_env.write_txn().unwrap();
+        let alice = G::new_mut(Arc::clone(&storage), &mut txn)
+            .add_n("user", None, None)
+            .collect_to_val();
+        let bob = G::new_mut(Arc::clone(&storage), &mut txn)
+            .add_n("user", None, None)
+            .collect_to_val();
+        for label in ["follows", "likes"] {
+            G::new_mut(Arc::clone(&storage), &mut txn)
+                .add_e(
+                    label,
+                    Some(props! { "external_ref" => "ref-1" }),
+                    alice.id(),
+                    bob.id(),
+                    false,
+                    EdgeType::Node,
+                )
+                .collect_to_val();
+        }
+        txn.commit().unwrap();
+    }
+
+    let mut config = super::config::Config::default();
+    config.graph_config.as_mut().unwrap().edge_secondary_indices =
+        Some(vec!["follows.external_ref".to_string()]);
+    let storage = Arc::new(HelixGraphStorage::new(db_path, config, Default::default()).unwrap());
+    let txn = storage.graph_env.read_txn().unwrap();
+    let follows = G::new(Arc::clone(&storage), &txn)
+        .e_from_index("follows", "external_ref", &"ref-1".to_string())
+        .unwrap()
+        .collect_to::<Vec<_>>();
+    assert_eq!(follows.len(), 1);
+    assert_eq!(follows[0].label(), "follows");
+}
+
+#[test]
+fn test_upsert_n() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().to_str().unwrap();
+    let mut config = super::config::Config::default();
+    config.graph_config.as_mut().unwrap().unique_indices = Some(vec!["user.email".to_string()]);
+    config.bm25_indices = Some(vec![super::config::BM25IndexConfig {
+        label: "user".to_string(),
+        fields: vec!["bio".to_string()],
+        analyzers: Default::default(),
+        scoring: Default::default(),
+    }]);
+    let storage = Arc::new(HelixGraphStorage::new(db_path, config, Default::default()).unwrap());
+    let bm25_ids = |txn: &RoTxn, query: &str| {
+        storage
+            .bm25
+            .as_ref()
+            .unwrap()
+            .collection("user")
+            .search(txn, query, 10)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>()
+    };
+
+    let mut txn = storage.graph_env.write_txn().unwrap();
+    let alice = G::new_mut(Arc::clone(&storage), &mut txn)
+        .upsert_n(
+            "user",
+            "email",
+            Some(props! { "email" => "a@x.com", "name" => "Alice", "bio" => "rust graphs" }),
+            Some(props! { "plan" => "free" }),
+            Some(&["email"]),
+        )
+        .try_collect_to_obj()
+        .unwrap();
+    txn.commit().unwrap();
+
+    let mut txn = storage.graph_env.write_txn().unwrap();
+    let updated = G::new_mut(Arc::clone(&storage), &mut txn)
+        .upsert_n(
+            "user",
+            "email",
+            Some(props! { "email" => "a@x.com", "name" => "Alicia", "bio" => "python scripts" }),
+            Some(props! { "plan" => "trial" }),
+            Some(&["email"]),
+        )
+        .try_collect_to_obj()
+        .unwrap();
+    assert_eq!(updated.id(), alice.id());
+    // a key the node has to be found by is required
+    let result = G::new_mut(Arc::clone(&storage), &mut txn)
+        .upsert_n(
+            "user",
+            "email",
+            Some(props! { "name" => "Bob" }),
+            None,
+            Some(&["email"]),
+        )
+        .try_collect_to_obj();
+    assert!(result.is_err());
+    txn.commit().unwrap();
+
+    let txn = storage.graph_env.read_txn().unwrap();
+    let users = G::new(Arc::clone(&storage), &txn)
+        .n_from_type("user")
+        .collect_to::<Vec<_>>();
+    assert_eq!(users.len(), 1);
+    let users = G::new(Arc::clone(&storage), &txn)
+        .n_from_index("user", "email", &"a@x.com".to_string())
+        .unwrap()
+        .collect_to::<Vec<_>>();
+    assert_eq!(users.len(), 1);
+    assert_eq!(users[0].id(), alice.id());
+    assert_eq!(
+        users[0].check_property("name").unwrap().as_ref(),
+        &Value::from("Alicia")
+    );
+    // defaults are only written when the node is added
+    assert_eq!(
+        users[0].check_property("plan").unwrap().as_ref(),
+        &Value::from("free")
+    );
+    assert!(bm25_ids(&txn, "rust").is_empty());
+    assert_eq!(bm25_ids(&txn, "python"), vec![alice.id()]);
+}
+
+#[test]
+fn test_upsert_e() {
+    let (storage, _temp_dir) = setup_edge_index_test_db();
+    let lookup = |txn: &RoTxn, external_ref: &str| {
+        G::new(Arc::clone(&storage), txn)
+            .e_from_index("follows", "external_ref", &external_ref.to_string())
+            .unwrap()
+            .collect_to::<Vec<_>>()
+    };
+
+    let mut txn = storage.graph_env.write_txn().unwrap();
+    let alice = G::new_mut(Arc::clone(&storage), &mut txn)
+        .add_n("user", None, None)
+        .collect_to_val();
+    let bob = G::new_mut(Arc::clone(&storage), &mut txn)
+        .add_n("user", None, None)
+        .collect_to_val();
+    let follows = G::new_mut(Arc::clone(&storage), &mut txn)
+        .upsert_e(
+            "follows",
+            Some(props! { "external_ref" => "ref-1" }),
+            alice.id(),
+            bob.id(),
+        )
+        .try_collect_to_obj()
+        .unwrap();
+    txn.commit().unwrap();
+
+    let mut txn = storage.graph_env.write_txn().unwrap();
+    let updated = G::new_mut(Arc::clone(&storage), &mut txn)
+        .upsert_e(
+            "follows",
+            Some(props! { "external_ref" => "ref-2" }),
+            alice.id(),
+            bob.id(),
+        )
+        .try_collect_to_obj()
+        .unwrap();
+    assert_eq!(updated.id(), follows.id());
+    // the same nodes in the other direction, or under another label, are other edges
+    let back = G::new_mut(Arc::clone(&storage), &mut txn)
+        .upsert_e("follows", None, bob.id(), alice.id())
+        .try_collect_to_obj()
+        .unwrap();
+    assert_ne!(back.id(), follows.id());
+    let likes = G::new_mut(Arc::clone(&storage), &mut txn)
+        .upsert_e("likes", None, alice.id(), bob.id())
+        .try_collect_to_obj()
+        .unwrap();
+    assert_ne!(likes.id(), follows.id());
+    txn.commit().unwrap();
+
+    let txn = storage.graph_env.read_txn().unwrap();
+    let edges = G::new(Arc::clone(&storage), &txn)
+        .e_from_type("follows")
+        .collect_to::<Vec<_>>();
+    assert_eq!(edges.len(), 2);
+    assert!(lookup(&txn, "ref-1").is_empty());
+    let found = lookup(&txn, "ref-2");
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].id(), follows.id());
+}
@@ -42,8 +42,8 @@ pub trait GraphVisualization {
     /// * `txn` - Read-only transaction for database access.
     ///
     /// # Returns
-    /// JSON string with counts of nodes, edges, and vectors, or a `GraphError` if serialization
-    /// fails.
+    /// JSON string with counts of nodes, edges, and vectors, and the scoring and statistics of
+    /// each BM25 index, or a `GraphError` if serialization fails.
     ///
     /// # Errors
     /// Returns `GraphError` if JSON serialization fails.
@@ -79,6 +79,7 @@ impl GraphVisualization for HelixGraphStorage {
             "num_nodes":   self.nodes_db.len(txn).unwrap_or(0),
             "num_edges":   self.edges_db.len(txn).unwrap_or(0),
             "num_vectors": self.vectors.vectors_db.len(txn).unwrap_or(0),
+            "bm25_indices": self.bm25_stats(txn)?,
         });
         debug_println!("db stats json: {:?}", result);
 
@@ -88,6 +89,31 @@ impl GraphVisualization for HelixGraphStorage {
 
 /// Implementing the helper functions needed to get the data for graph visualization
 impl HelixGraphStorage {
+    /// The active scoring parameters and collection statistics of each label's BM25 index
+    fn bm25_stats(&self, txn: &RoTxn) -> Result<Vec<JsonValue>, GraphError> {
+        let Some(bm25) = &self.bm25 else {
+            return Ok(Vec::new());
+        };
+        let mut labels = bm25.indices.keys().collect::<Vec<_>>();
+        labels.sort();
+        labels
+            .into_iter()
+            .map(|label| {
+                let collection = bm25.collection(label);
+                let metadata = collection.metadata(txn)?;
+                let scoring = sonic_rs::to_value(collection.scoring())
+                    .map_err(|e| GraphError::New(e.to_string()))?;
+                Ok(json!({
+                    "label": label.as_str(),
+                    "fields": bm25.indices[label].fields.clone(),
+                    "scoring": scoring,
+                    "total_docs": metadata.as_ref().map_or(0, |m| m.total_docs),
+                    "avgdl": metadata.as_ref().map_or(0.0, |m| m.avgdl),
+                }))
+            })
+            .collect()
+    }
+
     /// Get the top k nodes and all of the edges associated with them by checking their
     /// cardinalities (total number of in and out edges)
     ///
@@ -0,0 +1,205 @@
+//! Order-preserving key encoding for secondary indices.
+//!
+//! LMDB compares keys as raw bytes, so every [`Value`] is encoded such that the byte-wise
+//! ordering of two encoded keys matches the ordering of the values themselves.
+//! This is what allows secondary indices to be scanned with a range cursor.
+//!
+//! Each key starts with a 1 byte type tag followed by the payload:
+//!
+//! - integers are widened to `i128` and stored big-endian with the sign bit flipped,
+//!   so all integer widths share one ordering (`u128` values above `i128::MAX` get their own tag)
+//! - floats are widened to `f64` and stored in IEEE-754 total order
+//! - dates are stored as a sign-flipped seconds timestamp followed by the sub-second nanos
+//! - strings are stored as their UTF-8 bytes with `0x00` escaped and a `0x00 0x00` terminator
+//! - arrays and objects are the concatenation of their encoded elements with a `0x00` terminator
+//!
+//! Every encoding is self-delimiting, so a composite index key is simply the concatenation of
+//! the encoded field values and sorts by the first field, then the second, and so on.
+
+use crate::{
+    helix_engine::types::GraphError, protocol::value::Value, utils::filterable::Filterable,
+};
+
+const TAG_TERMINATOR: u8 = 0x00;
+const TAG_EMPTY: u8 = 0x01;
+const TAG_BOOLEAN: u8 = 0x02;
+const TAG_INT: u8 = 0x03;
+const TAG_BIG_UINT: u8 = 0x04;
+const TAG_FLOAT: u8 = 0x05;
+const TAG_DATE: u8 = 0x06;
+const TAG_STRING: u8 = 0x07;
+const TAG_ID: u8 = 0x08;
+const TAG_ARRAY: u8 = 0x09;
+const TAG_OBJECT: u8 = 0x0A;
+
+const ESCAPE: u8 = 0xFF;
+
+/// Version of the key encoding, stored for every secondary index so indices written with an older
+/// encoding, like the `bincode` keys used before this one, are rebuilt when the database is opened.
+pub const INDEX_KEY_FORMAT: u8 = 1;
+
+/// Separates the field names in the name of a composite index, e.g. `tenant_id,external_id`.
+pub const COMPOSITE_INDEX_SEPARATOR: char = ',';
+
+/// Separates the label from the indexed fields in the name of a secondary index, e.g. `User.email`.
+pub const LABEL_INDEX_SEPARATOR: char = '.';
+
+/// Encodes a value into an order-preserving secondary index key.
+#[inline]
+pub fn encode_index_key(value: &Value) -> Vec<u8> {
+    let mut key = Vec::with_capacity(17);
+    write_value(&mut key, value);
+    key
+}
+
+/// Encodes the values of a composite index, in field order, into a single key.
+pub fn encode_composite_index_key<'a>(values: impl IntoIterator<Item = &'a Value>) -> Vec<u8> {
+    let mut key = Vec::with_capacity(34);
+    for value in values {
+        write_value(&mut key, value);
+    }
+    key
+}
+
+/// Returns the name of the composite index over the given fields.
+#[inline]
+pub fn composite_index_name<S: AsRef<str>>(fields: &[S]) -> String {
+    fields
+        .iter()
+        .map(AsRef::as_ref)
+        .collect::<Vec<_>>()
+        .join(&COMPOSITE_INDEX_SEPARATOR.to_string())
+}
+
+/// Returns the name of the secondary index over `index` for nodes with the given label.
+#[inline]
+pub fn label_index_name(label: &str, index: &str) -> String {
+    format!("{label}{LABEL_INDEX_SEPARATOR}{index}")
+}
+
+/// Splits a secondary index name into its label and the fields it covers.
+#[inline]
+pub fn split_index_name(name: &str) -> Option<(&str, &str)> {
+    name.split_once(LABEL_INDEX_SEPARATOR)
+}
+
+/// Returns the fields covered by an index, a single field unless the index is composite.
+#[inline]
+pub fn index_fields(index: &str) -> impl Iterator<Item = &str> {
+    index.split(COMPOSITE_INDEX_SEPARATOR)
+}
+
+/// Encodes the key of `item` in the given index by reading each of the index's fields.
+///
+/// Errors if the item is missing any of the fields.
+pub fn encode_item_index_key<T: Filterable>(item: &T, index: &str) -> Result<Vec<u8>, GraphError> {
+    let mut key = Vec::with_capacity(17);
+    for field in index_fields(index) {
+        write_value(&mut key, item.check_property(field)?.as_ref());
+    }
+    Ok(key)
+}
+
+/// Returns the smallest key that is greater than every key starting with `prefix`,
+/// or `None` if there is no such key.
+pub fn prefix_successor(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut successor = prefix.to_vec();
+    while let Some(last) = successor.pop() {
+        if last < u8::MAX {
+            successor.push(last + 1);
+            return Some(successor);
+        }
+    }
+    None
+}
+
+fn write_value(key: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::Empty => key.push(TAG_EMPTY),
+        Value::Boolean(b) => {
+            key.push(TAG_BOOLEAN);
+            key.push(*b as u8);
+        }
+        Value::I8(i) => write_int(key, *i as i128),
+        Value::I16(i) => write_int(key, *i as i128),
+        Value::I32(i) => write_int(key, *i as i128),
+        Value::I64(i) => write_int(key, *i as i128),
+        Value::U8(u) => write_int(key, *u as i128),
+        Value::U16(u) => write_int(key, *u as i128),
+        Value::U32(u) => write_int(key, *u as i128),
+        Value::U64(u) => write_int(key, *u as i128),
+        Value::U128(u) => match i128::try_from(*u) {
+            Ok(i) => write_int(key, i),
+            Err(_) => {
+                key.push(TAG_BIG_UINT);
+                key.extend_from_slice(&u.to_be_bytes());
+            }
+        },
+        Value::F32(f) => write_float(key, *f as f64),
+        Value::F64(f) => write_float(key, *f),
+        Value::Date(d) => {
+            key.push(TAG_DATE);
+            let secs = d.timestamp() as u64 ^ (1 << 63);
+            key.extend_from_slice(&secs.to_be_bytes());
+            key.extend_from_slice(&d.timestamp_subsec_nanos().to_be_bytes());
+        }
+        Value::String(s) => {
+            key.push(TAG_STRING);
+            write_escaped(key, s.as_bytes());
+        }
+        Value::Id(id) => {
+            key.push(TAG_ID);
+            key.extend_from_slice(&id.inner().to_be_bytes());
+        }
+        Value::Array(values) => {
+            key.push(TAG_ARRAY);
+            for value in values {
+                write_value(key, value);
+            }
+            key.push(TAG_TERMINATOR);
+        }
+        Value::Object(map) => {
+            key.push(TAG_OBJECT);
+            // hash maps have no stable order so fields are written sorted by name
+            let mut fields = map.iter().collect::<Vec<_>>();
+            fields.sort_unstable_by_key(|(name, _)| *name);
+            for (name, value) in fields {
+                write_escaped(key, name.as_bytes());
+                write_value(key, value);
+            }
+            key.push(TAG_TERMINATOR);
+        }
+    }
+}
+
+#[inline(always)]
+fn write_int(key: &mut Vec<u8>, i: i128) {
+    key.push(TAG_INT);
+    key.extend_from_slice(&((i as u128) ^ (1 << 127)).to_be_bytes());
+}
+
+#[inline(always)]
+fn write_float(key: &mut Vec<u8>, f: f64) {
+    key.push(TAG_FLOAT);
+    let bits = f.to_bits();
+    // negative floats have all bits flipped so larger magnitudes sort first,
+    // positive floats only have the sign bit flipped so they sort after all negatives
+    let ordered = if bits >> 63 == 1 {
+        !bits
+    } else {
+        bits ^ (1 << 63)
+    };
+    key.extend_from_slice(&ordered.to_be_bytes());
+}
+
+#[inline(always)]
+fn write_escaped(key: &mut Vec<u8>, bytes: &[u8]) {
+    for byte in bytes {
+        key.push(*byte);
+        if *byte == TAG_TERMINATOR {
+            key.push(ESCAPE);
+        }
+    }
+    key.push(TAG_TERMINATOR);
+    key.push(TAG_TERMINATOR);
+}
@@ -0,0 +1,72 @@
+use crate::helix_engine::types::GraphError;
+use heed3::{Database, Env, RwTxn, types::Bytes};
+use std::ops::Bound;
+
+/// How many items are read per write transaction when an existing database is migrated on open
+pub const MIGRATION_BATCH_SIZE: usize = 10_000;
+
+/// Reads the id of a node, edge or vector from its big-endian key
+pub fn decode_id(key: &[u8]) -> Result<u128, GraphError> {
+    key.try_into()
+        .map(u128::from_be_bytes)
+        .map_err(|_| GraphError::New("invalid item key".to_string()))
+}
+
+/// Walks a whole table in batches, see [`for_each_batch_in`].
+pub fn for_each_batch<T, E: From<heed3::Error>>(
+    graph_env: &Env,
+    db: Database<Bytes, Bytes>,
+    batch_size: usize,
+    decode: impl Fn(&[u8], &[u8]) -> Result<T, E>,
+    write: impl FnMut(&mut RwTxn, Vec<T>) -> Result<(), E>,
+) -> Result<usize, E> {
+    for_each_batch_in(
+        graph_env,
+        db,
+        (Bound::Unbounded, Bound::Unbounded),
+        batch_size,
+        decode,
+        write,
+    )
+}
+
+/// Walks the entries of a table within `range` in key order, in batches of `batch_size`, decoding
+/// each entry with `decode` and handing every batch to `write` in its own write transaction.
+///
+/// Only the last key read is kept between batches, so tables of any size can be migrated without
+/// holding them in memory or in a single transaction. Entries `write` inserts past the last key of
+/// its batch are walked too. Returns how many entries were read.
+pub fn for_each_batch_in<T, E: From<heed3::Error>>(
+    graph_env: &Env,
+    db: Database<Bytes, Bytes>,
+    range: (Bound<&[u8]>, Bound<&[u8]>),
+    batch_size: usize,
+    decode: impl Fn(&[u8], &[u8]) -> Result<T, E>,
+    mut write: impl FnMut(&mut RwTxn, Vec<T>) -> Result<(), E>,
+) -> Result<usize, E> {
+    let batch_size = batch_size.max(1);
+    let mut read = 0;
+    let mut last_key: Option<Vec<u8>> = None;
+    loop {
+        let mut txn = graph_env.write_txn()?;
+        let start = match &last_key {
+            Some(key) => Bound::Excluded(key.as_slice()),
+            None => range.0,
+        };
+        let mut items = Vec::with_capacity(batch_size);
+        let mut batch_last_key = None;
+        for result in db.range(&txn, &(start, range.1))?.take(batch_size) {
+            let (key, data) = result?;
+            items.push(decode(key, data)?);
+            batch_last_key = Some(key);
+        }
+        let Some(key) = batch_last_key else {
+            return Ok(read);
+        };
+        last_key = Some(key.to_vec());
+
+        read += items.len();
+        write(&mut txn, items)?;
+        txn.commit()?;
+    }
+}
@@ -1,3 +1,5 @@
+pub mod index_key;
+pub mod migration;
 pub mod storage_core;
 pub mod storage_methods;
 pub mod graph_visualization;
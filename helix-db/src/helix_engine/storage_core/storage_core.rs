@@ -1,26 +1,41 @@
 use super::storage_methods::DBMethods;
 use crate::{
+    debug_println,
     helix_engine::{
-        bm25::bm25::HBM25Config,
+        bm25::bm25::{
+            DB_BM25_DOC_LENGTHS, DB_BM25_FORWARD_INDEX, DB_BM25_INVERTED_INDEX,
+            DB_BM25_LEGACY_INVERTED_INDEX, DB_BM25_METADATA, DB_BM25_TERM_FREQUENCIES, HBM25Config,
+        },
         graph_core::{config::Config, ops::version_info::VersionInfo},
-        storage_core::storage_methods::StorageMethods,
+        storage_core::{
+            index_key::{
+                INDEX_KEY_FORMAT, encode_item_index_key, index_fields, label_index_name,
+                split_index_name,
+            },
+            migration::{MIGRATION_BATCH_SIZE, decode_id, for_each_batch},
+            storage_methods::StorageMethods,
+        },
         types::GraphError,
         vector_core::{
             hnsw::HNSW,
             vector::HVector,
-            vector_core::{HNSWConfig, VectorCore},
+            vector_core::{DB_HNSW_EDGES, DB_VECTOR_DATA, DB_VECTORS, HNSWConfig, VectorCore},
         },
     },
+    protocol::value::Value,
     utils::{
         filterable::Filterable,
         items::{Edge, Node},
         label_hash::hash_label,
+        tqdm::tqdm,
     },
 };
 use heed3::{Database, DatabaseFlags, Env, EnvOpenOptions, RoTxn, RwTxn, byteorder::BE, types::*};
+use serde::{Deserialize, Serialize, de::IgnoredAny};
 use std::{
     collections::{HashMap, HashSet},
     fs,
+    ops::Bound,
     path::Path,
 };
 
@@ -29,10 +44,71 @@ const DB_NODES: &str = "nodes"; // for node data (n:)
 const DB_EDGES: &str = "edges"; // for edge data (e:)
 const DB_OUT_EDGES: &str = "out_edges"; // for outgoing edge indices (o:)
 const DB_IN_EDGES: &str = "in_edges"; // for incoming edge indices (i:)
+const DB_NODE_LABELS: &str = "node_labels"; // for node label indices (nl:)
+const DB_EDGE_LABELS: &str = "edge_labels"; // for edge label indices (el:)
+const DB_EDGE_INDEX_PREFIX: &str = "edge_index:"; // for edge secondary indices, node ones are named after the index
+const DB_INDEX_FORMATS: &str = "index_formats"; // for the key format of each secondary index
+
+/// Names of the tables that are not secondary indices, which are never cleared as one
+const RESERVED_DB_NAMES: [&str; 16] = [
+    DB_NODES,
+    DB_EDGES,
+    DB_OUT_EDGES,
+    DB_IN_EDGES,
+    DB_NODE_LABELS,
+    DB_EDGE_LABELS,
+    DB_INDEX_FORMATS,
+    DB_VECTORS,
+    DB_VECTOR_DATA,
+    DB_HNSW_EDGES,
+    DB_BM25_INVERTED_INDEX,
+    DB_BM25_LEGACY_INVERTED_INDEX,
+    DB_BM25_DOC_LENGTHS,
+    DB_BM25_TERM_FREQUENCIES,
+    DB_BM25_METADATA,
+    DB_BM25_FORWARD_INDEX,
+];
+
+/// The node types of the schema stored in the config, which legacy index names are resolved
+/// against
+#[derive(Deserialize)]
+struct SchemaJson {
+    schema: SchemaItems,
+}
+
+#[derive(Deserialize)]
+struct SchemaItems {
+    #[serde(default)]
+    nodes: Vec<SchemaNode>,
+}
+
+#[derive(Deserialize)]
+struct SchemaNode {
+    name: String,
+    #[serde(default)]
+    properties: HashMap<String, IgnoredAny>,
+}
 
 pub type NodeId = u128;
 pub type EdgeId = u128;
 
+/// A secondary index to build when the database is opened: its label-qualified name, the name of
+/// its database and the database
+type IndexToBuild = (String, String, Database<Bytes, U128<BE>>);
+
+/// How many items are indexed per write transaction when the BM25 indices are rebuilt
+pub const REINDEX_BATCH_SIZE: usize = 10_000;
+
+/// What a rebuild of the BM25 indices went through
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct ReindexStats {
+    pub nodes: usize,
+    pub edges: usize,
+    pub vectors: usize,
+    /// Items with searchable fields, which were indexed
+    pub indexed: usize,
+}
+
 pub struct StorageConfig {
     pub schema: String,
     pub graphvis_node_label: Option<String>,
@@ -46,7 +122,11 @@ pub struct HelixGraphStorage {
     pub edges_db: Database<U128<BE>, Bytes>,
     pub out_edges_db: Database<Bytes, Bytes>,
     pub in_edges_db: Database<Bytes, Bytes>,
+    pub node_labels_db: Database<Bytes, U128<BE>>,
+    pub edge_labels_db: Database<Bytes, U128<BE>>,
     pub secondary_indices: HashMap<String, Database<Bytes, U128<BE>>>,
+    pub edge_secondary_indices: HashMap<String, Database<Bytes, U128<BE>>>,
+    pub unique_indices: HashSet<String>,
     pub vectors: VectorCore,
     pub bm25: Option<HBM25Config>,
     pub version_info: VersionInfo,
@@ -68,10 +148,30 @@ impl HelixGraphStorage {
             config.db_max_size_gb.unwrap_or(100)
         };
 
+        let graph_config = config.get_graph_config();
+        // indices of configs generated before indices belonged to a label are mapped onto the
+        // labels that declare their fields
+        let node_indices = Self::qualify_legacy_indices(
+            graph_config.secondary_indices.unwrap_or_default(),
+            config.schema.as_deref(),
+        )?;
+        let unique_indices: HashSet<String> = Self::qualify_legacy_indices(
+            graph_config.unique_indices.unwrap_or_default(),
+            config.schema.as_deref(),
+        )?
+        .into_iter()
+        .collect();
+        let edge_indices = graph_config.edge_secondary_indices.unwrap_or_default();
+        // every secondary index has its own database, and a node index may also open the
+        // per-field database it supersedes to clear it
+        let max_dbs = RESERVED_DB_NAMES.len()
+            + 2 * (node_indices.len() + unique_indices.len())
+            + edge_indices.len();
+
         let graph_env = unsafe {
             EnvOpenOptions::new()
                 .map_size(db_size * 1024 * 1024 * 1024)
-                .max_dbs(20)
+                .max_dbs(max_dbs as u32)
                 .max_readers(200)
                 .open(Path::new(path))?
         };
@@ -122,20 +222,125 @@ impl HelixGraphStorage {
             .name(DB_IN_EDGES)
             .create(&mut wtxn)?;
 
+        // Node labels: [label]->[node_id]
+        //              [4 bytes]->[16 bytes]
+        //
+        // DUP_SORT used to store all node ids of the same label under a single key.
+        // DUP_FIXED used to ensure all values are the same size meaning 8 byte length header is discarded.
+        let node_labels_db: Database<Bytes, U128<BE>> = graph_env
+            .database_options()
+            .types::<Bytes, U128<BE>>()
+            .flags(DatabaseFlags::DUP_SORT | DatabaseFlags::DUP_FIXED)
+            .name(DB_NODE_LABELS)
+            .create(&mut wtxn)?;
+
+        // Edge labels: [label]->[edge_id]
+        //              [4 bytes]->[16 bytes]
+        //
+        // DUP_SORT used to store all edge ids of the same label under a single key.
+        // DUP_FIXED used to ensure all values are the same size meaning 8 byte length header is discarded.
+        let edge_labels_db: Database<Bytes, U128<BE>> = graph_env
+            .database_options()
+            .types::<Bytes, U128<BE>>()
+            .flags(DatabaseFlags::DUP_SORT | DatabaseFlags::DUP_FIXED)
+            .name(DB_EDGE_LABELS)
+            .create(&mut wtxn)?;
+
+        // Index formats: [index db name]->[version of its key encoding + whether it is unique]
+        //               [dynamic]->[1 + 1 bytes]
+        let index_formats_db: Database<Str, Bytes> = graph_env
+            .database_options()
+            .types::<Str, Bytes>()
+            .name(DB_INDEX_FORMATS)
+            .create(&mut wtxn)?;
+
+        // databases created before the label indices existed have data but missing label entries,
+        // also when a previous build was interrupted, so they are filled in after the tables exist
+        let build_node_labels = node_labels_db.len(&wtxn)? < nodes_db.len(&wtxn)?;
+        let build_edge_labels = edge_labels_db.len(&wtxn)? < edges_db.len(&wtxn)?;
+
         let mut secondary_indices = HashMap::new();
-        if let Some(indexes) = config.get_graph_config().secondary_indices {
-            for index in indexes {
-                secondary_indices.insert(
-                    index.clone(),
-                    graph_env
-                        .database_options()
-                        .types::<Bytes, U128<BE>>()
-                        .flags(DatabaseFlags::DUP_SORT) // DUP_SORT used to store all duplicated node keys under a single key. Saves on space and requires a single read to get all values.
-                        .name(&index)
-                        .create(&mut wtxn)?,
-                );
+        // indices added to an existing database, or written with an older key format, are built
+        // from its current nodes and edges once the tables exist
+        let mut node_indices_to_build = Vec::new();
+        // unique indices are secondary indices with an extra check on write
+        let indexes = node_indices
+            .into_iter()
+            .chain(unique_indices.iter().cloned());
+        for index in indexes {
+            if secondary_indices.contains_key(&index) {
+                continue;
+            }
+            let (db, build) = Self::open_secondary_index(
+                &graph_env,
+                &mut wtxn,
+                &index_formats_db,
+                &index,
+                &index,
+                unique_indices.contains(&index),
+            )?;
+            if build {
+                node_indices_to_build.push((index.clone(), index.clone(), db));
+            }
+            secondary_indices.insert(index, db);
+        }
+        Self::clear_superseded_indices(&graph_env, &mut wtxn, secondary_indices.keys())?;
+        let mut edge_secondary_indices = HashMap::new();
+        let mut edge_indices_to_build = Vec::new();
+        for index in edge_indices {
+            let db_name = format!("{DB_EDGE_INDEX_PREFIX}{index}");
+            let (db, build) = Self::open_secondary_index(
+                &graph_env,
+                &mut wtxn,
+                &index_formats_db,
+                &index,
+                &db_name,
+                false,
+            )?;
+            if build {
+                edge_indices_to_build.push((index.clone(), db_name, db));
             }
+            edge_secondary_indices.insert(index, db);
         }
+        wtxn.commit()?;
+
+        if build_node_labels {
+            Self::build_label_index(
+                &graph_env,
+                nodes_db,
+                node_labels_db,
+                MIGRATION_BATCH_SIZE,
+                |id, data| Ok(Node::decode_node(data, id)?.label),
+            )?;
+        }
+        if build_edge_labels {
+            Self::build_label_index(
+                &graph_env,
+                edges_db,
+                edge_labels_db,
+                MIGRATION_BATCH_SIZE,
+                |id, data| Ok(Edge::decode_edge(data, id)?.label),
+            )?;
+        }
+        Self::build_secondary_indices(
+            &graph_env,
+            nodes_db,
+            index_formats_db,
+            &node_indices_to_build,
+            &unique_indices,
+            MIGRATION_BATCH_SIZE,
+            Node::decode_node,
+        )?;
+        Self::build_secondary_indices(
+            &graph_env,
+            edges_db,
+            index_formats_db,
+            &edge_indices_to_build,
+            &HashSet::new(),
+            MIGRATION_BATCH_SIZE,
+            Edge::decode_edge,
+        )?;
+        let mut wtxn = graph_env.write_txn()?;
 
         let vector_config = config.get_vector_config();
         let vectors = VectorCore::new(
@@ -145,13 +350,21 @@ impl HelixGraphStorage {
                 vector_config.m,
                 vector_config.ef_construction,
                 vector_config.ef_search,
-            ),
+            )
+            .with_vector_types(vector_config.vector_types.unwrap_or_default()),
         )?;
 
+        // bm25 turned on for a database that already has items, which are indexed once opened
+        let index_existing = config.get_bm25()
+            && !HBM25Config::exists(&graph_env, &wtxn)?
+            && !(nodes_db.is_empty(&wtxn)?
+                && edges_db.is_empty(&wtxn)?
+                && vectors.vector_data_db.is_empty(&wtxn)?);
         let bm25 = config
             .get_bm25()
             .then(|| HBM25Config::new(&graph_env, &mut wtxn))
-            .transpose()?;
+            .transpose()?
+            .map(|bm25| bm25.with_indices(config.get_bm25_indices()));
 
         let storage_config = StorageConfig::new(
             config.schema.unwrap_or("".to_string()),
@@ -159,19 +372,32 @@ impl HelixGraphStorage {
             config.embedding_model,
         );
 
+        let rebuild_vectors = vectors.rebuild_pending(&wtxn)?;
+
         wtxn.commit()?;
-        Ok(Self {
+        if rebuild_vectors {
+            vectors.finish_rebuild(&graph_env, MIGRATION_BATCH_SIZE, false)?;
+        }
+        let storage = Self {
             graph_env,
             nodes_db,
             edges_db,
             out_edges_db,
             in_edges_db,
+            node_labels_db,
+            edge_labels_db,
             secondary_indices,
+            edge_secondary_indices,
+            unique_indices,
             vectors,
             bm25,
             storage_config,
             version_info,
-        })
+        };
+        if index_existing {
+            storage.reindex_bm25(REINDEX_BATCH_SIZE)?;
+        }
+        Ok(storage)
     }
 
     /// Used because in the case the key changes in the future.
@@ -250,10 +476,478 @@ impl HelixGraphStorage {
         Ok((edge_id, node_id))
     }
 
+    /// Label key generator. Hashes the label into the 4 byte key used by the label indices.
+    ///
+    /// key = `label-id(4)`                 ← 4 B
+    ///
+    /// All ids with the same label are stored in a sorted sub-tree, with this key being the root.
+    #[inline(always)]
+    pub fn label_key(label: &str) -> [u8; 4] {
+        hash_label(label, None)
+    }
+
+    /// Populates a label index from every node or edge in `items_db`, in batches of `batch_size`
+    /// written in their own transactions. Entries that already exist are kept, so an interrupted
+    /// build is finished by running it again.
+    pub(crate) fn build_label_index(
+        graph_env: &Env,
+        items_db: Database<U128<BE>, Bytes>,
+        labels_db: Database<Bytes, U128<BE>>,
+        batch_size: usize,
+        label: impl Fn(u128, &[u8]) -> Result<String, GraphError>,
+    ) -> Result<(), GraphError> {
+        for_each_batch::<_, GraphError>(
+            graph_env,
+            items_db.remap_key_type::<Bytes>(),
+            batch_size,
+            |key, data| {
+                let id = decode_id(key)?;
+                Ok((Self::label_key(&label(id, data)?), id))
+            },
+            |wtxn, entries| {
+                for (label, id) in entries {
+                    labels_db.put(wtxn, &label, &id)?;
+                }
+                Ok(())
+            },
+        )?;
+        Ok(())
+    }
+
+    /// The format recorded for a secondary index once it is built: the version of its key encoding
+    /// and whether it was checked to hold every key only once.
+    #[inline(always)]
+    fn index_format(unique: bool) -> [u8; 2] {
+        [INDEX_KEY_FORMAT, unique as u8]
+    }
+
+    /// Qualifies the indices of a config generated before indices belonged to a label, e.g.
+    /// `email`, with every node label in `schema` that has all of their fields, e.g. `User.email`.
+    /// They are then built under their new names on open, like any new index.
+    ///
+    /// Errors if no label in the schema has the fields of such an index.
+    fn qualify_legacy_indices(
+        indices: Vec<String>,
+        schema: Option<&str>,
+    ) -> Result<Vec<String>, GraphError> {
+        if indices
+            .iter()
+            .all(|index| split_index_name(index).is_some())
+        {
+            return Ok(indices);
+        }
+        let nodes = schema
+            .and_then(|schema| sonic_rs::from_str::<SchemaJson>(schema).ok())
+            .map(|json| json.schema.nodes)
+            .unwrap_or_default();
+        let mut qualified = Vec::with_capacity(indices.len());
+        for index in indices {
+            if split_index_name(&index).is_some() {
+                qualified.push(index);
+                continue;
+            }
+            let labels = nodes
+                .iter()
+                .filter(|node| index_fields(&index).all(|f| node.properties.contains_key(f)))
+                .map(|node| label_index_name(&node.name, &index))
+                .collect::<Vec<_>>();
+            if labels.is_empty() {
+                return Err(GraphError::New(format!(
+                    "Secondary Index {index} is not qualified by a label and no label in the \
+                    schema has its fields. Indices now belong to one label, e.g. `Label.{index}`: \
+                    regenerate the config by compiling the queries again with `helix compile` or \
+                    `helix deploy`"
+                )));
+            }
+            qualified.extend(labels);
+        }
+        Ok(qualified)
+    }
+
+    /// Opens the secondary index `name`, creating it if it does not exist yet, and returns whether
+    /// it has to be built. That is the case for new indices and for ones without the current
+    /// format, which are cleared, e.g. ones written with an older key encoding, whose build was
+    /// interrupted or that became unique.
+    fn open_secondary_index(
+        graph_env: &Env,
+        wtxn: &mut RwTxn,
+        index_formats_db: &Database<Str, Bytes>,
+        name: &str,
+        db_name: &str,
+        unique: bool,
+    ) -> Result<(Database<Bytes, U128<BE>>, bool), GraphError> {
+        if split_index_name(name).is_none() {
+            return Err(GraphError::New(format!(
+                "Secondary Index {name} is not qualified by a label. Indices now belong to one \
+                label, e.g. `Label.{name}`: regenerate the config by compiling the queries again \
+                with `helix compile` or `helix deploy`, and the index is rebuilt for each label \
+                that declares the field on the next start"
+            )));
+        }
+        let existing = graph_env
+            .database_options()
+            .types::<Bytes, U128<BE>>()
+            .name(db_name)
+            .open(wtxn)?;
+        match existing {
+            Some(db) => {
+                if index_formats_db.get(wtxn, db_name)? == Some(&Self::index_format(unique)[..]) {
+                    return Ok((db, false));
+                }
+                db.clear(wtxn)?;
+                Ok((db, true))
+            }
+            None => {
+                let db = graph_env
+                    .database_options()
+                    .types::<Bytes, U128<BE>>()
+                    .flags(DatabaseFlags::DUP_SORT) // DUP_SORT used to store all duplicated item keys under a single key. Saves on space and requires a single read to get all values.
+                    .name(db_name)
+                    .create(wtxn)?;
+                Ok((db, true))
+            }
+        }
+    }
+
+    /// Clears the databases of the indices that covered a field for every label, from before
+    /// indices were qualified by their label, once an index over the same fields replaces them.
+    /// E.g. `email` is cleared when `User.email` is opened.
+    fn clear_superseded_indices<'a>(
+        graph_env: &Env,
+        wtxn: &mut RwTxn,
+        indices: impl Iterator<Item = &'a String>,
+    ) -> Result<(), GraphError> {
+        let mut cleared = HashSet::new();
+        for index in indices {
+            let Some((_, fields)) = split_index_name(index) else {
+                continue;
+            };
+            if RESERVED_DB_NAMES.contains(&fields) || !cleared.insert(fields) {
+                continue;
+            }
+            if let Some(db) = graph_env
+                .database_options()
+                .types::<Bytes, Bytes>()
+                .name(fields)
+                .open(wtxn)?
+            {
+                db.clear(wtxn)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Populates secondary indices from every node or edge in `items_db` with their label, in
+    /// batches of `batch_size` written in their own transactions, then records that they use the
+    /// current key format.
+    ///
+    /// Errors with [`GraphError::DuplicateIndexKey`] if an index in `unique_indices` would hold a
+    /// key for more than one item, leaving it to be built again on the next open.
+    fn build_secondary_indices<T: Filterable>(
+        graph_env: &Env,
+        items_db: Database<U128<BE>, Bytes>,
+        index_formats_db: Database<Str, Bytes>,
+        indices: &[IndexToBuild],
+        unique_indices: &HashSet<String>,
+        batch_size: usize,
+        decode: impl Fn(&[u8], u128) -> Result<T, GraphError>,
+    ) -> Result<(), GraphError> {
+        if indices.is_empty() {
+            return Ok(());
+        }
+        let fields = indices
+            .iter()
+            .filter_map(|(name, _, _)| split_index_name(name))
+            .collect::<Vec<_>>();
+        for_each_batch::<_, GraphError>(
+            graph_env,
+            items_db.remap_key_type::<Bytes>(),
+            batch_size,
+            |key, data| {
+                let id = decode_id(key)?;
+                let item = decode(data, id)?;
+                let mut entries = Vec::new();
+                for (i, (label, fields)) in fields.iter().enumerate() {
+                    // items without every indexed field are not indexed, like on insert
+                    if item.label() == *label
+                        && let Ok(key) = encode_item_index_key(&item, fields)
+                    {
+                        entries.push((i, key, id));
+                    }
+                }
+                Ok(entries)
+            },
+            |wtxn, batch| {
+                for (i, key, id) in batch.into_iter().flatten() {
+                    let (name, _, db) = &indices[i];
+                    if unique_indices.contains(name)
+                        && db.get(wtxn, &key)?.is_some_and(|other| other != id)
+                    {
+                        let data = items_db.get(wtxn, &id)?.ok_or(GraphError::NodeNotFound)?;
+                        let item = decode(data, id)?;
+                        let values = index_fields(fields[i].1)
+                            .map(|field| Ok(format!("{:?}", item.check_property(field)?)))
+                            .collect::<Result<Vec<_>, GraphError>>()?;
+                        return Err(GraphError::DuplicateIndexKey(
+                            name.clone(),
+                            values.join(", "),
+                        ));
+                    }
+                    db.put(wtxn, &key, &id)?;
+                }
+                Ok(())
+            },
+        )?;
+
+        let mut wtxn = graph_env.write_txn()?;
+        for (name, db_name, _) in indices {
+            let format = Self::index_format(unique_indices.contains(name));
+            index_formats_db.put(&mut wtxn, db_name, &format)?;
+        }
+        wtxn.commit()?;
+        Ok(())
+    }
+
+    /// Returns the secondary index over `index` for nodes with the given label.
+    #[inline]
+    pub fn secondary_index(
+        &self,
+        label: &str,
+        index: &str,
+    ) -> Result<&Database<Bytes, U128<BE>>, GraphError> {
+        self.secondary_indices
+            .get(&label_index_name(label, index))
+            .ok_or(GraphError::New(format!(
+                "Secondary Index {index} not found for label {label}"
+            )))
+    }
+
+    /// Returns the secondary index over `index` for edges with the given label.
+    #[inline]
+    pub fn edge_secondary_index(
+        &self,
+        label: &str,
+        index: &str,
+    ) -> Result<&Database<Bytes, U128<BE>>, GraphError> {
+        self.edge_secondary_indices
+            .get(&label_index_name(label, index))
+            .ok_or(GraphError::New(format!(
+                "Edge Secondary Index {index} not found for label {label}"
+            )))
+    }
+
+    /// Returns the secondary indices of the given node label, with the fields each one covers.
+    #[inline]
+    pub fn label_secondary_indices<'a>(
+        &'a self,
+        label: &'a str,
+    ) -> impl Iterator<Item = (&'a str, &'a str, &'a Database<Bytes, U128<BE>>)> {
+        Self::indices_of_label(&self.secondary_indices, label)
+    }
+
+    /// Returns the secondary indices of the given edge label, with the fields each one covers.
+    #[inline]
+    pub fn label_edge_secondary_indices<'a>(
+        &'a self,
+        label: &'a str,
+    ) -> impl Iterator<Item = (&'a str, &'a str, &'a Database<Bytes, U128<BE>>)> {
+        Self::indices_of_label(&self.edge_secondary_indices, label)
+    }
+
+    fn indices_of_label<'a>(
+        indices: &'a HashMap<String, Database<Bytes, U128<BE>>>,
+        label: &'a str,
+    ) -> impl Iterator<Item = (&'a str, &'a str, &'a Database<Bytes, U128<BE>>)> {
+        indices
+            .iter()
+            .filter_map(move |(name, db)| match split_index_name(name) {
+                Some((index_label, fields)) if index_label == label => {
+                    Some((name.as_str(), fields, db))
+                }
+                _ => None,
+            })
+    }
+
+    /// Removes an edge from the secondary indices and the BM25 index of its label.
+    fn unindex_edge(&self, txn: &mut RwTxn, edge: &Edge) -> Result<(), GraphError> {
+        for (_, index, db) in self.label_edge_secondary_indices(&edge.label) {
+            if let Ok(key) = encode_item_index_key(edge, index) {
+                db.delete_one_duplicate(txn, &key, &edge.id)?;
+            }
+        }
+        if let Some(bm25) = &self.bm25 {
+            bm25.delete_item(txn, &edge.label, edge.id)?;
+        }
+        Ok(())
+    }
+
+    /// Whether dropping an edge has to read it to clean up the indices of its label
+    fn has_edge_indices(&self) -> bool {
+        !self.edge_secondary_indices.is_empty()
+            || self
+                .bm25
+                .as_ref()
+                .is_some_and(|bm25| !bm25.indices.is_empty())
+    }
+
+    /// Checks that no node other than `node_id` holds `key` in the given unique index.
+    ///
+    /// `index` is the label-qualified index name. Indices that are not unique always pass.
+    pub fn check_unique(
+        &self,
+        txn: &RoTxn,
+        index: &str,
+        key: &[u8],
+        node_id: &u128,
+    ) -> Result<(), GraphError> {
+        if !self.unique_indices.contains(index) {
+            return Ok(());
+        }
+        let db = self
+            .secondary_indices
+            .get(index)
+            .ok_or(GraphError::New(format!(
+                "Secondary Index {index} not found"
+            )))?;
+        if let Some(ids) = db.get_duplicates(txn, key)? {
+            for id in ids {
+                let (_, id) = id?;
+                if id != *node_id {
+                    return Err(GraphError::DuplicateKey(index.to_string()));
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Gets a vector from level 0 of HNSW index (because that's where all are stored)
     pub fn get_vector(&self, txn: &RoTxn, id: &u128) -> Result<HVector, GraphError> {
         Ok(self.vectors.get_vector(txn, *id, 0, true)?)
     }
+
+    /// Rebuilds the HNSW graphs without the tombstones left by deletes from older versions,
+    /// returning how many vectors were removed. Meant to be run while the database is offline.
+    pub fn compact_vectors(&self) -> Result<usize, GraphError> {
+        Ok(self
+            .vectors
+            .compact(&self.graph_env, MIGRATION_BATCH_SIZE)?)
+    }
+
+    /// Rebuilds the postings, document lengths and metadata of the BM25 indices from the stored
+    /// nodes, edges and vectors, e.g. after bm25 was turned on or analyzers changed.
+    ///
+    /// Items are read in batches of `batch_size`, each indexed in its own write transaction so
+    /// other writes can go on, and searches see a partial index until it finishes.
+    pub fn reindex_bm25(&self, batch_size: usize) -> Result<ReindexStats, GraphError> {
+        let bm25 = self
+            .bm25
+            .as_ref()
+            .ok_or(GraphError::New("BM25 not enabled!".to_string()))?;
+        let batch_size = batch_size.max(1);
+
+        let mut txn = self.graph_env.write_txn()?;
+        bm25.clear(&mut txn)?;
+        txn.commit()?;
+
+        let mut stats = ReindexStats::default();
+        stats.nodes = self.reindex_table(
+            self.nodes_db.remap_key_type::<Bytes>(),
+            batch_size,
+            "indexing nodes",
+            &mut stats.indexed,
+            |id, data| {
+                let node = Node::decode_node(data, id)?;
+                Ok(Some((node.label, node.properties)))
+            },
+        )?;
+        stats.edges = self.reindex_table(
+            self.edges_db.remap_key_type::<Bytes>(),
+            batch_size,
+            "indexing edges",
+            &mut stats.indexed,
+            |id, data| {
+                let edge = Edge::decode_edge(data, id)?;
+                Ok(Some((edge.label, edge.properties)))
+            },
+        )?;
+        stats.vectors = self.reindex_table(
+            self.vectors.vector_data_db,
+            batch_size,
+            "indexing vectors",
+            &mut stats.indexed,
+            |_, data| {
+                let properties: HashMap<String, Value> = bincode::deserialize(data)?;
+                if matches!(properties.get("is_deleted"), Some(Value::Boolean(true))) {
+                    return Ok(None);
+                }
+                let label = properties
+                    .get("label")
+                    .map_or("vector", |label| label.as_str())
+                    .to_string();
+                Ok(Some((label, Some(properties))))
+            },
+        )?;
+
+        debug_println!("rebuilt bm25 indices: {stats:?}");
+        Ok(stats)
+    }
+
+    /// Indexes the items of a table keyed by id in batches, returning how many were read
+    fn reindex_table(
+        &self,
+        db: Database<Bytes, Bytes>,
+        batch_size: usize,
+        message: &str,
+        indexed: &mut usize,
+        decode: impl Fn(
+            u128,
+            &[u8],
+        ) -> Result<Option<(String, Option<HashMap<String, Value>>)>, GraphError>,
+    ) -> Result<usize, GraphError> {
+        let bm25 = self
+            .bm25
+            .as_ref()
+            .ok_or(GraphError::New("BM25 not enabled!".to_string()))?;
+        let total = {
+            let txn = self.graph_env.read_txn()?;
+            db.len(&txn)? as usize
+        };
+        let batches = total.div_ceil(batch_size);
+
+        let mut read = 0;
+        let mut last_key: Option<Vec<u8>> = None;
+        for _ in tqdm::new(0..batches, batches, None, Some(message)) {
+            let mut txn = self.graph_env.write_txn()?;
+            let range = match &last_key {
+                Some(key) => (Bound::Excluded(key.as_slice()), Bound::Unbounded),
+                None => (Bound::Unbounded, Bound::Unbounded),
+            };
+            let mut items = Vec::with_capacity(batch_size);
+            for result in db.range(&txn, &range)?.take(batch_size) {
+                let (key, data) = result?;
+                let id = key
+                    .try_into()
+                    .map(u128::from_be_bytes)
+                    .map_err(|_| GraphError::New("invalid item key".to_string()))?;
+                items.push((key.to_vec(), id, decode(id, data)?));
+            }
+            let Some((key, _, _)) = items.last() else {
+                break;
+            };
+            last_key = Some(key.clone());
+
+            read += items.len();
+            for (_, id, item) in items {
+                if let Some((label, properties)) = item
+                    && bm25.reindex_item(&mut txn, &label, id, properties.as_ref())?
+                {
+                    *indexed += 1;
+                }
+            }
+            txn.commit()?;
+        }
+        Ok(read)
+    }
 }
 
 impl StorageConfig {
@@ -340,7 +1034,7 @@ impl StorageMethods for HelixGraphStorage {
             let mut label = [0u8; 4];
             label.copy_from_slice(&key[16..20]);
             let (edge_id, to_node_id) = Self::unpack_adj_edge_data(value)?;
-            edges.insert(edge_id);
+            edges.insert((edge_id, label));
             out_edges.insert(label);
             other_in_edges.push((to_node_id, label, edge_id));
         }
@@ -356,7 +1050,7 @@ impl StorageMethods for HelixGraphStorage {
             label.copy_from_slice(&key[16..20]);
             let (edge_id, from_node_id) = Self::unpack_adj_edge_data(value)?;
             in_edges.insert(label);
-            edges.insert(edge_id);
+            edges.insert((edge_id, label));
             other_out_edges.push((from_node_id, label, edge_id));
         }
 
@@ -364,8 +1058,14 @@ impl StorageMethods for HelixGraphStorage {
 
         // println!("Deleting edges: {}", );
         // Delete all related data
-        for edge in edges {
+        for (edge, label_bytes) in edges {
+            if self.has_edge_indices() {
+                let edge = self.get_edge(txn, &edge)?;
+                self.unindex_edge(txn, &edge)?;
+            }
             self.edges_db.delete(txn, Self::edge_key(&edge))?;
+            self.edge_labels_db
+                .delete_one_duplicate(txn, &label_bytes, &edge)?;
         }
         for label_bytes in out_edges.iter() {
             self.out_edges_db
@@ -393,17 +1093,12 @@ impl StorageMethods for HelixGraphStorage {
 
         // delete secondary indices
         let node = self.get_node(txn, id)?;
-        for (index_name, db) in &self.secondary_indices {
-            // Use check_property like we do when adding, to handle id, label, and regular properties consistently
-            match node.check_property(index_name) {
-                Ok(value) => {
-                    match bincode::serialize(&*value) {
-                        Ok(serialized) => {
-                            if let Err(e) = db.delete_one_duplicate(txn, &serialized, &node.id) {
-                                return Err(GraphError::from(e));
-                            }
-                        }
-                        Err(e) => return Err(GraphError::from(e)),
+        for (_, index, db) in self.label_secondary_indices(&node.label) {
+            // Use the same key encoding as when adding, to handle id, label, composite and regular properties consistently
+            match encode_item_index_key(&node, index) {
+                Ok(key) => {
+                    if let Err(e) = db.delete_one_duplicate(txn, &key, &node.id) {
+                        return Err(GraphError::from(e));
                     }
                 }
                 Err(_) => {
@@ -412,8 +1107,13 @@ impl StorageMethods for HelixGraphStorage {
                 }
             }
         }
+        if let Some(bm25) = &self.bm25 {
+            bm25.delete_item(txn, &node.label, node.id)?;
+        }
 
         // Delete node data and label
+        self.node_labels_db
+            .delete_one_duplicate(txn, &Self::label_key(&node.label), id)?;
         self.nodes_db.delete(txn, Self::node_key(id))?;
 
         Ok(())
@@ -425,12 +1125,15 @@ impl StorageMethods for HelixGraphStorage {
             Some(data) => data,
             None => return Err(GraphError::EdgeNotFound),
         };
-        let edge: Edge = bincode::deserialize(edge_data)?;
+        let edge = Edge::decode_edge(edge_data, *edge_id)?;
         let label_hash = hash_label(&edge.label, None);
         let out_edge_value = Self::pack_edge_data(edge_id, &edge.to_node);
         let in_edge_value = Self::pack_edge_data(edge_id, &edge.from_node);
         // Delete all edge-related data
+        self.unindex_edge(txn, &edge)?;
         self.edges_db.delete(txn, Self::edge_key(edge_id))?;
+        self.edge_labels_db
+            .delete_one_duplicate(txn, &label_hash, edge_id)?;
         self.out_edges_db.delete_one_duplicate(
             txn,
             &Self::out_edge_key(&edge.from_node, &label_hash),
@@ -462,7 +1165,7 @@ impl StorageMethods for HelixGraphStorage {
             let mut label = [0u8; 4];
             label.copy_from_slice(&key[16..20]);
             let (edge_id, to_node_id) = Self::unpack_adj_edge_data(value)?;
-            edges.insert(edge_id);
+            edges.insert((edge_id, label));
             out_edges.insert(label);
             other_in_edges.push((to_node_id, label, edge_id));
         }
@@ -478,7 +1181,7 @@ impl StorageMethods for HelixGraphStorage {
             label.copy_from_slice(&key[16..20]);
             let (edge_id, from_node_id) = Self::unpack_adj_edge_data(value)?;
             in_edges.insert(label);
-            edges.insert(edge_id);
+            edges.insert((edge_id, label));
             other_out_edges.push((from_node_id, label, edge_id));
         }
 
@@ -486,8 +1189,14 @@ impl StorageMethods for HelixGraphStorage {
 
         // println!("Deleting edges: {}", );
         // Delete all related data
-        for edge in edges {
+        for (edge, label_bytes) in edges {
+            if self.has_edge_indices() {
+                let edge = self.get_edge(txn, &edge)?;
+                self.unindex_edge(txn, &edge)?;
+            }
             self.edges_db.delete(txn, Self::edge_key(&edge))?;
+            self.edge_labels_db
+                .delete_one_duplicate(txn, &label_bytes, &edge)?;
         }
         for label_bytes in out_edges.iter() {
             self.out_edges_db
@@ -513,6 +1222,13 @@ impl StorageMethods for HelixGraphStorage {
             )?;
         }
 
+        if let Some(bm25) = &self.bm25
+            && !bm25.indices.is_empty()
+        {
+            let vector = self.vectors.get_vector(txn, *id, 0, true)?;
+            bm25.delete_item(txn, vector.label(), *id)?;
+        }
+
         // Delete vector data
         self.vectors.delete(txn, *id)?;
 
@@ -28,6 +28,8 @@ pub enum GraphError {
     ShortestPathNotFound,
     EmbeddingError(String),
     ParamNotFound(&'static str),
+    DuplicateKey(String),
+    DuplicateIndexKey(String, String),
 }
 
 impl std::error::Error for GraphError {}
@@ -61,6 +63,18 @@ impl fmt::Display for GraphError {
             GraphError::ShortestPathNotFound => write!(f, "Shortest path not found"),
             GraphError::EmbeddingError(msg) => write!(f, "Error while embedding text: {msg}"),
             GraphError::ParamNotFound(param) => write!(f, "Parameter {param} not found in request"),
+            GraphError::DuplicateKey(index) => {
+                write!(
+                    f,
+                    "Duplicate key: a node with this value already exists in unique index {index}"
+                )
+            }
+            GraphError::DuplicateIndexKey(index, key) => {
+                write!(
+                    f,
+                    "Duplicate key: more than one node has the key {key} in unique index {index}"
+                )
+            }
         }
     }
 }
@@ -146,6 +160,12 @@ pub enum VectorError {
     ConversionError(String),
     VectorCoreError(String),
     VectorAlreadyDeleted(String),
+    /// A vector whose length differs from the dimensions declared on its vector type
+    DimensionMismatch {
+        label: String,
+        expected: usize,
+        found: usize,
+    },
 }
 
 impl std::error::Error for VectorError {}
@@ -160,6 +180,14 @@ impl fmt::Display for VectorError {
             VectorError::ConversionError(msg) => write!(f, "Conversion error: {msg}"),
             VectorError::VectorCoreError(msg) => write!(f, "Vector core error: {msg}"),
             VectorError::VectorAlreadyDeleted(id) => write!(f, "Vector already deleted: {id}"),
+            VectorError::DimensionMismatch {
+                label,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Vector type {label} has {expected} dimensions but the vector has {found}"
+            ),
         }
     }
 }
@@ -11,6 +11,11 @@ pub trait HNSW
     /// * `txn` - The transaction to use
     /// * `query` - The query vector
     /// * `k` - The number of nearest neighbors to search for
+    /// * `label` - The label of the vectors to search, only its graph is walked
+    /// * `filter` - Filters the results must pass
+    /// * `should_trickle` - Whether to apply the filters while walking the graph, so that
+    ///   it keeps expanding until `k` matching vectors are found or the graph is exhausted,
+    ///   rather than to the nearest candidates afterwards
     ///
     /// # Returns
     ///
@@ -32,6 +37,7 @@ pub trait HNSW
     /// # Arguments
     ///
     /// * `txn` - The transaction to use
+    /// * `label` - The label of the vector, each label has its own graph
     /// * `data` - The vector data
     ///
     /// # Returns
@@ -40,6 +46,7 @@ pub trait HNSW
     fn insert<F>(
         &self,
         txn: &mut RwTxn,
+        label: &str,
         data: &[f64],
         fields: Option<Vec<(String, Value)>>,
     ) -> Result<HVector, VectorError>
@@ -1,10 +1,16 @@
 // MAKE SURE TO --release
 use crate::{
-    helix_engine::vector_core::{
-        hnsw::HNSW,
-        vector::HVector,
-        vector_core::{HNSWConfig, VectorCore},
+    helix_engine::{
+        graph_core::config::VectorTypeConfig,
+        types::VectorError,
+        vector_core::{
+            hnsw::HNSW,
+            vector::{HVector, VectorPrecision},
+            vector_core::{HNSWConfig, VectorCore},
+            vector_distance::DistanceMetric,
+        },
     },
+    protocol::value::Value,
 };
 use heed3::{Env, EnvOpenOptions, RoTxn};
 use rand::{
@@ -59,7 +65,7 @@ fn calc_ground_truths(
                             .iter()
                             .filter_map(|base_vec| {
                                 query_hvector
-                                    .distance_to(base_vec)
+                                    .distance_to(base_vec, DistanceMetric::Cosine)
                                     .map(|dist| (base_vec.id.clone(), dist))
                                     .ok()
                             })
@@ -148,8 +154,8 @@ fn test_hnsw_insert() {
     let vectors = gen_sim_vecs(n_base, dims, 0.8);
 
     for data in vectors {
-        let vec = index.insert::<Filter>(&mut txn, &data, None).unwrap();
-        assert_eq!(vec.data, data);
+        let vec = index.insert::<Filter>(&mut txn, "vector", &data, None).unwrap();
+        assert_eq!(vec.get_data(), data);
         assert!(vec.properties.is_none());
     }
 
@@ -169,7 +175,7 @@ fn test_get_vector() {
 
     let mut all_vectors: Vec<HVector> = Vec::with_capacity(n_base);
     for data in vectors {
-        all_vectors.push(index.insert::<Filter>(&mut txn, &data, None).unwrap());
+        all_vectors.push(index.insert::<Filter>(&mut txn, "vector", &data, None).unwrap());
     }
 
     for inserted_vec in all_vectors {
@@ -211,7 +217,7 @@ fn test_hnsw_search() {
 
     let mut base_all_vectors: Vec<HVector> = Vec::new();
     for data in base_vectors.iter() {
-        base_all_vectors.push(index.insert::<Filter>(&mut txn, &data, None).unwrap());
+        base_all_vectors.push(index.insert::<Filter>(&mut txn, "vector", &data, None).unwrap());
     }
     txn.commit().unwrap();
 
@@ -259,6 +265,187 @@ fn test_hnsw_search() {
     assert!(total_precision>= 0.8, "precision not high enough!");
 }
 
+fn label_fields(label: &str) -> Option<Vec<(String, Value)>> {
+    Some(vec![("label".to_string(), Value::from(label))])
+}
+
+#[test]
+fn test_hnsw_search_per_label() {
+    let env = setup_temp_env();
+    let mut txn = env.write_txn().unwrap();
+    let index = VectorCore::new(&env, &mut txn, HNSWConfig::new(None, None, None)).unwrap();
+
+    let dims = 16;
+    for data in gen_sim_vecs(500, dims, 0.8) {
+        index.insert::<Filter>(&mut txn, "big", &data, label_fields("big")).unwrap();
+    }
+    let small = gen_sim_vecs(20, dims, 0.8)
+        .into_iter()
+        .map(|data| index.insert::<Filter>(&mut txn, "small", &data, label_fields("small")).unwrap().get_id())
+        .collect::<HashSet<u128>>();
+    txn.commit().unwrap();
+
+    // walking only the small graph fills k from it, however large the other graph is
+    let txn = env.read_txn().unwrap();
+    let query = gen_sim_vecs(1, dims, 0.8).remove(0);
+    let results = index.search::<Filter>(&txn, &query, 10, "small", None, false).unwrap();
+    assert_eq!(results.len(), 10);
+    assert!(results.iter().all(|v| small.contains(&v.get_id())));
+
+    assert!(index.search::<Filter>(&txn, &query, 5, "missing", None, false).is_err());
+}
+
+#[test]
+fn test_shared_graph_split_by_label_on_open() {
+    let env = setup_temp_env();
+    let mut txn = env.write_txn().unwrap();
+    let index = VectorCore::new(&env, &mut txn, HNSWConfig::new(None, None, None)).unwrap();
+
+    // build a graph the way it was stored before each label had its own entry point
+    let dims = 16;
+    let mut ids = HashMap::new();
+    for (i, data) in gen_sim_vecs(200, dims, 0.8).into_iter().enumerate() {
+        let label = if i % 10 == 0 { "small" } else { "big" };
+        let vector = index.insert::<Filter>(&mut txn, "shared", &data, label_fields(label)).unwrap();
+        ids.insert(vector.get_id(), label);
+    }
+    let entry_point = index.vectors_db.get(&txn, b"entry_point:shared").unwrap().unwrap().to_vec();
+    index.vectors_db.delete(&mut txn, b"entry_point:shared").unwrap();
+    index.vectors_db.put(&mut txn, b"entry_point", &entry_point).unwrap();
+    txn.commit().unwrap();
+
+    let mut txn = env.write_txn().unwrap();
+    let index = VectorCore::new(&env, &mut txn, HNSWConfig::new(None, None, None)).unwrap();
+    assert!(index.rebuild_pending(&txn).unwrap());
+    txn.commit().unwrap();
+    assert_eq!(index.finish_rebuild(&env, 7, false).unwrap(), 0);
+
+    let txn = env.read_txn().unwrap();
+    assert!(!index.rebuild_pending(&txn).unwrap());
+    assert!(index.vectors_db.get(&txn, b"entry_point").unwrap().is_none());
+    let query = gen_sim_vecs(1, dims, 0.8).remove(0);
+    for label in ["small", "big"] {
+        let results = index.search::<Filter>(&txn, &query, 10, label, None, false).unwrap();
+        assert_eq!(results.len(), 10);
+        assert!(results.iter().all(|v| ids.get(&v.get_id()) == Some(&label)));
+    }
+}
+
+#[test]
+fn test_shared_graph_rebuilt_on_open_keeps_tombstones() {
+    let env = setup_temp_env();
+    let mut txn = env.write_txn().unwrap();
+    let index = VectorCore::new(&env, &mut txn, HNSWConfig::new(None, None, None)).unwrap();
+
+    let mut ids = Vec::new();
+    for data in gen_sim_vecs(50, 16, 0.8) {
+        ids.push(
+            index
+                .insert::<Filter>(&mut txn, "vector", &data, label_fields("vector"))
+                .unwrap()
+                .get_id(),
+        );
+    }
+    let properties = HashMap::from([
+        ("label".to_string(), Value::from("vector")),
+        ("is_deleted".to_string(), Value::Boolean(true)),
+    ]);
+    for id in ids.iter().take(5) {
+        index
+            .vector_data_db
+            .put(&mut txn, &id.to_be_bytes(), &bincode::serialize(&properties).unwrap())
+            .unwrap();
+    }
+    let entry_point = index.vectors_db.get(&txn, b"entry_point:vector").unwrap().unwrap().to_vec();
+    index.vectors_db.delete(&mut txn, b"entry_point:vector").unwrap();
+    index.vectors_db.put(&mut txn, b"entry_point", &entry_point).unwrap();
+    txn.commit().unwrap();
+
+    let mut txn = env.write_txn().unwrap();
+    let index = VectorCore::new(&env, &mut txn, HNSWConfig::new(None, None, None)).unwrap();
+    txn.commit().unwrap();
+    assert_eq!(index.finish_rebuild(&env, 3, false).unwrap(), 0);
+
+    let txn = env.read_txn().unwrap();
+    assert_eq!(index.get_all_vectors(&txn, Some(0)).unwrap().len(), ids.len());
+    for id in ids.iter().take(5) {
+        assert!(index.vector_data_db.get(&txn, &id.to_be_bytes()).unwrap().is_some());
+    }
+    assert!(entry_point_id(&index, &txn, "vector").is_some());
+}
+
+#[test]
+fn test_vector_types_enforce_dimensions_and_metric() {
+    let env = setup_temp_env();
+    let mut txn = env.write_txn().unwrap();
+    let config = HNSWConfig::new(None, None, None).with_vector_types(vec![VectorTypeConfig {
+        label: "image".to_string(),
+        dimensions: Some(2),
+        metric: DistanceMetric::Euclidean,
+        precision: VectorPrecision::F64,
+        rerank: false,
+    }]);
+    let index = VectorCore::new(&env, &mut txn, config).unwrap();
+
+    assert!(matches!(
+        index.insert::<Filter>(&mut txn, "image", &[1.0, 2.0, 3.0], label_fields("image")),
+        Err(VectorError::DimensionMismatch { expected: 2, found: 3, .. })
+    ));
+
+    // the same direction at different magnitudes is equally close under cosine but not under L2
+    let near = index.insert::<Filter>(&mut txn, "image", &[10.0, 0.0], label_fields("image")).unwrap();
+    index.insert::<Filter>(&mut txn, "image", &[1.0, 0.0], label_fields("image")).unwrap();
+    index.insert::<Filter>(&mut txn, "image", &[0.0, 9.0], label_fields("image")).unwrap();
+    txn.commit().unwrap();
+
+    let txn = env.read_txn().unwrap();
+    let results = index.search::<Filter>(&txn, &[9.0, 0.0], 1, "image", None, false).unwrap();
+    assert_eq!(results[0].get_id(), near.get_id());
+    assert_eq!(results[0].get_distance(), 1.0);
+    assert!(index.search::<Filter>(&txn, &[9.0], 1, "image", None, false).is_err());
+}
+
+#[test]
+fn test_vector_types_compact_precision_with_rerank() {
+    let env = setup_temp_env();
+    let mut txn = env.write_txn().unwrap();
+    let config = HNSWConfig::new(None, None, None).with_vector_types(vec![VectorTypeConfig {
+        label: "doc".to_string(),
+        dimensions: None,
+        metric: DistanceMetric::Cosine,
+        precision: VectorPrecision::Int8,
+        rerank: true,
+    }]);
+    let index = VectorCore::new(&env, &mut txn, config).unwrap();
+
+    let dims = 64;
+    let vectors = gen_sim_vecs(200, dims, 0.8);
+    for data in &vectors {
+        let vec = index.insert::<Filter>(&mut txn, "doc", data, label_fields("doc")).unwrap();
+        assert_eq!(vec.get_data(), *data);
+    }
+    txn.commit().unwrap();
+
+    let txn = env.read_txn().unwrap();
+    let stored = index.get_all_vectors(&txn, Some(0)).unwrap();
+    assert_eq!(stored.len(), vectors.len());
+    assert!(stored.iter().all(|v| v.data.precision() == VectorPrecision::Int8));
+
+    let query = &vectors[0];
+    let results = index.search::<Filter>(&txn, query, 5, "doc", None, false).unwrap();
+    assert_eq!(results.len(), 5);
+    // reranked results carry their full precision data and distances
+    assert_eq!(results[0].get_data(), *query);
+    assert!(results[0].get_distance().abs() < 1e-9);
+    for result in &results {
+        let full = HVector::new(result.get_data().into_owned());
+        let distance = full
+            .distance_to(&HVector::new(query.clone()), DistanceMetric::Cosine)
+            .unwrap();
+        assert!((result.get_distance() - distance).abs() < 1e-9);
+    }
+}
+
 #[test]
 fn test_hnsw_search_property_ordering() {
 }
@@ -267,7 +454,151 @@ fn test_hnsw_search_property_ordering() {
 fn test_hnsw_search_filter_ordering() {
 }
 
+fn entry_point_id(index: &VectorCore, txn: &RoTxn, label: &str) -> Option<u128> {
+    index
+        .vectors_db
+        .get(txn, format!("entry_point:{label}").as_bytes())
+        .unwrap()
+        .map(|bytes| u128::from_be_bytes(bytes.try_into().unwrap()))
+}
+
 #[test]
 fn test_hnsw_delete() {
+    let env = setup_temp_env();
+    let mut txn = env.write_txn().unwrap();
+    let index = VectorCore::new(&env, &mut txn, HNSWConfig::new(None, None, None)).unwrap();
+
+    let vectors = gen_sim_vecs(300, 32, 0.8);
+    let ids: Vec<u128> = vectors
+        .iter()
+        .map(|data| {
+            index
+                .insert::<Filter>(&mut txn, "vector", data, label_fields("vector"))
+                .unwrap()
+                .get_id()
+        })
+        .collect();
+    txn.commit().unwrap();
+
+    let mut txn = env.write_txn().unwrap();
+    let entry_point = entry_point_id(&index, &txn, "vector").unwrap();
+    let mut deleted: HashSet<u128> = ids.iter().copied().step_by(3).collect();
+    deleted.insert(entry_point);
+    for id in &deleted {
+        index.delete(&mut txn, *id).unwrap();
+    }
+    assert!(matches!(
+        index.delete(&mut txn, entry_point),
+        Err(VectorError::VectorNotFound(_))
+    ));
+    txn.commit().unwrap();
+
+    let txn = env.read_txn().unwrap();
+    let new_entry_point = entry_point_id(&index, &txn, "vector").unwrap();
+    assert!(!deleted.contains(&new_entry_point));
+    for id in &deleted {
+        assert!(index.get_vector(&txn, *id, 0, true).is_err());
+    }
+    // deleted vectors have no neighbours left to be traversed from
+    for result in index.edges_db.iter(&txn).unwrap() {
+        let (key, _) = result.unwrap();
+        let source = u128::from_be_bytes(key[..16].try_into().unwrap());
+        assert!(!deleted.contains(&source));
+    }
+    assert_eq!(
+        index.get_all_vectors(&txn, Some(0)).unwrap().len(),
+        ids.len() - deleted.len()
+    );
+
+    let kept = ids.iter().position(|id| !deleted.contains(id)).unwrap();
+    let results = index
+        .search::<Filter>(&txn, &vectors[kept], 10, "vector", None, false)
+        .unwrap();
+    assert_eq!(results.len(), 10);
+    assert_eq!(results[0].get_id(), ids[kept]);
+    assert!(results.iter().all(|v| !deleted.contains(&v.get_id())));
+}
+
+#[test]
+fn test_hnsw_delete_isolated_entry_point() {
+    let env = setup_temp_env();
+    let mut txn = env.write_txn().unwrap();
+    let index = VectorCore::new(&env, &mut txn, HNSWConfig::new(None, None, None)).unwrap();
+
+    let vectors = gen_sim_vecs(10, 8, 0.8);
+    let ids: Vec<u128> = vectors
+        .iter()
+        .map(|data| {
+            index
+                .insert::<Filter>(&mut txn, "vector", data, label_fields("vector"))
+                .unwrap()
+                .get_id()
+        })
+        .collect();
+    index
+        .insert::<Filter>(&mut txn, "other", &vectors[0], label_fields("other"))
+        .unwrap();
+    // leave the entry point without any neighbour to hand over to
+    index.edges_db.clear(&mut txn).unwrap();
+
+    let entry_point = entry_point_id(&index, &txn, "vector").unwrap();
+    index.delete(&mut txn, entry_point).unwrap();
+    let new_entry_point = entry_point_id(&index, &txn, "vector").unwrap();
+    assert_ne!(new_entry_point, entry_point);
+    assert!(ids.contains(&new_entry_point));
+
+    // the key is only removed once the label has no vectors left
+    for id in ids.iter().filter(|id| **id != entry_point) {
+        assert!(entry_point_id(&index, &txn, "vector").is_some());
+        index.delete(&mut txn, *id).unwrap();
+    }
+    assert!(entry_point_id(&index, &txn, "vector").is_none());
+    assert!(entry_point_id(&index, &txn, "other").is_some());
+}
+
+#[test]
+fn test_hnsw_compact_removes_tombstones() {
+    let env = setup_temp_env();
+    let mut txn = env.write_txn().unwrap();
+    let index = VectorCore::new(&env, &mut txn, HNSWConfig::new(None, None, None)).unwrap();
+
+    let vectors = gen_sim_vecs(100, 16, 0.8);
+    let mut ids = Vec::new();
+    for data in &vectors {
+        ids.push(
+            index
+                .insert::<Filter>(&mut txn, "vector", data, label_fields("vector"))
+                .unwrap()
+                .get_id(),
+        );
+    }
+
+    // deletes used to only mark vectors as deleted and leave them in the graph
+    let tombstones: HashSet<u128> = ids.iter().copied().take(20).collect();
+    for id in &tombstones {
+        let properties = HashMap::from([
+            ("label".to_string(), Value::from("vector")),
+            ("is_deleted".to_string(), Value::Boolean(true)),
+        ]);
+        index
+            .vector_data_db
+            .put(&mut txn, &id.to_be_bytes(), &bincode::serialize(&properties).unwrap())
+            .unwrap();
+    }
+
+    txn.commit().unwrap();
+    assert_eq!(index.compact(&env, 7).unwrap(), tombstones.len());
+
+    let txn = env.read_txn().unwrap();
+    let remaining = index.get_all_vectors(&txn, Some(0)).unwrap();
+    assert_eq!(remaining.len(), ids.len() - tombstones.len());
+    assert!(remaining.iter().all(|v| !tombstones.contains(&v.get_id())));
+    assert!(!tombstones.contains(&entry_point_id(&index, &txn, "vector").unwrap()));
+
+    let results = index
+        .search::<Filter>(&txn, &vectors[50], 10, "vector", None, false)
+        .unwrap();
+    assert_eq!(results.len(), 10);
+    assert_eq!(results[0].get_id(), ids[50]);
 }
 
@@ -1,7 +1,7 @@
 use crate::{
     helix_engine::{
         types::{GraphError, VectorError},
-        vector_core::vector_distance::DistanceCalc,
+        vector_core::vector_distance::{DistanceCalc, DistanceMetric},
     },
     protocol::{return_values::ReturnValue, value::Value},
     utils::{
@@ -13,10 +13,102 @@ use core::fmt;
 use serde::{Deserialize, Serialize};
 use std::{borrow::Cow, cmp::Ordering, collections::HashMap, fmt::Debug};
 
-// TODO: make this generic over the type of encoding (f32, f64, etc)
 // TODO: use const param to set dimension
 // TODO: set level as u8
 
+/// How the components of a vector type are stored, declared with `precision:` on its schema.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VectorPrecision {
+    #[default]
+    F64,
+    F32,
+    /// Scalar quantized to one byte per dimension, with a scale per vector
+    Int8,
+}
+
+impl fmt::Display for VectorPrecision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VectorPrecision::F64 => write!(f, "VectorPrecision::F64"),
+            VectorPrecision::F32 => write!(f, "VectorPrecision::F32"),
+            VectorPrecision::Int8 => write!(f, "VectorPrecision::Int8"),
+        }
+    }
+}
+
+/// The components of a vector in the precision it is stored with.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub enum VectorData {
+    F64(Vec<f64>),
+    F32(Vec<f32>),
+    /// Each component is `value * scale`
+    Int8 { scale: f32, values: Vec<i8> },
+}
+
+/// Marks vectors stored in a compact precision. As the first bytes of a big endian f64
+/// it is a NaN, so it can't be mistaken for a vector stored with full precision.
+const COMPACT_MAGIC: [u8; 3] = [0xFF, 0xFF, 0xFF];
+const F32_TAG: u8 = 1;
+const INT8_TAG: u8 = 2;
+
+impl VectorData {
+    /// Converts full precision components into the given precision
+    pub fn from_f64(data: Vec<f64>, precision: VectorPrecision) -> Self {
+        match precision {
+            VectorPrecision::F64 => VectorData::F64(data),
+            VectorPrecision::F32 => VectorData::F32(data.iter().map(|&v| v as f32).collect()),
+            VectorPrecision::Int8 => {
+                let max = data.iter().fold(0.0f64, |max, v| max.max(v.abs()));
+                let scale = if max > 0.0 { max / i8::MAX as f64 } else { 1.0 };
+                VectorData::Int8 {
+                    scale: scale as f32,
+                    values: data
+                        .iter()
+                        .map(|&v| (v / scale).round().clamp(-127.0, 127.0) as i8)
+                        .collect(),
+                }
+            }
+        }
+    }
+
+    #[inline(always)]
+    pub fn precision(&self) -> VectorPrecision {
+        match self {
+            VectorData::F64(_) => VectorPrecision::F64,
+            VectorData::F32(_) => VectorPrecision::F32,
+            VectorData::Int8 { .. } => VectorPrecision::Int8,
+        }
+    }
+
+    /// Returns the components as f64, dequantizing them if needed
+    pub fn to_f64(&self) -> Cow<'_, [f64]> {
+        match self {
+            VectorData::F64(data) => Cow::Borrowed(data),
+            VectorData::F32(data) => Cow::Owned(data.iter().map(|&v| v as f64).collect()),
+            VectorData::Int8 { scale, values } => Cow::Owned(
+                values
+                    .iter()
+                    .map(|&v| v as f64 * *scale as f64)
+                    .collect(),
+            ),
+        }
+    }
+
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        match self {
+            VectorData::F64(data) => data.len(),
+            VectorData::F32(data) => data.len(),
+            VectorData::Int8 { values, .. } => values.len(),
+        }
+    }
+
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
 #[repr(C, align(16))] // TODO: see performance impact of repr(C) and align(16)
 #[derive(Clone, Serialize, Deserialize, PartialEq)]
 pub struct HVector {
@@ -29,7 +121,7 @@ pub struct HVector {
     /// The distance of the HVector
     pub distance: Option<f64>,
     /// The actual vector
-    pub data: Vec<f64>,
+    pub data: VectorData,
     /// The properties of the HVector
     pub properties: Option<HashMap<String, Value>>,
 }
@@ -72,7 +164,7 @@ impl HVector {
             id,
             // is_deleted: false,
             level: 0,
-            data,
+            data: VectorData::F64(data),
             distance: None,
             properties: None,
         }
@@ -85,16 +177,25 @@ impl HVector {
             id,
             // is_deleted: false,
             level,
-            data,
+            data: VectorData::F64(data),
             distance: None,
             properties: None,
         }
     }
 
-    /// Returns the data of the HVector
+    /// Converts the data of the HVector into the given precision
     #[inline(always)]
-    pub fn get_data(&self) -> &[f64] {
-        &self.data
+    pub fn with_precision(mut self, precision: VectorPrecision) -> Self {
+        if self.data.precision() != precision {
+            self.data = VectorData::from_f64(self.data.to_f64().into_owned(), precision);
+        }
+        self
+    }
+
+    /// Returns the data of the HVector, dequantized if it is stored in a compact precision
+    #[inline(always)]
+    pub fn get_data(&self) -> Cow<'_, [f64]> {
+        self.data.to_f64()
     }
 
     /// Returns the id of the HVector
@@ -110,30 +211,70 @@ impl HVector {
     }
 
     /// Converts the HVector to an vec of bytes by accessing the data field directly
-    /// and converting each f64 to a byte slice
+    /// and converting each value to a byte slice.
+    ///
+    /// Full precision vectors are stored as plain f64s, compact ones are prefixed
+    /// with a header saying how they are encoded.
     pub fn to_bytes(&self) -> Vec<u8> {
-        let size = self.data.len() * std::mem::size_of::<f64>();
-        let mut bytes = Vec::with_capacity(size);
-        for &value in &self.data {
-            bytes.extend_from_slice(&value.to_be_bytes());
+        match &self.data {
+            VectorData::F64(data) => {
+                let mut bytes = Vec::with_capacity(data.len() * std::mem::size_of::<f64>());
+                for &value in data {
+                    bytes.extend_from_slice(&value.to_be_bytes());
+                }
+                bytes
+            }
+            VectorData::F32(data) => {
+                let mut bytes = Vec::with_capacity(4 + data.len() * std::mem::size_of::<f32>());
+                bytes.extend_from_slice(&COMPACT_MAGIC);
+                bytes.push(F32_TAG);
+                for &value in data {
+                    bytes.extend_from_slice(&value.to_be_bytes());
+                }
+                bytes
+            }
+            VectorData::Int8 { scale, values } => {
+                let mut bytes = Vec::with_capacity(8 + values.len());
+                bytes.extend_from_slice(&COMPACT_MAGIC);
+                bytes.push(INT8_TAG);
+                bytes.extend_from_slice(&scale.to_be_bytes());
+                bytes.extend(values.iter().map(|&v| v as u8));
+                bytes
+            }
         }
-        bytes
     }
 
-    // will make to use const param for type of encoding (f32, f64, etc)
-    /// Converts a byte array into a HVector by chunking the bytes into f64 values
+    /// Converts a byte array into a HVector by chunking the bytes into values
+    /// of the precision they were stored with
     pub fn from_bytes(id: u128, level: usize, bytes: &[u8]) -> Result<Self, VectorError> {
-        if bytes.len() % std::mem::size_of::<f64>() != 0 {
-            return Err(VectorError::InvalidVectorData);
-        }
-
-        let mut data = Vec::with_capacity(bytes.len() / std::mem::size_of::<f64>());
-        let chunks = bytes.chunks_exact(std::mem::size_of::<f64>());
-
-        for chunk in chunks {
-            let value = f64::from_be_bytes(chunk.try_into().unwrap());
-            data.push(value);
-        }
+        let data = match bytes {
+            [0xFF, 0xFF, 0xFF, F32_TAG, rest @ ..] => {
+                if !rest.len().is_multiple_of(std::mem::size_of::<f32>()) {
+                    return Err(VectorError::InvalidVectorData);
+                }
+                VectorData::F32(
+                    rest.chunks_exact(std::mem::size_of::<f32>())
+                        .map(|chunk| f32::from_be_bytes(chunk.try_into().unwrap()))
+                        .collect(),
+                )
+            }
+            [0xFF, 0xFF, 0xFF, INT8_TAG, s0, s1, s2, s3, rest @ ..] => VectorData::Int8 {
+                scale: f32::from_be_bytes([*s0, *s1, *s2, *s3]),
+                values: rest.iter().map(|&v| v as i8).collect(),
+            },
+            [0xFF, 0xFF, 0xFF, ..] => return Err(VectorError::InvalidVectorData),
+            _ => {
+                if !bytes.len().is_multiple_of(std::mem::size_of::<f64>()) {
+                    return Err(VectorError::InvalidVectorData);
+                }
+                VectorData::F64(
+                    bytes
+                        .chunks_exact(std::mem::size_of::<f64>())
+                        .map(|chunk| f64::from_be_bytes(chunk.try_into().unwrap()))
+                        .collect(),
+                )
+            }
+        };
 
         Ok(HVector {
             id,
@@ -155,9 +296,14 @@ impl HVector {
         self.data.is_empty()
     }
 
+    /// Returns the distance to another vector under the metric of their vector type
     #[inline(always)]
-    pub fn distance_to(&self, other: &HVector) -> Result<f64, VectorError> {
-        HVector::distance(self, other)
+    pub fn distance_to(
+        &self,
+        other: &HVector,
+        metric: DistanceMetric,
+    ) -> Result<f64, VectorError> {
+        HVector::distance(metric, self, other)
     }
 
     #[inline(always)]
@@ -216,16 +362,14 @@ impl Filterable for HVector {
     }
 
     fn properties(self) -> Option<HashMap<String, Value>> {
+        let data = Value::Array(self.get_data().iter().map(|f| Value::F64(*f)).collect());
         let mut properties = self.properties.unwrap_or_default();
-        properties.insert(
-            "data".to_string(),
-            Value::Array(self.data.iter().map(|f| Value::F64(*f)).collect()),
-        );
+        properties.insert("data".to_string(), data);
         Some(properties)
     }
 
-    fn vector_data(&self) -> &[f64] {
-        &self.data
+    fn vector_data(&self) -> Cow<'_, [f64]> {
+        self.get_data()
     }
 
     fn score(&self) -> f64 {
@@ -245,7 +389,7 @@ impl Filterable for HVector {
             "id" => Ok(Cow::Owned(Value::from(self.uuid()))),
             "label" => Ok(Cow::Owned(Value::from(self.label().to_string()))),
             "data" => Ok(Cow::Owned(Value::Array(
-                self.data.iter().map(|f| Value::F64(*f)).collect(),
+                self.get_data().iter().map(|f| Value::F64(*f)).collect(),
             ))),
             _ => match &self.properties {
                 Some(properties) => properties
@@ -1,14 +1,21 @@
 use crate::{
     debug_println,
     helix_engine::{
+        graph_core::config::VectorTypeConfig,
+        storage_core::{
+            index_key::prefix_successor,
+            migration::{for_each_batch, for_each_batch_in},
+        },
         types::VectorError,
         vector_core::{
             hnsw::HNSW,
             utils::{Candidate, HeapOps, VectorFilter},
-            vector::HVector,
+            vector::{HVector, VectorPrecision},
+            vector_distance::DistanceMetric,
         },
     },
     protocol::value::Value,
+    utils::filterable::Filterable,
 };
 use heed3::{
     Database, Env, RoTxn, RwTxn,
@@ -17,13 +24,18 @@ use heed3::{
 use itertools::Itertools;
 use rand::prelude::Rng;
 use serde::{Deserialize, Serialize};
-use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::{
+    collections::{BinaryHeap, HashMap, HashSet},
+    ops::Bound,
+};
 
-const DB_VECTORS: &str = "vectors"; // for vector data (v:)
-const DB_VECTOR_DATA: &str = "vector_data"; // for vector data (v:)
-const DB_HNSW_EDGES: &str = "hnsw_out_nodes"; // for hnsw out node data
+pub(crate) const DB_VECTORS: &str = "vectors"; // for vector data (v:)
+pub(crate) const DB_VECTOR_DATA: &str = "vector_data"; // for vector data (v:)
+pub(crate) const DB_HNSW_EDGES: &str = "hnsw_out_nodes"; // for hnsw out node data
 const VECTOR_PREFIX: &[u8] = b"v:";
-const ENTRY_POINT_KEY: &str = "entry_point";
+const FULL_PRECISION_PREFIX: &[u8] = b"f:"; // full precision copies of compact vectors, for reranking
+const ENTRY_POINT_KEY: &str = "entry_point"; // suffixed with `:{label}`, one per vector label
+const REBUILD_KEY: &[u8] = b"rebuild"; // key of the last vector linked by a pending rebuild
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HNSWConfig {
@@ -33,6 +45,7 @@ pub struct HNSWConfig {
     pub m_l: f64,             // level generation factor
     pub ef: usize,            // search param, num of cands to search
     pub min_neighbors: usize, // for get_neighbors, always 512
+    pub vector_types: HashMap<String, VectorTypeConfig>, // dimensions, metric and precision by vector label
 }
 
 impl HNSWConfig {
@@ -55,8 +68,18 @@ impl HNSWConfig {
             m_l: 1.0 / (m as f64).ln(),
             ef,
             min_neighbors: 512,
+            vector_types: HashMap::new(),
         }
     }
+
+    /// Sets the dimensions, distance metric and storage precision of each vector type
+    pub fn with_vector_types(mut self, vector_types: Vec<VectorTypeConfig>) -> Self {
+        self.vector_types = vector_types
+            .into_iter()
+            .map(|vector_type| (vector_type.label.clone(), vector_type))
+            .collect();
+        self
+    }
 }
 
 pub struct VectorCore {
@@ -72,12 +95,171 @@ impl VectorCore {
         let vector_data_db = env.create_database(txn, Some(DB_VECTOR_DATA))?;
         let edges_db = env.create_database(txn, Some(DB_HNSW_EDGES))?;
 
-        Ok(Self {
+        let core = Self {
             vectors_db,
             vector_data_db,
             edges_db,
             config,
-        })
+        };
+
+        // databases from before per-label graphs have a single entry point shared by every label,
+        // rebuilding them links each vector into the graph of its own label once opened
+        if core
+            .vectors_db
+            .get(txn, ENTRY_POINT_KEY.as_bytes())?
+            .is_some()
+            && !core.rebuild_pending(txn)?
+        {
+            core.vectors_db.put(txn, REBUILD_KEY, &[])?;
+        }
+
+        Ok(core)
+    }
+
+    /// Whether a rebuild of the graphs was started and not finished yet,
+    /// see [`finish_rebuild`](Self::finish_rebuild)
+    pub fn rebuild_pending(&self, txn: &RoTxn) -> Result<bool, VectorError> {
+        Ok(self.vectors_db.get(txn, REBUILD_KEY)?.is_some())
+    }
+
+    /// Rebuilds the graph of every vector label from the vectors stored at level 0,
+    /// dropping the vectors left behind as `is_deleted` tombstones.
+    ///
+    /// Vectors keep their ids and data, only their levels and neighbours are recomputed.
+    /// Returns the number of vectors removed.
+    pub fn compact(&self, env: &Env, batch_size: usize) -> Result<usize, VectorError> {
+        let mut txn = env.write_txn()?;
+        self.vectors_db.put(&mut txn, REBUILD_KEY, &[])?;
+        txn.commit()?;
+        self.finish_rebuild(env, batch_size, true)
+    }
+
+    /// Finishes the pending rebuild of the graphs, started by [`compact`](Self::compact) or by
+    /// opening a database with a graph shared by every label, from where it was interrupted.
+    ///
+    /// The graphs are cleared first, then the vectors at level 0 are linked back in key order,
+    /// `batch_size` at a time, each batch in its own write transaction along with the key of its
+    /// last vector. Tombstones are only removed when `purge_deleted` is set, otherwise they are
+    /// linked back like any other vector. Returns the number of vectors removed.
+    pub fn finish_rebuild(
+        &self,
+        env: &Env,
+        batch_size: usize,
+        purge_deleted: bool,
+    ) -> Result<usize, VectorError> {
+        let txn = env.read_txn()?;
+        let cursor = match self.vectors_db.get(&txn, REBUILD_KEY)? {
+            Some(cursor) => cursor.to_vec(),
+            None => return Ok(0),
+        };
+        drop(txn);
+        // an empty cursor means the graphs are not cleared yet
+        let cursor = match cursor.is_empty() {
+            true => {
+                self.clear_graphs(env, batch_size)?;
+                VECTOR_PREFIX.to_vec()
+            }
+            false => cursor,
+        };
+
+        let end = prefix_successor(VECTOR_PREFIX).ok_or(VectorError::InvalidVectorData)?;
+        let mut removed = 0;
+        for_each_batch_in::<_, VectorError>(
+            env,
+            self.vectors_db,
+            (Bound::Excluded(&cursor), Bound::Excluded(&end)),
+            batch_size,
+            |key, _| Self::parse_vector_key(key),
+            |txn, batch| {
+                // levels above 0 are the ones linked in by this rebuild
+                for &(id, level) in &batch {
+                    if level == 0 && self.relink_vector(txn, id, purge_deleted)? {
+                        removed += 1;
+                    }
+                }
+                if let Some(&(id, level)) = batch.last() {
+                    self.vectors_db
+                        .put(txn, REBUILD_KEY, &Self::vector_key(id, level))?;
+                }
+                Ok(())
+            },
+        )?;
+
+        let mut txn = env.write_txn()?;
+        self.vectors_db.delete(&mut txn, REBUILD_KEY)?;
+        txn.commit()?;
+
+        debug_println!("rebuilt hnsw graphs, removed {removed} deleted vectors");
+        Ok(removed)
+    }
+
+    /// Removes the upper levels, edges and entry points of every graph in batches,
+    /// leaving the vectors at level 0 for [`finish_rebuild`](Self::finish_rebuild) to link back
+    fn clear_graphs(&self, env: &Env, batch_size: usize) -> Result<(), VectorError> {
+        let end = prefix_successor(VECTOR_PREFIX).ok_or(VectorError::InvalidVectorData)?;
+        for_each_batch_in::<_, VectorError>(
+            env,
+            self.vectors_db,
+            (Bound::Excluded(VECTOR_PREFIX), Bound::Excluded(&end)),
+            batch_size,
+            |key, _| Self::parse_vector_key(key),
+            |txn, batch| {
+                for (id, level) in batch {
+                    if level > 0 {
+                        self.vectors_db.delete(txn, &Self::vector_key(id, level))?;
+                    }
+                }
+                Ok(())
+            },
+        )?;
+        for_each_batch::<_, VectorError>(
+            env,
+            self.edges_db.remap_data_type::<Bytes>(),
+            batch_size,
+            |key, _| Ok(key.to_vec()),
+            |txn, keys| {
+                for key in keys {
+                    self.edges_db.delete(txn, &key)?;
+                }
+                Ok(())
+            },
+        )?;
+
+        let mut txn = env.write_txn()?;
+        let entry_points = self
+            .vectors_db
+            .lazily_decode_data()
+            .prefix_iter(&txn, ENTRY_POINT_KEY.as_bytes())?
+            .map(|result| result.map(|(key, _)| key.to_vec()))
+            .collect::<Result<Vec<_>, _>>()?;
+        for key in entry_points {
+            self.vectors_db.delete(&mut txn, &key)?;
+        }
+        self.vectors_db.put(&mut txn, REBUILD_KEY, VECTOR_PREFIX)?;
+        txn.commit()?;
+        Ok(())
+    }
+
+    /// Links a vector stored at level 0 back into the graph of its label, or removes it if it is
+    /// a tombstone and `purge_deleted` is set. Returns whether it was removed.
+    fn relink_vector(
+        &self,
+        txn: &mut RwTxn,
+        id: u128,
+        purge_deleted: bool,
+    ) -> Result<bool, VectorError> {
+        let mut vector = self.get_vector(txn, id, 0, true)?;
+        if purge_deleted
+            && let Ok(is_deleted) = vector.check_property("is_deleted")
+            && let Value::Boolean(true) = is_deleted.as_ref()
+        {
+            self.remove_vector_data(txn, id)?;
+            return Ok(true);
+        }
+        let label = vector.label().to_string();
+        vector.properties = None;
+        self.index_vector::<fn(&HVector, &RoTxn) -> bool>(txn, &label, &mut vector)?;
+        Ok(false)
     }
 
     #[inline(always)]
@@ -85,6 +267,32 @@ impl VectorCore {
         [VECTOR_PREFIX, &id.to_be_bytes(), &level.to_be_bytes()].concat()
     }
 
+    #[inline(always)]
+    fn parse_vector_key(key: &[u8]) -> Result<(u128, usize), VectorError> {
+        let id_end = VECTOR_PREFIX.len() + 16;
+        match (key.get(VECTOR_PREFIX.len()..id_end), key.get(id_end..)) {
+            (Some(id), Some(level)) => Ok((
+                u128::from_be_bytes(id.try_into().map_err(|_| VectorError::InvalidVectorData)?),
+                usize::from_be_bytes(
+                    level
+                        .try_into()
+                        .map_err(|_| VectorError::InvalidVectorData)?,
+                ),
+            )),
+            _ => Err(VectorError::InvalidVectorData),
+        }
+    }
+
+    #[inline(always)]
+    fn full_precision_key(id: u128) -> Vec<u8> {
+        [FULL_PRECISION_PREFIX, &id.to_be_bytes()].concat()
+    }
+
+    #[inline(always)]
+    fn entry_point_key(label: &str) -> Vec<u8> {
+        [ENTRY_POINT_KEY.as_bytes(), b":", label.as_bytes()].concat()
+    }
+
     #[inline(always)]
     fn out_edges_key(source_id: u128, level: usize, sink_id: Option<u128>) -> Vec<u8> {
         match sink_id {
@@ -104,6 +312,109 @@ impl VectorCore {
         }
     }
 
+    /// Distance metric declared on a vector type, cosine for undeclared ones
+    #[inline]
+    pub fn metric(&self, label: &str) -> DistanceMetric {
+        self.config
+            .vector_types
+            .get(label)
+            .map(|vector_type| vector_type.metric)
+            .unwrap_or_default()
+    }
+
+    #[inline]
+    fn precision(&self, label: &str) -> VectorPrecision {
+        self.config
+            .vector_types
+            .get(label)
+            .map(|vector_type| vector_type.precision)
+            .unwrap_or_default()
+    }
+
+    /// Whether search candidates of a compact vector type are reranked with full precision data
+    #[inline]
+    fn should_rerank(&self, label: &str) -> bool {
+        self.config
+            .vector_types
+            .get(label)
+            .is_some_and(|t| t.rerank && t.precision != VectorPrecision::F64)
+    }
+
+    /// Checks a vector against the dimensions declared on its vector type, if any
+    #[inline]
+    fn check_dimensions(&self, label: &str, data: &[f64]) -> Result<(), VectorError> {
+        match self
+            .config
+            .vector_types
+            .get(label)
+            .and_then(|t| t.dimensions)
+        {
+            Some(expected) if expected != data.len() => Err(VectorError::DimensionMismatch {
+                label: label.to_string(),
+                expected,
+                found: data.len(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the highest level a vector is stored at
+    fn top_level(&self, txn: &RoTxn, id: u128) -> Result<usize, VectorError> {
+        let prefix = [VECTOR_PREFIX, &id.to_be_bytes()].concat();
+        let mut top_level = None;
+        for result in self
+            .vectors_db
+            .lazily_decode_data()
+            .prefix_iter(txn, &prefix)?
+        {
+            let (_, level) = Self::parse_vector_key(result?.0)?;
+            top_level = top_level.max(Some(level));
+        }
+        top_level.ok_or_else(|| VectorError::VectorNotFound(id.to_string()))
+    }
+
+    /// Returns the id and level of the vector with `label` stored at the highest level,
+    /// other than `excluded`
+    fn highest_vector_of_label(
+        &self,
+        txn: &RoTxn,
+        label: &str,
+        excluded: u128,
+    ) -> Result<Option<(u128, usize)>, VectorError> {
+        let mut highest = None;
+        for result in self
+            .vectors_db
+            .lazily_decode_data()
+            .prefix_iter(txn, VECTOR_PREFIX)?
+        {
+            let (id, level) = Self::parse_vector_key(result?.0)?;
+            if id == excluded || highest.is_some_and(|(_, top)| level <= top) {
+                continue;
+            }
+            if self.get_vector(txn, id, 0, true)?.label() == label {
+                highest = Some((id, level));
+            }
+        }
+        Ok(highest)
+    }
+
+    /// Removes everything stored for a vector, at every level, but not its edges
+    fn remove_vector_data(&self, txn: &mut RwTxn, id: u128) -> Result<(), VectorError> {
+        let prefix = [VECTOR_PREFIX, &id.to_be_bytes()].concat();
+        let keys = self
+            .vectors_db
+            .lazily_decode_data()
+            .prefix_iter(txn, &prefix)?
+            .map(|result| result.map(|(key, _)| key.to_vec()))
+            .collect::<Result<Vec<_>, _>>()?;
+        for key in keys {
+            self.vectors_db.delete(txn, &key)?;
+        }
+        self.vectors_db.delete(txn, &Self::full_precision_key(id))?;
+        self.vector_data_db.delete(txn, &id.to_be_bytes())?;
+        Ok(())
+    }
+
     #[inline]
     fn get_new_level(&self) -> usize {
         // TODO: look at using the XOR shift algorithm for random number generation
@@ -114,8 +425,8 @@ impl VectorCore {
     }
 
     #[inline]
-    fn get_entry_point(&self, txn: &RoTxn) -> Result<HVector, VectorError> {
-        let ep_id = self.vectors_db.get(txn, ENTRY_POINT_KEY.as_bytes())?;
+    fn get_entry_point(&self, txn: &RoTxn, label: &str) -> Result<HVector, VectorError> {
+        let ep_id = self.vectors_db.get(txn, &Self::entry_point_key(label))?;
         if let Some(ep_id) = ep_id {
             let mut arr = [0u8; 16];
             let len = std::cmp::min(ep_id.len(), 16);
@@ -131,8 +442,13 @@ impl VectorCore {
     }
 
     #[inline]
-    fn set_entry_point(&self, txn: &mut RwTxn, entry: &HVector) -> Result<(), VectorError> {
-        let entry_key = ENTRY_POINT_KEY.as_bytes().to_vec();
+    fn set_entry_point(
+        &self,
+        txn: &mut RwTxn,
+        label: &str,
+        entry: &HVector,
+    ) -> Result<(), VectorError> {
+        let entry_key = Self::entry_point_key(label);
         self.vectors_db
             .put(txn, &entry_key, &entry.get_id().to_be_bytes())
             .map_err(VectorError::from)?;
@@ -185,7 +501,12 @@ impl VectorCore {
                 continue;
             }
 
-            let vector = self.get_vector(txn, neighbor_id, level, false)?;
+            // edges towards deleted vectors can outlive them when only one side was unlinked
+            let vector = match self.get_vector(txn, neighbor_id, level, false) {
+                Ok(vector) => vector,
+                Err(VectorError::VectorNotFound(_)) => continue,
+                Err(e) => return Err(e),
+            };
 
             let passes_filters = match filter {
                 // TODO: look at implementing a macro that actually just runs each function rather than iterating through
@@ -256,6 +577,7 @@ impl VectorCore {
         query: &'a HVector,
         mut cands: BinaryHeap<HVector>,
         level: usize,
+        metric: DistanceMetric,
         should_extend: bool,
         filter: Option<&[F]>,
     ) -> Result<BinaryHeap<HVector>, VectorError>
@@ -276,7 +598,7 @@ impl VectorCore {
                     continue;
                 }
 
-                neighbor.set_distance(neighbor.distance_to(query)?);
+                neighbor.set_distance(neighbor.distance_to(query, metric)?);
 
                 /*
                 let passes_filters = match filter {
@@ -299,6 +621,33 @@ impl VectorCore {
         Ok(result.take_inord(m))
     }
 
+    /// Whether a vector passes every filter, loading its properties first so filters can read them
+    fn passes_filters<F>(
+        &self,
+        txn: &RoTxn,
+        vector: &mut HVector,
+        filter: &[F],
+    ) -> Result<bool, VectorError>
+    where
+        F: Fn(&HVector, &RoTxn) -> bool,
+    {
+        if vector.properties.is_none() {
+            vector.properties = match self
+                .vector_data_db
+                .get(txn, &vector.get_id().to_be_bytes())?
+            {
+                Some(bytes) => Some(bincode::deserialize(bytes)?),
+                None => None,
+            };
+        }
+        Ok(filter.iter().all(|f| f(vector, txn)))
+    }
+
+    /// Greedily searches one level of the graph for the `ef` closest vectors to the query.
+    ///
+    /// Vectors failing the filter are still walked through, so the search reaches matching
+    /// vectors behind them, but are left out of the results. The search only stops early
+    /// once it has `ef` results, otherwise it exhausts the graph.
     fn search_level<'a, F>(
         &'a self,
         txn: &RoTxn,
@@ -306,6 +655,7 @@ impl VectorCore {
         entry_point: &'a mut HVector,
         ef: usize,
         level: usize,
+        metric: DistanceMetric,
         filter: Option<&[F]>,
     ) -> Result<BinaryHeap<HVector>, VectorError>
     where
@@ -315,12 +665,18 @@ impl VectorCore {
         let mut candidates: BinaryHeap<Candidate> = BinaryHeap::new();
         let mut results: BinaryHeap<HVector> = BinaryHeap::new();
 
-        entry_point.set_distance(entry_point.distance_to(query)?);
+        entry_point.set_distance(entry_point.distance_to(query, metric)?);
         candidates.push(Candidate {
             id: entry_point.get_id(),
             distance: entry_point.get_distance(),
         });
-        results.push(entry_point.clone());
+        let passes = match filter {
+            Some(filter) => self.passes_filters(txn, entry_point, filter)?,
+            None => true,
+        };
+        if passes {
+            results.push(entry_point.clone());
+        }
         visited.insert(entry_point.get_id());
 
         while let Some(curr_cand) = candidates.pop() {
@@ -338,35 +694,166 @@ impl VectorCore {
                 None
             };
 
-            self.get_neighbors(txn, curr_cand.id, level, filter)?
-                .into_iter()
-                .filter(|neighbor| visited.insert(neighbor.get_id()))
-                .filter_map(|mut neighbor| {
-                    let distance = neighbor.distance_to(query).ok()?;
-
-                    if max_distance.is_none_or(|max| distance < max) {
-                        neighbor.set_distance(distance);
-                        Some((neighbor, distance))
-                    } else {
-                        None
-                    }
-                })
-                .for_each(|(neighbor, distance)| {
-                    candidates.push(Candidate {
-                        id: neighbor.get_id(),
-                        distance,
-                    });
-
-                    results.push(neighbor);
+            for mut neighbor in self.get_neighbors::<F>(txn, curr_cand.id, level, None)? {
+                if !visited.insert(neighbor.get_id()) {
+                    continue;
+                }
+                let Ok(distance) = neighbor.distance_to(query, metric) else {
+                    continue;
+                };
+                if max_distance.is_some_and(|max| distance >= max) {
+                    continue;
+                }
 
-                    if results.len() > ef {
-                        results = results.take_inord(ef);
-                    }
+                neighbor.set_distance(distance);
+                candidates.push(Candidate {
+                    id: neighbor.get_id(),
+                    distance,
                 });
+
+                if let Some(filter) = filter
+                    && !self.passes_filters(txn, &mut neighbor, filter)?
+                {
+                    continue;
+                }
+                results.push(neighbor);
+
+                if results.len() > ef {
+                    results = results.take_inord(ef);
+                }
+            }
         }
         Ok(results)
     }
 
+    /// Reconnects the former neighbours of a removed vector at one level. Each keeps the
+    /// closest of its remaining neighbours and of the other former neighbours.
+    fn repair_neighbors(
+        &self,
+        txn: &mut RwTxn,
+        orphans: &[HVector],
+        level: usize,
+        metric: DistanceMetric,
+    ) -> Result<(), VectorError> {
+        type Unfiltered = fn(&HVector, &RoTxn) -> bool;
+        for orphan in orphans {
+            let mut seen = HashSet::from([orphan.get_id()]);
+            let mut candidates = BinaryHeap::new();
+            for mut candidate in self
+                .get_neighbors::<Unfiltered>(txn, orphan.get_id(), level, None)?
+                .into_iter()
+                .chain(orphans.iter().cloned())
+            {
+                if !seen.insert(candidate.get_id()) {
+                    continue;
+                }
+                candidate.set_distance(candidate.distance_to(orphan, metric)?);
+                candidates.push(candidate);
+            }
+
+            let neighbors = self.select_neighbors::<Unfiltered>(
+                txn, orphan, candidates, level, metric, false, None,
+            )?;
+            self.set_neighbours(txn, orphan.get_id(), &neighbors, level)?;
+        }
+        Ok(())
+    }
+
+    /// Links a vector already stored at level 0 into the graph of its label,
+    /// at a newly drawn level.
+    fn index_vector<F>(
+        &self,
+        txn: &mut RwTxn,
+        label: &str,
+        query: &mut HVector,
+    ) -> Result<(), VectorError>
+    where
+        F: Fn(&HVector, &RoTxn) -> bool,
+    {
+        let metric = self.metric(label);
+        let new_level = self.get_new_level();
+
+        query.level = new_level;
+        if new_level > 0 {
+            self.put_vector(txn, query)?;
+        }
+
+        let entry_point = match self.get_entry_point(txn, label) {
+            Ok(ep) => ep,
+            Err(_) => {
+                self.set_entry_point(txn, label, query)?;
+                query.set_distance(0.0);
+                return Ok(());
+            }
+        };
+
+        let l = entry_point.get_level();
+        let mut curr_ep = entry_point;
+        for level in (new_level + 1..=l).rev() {
+            let nearest =
+                self.search_level::<F>(txn, query, &mut curr_ep, 1, level, metric, None)?;
+            curr_ep = nearest
+                .peek()
+                .ok_or(VectorError::VectorCoreError(
+                    "emtpy search result".to_string(),
+                ))?
+                .clone();
+        }
+
+        for level in (0..=l.min(new_level)).rev() {
+            let nearest = self.search_level::<F>(
+                txn,
+                query,
+                &mut curr_ep,
+                self.config.ef_construct,
+                level,
+                metric,
+                None,
+            )?;
+            curr_ep = nearest.peek().unwrap().clone();
+
+            let neighbors =
+                self.select_neighbors::<F>(txn, query, nearest, level, metric, true, None)?;
+            self.set_neighbours(txn, query.get_id(), &neighbors, level)?;
+
+            for e in neighbors {
+                let id = e.get_id();
+                let e_conns = BinaryHeap::from(self.get_neighbors::<F>(txn, id, level, None)?);
+                let e_new_conn =
+                    self.select_neighbors::<F>(txn, query, e_conns, level, metric, true, None)?;
+                self.set_neighbours(txn, id, &e_new_conn, level)?;
+            }
+        }
+
+        if new_level > l {
+            self.set_entry_point(txn, label, query)?;
+        }
+
+        Ok(())
+    }
+
+    /// Recomputes the distances of search candidates using the full precision copies
+    /// of their vectors, so they can be ordered more accurately than the compact data allows.
+    fn rerank(
+        &self,
+        txn: &RoTxn,
+        query: &HVector,
+        candidates: BinaryHeap<HVector>,
+        metric: DistanceMetric,
+    ) -> Result<BinaryHeap<HVector>, VectorError> {
+        candidates
+            .into_iter()
+            .map(|mut candidate| {
+                let key = Self::full_precision_key(candidate.get_id());
+                if let Some(bytes) = self.vectors_db.get(txn, &key)? {
+                    candidate.data = HVector::from_bytes(candidate.get_id(), 0, bytes)?.data;
+                    candidate.set_distance(candidate.distance_to(query, metric)?);
+                }
+                Ok(candidate)
+            })
+            .collect()
+    }
+
     pub fn num_inserted_vectors(&self, txn: &RoTxn) -> Result<u64, VectorError> {
         Ok(self.vectors_db.len(txn)?)
     }
@@ -417,25 +904,19 @@ impl HNSW for VectorCore {
     where
         F: Fn(&HVector, &RoTxn) -> bool,
     {
+        self.check_dimensions(label, query)?;
+        let metric = self.metric(label);
         let query = HVector::from_slice(0, query.to_vec());
 
-        let mut entry_point = self.get_entry_point(txn)?;
+        let mut entry_point = self.get_entry_point(txn, label)?;
 
-        let ef = self.config.ef;
+        let ef = self.config.ef.max(k);
         let curr_level = entry_point.get_level();
 
+        // the upper levels only lead to the neighbourhood of the query, so they aren't filtered
         for level in (1..=curr_level).rev() {
-            let mut nearest = self.search_level(
-                txn,
-                &query,
-                &mut entry_point,
-                1,
-                level,
-                match should_trickle {
-                    true => filter,
-                    false => None,
-                },
-            )?;
+            let mut nearest =
+                self.search_level::<F>(txn, &query, &mut entry_point, 1, level, metric, None)?;
 
             if let Some(closest) = nearest.pop() {
                 entry_point = closest;
@@ -448,12 +929,17 @@ impl HNSW for VectorCore {
             &mut entry_point,
             ef,
             0,
+            metric,
             match should_trickle {
                 true => filter,
                 false => None,
             },
         )?;
 
+        if self.should_rerank(label) {
+            candidates = self.rerank(txn, &query, candidates, metric)?;
+        }
+
         let results =
             candidates.to_vec_with_filter::<F, true>(k, filter, label, txn, self.vector_data_db)?;
 
@@ -464,76 +950,26 @@ impl HNSW for VectorCore {
     fn insert<F>(
         &self,
         txn: &mut RwTxn,
+        label: &str,
         data: &[f64],
         fields: Option<Vec<(String, Value)>>,
     ) -> Result<HVector, VectorError>
     where
         F: Fn(&HVector, &RoTxn) -> bool,
     {
-        let new_level = self.get_new_level();
-
-        let mut query = HVector::from_slice(0, data.to_vec());
+        self.check_dimensions(label, data)?;
+        let full = HVector::from_slice(0, data.to_vec());
+        let mut query = full.clone().with_precision(self.precision(label));
         self.put_vector(txn, &query)?;
-        query.level = new_level;
-        if new_level > 0 {
-            self.put_vector(txn, &query)?;
-        }
-
-        let entry_point = match self.get_entry_point(txn) {
-            Ok(ep) => ep,
-            Err(_) => {
-                self.set_entry_point(txn, &query)?;
-                query.set_distance(0.0);
-
-                if let Some(fields) = fields {
-                    self.vector_data_db.put(
-                        txn,
-                        &query.get_id().to_be_bytes(),
-                        &bincode::serialize(&fields)?,
-                    )?;
-                }
-                return Ok(query);
-            }
-        };
-
-        let l = entry_point.get_level();
-        let mut curr_ep = entry_point;
-        for level in (new_level + 1..=l).rev() {
-            let nearest = self.search_level::<F>(txn, &query, &mut curr_ep, 1, level, None)?;
-            curr_ep = nearest
-                .peek()
-                .ok_or(VectorError::VectorCoreError(
-                    "emtpy search result".to_string(),
-                ))?
-                .clone();
-        }
-
-        for level in (0..=l.min(new_level)).rev() {
-            let nearest = self.search_level::<F>(
+        if self.should_rerank(label) {
+            self.vectors_db.put(
                 txn,
-                &query,
-                &mut curr_ep,
-                self.config.ef_construct,
-                level,
-                None,
+                &Self::full_precision_key(query.get_id()),
+                &full.to_bytes(),
             )?;
-            curr_ep = nearest.peek().unwrap().clone();
-
-            let neighbors = self.select_neighbors::<F>(txn, &query, nearest, level, true, None)?;
-            self.set_neighbours(txn, query.get_id(), &neighbors, level)?;
-
-            for e in neighbors {
-                let id = e.get_id();
-                let e_conns = BinaryHeap::from(self.get_neighbors::<F>(txn, id, level, None)?);
-                let e_new_conn =
-                    self.select_neighbors::<F>(txn, &query, e_conns, level, true, None)?;
-                self.set_neighbours(txn, id, &e_new_conn, level)?;
-            }
-        }
-
-        if new_level > l {
-            self.set_entry_point(txn, &query)?;
         }
+        self.index_vector::<F>(txn, label, &mut query)?;
+        query.data = full.data;
 
         if let Some(fields) = fields {
             self.vector_data_db.put(
@@ -548,34 +984,68 @@ impl HNSW for VectorCore {
     }
 
     fn delete(&self, txn: &mut RwTxn, id: u128) -> Result<(), VectorError> {
-        let properties: Option<HashMap<String, Value>> =
-            match self.vector_data_db.get(txn, &id.to_be_bytes())? {
-                Some(bytes) => Some(bincode::deserialize(bytes).map_err(VectorError::from)?),
-                None => None,
-            };
-
-        debug_println!("properties: {properties:?}");
-        if let Some(mut properties) = properties {
-            if let Some(Value::Boolean(is_deleted)) = properties.get("is_deleted")
-                && *is_deleted
-            {
-                return Err(VectorError::VectorAlreadyDeleted(id.to_string()));
+        let top_level = self.top_level(txn, id)?;
+        let vector = self.get_vector(txn, id, 0, true)?;
+        let label = vector.label().to_string();
+        let metric = self.metric(&label);
+
+        // unlink the vector from every level it is on, reconnecting the neighbours it leaves behind
+        let mut former_neighbors = HashSet::new();
+        for level in 0..=top_level {
+            let neighbors =
+                self.get_neighbors::<fn(&HVector, &RoTxn) -> bool>(txn, id, level, None)?;
+
+            let out_key = Self::out_edges_key(id, level, None);
+            let out_keys = self
+                .edges_db
+                .prefix_iter(txn, &out_key)?
+                .map(|result| result.map(|(key, _)| key.to_vec()))
+                .collect::<Result<Vec<_>, _>>()?;
+            for key in out_keys {
+                let neighbor_id = u128::from_be_bytes(
+                    key[out_key.len()..]
+                        .try_into()
+                        .map_err(|_| VectorError::InvalidVectorData)?,
+                );
+                self.edges_db.delete(txn, &key)?;
+                self.edges_db
+                    .delete(txn, &Self::out_edges_key(neighbor_id, level, Some(id)))?;
             }
 
-            properties.insert("is_deleted".to_string(), Value::Boolean(true));
-            debug_println!("properties: {properties:?}");
-
-            self.vector_data_db
-                .put(txn, &id.to_be_bytes(), &bincode::serialize(&properties)?)?;
-        } else {
-            let mut n_properties: HashMap<String, Value> = HashMap::new();
-            n_properties.insert("is_deleted".to_string(), Value::Boolean(true));
-            debug_println!("properties: {n_properties:?}");
+            former_neighbors.extend(neighbors.iter().map(HVector::get_id));
+            self.repair_neighbors(txn, &neighbors, level, metric)?;
+        }
 
-            self.vector_data_db
-                .put(txn, &id.to_be_bytes(), &bincode::serialize(&n_properties)?)?;
+        if self
+            .get_entry_point(txn, &label)
+            .is_ok_and(|entry_point| entry_point.get_id() == id)
+        {
+            // the highest neighbour is the closest stand in for the old entry point, without any
+            // neighbours left the highest remaining vector of the label takes over
+            let mut replacement = None;
+            for neighbor_id in former_neighbors {
+                let level = self.top_level(txn, neighbor_id)?;
+                if replacement.is_none_or(|(_, top)| level > top) {
+                    replacement = Some((neighbor_id, level));
+                }
+            }
+            if replacement.is_none() {
+                replacement = self.highest_vector_of_label(txn, &label, id)?;
+            }
+            match replacement {
+                Some((neighbor_id, level)) => {
+                    let entry_point = self.get_vector(txn, neighbor_id, level, false)?;
+                    self.set_entry_point(txn, &label, &entry_point)?;
+                }
+                None => {
+                    self.vectors_db
+                        .delete(txn, &Self::entry_point_key(&label))?;
+                }
+            }
         }
 
+        self.remove_vector_data(txn, id)?;
+
         debug_println!("vector deleted with id {}", &id);
         Ok(())
     }
@@ -588,9 +1058,9 @@ impl HNSW for VectorCore {
         self.vectors_db
             .prefix_iter(txn, VECTOR_PREFIX)?
             .map(|result| {
-                result
-                    .map_err(VectorError::from)
-                    .and_then(|(_, value)| bincode::deserialize(value).map_err(VectorError::from))
+                let (key, value) = result?;
+                let (id, level) = Self::parse_vector_key(key)?;
+                HVector::from_bytes(id, level, value)
             })
             .filter_ok(|vector: &HVector| level.is_none_or(|l| vector.level == l))
             .collect()
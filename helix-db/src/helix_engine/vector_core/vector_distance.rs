@@ -1,43 +1,194 @@
-use crate::helix_engine::{types::VectorError, vector_core::vector::HVector};
+use crate::helix_engine::{
+    types::VectorError,
+    vector_core::vector::{HVector, VectorData},
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
 
 pub const MAX_DISTANCE: f64 = 2.0;
 pub const ORTHOGONAL: f64 = 1.0;
 pub const MIN_DISTANCE: f64 = 0.0;
 
+/// How the vectors of a vector type are compared, declared with `metric:` on its schema.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DistanceMetric {
+    #[default]
+    Cosine,
+    DotProduct,
+    Euclidean,
+}
+
+impl fmt::Display for DistanceMetric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DistanceMetric::Cosine => write!(f, "DistanceMetric::Cosine"),
+            DistanceMetric::DotProduct => write!(f, "DistanceMetric::DotProduct"),
+            DistanceMetric::Euclidean => write!(f, "DistanceMetric::Euclidean"),
+        }
+    }
+}
+
+/// A component of a vector in any of the precisions it can be stored with
+trait Component: Copy {
+    fn to_f64(self) -> f64;
+}
+
+impl Component for f64 {
+    #[inline(always)]
+    fn to_f64(self) -> f64 {
+        self
+    }
+}
+
+impl Component for f32 {
+    #[inline(always)]
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+impl Component for i8 {
+    #[inline(always)]
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+/// Binds the components of a `VectorData` and the scale they are multiplied by
+macro_rules! with_components {
+    ($data:expr, |$values:ident, $scale:ident| $body:expr) => {
+        match $data {
+            VectorData::F64($values) => {
+                let $scale = 1.0;
+                $body
+            }
+            VectorData::F32($values) => {
+                let $scale = 1.0;
+                $body
+            }
+            VectorData::Int8 {
+                scale,
+                values: $values,
+            } => {
+                let $scale = *scale as f64;
+                $body
+            }
+        }
+    };
+}
+
 pub trait DistanceCalc {
-    fn distance(from: &HVector, to: &HVector) -> Result<f64, VectorError>;
+    fn distance(metric: DistanceMetric, from: &HVector, to: &HVector) -> Result<f64, VectorError>;
 }
 impl DistanceCalc for HVector {
-    /// Calculates the distance between two vectors.
+    /// Calculates the distance between two vectors, the lower the closer.
     ///
-    /// It normalizes the distance to be between 0 and 2.
+    /// Cosine distances are normalized to be between 0 and 2.
     ///
     /// - 1.0 (most similar) → Distance 0.0 (closest)
     /// - 0.0 (orthogonal) → Distance 1.0
     /// - -1.0 (most dissimilar) → Distance 2.0 (furthest)
+    ///
+    /// Dot product distances are `1 - a·b`, the same as cosine for unit vectors,
+    /// and euclidean distances are the L2 norm of `a - b`.
+    ///
+    /// Vectors stored in a compact precision are compared without converting them to f64 first.
     #[inline(always)]
-    #[cfg(feature = "cosine")]
-    fn distance(from: &HVector, to: &HVector) -> Result<f64, VectorError> {
-        cosine_similarity(&from.data, &to.data).map(|sim| 1.0 - sim)
+    fn distance(metric: DistanceMetric, from: &HVector, to: &HVector) -> Result<f64, VectorError> {
+        match (&from.data, &to.data) {
+            (VectorData::F64(from), VectorData::F64(to)) => match metric {
+                DistanceMetric::Cosine => cosine_similarity(from, to).map(|sim| 1.0 - sim),
+                DistanceMetric::DotProduct => dot_product(from, to).map(|dot| 1.0 - dot),
+                DistanceMetric::Euclidean => euclidean_distance(from, to),
+            },
+            (from, to) => with_components!(from, |from, from_scale| {
+                with_components!(to, |to, to_scale| {
+                    compact_distance(metric, from, from_scale, to, to_scale)
+                })
+            }),
+        }
     }
 }
 
-
+/// Distance between two vectors of any precision, accumulating over the stored components
+/// and applying the per vector scales once at the end.
 #[inline]
-#[cfg(feature = "cosine")]
-pub fn cosine_similarity(from: &[f64], to: &[f64]) -> Result<f64, VectorError> {
-    let len = from.len();
-    let other_len = to.len();
+fn compact_distance<A: Component, B: Component>(
+    metric: DistanceMetric,
+    from: &[A],
+    from_scale: f64,
+    to: &[B],
+    to_scale: f64,
+) -> Result<f64, VectorError> {
+    check_lengths(from, to)?;
+    match metric {
+        DistanceMetric::Euclidean => Ok(from
+            .iter()
+            .zip(to)
+            .map(|(a, b)| {
+                let diff = a.to_f64() * from_scale - b.to_f64() * to_scale;
+                diff * diff
+            })
+            .sum::<f64>()
+            .sqrt()),
+        DistanceMetric::DotProduct => {
+            let dot: f64 = from
+                .iter()
+                .zip(to)
+                .map(|(a, b)| a.to_f64() * b.to_f64())
+                .sum();
+            Ok(1.0 - dot * from_scale * to_scale)
+        }
+        DistanceMetric::Cosine => {
+            let (mut dot, mut magnitude_a, mut magnitude_b) = (0.0, 0.0, 0.0);
+            for (a, b) in from.iter().zip(to) {
+                let (a, b) = (a.to_f64(), b.to_f64());
+                dot += a * b;
+                magnitude_a += a * a;
+                magnitude_b += b * b;
+            }
+            if magnitude_a == 0.0 || magnitude_b == 0.0 {
+                return Ok(2.0);
+            }
+            // scales cancel out for cosine
+            Ok(1.0 - dot / (magnitude_a.sqrt() * magnitude_b.sqrt()))
+        }
+    }
+}
 
-    if len != other_len {
-        println!("mis-match in vector dimensions!\n{len} != {other_len}");
+#[inline(always)]
+fn check_lengths<A, B>(from: &[A], to: &[B]) -> Result<(), VectorError> {
+    if from.len() != to.len() {
         return Err(VectorError::InvalidVectorLength);
     }
-    //debug_assert_eq!(len, other.data.len(), "Vectors must have the same length");
+    Ok(())
+}
+
+#[inline]
+pub fn dot_product(from: &[f64], to: &[f64]) -> Result<f64, VectorError> {
+    check_lengths(from, to)?;
+    Ok(from.iter().zip(to).map(|(a, b)| a * b).sum())
+}
+
+#[inline]
+pub fn euclidean_distance(from: &[f64], to: &[f64]) -> Result<f64, VectorError> {
+    check_lengths(from, to)?;
+    Ok(from
+        .iter()
+        .zip(to)
+        .map(|(a, b)| (a - b) * (a - b))
+        .sum::<f64>()
+        .sqrt())
+}
+
+#[inline]
+pub fn cosine_similarity(from: &[f64], to: &[f64]) -> Result<f64, VectorError> {
+    check_lengths(from, to)?;
+    let len = from.len();
 
     #[cfg(target_feature = "avx2")]
     {
-        return cosine_similarity_avx2(from, to);
+        return Ok(cosine_similarity_avx2(from, to));
     }
 
     let mut dot_product = 0.0;
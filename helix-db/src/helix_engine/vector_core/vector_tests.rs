@@ -1,26 +1,28 @@
-use crate::helix_engine::vector_core::vector_distance::{MAX_DISTANCE, MIN_DISTANCE, ORTHOGONAL};
+use crate::helix_engine::vector_core::vector_distance::{
+    DistanceMetric, MAX_DISTANCE, MIN_DISTANCE, ORTHOGONAL,
+};
 
-use super::vector::HVector;
+use super::vector::{HVector, VectorData, VectorPrecision};
 
 #[test]
 fn test_hvector_new() {
     let data: Vec<f64> = vec![1.0, 2.0, 3.0];
     let vector = HVector::new(data);
-    assert_eq!(vector.get_data(), &[1.0, 2.0, 3.0]);
+    assert_eq!(*vector.get_data(), [1.0, 2.0, 3.0]);
 }
 
 #[test]
 fn test_hvector_from_slice() {
     let data: Vec<f64> = vec![1.0, 2.0, 3.0];
     let vector = HVector::from_slice(0, data);
-    assert_eq!(vector.get_data(), &[1.0, 2.0, 3.0]);
+    assert_eq!(*vector.get_data(), [1.0, 2.0, 3.0]);
 }
 
 #[test]
 fn test_hvector_distance_orthogonal() {
     let v1 = HVector::new(vec![1.0, 0.0]);
     let v2 = HVector::new(vec![0.0, 1.0]);
-    let distance = v1.distance_to(&v2).unwrap();
+    let distance = v1.distance_to(&v2, DistanceMetric::Cosine).unwrap();
     assert!(distance == ORTHOGONAL);
 }
 
@@ -28,7 +30,7 @@ fn test_hvector_distance_orthogonal() {
 fn test_hvector_distance_min() {
     let v1 = HVector::new(vec![1.0, 2.0, 3.0]);
     let v2 = HVector::new(vec![1.0, 2.0, 3.0]);
-    let distance = v2.distance_to(&v1).unwrap();
+    let distance = v2.distance_to(&v1, DistanceMetric::Cosine).unwrap();
     assert!(distance.abs() == MIN_DISTANCE);
 }
 
@@ -36,7 +38,7 @@ fn test_hvector_distance_min() {
 fn test_hvector_distance_max() {
     let v1 = HVector::new(vec![0.0, 0.0]);
     let v2 = HVector::new(vec![3.0, 4.0]);
-    let distance = v1.distance_to(&v2).unwrap();
+    let distance = v1.distance_to(&v2, DistanceMetric::Cosine).unwrap();
     assert!(distance == MAX_DISTANCE);
 }
 
@@ -48,6 +50,51 @@ fn test_bytes_roundtrip() {
     assert_eq!(original.get_data(), reconstructed.get_data());
 }
 
+#[test]
+fn test_bytes_roundtrip_compact() {
+    let original = HVector::new(vec![0.5, -1.0, 0.25, 0.0]);
+    let full_len = original.to_bytes().len();
+
+    let f32_vector = original.clone().with_precision(VectorPrecision::F32);
+    let bytes = f32_vector.to_bytes();
+    assert!(bytes.len() < full_len);
+    let reconstructed = HVector::from_bytes(original.get_id(), 0, &bytes).unwrap();
+    assert_eq!(reconstructed.data, f32_vector.data);
+    assert_eq!(reconstructed.get_data(), original.get_data());
+
+    let int8_vector = original.clone().with_precision(VectorPrecision::Int8);
+    let bytes = int8_vector.to_bytes();
+    assert_eq!(bytes.len(), 8 + 4);
+    let reconstructed = HVector::from_bytes(original.get_id(), 0, &bytes).unwrap();
+    assert_eq!(reconstructed.data, int8_vector.data);
+    assert!(matches!(reconstructed.data, VectorData::Int8 { .. }));
+    for (a, b) in reconstructed.get_data().iter().zip(original.get_data().iter()) {
+        assert!((a - b).abs() < 0.01);
+    }
+}
+
+#[test]
+fn test_compact_distance_matches_full_precision() {
+    let v1 = HVector::new(vec![0.1, -0.7, 0.3, 0.9]);
+    let v2 = HVector::new(vec![-0.4, 0.2, 0.8, 0.5]);
+    for metric in [
+        DistanceMetric::Cosine,
+        DistanceMetric::DotProduct,
+        DistanceMetric::Euclidean,
+    ] {
+        let full = v1.distance_to(&v2, metric).unwrap();
+        for precision in [VectorPrecision::F32, VectorPrecision::Int8] {
+            let compact = v1.clone().with_precision(precision);
+            let distance = compact.distance_to(&v2, metric).unwrap();
+            assert!((distance - full).abs() < 0.02, "{metric:?} {precision:?}");
+            let both = compact
+                .distance_to(&v2.clone().with_precision(precision), metric)
+                .unwrap();
+            assert!((both - full).abs() < 0.02, "{metric:?} {precision:?}");
+        }
+    }
+}
+
 #[test]
 fn test_hvector_len() {
     let data = vec![1.0, 2.0, 3.0, 4.0];
@@ -69,7 +116,7 @@ fn test_hvector_is_empty() {
 fn test_hvector_distance_different_dimensions() {
     let v1 = HVector::new(vec![1.0, 2.0, 3.0]);
     let v2 = HVector::new(vec![1.0, 2.0, 3.0, 4.0]);
-    let distance = v1.distance_to(&v2).unwrap();
+    let distance = v1.distance_to(&v2, DistanceMetric::Cosine).unwrap();
     println!("distance: {distance}");
     assert!(distance.is_finite());
 }
@@ -78,7 +125,7 @@ fn test_hvector_distance_different_dimensions() {
 fn test_hvector_large_values() {
     let v1 = HVector::new(vec![1e6, 2e6]);
     let v2 = HVector::new(vec![1e6, 2e6]);
-    let distance = v1.distance_to(&v2).unwrap();
+    let distance = v1.distance_to(&v2, DistanceMetric::Cosine).unwrap();
     assert!(distance.abs() < 1e-10);
 }
 
@@ -86,7 +133,7 @@ fn test_hvector_large_values() {
 fn test_hvector_negative_values() {
     let v1 = HVector::new(vec![-1.0, -2.0]);
     let v2 = HVector::new(vec![1.0, 2.0]);
-    let distance = v1.distance_to(&v2).unwrap();
+    let distance = v1.distance_to(&v2, DistanceMetric::Cosine).unwrap();
     // used round to avoid floating point precision issues
     assert!(distance.round() == MAX_DISTANCE);
 }
@@ -95,7 +142,27 @@ fn test_hvector_negative_values() {
 fn test_hvector_cosine_similarity() {
     let v1 = HVector::new(vec![1.0, 2.0, 3.0]);
     let v2 = HVector::new(vec![4.0, 5.0, 6.0]);
-    let similarity = v1.distance_to(&v2).unwrap();
+    let similarity = v1.distance_to(&v2, DistanceMetric::Cosine).unwrap();
     assert!(similarity == 1.0 - 0.9746318461970762);
 }
 
+
+#[test]
+fn test_hvector_distance_dot_product() {
+    let v1 = HVector::new(vec![1.0, 2.0, 3.0]);
+    let v2 = HVector::new(vec![4.0, 5.0, 6.0]);
+    let distance = v1.distance_to(&v2, DistanceMetric::DotProduct).unwrap();
+    assert_eq!(distance, 1.0 - 32.0);
+}
+
+#[test]
+fn test_hvector_distance_euclidean() {
+    let v1 = HVector::new(vec![0.0, 0.0]);
+    let v2 = HVector::new(vec![3.0, 4.0]);
+    let distance = v1.distance_to(&v2, DistanceMetric::Euclidean).unwrap();
+    assert_eq!(distance, 5.0);
+    assert!(
+        v1.distance_to(&HVector::new(vec![1.0]), DistanceMetric::Euclidean)
+            .is_err()
+    );
+}
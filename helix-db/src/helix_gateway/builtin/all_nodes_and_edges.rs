@@ -106,7 +106,7 @@ pub fn nodes_edges_inner(input: &HandlerInput) -> Result<protocol::Response, Gra
                         "id": v.id.to_string(),
                         "level": v.level,
                         "distance": v.distance,
-                        "data": v.data,
+                        "data": v.get_data(),
                         "dimension": v.data.len()
                     })
                 })
@@ -1,2 +1,3 @@
 pub mod all_nodes_and_edges;
 pub mod nodes_by_label;
+pub mod reindex;
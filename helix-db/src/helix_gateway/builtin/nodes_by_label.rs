@@ -8,6 +8,8 @@ use serde::{Deserialize, Serialize};
 use sonic_rs::{JsonValueTrait, json};
 use tracing::info;
 
+use crate::helix_engine::graph_core::ops::g::G;
+use crate::helix_engine::graph_core::ops::source::n_from_type::NFromTypeAdapter;
 use crate::helix_engine::graph_core::ops::tr_val::TraversalVal;
 use crate::helix_engine::types::GraphError;
 use crate::helix_gateway::gateway::AppState;
@@ -15,8 +17,6 @@ use crate::helix_gateway::router::router::{Handler, HandlerInput, HandlerSubmiss
 use crate::protocol::remapping::RemappingMap;
 use crate::protocol::return_values::ReturnValue;
 use crate::protocol::{self, request::RequestType};
-use crate::utils::filterable::Filterable;
-use crate::utils::items::Node;
 
 // get all nodes with a specific label
 // curl "http://localhost:PORT/nodes-by-label?label=YOUR_LABEL"
@@ -81,22 +81,8 @@ pub fn nodes_by_label_inner(input: &HandlerInput) -> Result<protocol::Response,
 
     let remapping_vals = RemappingMap::new();
 
-    let nodes: Vec<TraversalVal> = db
-        .nodes_db
-        .iter(&txn)?
-        .filter_map(|result| match result {
-            Ok((id, node_data)) => match Node::decode_node(node_data, id) {
-                Ok(node) => {
-                    if node.label() == label {
-                        Some(Ok(TraversalVal::Node(node)))
-                    } else {
-                        None
-                    }
-                }
-                Err(e) => Some(Err(e)),
-            },
-            Err(e) => Some(Err(GraphError::from(e))),
-        })
+    let nodes: Vec<TraversalVal> = G::new(Arc::clone(&db), &txn)
+        .n_from_type(&label)
         .collect::<Result<Vec<_>, GraphError>>()?;
 
     let count = nodes.len();
@@ -0,0 +1,83 @@
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::extract::{Query, State};
+use axum::response::IntoResponse;
+use serde::Deserialize;
+use sonic_rs::{JsonValueTrait, json};
+use tracing::info;
+
+use crate::helix_engine::storage_core::storage_core::REINDEX_BATCH_SIZE;
+use crate::helix_engine::types::GraphError;
+use crate::helix_gateway::gateway::AppState;
+use crate::helix_gateway::router::router::{Handler, HandlerInput, HandlerSubmission};
+use crate::protocol::{self, request::RequestType};
+
+// rebuild the bm25 indices from the stored nodes, edges and vectors
+// curl -X POST "http://localhost:PORT/reindex"
+
+// with fewer items per write transaction
+// curl -X POST "http://localhost:PORT/reindex?batch_size=1000"
+
+#[derive(Deserialize)]
+pub struct ReindexQuery {
+    batch_size: Option<usize>,
+}
+
+pub async fn reindex_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ReindexQuery>,
+) -> axum::http::Response<Body> {
+    let mut req = protocol::request::Request {
+        name: "reindex".to_string(),
+        req_type: RequestType::Query,
+        body: axum::body::Bytes::new(),
+        in_fmt: protocol::Format::default(),
+        out_fmt: protocol::Format::default(),
+    };
+
+    if let Ok(params_json) = sonic_rs::to_vec(&json!({
+        "batch_size": params.batch_size
+    })) {
+        req.body = axum::body::Bytes::from(params_json);
+    }
+
+    let res = state.worker_pool.process(req).await;
+
+    match res {
+        Ok(r) => r.into_response(),
+        Err(e) => {
+            info!(?e, "Got error");
+            e.into_response()
+        }
+    }
+}
+
+pub fn reindex_inner(input: &HandlerInput) -> Result<protocol::Response, GraphError> {
+    let db = Arc::clone(&input.graph.storage);
+
+    let batch_size = if !input.request.body.is_empty() {
+        match sonic_rs::from_slice::<sonic_rs::Value>(&input.request.body) {
+            Ok(params) => params
+                .get("batch_size")
+                .and_then(|v| v.as_u64())
+                .map(|v| v as usize),
+            Err(_) => None,
+        }
+    } else {
+        None
+    };
+
+    let stats = db.reindex_bm25(batch_size.unwrap_or(REINDEX_BATCH_SIZE))?;
+
+    Ok(protocol::Response {
+        body: sonic_rs::to_vec(&stats).map_err(|e| GraphError::New(e.to_string()))?,
+        fmt: Default::default(),
+    })
+}
+
+inventory::submit! {
+    HandlerSubmission(
+        Handler::new("reindex", reindex_inner)
+    )
+}
@@ -1,8 +1,9 @@
 use crate::{
     debug_println,
     helix_engine::{
+        bm25::bm25::HybridFusion,
         graph_core::ops::{
-            bm25::search_bm25::SearchBM25Adapter,
+            bm25::{hybrid_search_bm25::SearchHybridAdapter, search_bm25::SearchBM25Adapter},
             g::G,
             in_::{
                 in_::{InAdapter, InNodesIterator},
@@ -193,6 +194,19 @@ trait McpTools<'a> {
         k: usize,
         min_score: Option<f64>,
     ) -> Result<Vec<TraversalVal>, GraphError>;
+
+    /// BM25 and HNSW search fused, the query is embedded with the built in embedding model
+    /// unless a vector is given, and results are fused by reciprocal rank unless alpha is given
+    fn search_hybrid(
+        &'a self,
+        txn: &'a RoTxn,
+        connection: &'a MCPConnection,
+        query: String,
+        label: String,
+        k: usize,
+        vector: Option<Vec<f64>>,
+        alpha: Option<f32>,
+    ) -> Result<Vec<TraversalVal>, GraphError>;
 }
 
 impl<'a> McpTools<'a> for McpBackend {
@@ -365,7 +379,12 @@ impl<'a> McpTools<'a> for McpBackend {
         let db = Arc::clone(&self.db);
 
         let iter = NFromType {
-            iter: db.nodes_db.lazily_decode_data().iter(txn).unwrap(),
+            iter: db
+                .node_labels_db
+                .lazily_decode_data()
+                .prefix_iter(txn, &HelixGraphStorage::label_key(&node_type))?,
+            txn,
+            storage: Arc::clone(&db),
             label: &node_type,
         };
 
@@ -383,7 +402,12 @@ impl<'a> McpTools<'a> for McpBackend {
         let db = Arc::clone(&self.db);
 
         let iter = EFromType {
-            iter: db.edges_db.lazily_decode_data().iter(txn).unwrap(),
+            iter: db
+                .edge_labels_db
+                .lazily_decode_data()
+                .prefix_iter(txn, &HelixGraphStorage::label_key(&edge_type))?,
+            txn,
+            storage: Arc::clone(&db),
             label: &edge_type,
         };
 
@@ -417,11 +441,11 @@ impl<'a> McpTools<'a> for McpBackend {
 
         // Check if BM25 is enabled and has metadata
         if let Some(bm25) = &db.bm25 {
-            match bm25.metadata_db.get(txn, crate::helix_engine::bm25::bm25::METADATA_KEY) {
+            match bm25.collection(&label).metadata(txn) {
                 Ok(Some(_)) => {
                     let results = G::new(db, txn)
                         .search_bm25(&label, &query, limit)?
-                        .collect_to::<Vec<_>>();
+                        .try_collect_to::<Vec<_>>()?;
 
                     println!("BM25 search results: {results:?}");
                     Ok(results)
@@ -459,7 +483,7 @@ impl<'a> McpTools<'a> for McpBackend {
 
         let res = G::new(db, txn)
             .search_v::<fn(&HVector, &RoTxn) -> bool, _>(&embedding, 5, &label, None)
-            .collect_to::<Vec<_>>();
+            .try_collect_to::<Vec<_>>()?;
 
         debug_println!("result: {res:?}");
         Ok(res)
@@ -479,7 +503,7 @@ impl<'a> McpTools<'a> for McpBackend {
 
         let mut res = G::new_from(db, txn, items)
             .brute_force_search_v(&vector, k)
-            .collect_to::<Vec<_>>();
+            .try_collect_to::<Vec<_>>()?;
 
         if let Some(min_score) = min_score {
             res.retain(|item| {
@@ -494,6 +518,32 @@ impl<'a> McpTools<'a> for McpBackend {
         println!("result: {res:?}");
         Ok(res)
     }
+
+    fn search_hybrid(
+        &'a self,
+        txn: &'a RoTxn,
+        _connection: &'a MCPConnection,
+        query: String,
+        label: String,
+        k: usize,
+        vector: Option<Vec<f64>>,
+        alpha: Option<f32>,
+    ) -> Result<Vec<TraversalVal>, GraphError> {
+        let db = Arc::clone(&self.db);
+
+        let vector = match vector {
+            Some(vector) => vector,
+            None => get_embedding_model(None, None, None)?.fetch_embedding(&query)?,
+        };
+        let fusion = alpha.map_or_else(HybridFusion::default, HybridFusion::Alpha);
+
+        let res = G::new(db, txn)
+            .search_hybrid(&label, &query, &vector, k, fusion)?
+            .try_collect_to::<Vec<_>>()?;
+
+        debug_println!("result: {res:?}");
+        Ok(res)
+    }
 }
 
 pub trait FilterValues {
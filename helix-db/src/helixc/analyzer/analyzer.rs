@@ -1,4 +1,9 @@
 //! Semantic analyzer for Helix‑QL.
+use crate::helix_engine::{
+    bm25::analyzer::AnalyzerConfig,
+    graph_core::config::{BM25IndexConfig, VectorTypeConfig},
+    storage_core::index_key::label_index_name,
+};
 use crate::helixc::{
     analyzer::{
         diagnostic::Diagnostic,
@@ -44,6 +49,10 @@ pub(crate) struct Ctx<'a> {
 
 pub static INTROSPECTION_DATA: OnceLock<IntrospectionData> = OnceLock::new();
 pub static SECONDARY_INDICES: OnceLock<Vec<String>> = OnceLock::new();
+pub static EDGE_SECONDARY_INDICES: OnceLock<Vec<String>> = OnceLock::new();
+pub static UNIQUE_INDICES: OnceLock<Vec<String>> = OnceLock::new();
+pub static VECTOR_TYPES: OnceLock<Vec<VectorTypeConfig>> = OnceLock::new();
+pub static BM25_INDICES: OnceLock<Vec<BM25IndexConfig>> = OnceLock::new();
 
 impl<'a> Ctx<'a> {
     pub(super) fn new(src: &'a Source) -> Self {
@@ -99,6 +108,100 @@ impl<'a> Ctx<'a> {
                             .iter()
                             .filter(|f| f.is_indexed())
                             .map(|f| f.name.clone())
+                            .chain(schema.composite_indices.iter().map(|index| index.name()))
+                            .map(|index| label_index_name(&schema.name.1, &index))
+                    })
+                    .collect(),
+            )
+            .ok();
+
+        EDGE_SECONDARY_INDICES
+            .set(
+                src.get_latest_schema()
+                    .edge_schemas
+                    .iter()
+                    .flat_map(|schema| {
+                        schema
+                            .properties
+                            .iter()
+                            .flatten()
+                            .filter(|f| f.is_indexed())
+                            .map(|f| label_index_name(&schema.name.1, &f.name))
+                    })
+                    .collect(),
+            )
+            .ok();
+
+        VECTOR_TYPES
+            .set(
+                src.get_latest_schema()
+                    .vector_schemas
+                    .iter()
+                    .map(|schema| VectorTypeConfig {
+                        label: schema.name.clone(),
+                        dimensions: schema.dimensions,
+                        metric: schema.metric,
+                        precision: schema.precision,
+                        rerank: schema.rerank,
+                    })
+                    .collect(),
+            )
+            .ok();
+
+        UNIQUE_INDICES
+            .set(
+                src.get_latest_schema()
+                    .node_schemas
+                    .iter()
+                    .flat_map(|schema| {
+                        schema
+                            .fields
+                            .iter()
+                            .filter(|f| f.is_unique())
+                            .map(|f| label_index_name(&schema.name.1, &f.name))
+                    })
+                    .collect(),
+            )
+            .ok();
+
+        let latest = src.get_latest_schema();
+        BM25_INDICES
+            .set(
+                latest
+                    .node_schemas
+                    .iter()
+                    .map(|schema| (&schema.name.1, schema.fields.as_slice()))
+                    .chain(
+                        latest
+                            .edge_schemas
+                            .iter()
+                            .map(|schema| (&schema.name.1, schema.properties.as_deref().unwrap_or(&[]))),
+                    )
+                    .chain(
+                        latest
+                            .vector_schemas
+                            .iter()
+                            .map(|schema| (&schema.name, schema.fields.as_slice())),
+                    )
+                    .filter_map(|(label, fields)| {
+                        let fields = fields.iter().filter(|f| f.searchable).collect::<Vec<_>>();
+                        (!fields.is_empty()).then(|| BM25IndexConfig {
+                            label: label.clone(),
+                            fields: fields.iter().map(|f| f.name.clone()).collect(),
+                            analyzers: fields
+                                .iter()
+                                .filter_map(|f| {
+                                    f.analyzer.map(|analyzer| {
+                                        let config = AnalyzerConfig {
+                                            analyzer,
+                                            ..Default::default()
+                                        };
+                                        (f.name.clone(), config)
+                                    })
+                                })
+                                .collect(),
+                            scoring: Default::default(),
+                        })
                     })
                     .collect(),
             )
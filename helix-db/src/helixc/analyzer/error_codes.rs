@@ -36,6 +36,14 @@ pub enum ErrorCode {
     E208,
     /// `E209` – `unknown type for parameter`
     E209,
+    /// `E210` – `unique constraint is only supported on node fields`
+    E210,
+    /// `E211` – `no composite index covers the fields of a lookup`
+    E211,
+    /// `E212` – `vector literal does not match the dimensions of its vector type`
+    E212,
+    /// `E213` – `searchable is only supported on string fields`
+    E213,
 
     // QUERY ERRORS
     /// `E301` – `variable not in scope`
@@ -52,6 +60,8 @@ pub enum ErrorCode {
     // MCP ERRORS
     /// `E401` – `MCP query must return a single value`
     E401,
+    /// `E402` – `MCP query cannot return from an if block`
+    E402,
 
     // CONVERSION ERRORS
     /// `E501` - `invalid date`
@@ -71,6 +81,8 @@ pub enum ErrorCode {
     E611,
     /// `E612` - `edge creation must have a from id`
     E612,
+    /// `E613` - `node upsert must be given a value for a unique field`
+    E613,
 
     /// `E621` - `boolean comparison operation cannot be applied to given type`
     E621,
@@ -84,6 +96,8 @@ pub enum ErrorCode {
     E625,
     /// `E626` - `edge type does not have a vector type as its To source`
     E626,
+    /// `E627` - `edge type must go from and to the same node type to be repeated`
+    E627,
 
     /// `E631` - `range must have a start and end`
     E631,
@@ -112,9 +126,23 @@ pub enum ErrorCode {
     /// `E653` - `inner type of in variable is not an object`
     E653,
 
+    /// `E661` - `aggregate must be given a property`
+    E661,
+    /// `E662` - `aggregate can only be applied to numeric properties`
+    E662,
+    /// `E663` - `group by and aggregate by are only valid as the last steps of a traversal`
+    E663,
+    /// `E664` - `grouped aggregates can't be traversed`
+    E664,
+
+    /// `E671` - `if condition must be a boolean`
+    E671,
+
 
     /// `W101` - `query has no return`
     W101,
+    /// `W102` - `full-text search on a type without searchable fields`
+    W102,
 }
 impl std::fmt::Display for ErrorCode {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -134,12 +162,17 @@ impl std::fmt::Display for ErrorCode {
             ErrorCode::E207 => write!(f, "E207"),
             ErrorCode::E208 => write!(f, "E208"),
             ErrorCode::E209 => write!(f, "E209"),
+            ErrorCode::E210 => write!(f, "E210"),
+            ErrorCode::E211 => write!(f, "E211"),
+            ErrorCode::E212 => write!(f, "E212"),
+            ErrorCode::E213 => write!(f, "E213"),
             ErrorCode::E301 => write!(f, "E301"),
             ErrorCode::E302 => write!(f, "E302"),
             ErrorCode::E303 => write!(f, "E303"),
             ErrorCode::E304 => write!(f, "E304"),
             ErrorCode::E305 => write!(f, "E305"),
             ErrorCode::E401 => write!(f, "E401"),
+            ErrorCode::E402 => write!(f, "E402"),
             ErrorCode::E501 => write!(f, "E501"),
             ErrorCode::E601 => write!(f, "E601"),
             ErrorCode::E602 => write!(f, "E602"),
@@ -147,12 +180,14 @@ impl std::fmt::Display for ErrorCode {
             ErrorCode::E604 => write!(f, "E604"),
             ErrorCode::E611 => write!(f, "E611"),
             ErrorCode::E612 => write!(f, "E612"),
+            ErrorCode::E613 => write!(f, "E613"),
             ErrorCode::E621 => write!(f, "E621"),
             ErrorCode::E622 => write!(f, "E622"),
             ErrorCode::E623 => write!(f, "E623"),
             ErrorCode::E624 => write!(f, "E624"),
             ErrorCode::E625 => write!(f, "E625"),
             ErrorCode::E626 => write!(f, "E626"),
+            ErrorCode::E627 => write!(f, "E627"),
             ErrorCode::E631 => write!(f, "E631"),
             ErrorCode::E632 => write!(f, "E632"),
             ErrorCode::E633 => write!(f, "E633"),
@@ -165,7 +200,13 @@ impl std::fmt::Display for ErrorCode {
             ErrorCode::E651 => write!(f, "E651"),
             ErrorCode::E652 => write!(f, "E652"),
             ErrorCode::E653 => write!(f, "E653"),
+            ErrorCode::E661 => write!(f, "E661"),
+            ErrorCode::E662 => write!(f, "E662"),
+            ErrorCode::E663 => write!(f, "E663"),
+            ErrorCode::E664 => write!(f, "E664"),
+            ErrorCode::E671 => write!(f, "E671"),
             ErrorCode::W101 => write!(f, "W101"),
+            ErrorCode::W102 => write!(f, "W102"),
         }
     }
 }
@@ -210,8 +251,12 @@ implement_error_code!(E205,
     "change the value type to match the field type defined in the schema" => {});
 implement_error_code!(E206, "invalid value type `{}`" => { value_type }, "use a literal or an identifier" => {});
 implement_error_code!(E207, "edge type `{}` exists but it is not a valid edge type for the given {} type `{}`" => { edge_type, item_type, item_type_name }, "check the schema field names" => {});
-implement_error_code!(E208, "field `{}` has not been indexed for node type `{}`" => { field_name, node_type }, "use a field that has been indexed with `INDEX` in the schema for node type `{}`" => { node_type });
+implement_error_code!(E208, "field `{}` has not been indexed for {} type `{}`" => { field_name, item_type, item_type_name }, "use a field that has been indexed with `INDEX` in the schema for {} type `{}`" => { item_type, item_type_name });
 implement_error_code!(E209, "unknown type `{}` for parameter `{}`" => { parameter_type, parameter_name }, "declare or use a matching schema object or use a primitive type" => {});
+implement_error_code!(E210, "field `{}` cannot be `UNIQUE` on {} type `{}`" => { field_name, item_type, item_type_name }, "`UNIQUE` is only supported on node fields" => {});
+implement_error_code!(E211, "no composite index on node type `{}` covers the fields `{}`" => { node_type, fields }, "declare `INDEX({})` in the schema for node type `{}`, with range fields last" => { fields, node_type });
+implement_error_code!(E212, "vector of length `{}` does not match the `{}` dimensions of vector type `{}`" => { length, dimensions, vector_type }, "use a vector with `{}` values or change the dimensions of `V::{}` in the schema" => { dimensions, vector_type });
+implement_error_code!(E213, "field `{}` of type `{}` cannot be `SEARCHABLE` on {} type `{}`" => { field_name, field_type, item_type, item_type_name }, "`SEARCHABLE` is only supported on `String` fields" => {});
 
 // Query errors
 implement_error_code!(E301, "variable `{}` not in scope" => { variable }, "check the variable" => {});
@@ -221,6 +266,7 @@ implement_error_code!(E305, "missing parameter `{}` for method `{}`" => { parame
 
 // MCP errors
 implement_error_code!(E401, "MCP query must return a single value, but got `{}`" => { number_of_values }, "return a single value" => {});
+implement_error_code!(E402, "MCP query cannot `RETURN` from an `IF` block" => {}, "move the `RETURN` to the end of the query" => {});
 
 // Conversion errors
 implement_error_code!(E501, "invalid date `{}`" => { date }, "ensure the date conforms to the ISO 8601 or RFC 3339 formats" => {});
@@ -232,6 +278,7 @@ implement_error_code!(E603, "`SearchV` must be used on a vector type, got `{}`,
 implement_error_code!(E604, "`UPDATE` step is only valid on nodes or edges, but got `{}`" => { step }, "use `UPDATE` on a node or edge or remove the `UPDATE` step" => {});
 implement_error_code!(E611, "edge creation must have a to id" => {}, "add a `::To(target_node_id)` step to your edge creation" => {});
 implement_error_code!(E612, "edge creation must have a from id" => {}, "add a `::From(source_node_id)` step to your edge creation" => {});
+implement_error_code!(E613, "`UpsertN<{}>` must be given a value for a `UNIQUE` field to find the node to update" => { node_type }, "set a `UNIQUE` field of `{}`, or mark the field to upsert on as `UNIQUE` in the schema" => { node_type });
 
 // Edge type errors
 implement_error_code!(E621, "boolean comparison operation cannot be applied to given {} type `{}`" => { item_type, item_type_name }, "use a valid boolean comparison operation" => {});
@@ -242,6 +289,7 @@ implement_error_code!(E623, "edge type `{}` does not have a node type as its `Fr
 implement_error_code!(E624, "edge type `{}` does not have a node type as its `To` source" => { edge_type }, "set the `To` type of the edge to a node type" => {});
 implement_error_code!(E625, "edge type `{}` does not have a vector type as its `From` source" => { edge_type }, "set the `From` type of the edge to a vector type" => {});
 implement_error_code!(E626, "edge type `{}` does not have a vector type as its `To` source" => { edge_type }, "set the `To` type of the edge to a vector type" => {});
+implement_error_code!(E627, "edge type `{}` must go from and to the same node type to be repeated, but goes from `{}` to `{}`" => { edge_type, from_type, to_type }, "use `Out` or `In` instead, or repeat an edge type between nodes of one type" => {});
 
 // Range errors
 implement_error_code!(E631, "range must have a start and end, missing the `{}` value" => { start_or_end }, "add a `{}` value to the range" => { start_or_end });
@@ -261,6 +309,17 @@ implement_error_code!(E651, "`IN` variable `{}` is not iterable" => { in_variabl
 implement_error_code!(E652, "variable `{}` is not a field of the inner object of the `IN` variable `{}`" => { variable, in_variable }, "ensure `{}` is a field of `{}`" => { variable, in_variable });
 implement_error_code!(E653, "inner object of `IN` variable `{}` is not an object" => { in_variable }, "ensure the inner type of `{}` is an object" => { in_variable });
 
+// Aggregation errors
+implement_error_code!(E661, "`{}` must be given a property to aggregate" => { aggregate }, "use `{}(property)`" => { aggregate });
+implement_error_code!(E662, 
+    "`{}` can only be applied to numeric properties, but `{}` of {} `{}` is of type `{}`" => { aggregate, property_name, item_type, item_type_name, property_type }, 
+    "use `MIN`, `MAX` or `COUNT` for properties that aren't numbers" => {});
+implement_error_code!(E663, "`{}` is only valid as the last step of a traversal" => { step }, "move `{}` to the end of the traversal, only `AGGREGATE_BY` can follow `GROUP_BY`" => { step });
+implement_error_code!(E664, "`{}` holds the aggregates of each group of a `GROUP_BY` and can't be traversed" => { variable }, "return `{}` as it is, or aggregate without `GROUP_BY` to get a single object" => { variable });
+
+// If errors
+implement_error_code!(E671, "`IF` condition must evaluate to a boolean, but got `{}`" => { condition_type }, "use a boolean, a comparison such as `::GT(0)`, `EXISTS(...)`, `AND(...)` or `OR(...)`" => {});
+
 #[macro_export]
 macro_rules! generate_error {
     ($ctx:ident, $original_query:ident, $loc:expr, $error_code:ident, [$($message_args:expr),*], [$($hint_args:expr),*]) => {
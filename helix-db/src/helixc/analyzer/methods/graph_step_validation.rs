@@ -9,13 +9,13 @@ use crate::{
             analyzer::Ctx,
             errors::push_query_err,
             types::Type,
-            utils::{gen_identifier_or_param, is_valid_identifier},
+            utils::{check_vector_dimensions, gen_identifier_or_param, is_valid_identifier},
         },
         generator::{
             traversal_steps::{
-                In as GeneratedIn, InE as GeneratedInE, Out as GeneratedOut, OutE as GeneratedOutE,
-                SearchVectorStep, ShortestPath as GeneratedShortestPath, ShouldCollect,
-                Step as GeneratedStep, Traversal as GeneratedTraversal,
+                Hops as GeneratedHops, In as GeneratedIn, InE as GeneratedInE, Out as GeneratedOut,
+                OutE as GeneratedOutE, SearchVectorStep, ShortestPath as GeneratedShortestPath,
+                ShouldCollect, Step as GeneratedStep, Traversal as GeneratedTraversal,
             },
             utils::{GenRef, GeneratedValue, Separator, VecData},
             queries::Query as GeneratedQuery,
@@ -240,6 +240,65 @@ pub(crate) fn apply_graph_step<'a>(
             }
         }
 
+        // Variable-length Node‑to‑Node
+        (
+            OutRepeat(repeat) | InRepeat(repeat),
+            Type::Nodes(Some(node_label)) | Type::Node(Some(node_label)),
+        ) => {
+            let label = repeat.edge_type.as_str();
+            let Some(edge) = ctx.edge_map.get(label) else {
+                generate_error!(ctx, original_query, gs.loc.clone(), E102, label);
+                return None;
+            };
+            let (from, to) = (edge.from.1.clone(), edge.to.1.clone());
+            if from != to || !ctx.node_set.contains(from.as_str()) {
+                generate_error!(
+                    ctx,
+                    original_query,
+                    gs.loc.clone(),
+                    E627,
+                    label,
+                    from.as_str(),
+                    to.as_str()
+                );
+                return None;
+            }
+            if from != *node_label {
+                generate_error!(
+                    ctx,
+                    original_query,
+                    gs.loc.clone(),
+                    E207,
+                    label,
+                    "node",
+                    node_label.as_str()
+                );
+                return None;
+            }
+            if repeat.min_depth > repeat.max_depth {
+                generate_error!(
+                    ctx,
+                    original_query,
+                    repeat.loc.clone(),
+                    E632,
+                    &repeat.min_depth.to_string(),
+                    &repeat.max_depth.to_string()
+                );
+                return None;
+            }
+            let hops = GeneratedHops {
+                label: GenRef::Literal(label.to_string()),
+                min_depth: repeat.min_depth,
+                max_depth: repeat.max_depth,
+            };
+            traversal.steps.push(Separator::Period(match gs.step {
+                OutRepeat(_) => GeneratedStep::OutHops(hops),
+                _ => GeneratedStep::InHops(hops),
+            }));
+            traversal.should_collect = ShouldCollect::ToVec;
+            Some(Type::Nodes(Some(to)))
+        }
+
         // Edge‑to‑Node
         (FromN, Type::Edges(Some(edge_ty)) | Type::Edge(Some(edge_ty))) => {
             let new_ty = if let Some(edge_schema) = ctx.edge_map.get(edge_ty.as_str()) {
@@ -365,6 +424,9 @@ pub(crate) fn apply_graph_step<'a>(
             }
             let vec = match &sv.data {
                 Some(VectorData::Vector(v)) => {
+                    if let Some(ty) = &sv.vector_type {
+                        check_vector_dimensions(ctx, original_query, sv.loc.clone(), ty, v);
+                    }
                     VecData::Standard(GeneratedValue::Literal(GenRef::Ref(format!(
                         "[{}]",
                         v.iter()
@@ -5,25 +5,28 @@ use crate::{
     helixc::{
         analyzer::{
             analyzer::Ctx,
-            errors::push_query_err,
+            errors::{push_query_err, push_query_warn},
             methods::traversal_validation::validate_traversal,
             types::Type,
             utils::{
-                gen_id_access_or_param, gen_identifier_or_param, is_valid_identifier, type_in_scope,
+                check_vector_dimensions, gen_id_access_or_param, gen_identifier_or_param,
+                is_valid_identifier, type_in_scope, validate_field_name_existence_for_item_type,
             },
         },
         generator::{
             bool_op::BoExp,
             queries::Query as GeneratedQuery,
             source_steps::{
-                AddE, AddN, AddV, SearchBM25, SearchVector as GeneratedSearchVector, SourceStep,
+                AddE, AddN, AddV, HybridFusion as GeneratedHybridFusion, SearchBM25,
+                SearchHybrid as GeneratedSearchHybrid, SearchVector as GeneratedSearchVector,
+                SourceStep, UpsertE, UpsertN,
             },
             statements::Statement as GeneratedStatement,
             traversal_steps::{
                 ShouldCollect, Step as GeneratedStep, Traversal as GeneratedTraversal,
                 TraversalType, Where, WhereRef,
             },
-            utils::{GenRef, GeneratedValue, Separator, VecData},
+            utils::{GenRef, GeneratedValue, Separator, VecData, write_date},
         },
         parser::helix_parser::*,
     },
@@ -119,7 +122,8 @@ pub(crate) fn infer_expr_type<'a>(
             }
         }
 
-        AddNode(add) => {
+        AddNode(add) | UpsertNode(add) => {
+            let upsert = matches!(expression.expr, UpsertNode(_));
             if let Some(ref ty) = add.node_type {
                 if !ctx.node_set.contains(ty.as_str()) {
                     generate_error!(ctx, original_query, add.loc.clone(), E101, ty.as_str());
@@ -214,9 +218,9 @@ pub(crate) fn infer_expr_type<'a>(
                                             == FieldType::Date
                                         {
                                             true => match Date::new(value) {
-                                                Ok(date) => GeneratedValue::Literal(
-                                                    GenRef::Literal(date.to_rfc3339()),
-                                                ),
+                                                Ok(date) => GeneratedValue::Primitive(GenRef::Std(
+                                                    write_date(&date),
+                                                )),
                                                 Err(_) => {
                                                     generate_error!(
                                                         ctx,
@@ -261,20 +265,24 @@ pub(crate) fn infer_expr_type<'a>(
                         .properties
                         .iter()
                         .filter_map(|p| p.default_value.clone().map(|v| (p.name.clone(), v)))
+                        .filter(|(field_name, _)| !properties.contains_key(field_name.as_str()))
                         .collect::<Vec<(String, GeneratedValue)>>();
 
-                    for (field_name, default_value) in default_properties {
-                        if !properties.contains_key(field_name.as_str()) {
-                            properties.insert(field_name, default_value);
+                    // an upsert only writes defaults when it adds the node
+                    let defaults = match upsert {
+                        true => Some(default_properties),
+                        false => {
+                            properties.extend(default_properties);
+                            None
                         }
-                    }
+                    };
 
                     let secondary_indices = {
                         let secondary_indices = node_in_schema
                             .properties
                             .iter()
                             .filter_map(|p| {
-                                matches!(p.is_index, FieldPrefix::Index).then_some(p.name.clone())
+                                p.is_index.is_indexed().then_some(p.name.clone())
                             })
                             .collect::<Vec<_>>();
                         match secondary_indices.is_empty() {
@@ -283,14 +291,44 @@ pub(crate) fn infer_expr_type<'a>(
                         }
                     };
 
-                    let add_n = AddN {
-                        label,
-                        properties: Some(properties.into_iter().collect()),
-                        secondary_indices,
+                    let source_step = match defaults {
+                        Some(defaults) => {
+                            // the node to update is the one holding the same unique key
+                            let key = node_in_schema
+                                .properties
+                                .iter()
+                                .find(|p| p.is_index.is_unique() && fields.contains_key(&p.name));
+                            let key = match key {
+                                Some(key) => GenRef::Literal(key.name.clone()),
+                                None => {
+                                    generate_error!(
+                                        ctx,
+                                        original_query,
+                                        add.loc.clone(),
+                                        E613,
+                                        [ty.as_str()],
+                                        [ty.as_str()]
+                                    );
+                                    GenRef::Unknown
+                                }
+                            };
+                            SourceStep::UpsertN(UpsertN {
+                                label,
+                                key,
+                                properties: Some(properties.into_iter().collect()),
+                                defaults: (!defaults.is_empty()).then_some(defaults),
+                                secondary_indices,
+                            })
+                        }
+                        None => SourceStep::AddN(AddN {
+                            label,
+                            properties: Some(properties.into_iter().collect()),
+                            secondary_indices,
+                        }),
                     };
 
                     let stmt = GeneratedStatement::Traversal(GeneratedTraversal {
-                        source_step: Separator::Period(SourceStep::AddN(add_n)),
+                        source_step: Separator::Period(source_step),
                         steps: vec![],
                         traversal_type: TraversalType::Mut,
                         should_collect: ShouldCollect::ToVal,
@@ -309,7 +347,7 @@ pub(crate) fn infer_expr_type<'a>(
             );
             (Type::Node(None), None)
         }
-        AddEdge(add) => {
+        AddEdge(add) | UpsertEdge(add) => {
             if let Some(ref ty) = add.edge_type {
                 if !ctx.edge_map.contains_key(ty.as_str()) {
                     generate_error!(ctx, original_query, add.loc.clone(), E102, ty.as_str());
@@ -397,8 +435,8 @@ pub(crate) fn infer_expr_type<'a>(
                                                     == FieldType::Date
                                                 {
                                                     true => match Date::new(value) {
-                                                        Ok(date) => GeneratedValue::Literal(
-                                                            GenRef::Literal(date.to_rfc3339()),
+                                                        Ok(date) => GeneratedValue::Primitive(
+                                                            GenRef::Std(write_date(&date)),
                                                         ),
                                                         Err(_) => {
                                                             generate_error!(
@@ -481,15 +519,24 @@ pub(crate) fn infer_expr_type<'a>(
                         GeneratedValue::Unknown
                     }
                 };
-                let add_e = AddE {
-                    to,
-                    from,
-                    label,
-                    properties,
-                    // secondary_indices: None, // TODO: Add secondary indices by checking against labeled `INDEX` fields in schema
+                let source_step = match expression.expr {
+                    // an edge is upserted on its label and the nodes it connects
+                    UpsertEdge(_) => SourceStep::UpsertE(UpsertE {
+                        to,
+                        from,
+                        label,
+                        properties,
+                    }),
+                    _ => SourceStep::AddE(AddE {
+                        to,
+                        from,
+                        label,
+                        properties,
+                        // secondary_indices: None, // TODO: Add secondary indices by checking against labeled `INDEX` fields in schema
+                    }),
                 };
                 let stmt = GeneratedStatement::Traversal(GeneratedTraversal {
-                    source_step: Separator::Period(SourceStep::AddE(add_e)),
+                    source_step: Separator::Period(source_step),
                     steps: vec![],
                     traversal_type: TraversalType::Mut,
                     should_collect: ShouldCollect::ToVal,
@@ -592,8 +639,8 @@ pub(crate) fn infer_expr_type<'a>(
                                                 == FieldType::Date
                                             {
                                                 true => match Date::new(value) {
-                                                    Ok(date) => GeneratedValue::Literal(
-                                                        GenRef::Literal(date.to_rfc3339()),
+                                                    Ok(date) => GeneratedValue::Primitive(
+                                                        GenRef::Std(write_date(&date)),
                                                     ),
                                                     Err(_) => {
                                                         generate_error!(
@@ -646,6 +693,7 @@ pub(crate) fn infer_expr_type<'a>(
                 if let Some(vec_data) = &add.data {
                     let vec = match vec_data {
                         VectorData::Vector(v) => {
+                            check_vector_dimensions(ctx, original_query, add.loc.clone(), ty, v);
                             VecData::Standard(GeneratedValue::Literal(GenRef::Ref(format!(
                                 "[{}]",
                                 v.iter()
@@ -721,6 +769,9 @@ pub(crate) fn infer_expr_type<'a>(
             }
             let vec: VecData = match &sv.data {
                 Some(VectorData::Vector(v)) => {
+                    if let Some(ty) = &sv.vector_type {
+                        check_vector_dimensions(ctx, original_query, sv.loc.clone(), ty, v);
+                    }
                     VecData::Standard(GeneratedValue::Literal(GenRef::Ref(format!(
                         "[{}]",
                         v.iter()
@@ -829,9 +880,9 @@ pub(crate) fn infer_expr_type<'a>(
                     assert!(stmt.is_some());
                     let stmt = stmt.unwrap();
                     let mut gen_traversal = GeneratedTraversal {
-                        traversal_type: TraversalType::NestedFrom(GenRef::Std("v".to_string())),
+                        traversal_type: TraversalType::NestedFrom(GenRef::Std("val".to_string())),
                         steps: vec![],
-                        should_collect: ShouldCollect::ToVec,
+                        should_collect: ShouldCollect::No,
                         source_step: Separator::Empty(SourceStep::Anonymous),
                     };
                     match stmt {
@@ -859,7 +910,8 @@ pub(crate) fn infer_expr_type<'a>(
                         }
                         _ => unreachable!(),
                     }
-                    Some(vec![BoExp::Expr(gen_traversal)])
+                    // a vector passes the filter if the traversal from it yields anything
+                    Some(vec![BoExp::Exists(gen_traversal)])
                 }
                 None => None,
             };
@@ -955,17 +1007,21 @@ pub(crate) fn infer_expr_type<'a>(
             )
         }
         Exists(expr) => {
+            let nested = parent_ty.is_some();
             let (_, stmt) = infer_expr_type(ctx, expr, scope, original_query, parent_ty, gen_query);
             assert!(stmt.is_some());
             assert!(matches!(stmt, Some(GeneratedStatement::Traversal(_))));
             let expr = match stmt.unwrap() {
                 GeneratedStatement::Traversal(mut tr) => {
-                    // TODO: FIX VALUE HERE
-                    let source_variable = match tr.source_step.inner() {
-                        SourceStep::Identifier(id) => id.inner().clone(),
-                        _ => "val".to_string(),
-                    };
-                    tr.traversal_type = TraversalType::NestedFrom(GenRef::Std(source_variable));
+                    // outside of a traversal, e.g. in an `IF`, the traversal starts from its own source
+                    if nested {
+                        // TODO: FIX VALUE HERE
+                        let source_variable = match tr.source_step.inner() {
+                            SourceStep::Identifier(id) => id.inner().clone(),
+                            _ => "val".to_string(),
+                        };
+                        tr.traversal_type = TraversalType::NestedFrom(GenRef::Std(source_variable));
+                    }
                     tr.should_collect = ShouldCollect::No;
                     tr
                 }
@@ -988,6 +1044,22 @@ pub(crate) fn infer_expr_type<'a>(
                         E101,
                         ty.as_str()
                     );
+            } else if let Some(ref ty) = bm25_search.type_arg
+                && !ctx
+                    .node_fields
+                    .get(ty.as_str())
+                    .is_some_and(|fields| fields.values().any(|f| f.searchable))
+            {
+                // only `SEARCHABLE` fields are indexed so the search can never match anything
+                push_query_warn(
+                    ctx,
+                    original_query,
+                    bm25_search.loc.clone(),
+                    ErrorCode::W102,
+                    format!("node type `{ty}` has no `SEARCHABLE` fields to search"),
+                    format!("mark the fields of `N::{ty}` to full-text index with `SEARCHABLE` in the schema"),
+                    None,
+                );
             }
             let vec = match &bm25_search.data {
                 Some(ValueType::Literal { value, loc: _ }) => {
@@ -1109,9 +1181,230 @@ pub(crate) fn infer_expr_type<'a>(
                 })),
             )
         }
+        SearchHybrid(sh) => {
+            if let Some(ref ty) = sh.type_arg
+                && !ctx.vector_set.contains(ty.as_str()) {
+                    generate_error!(ctx, original_query, sh.loc.clone(), E103, ty.as_str());
+            } else if let Some(ref ty) = sh.type_arg
+                && !ctx
+                    .vector_fields
+                    .get(ty.as_str())
+                    .is_some_and(|fields| fields.values().any(|f| f.searchable))
+            {
+                // without `SEARCHABLE` fields only the vector search can match anything
+                push_query_warn(
+                    ctx,
+                    original_query,
+                    sh.loc.clone(),
+                    ErrorCode::W102,
+                    format!("vector type `{ty}` has no `SEARCHABLE` fields to search"),
+                    format!("mark the fields of `V::{ty}` to full-text index with `SEARCHABLE` in the schema"),
+                    None,
+                );
+            }
+            let query = match &sh.query {
+                Some(ValueType::Literal { value, loc: _ }) => {
+                    GeneratedValue::Literal(GenRef::Std(value.to_string()))
+                }
+                Some(ValueType::Identifier { value: i, loc: _ }) => {
+                    is_valid_identifier(ctx, original_query, sh.loc.clone(), i.as_str());
+                    let _ = type_in_scope(ctx, original_query, sh.loc.clone(), scope, i.as_str());
+                    gen_identifier_or_param(original_query, i, true, false)
+                }
+                _ => {
+                    generate_error!(
+                        ctx,
+                        original_query,
+                        sh.loc.clone(),
+                        E305,
+                        ["query", "SearchHybrid"],
+                        ["query"]
+                    );
+                    GeneratedValue::Unknown
+                }
+            };
+            let vec: VecData = match &sh.data {
+                Some(VectorData::Vector(v)) => {
+                    if let Some(ty) = &sh.type_arg {
+                        check_vector_dimensions(ctx, original_query, sh.loc.clone(), ty, v);
+                    }
+                    VecData::Standard(GeneratedValue::Literal(GenRef::Ref(format!(
+                        "[{}]",
+                        v.iter()
+                            .map(|f| f.to_string())
+                            .collect::<Vec<String>>()
+                            .join(",")
+                    ))))
+                }
+                Some(VectorData::Identifier(i)) => {
+                    is_valid_identifier(ctx, original_query, sh.loc.clone(), i.as_str());
+                    let _ = type_in_scope(ctx, original_query, sh.loc.clone(), scope, i.as_str());
+                    VecData::Standard(gen_identifier_or_param(
+                        original_query,
+                        i.as_str(),
+                        true,
+                        false,
+                    ))
+                }
+                Some(VectorData::Embed(e)) => match &e.value {
+                    EvaluatesToString::Identifier(i) => VecData::Embed {
+                        data: gen_identifier_or_param(original_query, i.as_str(), true, false),
+                        model_name: gen_query.embedding_model_to_use.clone(),
+                    },
+                    EvaluatesToString::StringLiteral(s) => VecData::Embed {
+                        data: GeneratedValue::Literal(GenRef::Ref(s.clone())),
+                        model_name: gen_query.embedding_model_to_use.clone(),
+                    },
+                },
+                None => {
+                    generate_error!(
+                        ctx,
+                        original_query,
+                        sh.loc.clone(),
+                        E305,
+                        ["vector_data", "SearchHybrid"],
+                        ["vector_data"]
+                    );
+                    VecData::Unknown
+                }
+            };
+            // the parser only produces integers, floats and identifiers for these arguments
+            let number = |ctx: &mut Ctx<'a>, n: &EvaluatesToNumber, name: &str| match &n.value {
+                EvaluatesToNumberType::I32(i) => {
+                    GeneratedValue::Primitive(GenRef::Std(i.to_string()))
+                }
+                EvaluatesToNumberType::F64(f) => {
+                    GeneratedValue::Primitive(GenRef::Std(f.to_string()))
+                }
+                EvaluatesToNumberType::Identifier(i) => {
+                    is_valid_identifier(ctx, original_query, sh.loc.clone(), i.as_str());
+                    gen_identifier_or_param(original_query, i, false, false)
+                }
+                _ => {
+                    generate_error!(
+                        ctx,
+                        original_query,
+                        sh.loc.clone(),
+                        E305,
+                        [name, "SearchHybrid"],
+                        [name]
+                    );
+                    GeneratedValue::Unknown
+                }
+            };
+            let k = match &sh.k {
+                Some(k) => number(ctx, k, "k"),
+                None => {
+                    generate_error!(ctx, original_query, sh.loc.clone(), E601, &sh.loc.span);
+                    GeneratedValue::Unknown
+                }
+            };
+            let fusion = match &sh.fusion {
+                Some(HybridFusion::Rrf(k)) => {
+                    GeneratedHybridFusion::Rrf(k.as_ref().map(|k| number(ctx, k, "k")))
+                }
+                Some(HybridFusion::Alpha(alpha)) => {
+                    GeneratedHybridFusion::Alpha(number(ctx, alpha, "alpha"))
+                }
+                None => GeneratedHybridFusion::Rrf(None),
+            };
+
+            (
+                Type::Vectors(sh.type_arg.clone()),
+                Some(GeneratedStatement::Traversal(GeneratedTraversal {
+                    traversal_type: TraversalType::Ref,
+                    steps: vec![],
+                    should_collect: ShouldCollect::ToVec,
+                    source_step: Separator::Period(SourceStep::SearchHybrid(
+                        GeneratedSearchHybrid {
+                            label: GenRef::Literal(sh.type_arg.clone().unwrap()),
+                            query,
+                            vec,
+                            k,
+                            fusion,
+                        },
+                    )),
+                })),
+            )
+        }
         _ => {
             println!("Unknown expression: {expr:?}");
             todo!()
         }
     }
 }
+
+/// Infer the type of an aggregate of the items of `item_ty`, checking the property it aggregates
+///
+/// `COUNT` is an unsigned integer, `SUM` and `AVG` only apply to numeric properties and `MIN` and
+/// `MAX` have the type of their property. Properties of untyped items can't be checked.
+pub(crate) fn infer_aggregate_type<'a>(
+    ctx: &mut Ctx<'a>,
+    original_query: &'a Query,
+    item_ty: &Type,
+    aggregate: &Aggregate,
+) -> Type {
+    let property = match (&aggregate.function, &aggregate.property) {
+        (AggregateFunction::Count, None) => return Type::Scalar(FieldType::U64),
+        (_, Some(_)) if item_ty.item_type().is_none() => return Type::Unknown,
+        (_, Some(property)) => property.as_str(),
+        (function, None) => {
+            generate_error!(
+                ctx,
+                original_query,
+                aggregate.loc.clone(),
+                E661,
+                [&function.to_string()],
+                [&function.to_string()]
+            );
+            return Type::Unknown;
+        }
+    };
+
+    let field_type = ctx
+        .get_item_fields(item_ty)
+        .and_then(|fields| fields.get(property))
+        .map(|field| field.field_type.clone());
+    let property_ty = match (field_type, property) {
+        (Some(field_type), _) => Type::from(field_type),
+        (None, "id" | "ID" | "from_node" | "to_node") => Type::Scalar(FieldType::Uuid),
+        (None, "label") => Type::Scalar(FieldType::String),
+        (None, "score") => Type::Scalar(FieldType::F64),
+        (None, _) => {
+            validate_field_name_existence_for_item_type(
+                ctx,
+                original_query,
+                aggregate.loc.clone(),
+                item_ty,
+                property,
+            );
+            Type::Unknown
+        }
+    };
+
+    match aggregate.function {
+        AggregateFunction::Count => Type::Scalar(FieldType::U64),
+        AggregateFunction::Sum | AggregateFunction::Avg
+            if !property_ty.is_numeric() && !matches!(property_ty, Type::Unknown) =>
+        {
+            generate_error!(
+                ctx,
+                original_query,
+                aggregate.loc.clone(),
+                E662,
+                &aggregate.function.to_string(),
+                property,
+                item_ty.kind_str(),
+                &item_ty.get_type_name(),
+                &property_ty.get_type_name()
+            );
+            Type::Unknown
+        }
+        AggregateFunction::Sum => match property_ty {
+            Type::Scalar(FieldType::F32 | FieldType::F64) => Type::Scalar(FieldType::F64),
+            _ => Type::Scalar(FieldType::I64),
+        },
+        AggregateFunction::Avg => Type::Scalar(FieldType::F64),
+        AggregateFunction::Min | AggregateFunction::Max => property_ty,
+    }
+}
@@ -100,11 +100,48 @@ pub(crate) fn validate_query<'a>(ctx: &mut Ctx<'a>, original_query: &'a Query) {
             None,
         );
     }
-    for ret in &original_query.return_values {
-        let (_, stmt) = infer_expr_type(ctx, ret, &mut scope, original_query, None, &mut query);
+    query.return_values = validate_return_values(
+        ctx,
+        &mut scope,
+        original_query,
+        &mut query,
+        &original_query.return_values,
+    );
 
-        assert!(stmt.is_some(), "RETURN value should be a valid expression");
-        match stmt.unwrap() {
+    if let Some(BuiltInMacro::MCP) = &original_query.built_in_macro {
+        if query.return_values.len() != 1 {
+            generate_error!(
+                ctx,
+                original_query,
+                original_query.loc.clone(),
+                E401,
+                &query.return_values.len().to_string()
+            );
+        }
+        let return_name = query.return_values.first().unwrap().get_name();
+        query.mcp_handler = Some(return_name);
+    }
+
+    ctx.output.queries.push(query);
+}
+
+/// Validates the values of a `RETURN`, at the end of the query or of an `IF` block
+pub(crate) fn validate_return_values<'a>(
+    ctx: &mut Ctx<'a>,
+    scope: &mut HashMap<&'a str, Type>,
+    original_query: &'a Query,
+    query: &mut GeneratedQuery,
+    returns: &'a [Expression],
+) -> Vec<ReturnValue> {
+    let mut return_values = Vec::new();
+    for ret in returns {
+        let (_, stmt) = infer_expr_type(ctx, ret, scope, original_query, None, query);
+
+        // a variable that isn't in scope, e.g. one assigned in an `IF` block, has been reported
+        let Some(stmt) = stmt else {
+            continue;
+        };
+        match stmt {
             GeneratedStatement::Traversal(traversal) => {
                 match &traversal.source_step.inner() {
                     SourceStep::Identifier(v) => {
@@ -119,13 +156,13 @@ pub(crate) fn validate_query<'a>(ctx: &mut Ctx<'a>, original_query: &'a Query) {
                         // if is array, need to handle it as an array
                         match traversal.should_collect {
                             ShouldCollect::ToVec => {
-                                query.return_values.push(ReturnValue::new_named(
+                                return_values.push(ReturnValue::new_named(
                                     GeneratedValue::Literal(GenRef::Literal(v.inner().clone())),
                                     ReturnValueExpr::Traversal(traversal.clone()),
                                 ));
                             }
                             ShouldCollect::ToVal => {
-                                query.return_values.push(ReturnValue::new_single_named(
+                                return_values.push(ReturnValue::new_single_named(
                                     GeneratedValue::Literal(GenRef::Literal(v.inner().clone())),
                                     ReturnValueExpr::Traversal(traversal.clone()),
                                 ));
@@ -136,9 +173,9 @@ pub(crate) fn validate_query<'a>(ctx: &mut Ctx<'a>, original_query: &'a Query) {
                         }
                     }
                     _ => {
-                        query.return_values.push(ReturnValue::new_unnamed(
-                            ReturnValueExpr::Traversal(traversal.clone()),
-                        ));
+                        return_values.push(ReturnValue::new_unnamed(ReturnValueExpr::Traversal(
+                            traversal.clone(),
+                        )));
                     }
                 }
             }
@@ -162,19 +199,24 @@ pub(crate) fn validate_query<'a>(ctx: &mut Ctx<'a>, original_query: &'a Query) {
 
                 match identifier_end_type {
                     Type::Scalar(_) | Type::Boolean => {
-                        query.return_values.push(ReturnValue::new_named_literal(
+                        return_values.push(ReturnValue::new_named_literal(
                             GeneratedValue::Literal(GenRef::Literal(id.inner().clone())),
                             value,
                         ));
                     }
-                    Type::Node(_) | Type::Vector(_) | Type::Edge(_) => {
-                        query.return_values.push(ReturnValue::new_single_named(
+                    // aggregates and groups are a single object
+                    Type::Node(_)
+                    | Type::Vector(_)
+                    | Type::Edge(_)
+                    | Type::Object(_)
+                    | Type::Groups(_) => {
+                        return_values.push(ReturnValue::new_single_named(
                             GeneratedValue::Literal(GenRef::Literal(id.inner().clone())),
                             ReturnValueExpr::Identifier(value),
                         ));
                     }
                     _ => {
-                        query.return_values.push(ReturnValue::new_named(
+                        return_values.push(ReturnValue::new_named(
                             GeneratedValue::Literal(GenRef::Literal(id.inner().clone())),
                             ReturnValueExpr::Identifier(value),
                         ));
@@ -182,32 +224,17 @@ pub(crate) fn validate_query<'a>(ctx: &mut Ctx<'a>, original_query: &'a Query) {
                 }
             }
             GeneratedStatement::Literal(l) => {
-                query.return_values.push(ReturnValue::new_literal(
+                return_values.push(ReturnValue::new_literal(
                     GeneratedValue::Literal(l.clone()),
                     GeneratedValue::Literal(l.clone()),
                 ));
             }
-            GeneratedStatement::Empty => query.return_values = vec![],
+            GeneratedStatement::Empty => return_values.clear(),
 
             // given all erroneous statements are caught by the analyzer, this should never happen
             // all malformed statements (not gramatically correct) should be caught by the parser
             _ => unreachable!(),
         }
     }
-
-    if let Some(BuiltInMacro::MCP) = &original_query.built_in_macro {
-        if query.return_values.len() != 1 {
-            generate_error!(
-                ctx,
-                original_query,
-                original_query.loc.clone(),
-                E401,
-                &query.return_values.len().to_string()
-            );
-        }
-        let return_name = query.return_values.first().unwrap().get_name();
-        query.mcp_handler = Some(return_name);
-    }
-
-    ctx.output.queries.push(query);
+    return_values
 }
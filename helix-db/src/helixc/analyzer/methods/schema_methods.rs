@@ -42,6 +42,8 @@ pub(crate) fn build_field_lookups<'a>(src: &'a Source) -> SchemaVersionMap<'a> {
                             "id",
                             Cow::Owned(Field {
                                 prefix: FieldPrefix::Empty,
+                                searchable: false,
+                                analyzer: None,
                                 defaults: None,
                                 name: "id".to_string(),
                                 field_type: FieldType::Uuid,
@@ -69,6 +71,8 @@ pub(crate) fn build_field_lookups<'a>(src: &'a Source) -> SchemaVersionMap<'a> {
                             "id",
                             Cow::Owned(Field {
                                 prefix: FieldPrefix::Empty,
+                                searchable: false,
+                                analyzer: None,
                                 defaults: None,
                                 name: "id".to_string(),
                                 field_type: FieldType::Uuid,
@@ -92,6 +96,8 @@ pub(crate) fn build_field_lookups<'a>(src: &'a Source) -> SchemaVersionMap<'a> {
                             "id",
                             Cow::Owned(Field {
                                 prefix: FieldPrefix::Empty,
+                                searchable: false,
+                                analyzer: None,
                                 defaults: None,
                                 name: "id".to_string(),
                                 field_type: FieldType::Uuid,
@@ -155,6 +161,16 @@ pub(crate) fn check_schema(ctx: &mut Ctx) {
                         Some("rename the field".to_string()),
                     );
                 }
+                if f.is_unique() {
+                    push_schema_err(
+                        ctx,
+                        f.loc.clone(),
+                        ErrorCode::E210,
+                        ErrorCode::E210_message(&f.name, "edge", &edge.name.1),
+                        Some(ErrorCode::E210_hint()),
+                    );
+                }
+                check_searchable(ctx, f, "edge", &edge.name.1);
             })
         }
         ctx.output.edges.push(edge.clone().into());
@@ -170,7 +186,21 @@ pub(crate) fn check_schema(ctx: &mut Ctx) {
                     Some("rename the field".to_string()),
                 );
             }
+            check_searchable(ctx, f, "node", &node.name.1);
         });
+        for index in &node.composite_indices {
+            for (loc, field) in &index.fields {
+                if !node.fields.iter().any(|f| f.name == *field) {
+                    push_schema_err(
+                        ctx,
+                        loc.clone(),
+                        ErrorCode::E202,
+                        ErrorCode::E202_message(field, "node", &node.name.1),
+                        Some(ErrorCode::E202_hint()),
+                    );
+                }
+            }
+        }
         ctx.output.nodes.push(node.clone().into());
     }
     for vector in &ctx.src.get_latest_schema().vector_schemas {
@@ -184,7 +214,35 @@ pub(crate) fn check_schema(ctx: &mut Ctx) {
                     Some("rename the field".to_string()),
                 );
             }
+            if f.is_unique() {
+                push_schema_err(
+                    ctx,
+                    f.loc.clone(),
+                    ErrorCode::E210,
+                    ErrorCode::E210_message(&f.name, "vector", &vector.name),
+                    Some(ErrorCode::E210_hint()),
+                );
+            }
+            check_searchable(ctx, f, "vector", &vector.name);
         });
         ctx.output.vectors.push(vector.clone().into());
     }
 }
+
+/// Only string fields can be tokenized into the BM25 index of their label
+fn check_searchable(ctx: &mut Ctx, field: &Field, item_type: &str, item_type_name: &str) {
+    if field.searchable && !matches!(field.field_type, FieldType::String) {
+        push_schema_err(
+            ctx,
+            field.loc.clone(),
+            ErrorCode::E213,
+            ErrorCode::E213_message(
+                &field.name,
+                &field.field_type.to_string(),
+                item_type,
+                item_type_name,
+            ),
+            Some(ErrorCode::E213_hint()),
+        );
+    }
+}
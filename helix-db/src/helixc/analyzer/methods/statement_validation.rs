@@ -5,15 +5,21 @@ use crate::{
     generate_error,
     helixc::{
         analyzer::{
-            analyzer::Ctx, errors::push_query_err, methods::infer_expr_type::infer_expr_type,
-            types::Type, utils::is_valid_identifier,
+            analyzer::Ctx,
+            errors::push_query_err,
+            methods::{infer_expr_type::infer_expr_type, query_validation::validate_return_values},
+            types::Type,
+            utils::{gen_identifier_or_param, is_valid_identifier},
         },
         generator::{
-            queries::Query as GeneratedQuery, statements::Statement as GeneratedStatement,
+            queries::Query as GeneratedQuery,
+            statements::Statement as GeneratedStatement,
             statements::{
                 Assignment as GeneratedAssignment, Drop as GeneratedDrop,
-                ForEach as GeneratedForEach, ForLoopInVariable, ForVariable,
+                ForEach as GeneratedForEach, ForLoopInVariable, ForVariable, If as GeneratedIf,
+                IfBlock as GeneratedIfBlock,
             },
+            traversal_steps::Step as GeneratedStep,
             utils::GenRef,
         },
         parser::helix_parser::*,
@@ -251,5 +257,102 @@ pub(crate) fn validate_statements<'a>(
             });
             Some(stmt)
         }
+
+        If(if_stmt) => {
+            let condition =
+                validate_if_condition(ctx, scope, original_query, query, &if_stmt.condition);
+            let then_block =
+                validate_if_block(ctx, scope, original_query, query, &if_stmt.then_block);
+            let else_block = if_stmt
+                .else_block
+                .as_ref()
+                .map(|block| validate_if_block(ctx, scope, original_query, query, block));
+
+            Some(GeneratedStatement::If(GeneratedIf {
+                condition: Box::new(condition),
+                then_block,
+                else_block,
+            }))
+        }
+    }
+}
+
+/// Validates the condition of an `IF`, which must be a boolean, a traversal ending in a
+/// comparison, or an `EXISTS`, `AND` or `OR`
+fn validate_if_condition<'a>(
+    ctx: &mut Ctx<'a>,
+    scope: &mut HashMap<&'a str, Type>,
+    original_query: &'a Query,
+    query: &mut GeneratedQuery,
+    condition: &'a Expression,
+) -> GeneratedStatement {
+    let (ty, stmt) = infer_expr_type(ctx, condition, scope, original_query, None, query);
+    match stmt {
+        Some(GeneratedStatement::BoExp(expr)) => GeneratedStatement::BoExp(expr),
+        Some(GeneratedStatement::Traversal(tr))
+            if matches!(
+                tr.steps.last().map(|step| step.inner()),
+                Some(GeneratedStep::BoolOp(_))
+            ) =>
+        {
+            GeneratedStatement::Traversal(tr)
+        }
+        // a literal is written as a string, `true` and `false` are written as they are
+        Some(GeneratedStatement::Literal(literal)) if matches!(ty, Type::Boolean) => {
+            GeneratedStatement::Literal(GenRef::Std(literal.inner().clone()))
+        }
+        Some(GeneratedStatement::Identifier(id))
+            if matches!(
+                ty,
+                Type::Boolean | Type::Scalar(FieldType::Boolean) | Type::Unknown
+            ) =>
+        {
+            GeneratedStatement::Identifier(GenRef::Std(
+                gen_identifier_or_param(original_query, id.inner(), false, false).to_string(),
+            ))
+        }
+        // the identifier wasn't in scope, which has already been reported
+        None => GeneratedStatement::Empty,
+        Some(_) => {
+            generate_error!(
+                ctx,
+                original_query,
+                condition.loc.clone(),
+                E671,
+                ty.kind_str()
+            );
+            GeneratedStatement::Empty
+        }
+    }
+}
+
+/// Validates the statements and `RETURN` of an `IF` or `ELSE` block
+///
+/// Variables assigned in the block are only in scope in the block, like in the generated Rust.
+fn validate_if_block<'a>(
+    ctx: &mut Ctx<'a>,
+    scope: &HashMap<&'a str, Type>,
+    original_query: &'a Query,
+    query: &mut GeneratedQuery,
+    block: &'a IfBlock,
+) -> GeneratedIfBlock {
+    let mut block_scope = scope.clone();
+    let statements = block
+        .statements
+        .iter()
+        .filter_map(|stmt| validate_statements(ctx, &mut block_scope, original_query, query, stmt))
+        .collect();
+
+    let return_values = block.return_values.as_ref().map(|returns| {
+        // an MCP handler returns the iterator of its single value rather than a response
+        if let Some(BuiltInMacro::MCP) = &original_query.built_in_macro {
+            generate_error!(ctx, original_query, block.loc.clone(), E402);
+        }
+        validate_return_values(ctx, &mut block_scope, original_query, query, returns)
+    });
+
+    GeneratedIfBlock {
+        statements,
+        return_values,
     }
 }
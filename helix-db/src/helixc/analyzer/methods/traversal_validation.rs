@@ -8,30 +8,36 @@ use crate::{
             analyzer::Ctx,
             errors::push_query_err,
             methods::{
-                exclude_validation::validate_exclude, graph_step_validation::apply_graph_step,
-                infer_expr_type::infer_expr_type, object_validation::validate_object,
+                exclude_validation::validate_exclude,
+                graph_step_validation::apply_graph_step,
+                infer_expr_type::{infer_aggregate_type, infer_expr_type},
+                object_validation::validate_object,
             },
             types::Type,
             utils::{
-                Variable, field_exists_on_item_type, gen_identifier_or_param, is_valid_identifier,
-                type_in_scope,
+                Variable, check_vector_dimensions, field_exists_on_item_type,
+                gen_identifier_or_param, is_valid_identifier, type_in_scope,
+                validate_field_name_existence_for_item_type,
             },
         },
         generator::{
             bool_op::{BoExp, BoolOp, Eq, Gt, Gte, Lt, Lte, Neq},
             object_remapping_generation::{ExcludeField, Remapping, RemappingType},
             queries::Query as GeneratedQuery,
-            source_steps::{EFromID, EFromType, NFromID, NFromIndex, NFromType, SourceStep},
+            source_steps::{
+                EFromID, EFromIndex, EFromIndexRange, EFromType, IndexRangeBound, NFromCompositeIndex, NFromID, NFromIndex,
+                NFromIndexRange, NFromType, SourceStep,
+            },
             statements::Statement as GeneratedStatement,
             traversal_steps::{
-                OrderBy, Range, ShouldCollect, Step as GeneratedStep,
+                AggregateBy, Aggregation, OrderBy, Range, ShouldCollect, Step as GeneratedStep,
                 Traversal as GeneratedTraversal, TraversalType, Where, WhereRef,
             },
-            utils::{GenRef, GeneratedValue, Order, Separator},
+            utils::{GenRef, GeneratedValue, Order, Separator, write_date},
         },
         parser::{helix_parser::*, location::Loc},
     },
-    protocol::value::Value,
+    protocol::{date::Date, value::Value},
 };
 use paste::paste;
 use std::collections::HashMap;
@@ -64,58 +70,15 @@ pub(crate) fn validate_traversal<'a>(
                 // check id exists in scope
                 match ids[0].clone() {
                     IdType::ByIndex { index, value, loc } => {
-                        is_valid_identifier(
+                        let field_type = check_index_field(
                             ctx,
                             original_query,
-                            loc.clone(),
-                            index.to_string().as_str(),
+                            "node",
+                            node_type,
+                            &index,
+                            &[&value],
+                            &loc,
                         );
-                        let corresponding_field = ctx.node_fields.get(node_type.as_str()).cloned();
-                        match corresponding_field {
-                            Some(node_fields) => {
-                                match node_fields
-                                    .iter()
-                                    .find(|(name, _)| name.to_string() == *index.to_string())
-                                {
-                                    Some((_, field)) => {
-                                        if !field.is_indexed() {
-                                            generate_error!(
-                                                ctx,
-                                                original_query,
-                                                loc.clone(),
-                                                E208,
-                                                [&index.to_string(), node_type],
-                                                [node_type]
-                                            );
-                                        } else if let ValueType::Literal { ref value, ref loc } =
-                                            *value
-                                                && !field.field_type.eq(value) {
-                                                    generate_error!(
-                                                        ctx,
-                                                        original_query,
-                                                        loc.clone(),
-                                                        E205,
-                                                        &value.to_string(),
-                                                        &field.field_type.to_string(),
-                                                        "node",
-                                                        node_type
-                                                    );
-                                        }
-                                    }
-                                    None => {
-                                        generate_error!(
-                                            ctx,
-                                            original_query,
-                                            loc.clone(),
-                                            E208,
-                                            [&index.to_string(), node_type],
-                                            [node_type]
-                                        );
-                                    }
-                                }
-                            }
-                            None => unreachable!(),
-                        };
                         gen_traversal.source_step =
                             Separator::Period(SourceStep::NFromIndex(NFromIndex {
                                 label: GenRef::Literal(node_type.clone()),
@@ -124,55 +87,116 @@ pub(crate) fn validate_traversal<'a>(
                                     // would be caught by the parser
                                     _ => unreachable!(),
                                 }),
-                                key: match *value {
-                                    ValueType::Identifier { value, loc } => {
-                                        if is_valid_identifier(
-                                            ctx,
-                                            original_query,
-                                            loc.clone(),
-                                            value.as_str(),
-                                        ) && !scope.contains_key(value.as_str())
-                                        {
-                                            generate_error!(
-                                                ctx,
-                                                original_query,
-                                                loc.clone(),
-                                                E301,
-                                                value.as_str()
-                                            );
-                                        }
-                                        gen_identifier_or_param(
-                                            original_query,
-                                            value.as_str(),
-                                            true,
-                                            false,
-                                        )
-                                    }
-                                    ValueType::Literal { value, loc: _ } => {
-                                        GeneratedValue::Primitive(GenRef::Std(match value {
-                                            Value::String(s) => format!("\"{s}\""),
-                                            Value::I8(i) => i.to_string(),
-                                            Value::I16(i) => i.to_string(),
-                                            Value::I32(i) => i.to_string(),
-                                            Value::I64(i) => i.to_string(),
-                                            Value::U8(i) => i.to_string(),
-                                            Value::U16(i) => i.to_string(),
-                                            Value::U32(i) => i.to_string(),
-                                            Value::U64(i) => i.to_string(),
-                                            Value::U128(i) => i.to_string(),
-                                            Value::F32(i) => i.to_string(),
-                                            Value::F64(i) => i.to_string(),
-                                            Value::Boolean(b) => b.to_string(),
-                                            _ => unreachable!(),
-                                        }))
-                                    }
-                                    _ => unreachable!(),
-                                },
+                                key: gen_index_key(
+                                    ctx,
+                                    original_query,
+                                    scope,
+                                    *value,
+                                    field_type.as_ref(),
+                                ),
                             }));
                         gen_traversal.should_collect = ShouldCollect::ToVal;
                         gen_traversal.traversal_type = TraversalType::Ref;
                         Type::Node(Some(node_type.to_string()))
                     }
+                    IdType::ByIndexRange {
+                        index,
+                        start,
+                        end,
+                        loc,
+                    } => {
+                        let bound_values = start
+                            .iter()
+                            .chain(end.iter())
+                            .map(|bound| match bound {
+                                IndexBound::Included(value) | IndexBound::Excluded(value) => value,
+                            })
+                            .collect::<Vec<_>>();
+                        let field_type = check_index_field(
+                            ctx,
+                            original_query,
+                            "node",
+                            node_type,
+                            &index,
+                            &bound_values,
+                            &loc,
+                        );
+                        let mut gen_bound = |bound: Option<IndexBound>| match bound {
+                            Some(IndexBound::Included(value)) => IndexRangeBound::Included(
+                                gen_index_key(ctx, original_query, scope, value, field_type.as_ref()),
+                            ),
+                            Some(IndexBound::Excluded(value)) => IndexRangeBound::Excluded(
+                                gen_index_key(ctx, original_query, scope, value, field_type.as_ref()),
+                            ),
+                            None => IndexRangeBound::Unbounded,
+                        };
+                        let start = gen_bound(start);
+                        let end = gen_bound(end);
+                        gen_traversal.source_step =
+                            Separator::Period(SourceStep::NFromIndexRange(NFromIndexRange {
+                                label: GenRef::Literal(node_type.clone()),
+                                index: GenRef::Literal(match *index {
+                                    IdType::Identifier { value, loc: _ } => value,
+                                    // would be caught by the parser
+                                    _ => unreachable!(),
+                                }),
+                                start,
+                                end,
+                            }));
+                        gen_traversal.traversal_type = TraversalType::Ref;
+                        Type::Nodes(Some(node_type.to_string()))
+                    }
+                    IdType::ByCompositeIndex { fields, loc } => {
+                        let (index, prefix, range) = match find_composite_index(
+                            ctx,
+                            original_query,
+                            node_type,
+                            fields,
+                            &loc,
+                        ) {
+                            Some(lookup) => lookup,
+                            None => return Type::Unknown,
+                        };
+                        let field_type = |ctx: &Ctx<'a>, field: &str| {
+                            ctx.node_fields
+                                .get(node_type.as_str())
+                                .and_then(|fields| fields.get(field))
+                                .map(|field| field.field_type.clone())
+                        };
+                        let prefix = prefix
+                            .into_iter()
+                            .map(|(field, value)| {
+                                let field_type = field_type(ctx, &field);
+                                gen_index_key(ctx, original_query, scope, value, field_type.as_ref())
+                            })
+                            .collect();
+                        let (start, end) = match range {
+                            Some((field, start, end)) => {
+                                let field_type = field_type(ctx, &field);
+                                let mut gen_bound = |bound: Option<IndexBound>| match bound {
+                                    Some(IndexBound::Included(value)) => IndexRangeBound::Included(
+                                        gen_index_key(ctx, original_query, scope, value, field_type.as_ref()),
+                                    ),
+                                    Some(IndexBound::Excluded(value)) => IndexRangeBound::Excluded(
+                                        gen_index_key(ctx, original_query, scope, value, field_type.as_ref()),
+                                    ),
+                                    None => IndexRangeBound::Unbounded,
+                                };
+                                (gen_bound(start), gen_bound(end))
+                            }
+                            None => (IndexRangeBound::Unbounded, IndexRangeBound::Unbounded),
+                        };
+                        gen_traversal.source_step =
+                            Separator::Period(SourceStep::NFromCompositeIndex(NFromCompositeIndex {
+                                label: GenRef::Literal(node_type.clone()),
+                                index: GenRef::Literal(index),
+                                prefix,
+                                start,
+                                end,
+                            }));
+                        gen_traversal.traversal_type = TraversalType::Ref;
+                        Type::Nodes(Some(node_type.to_string()))
+                    }
                     IdType::Identifier { value: i, loc } => {
                         gen_traversal.source_step =
                             Separator::Period(SourceStep::NFromID(NFromID {
@@ -229,24 +253,120 @@ pub(crate) fn validate_traversal<'a>(
             }
             if let Some(ids) = ids {
                 assert!(ids.len() == 1, "multiple ids not supported yet");
-                gen_traversal.source_step = Separator::Period(SourceStep::EFromID(EFromID {
-                    id: match ids[0].clone() {
-                        IdType::Identifier { value: i, loc } => {
-                            is_valid_identifier(ctx, original_query, loc.clone(), i.as_str());
-                            let _ =
-                                type_in_scope(ctx, original_query, loc.clone(), scope, i.as_str());
-                            let value =
-                                gen_identifier_or_param(original_query, i.as_str(), true, false);
-                            value.inner().clone()
-                        }
-                        IdType::Literal { value: s, loc: _ } => GenRef::Std(s),
-                        _ => unreachable!(),
-                    },
-                    label: GenRef::Literal(edge_type.clone()),
-                }));
-                gen_traversal.traversal_type = TraversalType::Ref;
-                gen_traversal.should_collect = ShouldCollect::ToVal;
-                Type::Edge(Some(edge_type.to_string()))
+                match ids[0].clone() {
+                    IdType::ByIndex { index, value, loc } => {
+                        let field_type = check_index_field(
+                            ctx,
+                            original_query,
+                            "edge",
+                            edge_type,
+                            &index,
+                            &[&value],
+                            &loc,
+                        );
+                        gen_traversal.source_step =
+                            Separator::Period(SourceStep::EFromIndex(EFromIndex {
+                                label: GenRef::Literal(edge_type.clone()),
+                                index: GenRef::Literal(match *index {
+                                    IdType::Identifier { value, loc: _ } => value,
+                                    // would be caught by the parser
+                                    _ => unreachable!(),
+                                }),
+                                key: gen_index_key(
+                                    ctx,
+                                    original_query,
+                                    scope,
+                                    *value,
+                                    field_type.as_ref(),
+                                ),
+                            }));
+                        gen_traversal.should_collect = ShouldCollect::ToVal;
+                        gen_traversal.traversal_type = TraversalType::Ref;
+                        Type::Edge(Some(edge_type.to_string()))
+                    }
+                    IdType::ByIndexRange {
+                        index,
+                        start,
+                        end,
+                        loc,
+                    } => {
+                        let bound_values = start
+                            .iter()
+                            .chain(end.iter())
+                            .map(|bound| match bound {
+                                IndexBound::Included(value) | IndexBound::Excluded(value) => value,
+                            })
+                            .collect::<Vec<_>>();
+                        let field_type = check_index_field(
+                            ctx,
+                            original_query,
+                            "edge",
+                            edge_type,
+                            &index,
+                            &bound_values,
+                            &loc,
+                        );
+                        let mut gen_bound = |bound: Option<IndexBound>| match bound {
+                            Some(IndexBound::Included(value)) => IndexRangeBound::Included(
+                                gen_index_key(ctx, original_query, scope, value, field_type.as_ref()),
+                            ),
+                            Some(IndexBound::Excluded(value)) => IndexRangeBound::Excluded(
+                                gen_index_key(ctx, original_query, scope, value, field_type.as_ref()),
+                            ),
+                            None => IndexRangeBound::Unbounded,
+                        };
+                        let start = gen_bound(start);
+                        let end = gen_bound(end);
+                        gen_traversal.source_step =
+                            Separator::Period(SourceStep::EFromIndexRange(EFromIndexRange {
+                                label: GenRef::Literal(edge_type.clone()),
+                                index: GenRef::Literal(match *index {
+                                    IdType::Identifier { value, loc: _ } => value,
+                                    // would be caught by the parser
+                                    _ => unreachable!(),
+                                }),
+                                start,
+                                end,
+                            }));
+                        gen_traversal.traversal_type = TraversalType::Ref;
+                        Type::Edges(Some(edge_type.to_string()))
+                    }
+                    id => {
+                        gen_traversal.source_step =
+                            Separator::Period(SourceStep::EFromID(EFromID {
+                                id: match id {
+                                    IdType::Identifier { value: i, loc } => {
+                                        is_valid_identifier(
+                                            ctx,
+                                            original_query,
+                                            loc.clone(),
+                                            i.as_str(),
+                                        );
+                                        let _ = type_in_scope(
+                                            ctx,
+                                            original_query,
+                                            loc.clone(),
+                                            scope,
+                                            i.as_str(),
+                                        );
+                                        let value = gen_identifier_or_param(
+                                            original_query,
+                                            i.as_str(),
+                                            true,
+                                            false,
+                                        );
+                                        value.inner().clone()
+                                    }
+                                    IdType::Literal { value: s, loc: _ } => GenRef::Std(s),
+                                    _ => unreachable!(),
+                                },
+                                label: GenRef::Literal(edge_type.clone()),
+                            }));
+                        gen_traversal.traversal_type = TraversalType::Ref;
+                        gen_traversal.should_collect = ShouldCollect::ToVal;
+                        Type::Edge(Some(edge_type.to_string()))
+                    }
+                }
             } else {
                 gen_traversal.source_step = Separator::Period(SourceStep::EFromType(EFromType {
                     label: GenRef::Literal(edge_type.clone()),
@@ -295,6 +415,9 @@ pub(crate) fn validate_traversal<'a>(
             }
             let vec: VecData = match &sv.data {
                 Some(VectorData::Vector(v)) => {
+                    if let Some(ty) = &sv.vector_type {
+                        check_vector_dimensions(ctx, original_query, sv.loc.clone(), ty, v);
+                    }
                     VecData::Standard(GeneratedValue::Literal(GenRef::Ref(format!(
                         "[{}]",
                         v.iter()
@@ -403,9 +526,9 @@ pub(crate) fn validate_traversal<'a>(
                     assert!(stmt.is_some());
                     let stmt = stmt.unwrap();
                     let mut gen_traversal = GeneratedTraversal {
-                        traversal_type: TraversalType::NestedFrom(GenRef::Std("v".to_string())),
+                        traversal_type: TraversalType::NestedFrom(GenRef::Std("val".to_string())),
                         steps: vec![],
-                        should_collect: ShouldCollect::ToVec,
+                        should_collect: ShouldCollect::No,
                         source_step: Separator::Empty(SourceStep::Anonymous),
                     };
                     match stmt {
@@ -433,7 +556,8 @@ pub(crate) fn validate_traversal<'a>(
                         }
                         _ => unreachable!(),
                     }
-                    Some(vec![BoExp::Expr(gen_traversal)])
+                    // a vector passes the filter if the traversal from it yields anything
+                    Some(vec![BoExp::Exists(gen_traversal)])
                 }
                 None => None,
             };
@@ -451,6 +575,21 @@ pub(crate) fn validate_traversal<'a>(
         }
     };
 
+    // groups are keyed by values only known once the query runs, so they can only be returned
+    if let (StartNode::Identifier(identifier), Type::Groups(_), Some(step)) =
+        (&tr.start, &cur_ty, tr.steps.first())
+    {
+        generate_error!(
+            ctx,
+            original_query,
+            step.loc.clone(),
+            E664,
+            [identifier.as_str()],
+            [identifier.as_str()]
+        );
+        return Type::Unknown;
+    }
+
     // Track excluded fields for property validation
     let mut excluded: HashMap<&str, Loc> = HashMap::new();
 
@@ -490,6 +629,109 @@ pub(crate) fn validate_traversal<'a>(
                 gen_traversal.should_collect = ShouldCollect::No;
             }
 
+            StepType::GroupBy(group_by) => {
+                let followed_by_aggregate =
+                    i < number_of_steps && matches!(tr.steps[i + 1].step, StepType::AggregateBy(_));
+                if i != number_of_steps && !followed_by_aggregate {
+                    generate_error!(
+                        ctx,
+                        original_query,
+                        group_by.loc.clone(),
+                        E663,
+                        ["GROUP_BY"],
+                        ["GROUP_BY"]
+                    );
+                }
+                if !is_aggregatable(ctx, original_query, &group_by.loc, &cur_ty) {
+                    return cur_ty.clone();
+                }
+                if cur_ty.item_type().is_some() {
+                    validate_field_name_existence_for_item_type(
+                        ctx,
+                        original_query,
+                        group_by.loc.clone(),
+                        &cur_ty,
+                        &group_by.property,
+                    );
+                }
+                // the aggregates of each group are set by the `AGGREGATE_BY` that follows,
+                // a `GROUP_BY` on its own counts the items of each group
+                if !followed_by_aggregate {
+                    cur_ty = Type::Groups(HashMap::from([(
+                        "count".to_string(),
+                        Type::Scalar(FieldType::U64),
+                    )]));
+                    gen_traversal
+                        .steps
+                        .push(Separator::Period(GeneratedStep::AggregateBy(AggregateBy {
+                            group_by: Some(GenRef::Literal(group_by.property.clone())),
+                            aggregations: vec![Aggregation::Count(None)],
+                        })));
+                    gen_traversal.should_collect = ShouldCollect::ToVal;
+                }
+                excluded.clear();
+            }
+
+            StepType::AggregateBy(aggregate_by) => {
+                if i != number_of_steps {
+                    generate_error!(
+                        ctx,
+                        original_query,
+                        aggregate_by.loc.clone(),
+                        E663,
+                        ["AGGREGATE_BY"],
+                        ["AGGREGATE_BY"]
+                    );
+                }
+                if !is_aggregatable(ctx, original_query, &aggregate_by.loc, &cur_ty) {
+                    return cur_ty.clone();
+                }
+                let mut fields = HashMap::new();
+                let mut aggregations = Vec::with_capacity(aggregate_by.aggregates.len());
+                for aggregate in &aggregate_by.aggregates {
+                    let ty = infer_aggregate_type(ctx, original_query, &cur_ty, aggregate);
+                    let property = aggregate.property.clone().map(GenRef::Literal);
+                    let aggregation = match (&aggregate.function, property) {
+                        (AggregateFunction::Count, property) => Aggregation::Count(property),
+                        (AggregateFunction::Sum, Some(property)) => Aggregation::Sum(property),
+                        (AggregateFunction::Avg, Some(property)) => Aggregation::Avg(property),
+                        (AggregateFunction::Min, Some(property)) => Aggregation::Min(property),
+                        (AggregateFunction::Max, Some(property)) => Aggregation::Max(property),
+                        // missing properties are reported by `infer_aggregate_type`
+                        (_, None) => continue,
+                    };
+                    let name = match &aggregate.property {
+                        Some(property) => {
+                            format!(
+                                "{}_{property}",
+                                aggregate.function.to_string().to_lowercase()
+                            )
+                        }
+                        None => "count".to_string(),
+                    };
+                    fields.insert(name, ty);
+                    aggregations.push(aggregation);
+                }
+                let group_by = match &previous_step {
+                    Some(StepType::GroupBy(group_by)) => {
+                        Some(GenRef::Literal(group_by.property.clone()))
+                    }
+                    _ => None,
+                };
+                cur_ty = match group_by {
+                    Some(_) => Type::Groups(fields),
+                    None => Type::Object(fields),
+                };
+                gen_traversal
+                    .steps
+                    .push(Separator::Period(GeneratedStep::AggregateBy(AggregateBy {
+                        group_by,
+                        aggregations,
+                    })));
+                gen_traversal.should_collect = ShouldCollect::ToVal;
+                excluded.clear();
+            }
+
             StepType::Exclude(ex) => {
                 // checks if exclude is either the last step or the step before an object remapping or closure
                 // i.e. you cant have `N<Type>::!{field1}::Out<Label>`
@@ -603,6 +845,7 @@ pub(crate) fn validate_traversal<'a>(
                             gen_query,
                         ) {
                             (Type::Scalar(ft), _) => ft.clone(),
+                            (Type::Boolean, _) => FieldType::Boolean,
                             (field_type, _) => {
                                 generate_error!(
                                     ctx,
@@ -1266,3 +1509,250 @@ pub(crate) fn validate_traversal<'a>(
     }
     cur_ty
 }
+
+/// Checks that the items of a traversal can be grouped and aggregated, i.e. they are nodes, edges or vectors
+fn is_aggregatable<'a>(
+    ctx: &mut Ctx<'a>,
+    original_query: &'a Query,
+    loc: &Loc,
+    cur_ty: &Type,
+) -> bool {
+    match cur_ty {
+        Type::Node(_)
+        | Type::Nodes(_)
+        | Type::Edge(_)
+        | Type::Edges(_)
+        | Type::Vector(_)
+        | Type::Vectors(_) => true,
+        _ => {
+            generate_error!(
+                ctx,
+                original_query,
+                loc.clone(),
+                E203,
+                &cur_ty.get_type_name()
+            );
+            false
+        }
+    }
+}
+
+/// Checks that `index` is an indexed field of the node or edge type `type_name` and that any literal keys match its type.
+///
+/// Returns the type of the indexed field if it exists.
+fn check_index_field<'a>(
+    ctx: &mut Ctx<'a>,
+    original_query: &'a Query,
+    item_type: &str,
+    type_name: &str,
+    index: &IdType,
+    values: &[&ValueType],
+    loc: &Loc,
+) -> Option<FieldType> {
+    is_valid_identifier(ctx, original_query, loc.clone(), index.to_string().as_str());
+    let fields = match item_type {
+        "edge" => ctx.edge_fields.get(type_name),
+        _ => ctx.node_fields.get(type_name),
+    };
+    let field = match fields {
+        Some(fields) => fields
+            .iter()
+            .find(|(name, _)| name.to_string() == *index.to_string())
+            .map(|(_, field)| field.clone().into_owned()),
+        None => return None,
+    };
+    match field {
+        Some(field) if field.is_indexed() => {
+            for value in values {
+                if let ValueType::Literal { value, loc } = value
+                    && !field.field_type.eq(value)
+                {
+                    generate_error!(
+                        ctx,
+                        original_query,
+                        loc.clone(),
+                        E205,
+                        &value.to_string(),
+                        &field.field_type.to_string(),
+                        item_type,
+                        type_name
+                    );
+                }
+            }
+            Some(field.field_type)
+        }
+        _ => {
+            generate_error!(
+                ctx,
+                original_query,
+                loc.clone(),
+                E208,
+                [&index.to_string(), item_type, type_name],
+                [item_type, type_name]
+            );
+            None
+        }
+    }
+}
+
+/// A composite index lookup resolved against the schema: the index name, the exact-match
+/// fields in index order, and the optional range on the field that follows them.
+type CompositeIndexLookup = (
+    String,
+    Vec<(String, ValueType)>,
+    Option<(String, Option<IndexBound>, Option<IndexBound>)>,
+);
+
+/// Finds a composite index of `node_type` whose leading fields are the exact-match fields of the
+/// lookup, in any order, followed by the range field if there is one.
+///
+/// Also checks that any literal keys match the types of their fields.
+fn find_composite_index<'a>(
+    ctx: &mut Ctx<'a>,
+    original_query: &'a Query,
+    node_type: &str,
+    fields: Vec<(IdType, IndexLookup)>,
+    loc: &Loc,
+) -> Option<CompositeIndexLookup> {
+    let mut exact = Vec::new();
+    let mut range = None;
+    for (field, lookup) in fields {
+        match lookup {
+            IndexLookup::Eq(value) => exact.push((field.to_string(), value)),
+            IndexLookup::Range { start, end } => range = Some((field.to_string(), start, end)),
+        }
+    }
+
+    let schema = ctx
+        .src
+        .get_latest_schema()
+        .node_schemas
+        .iter()
+        .find(|schema| schema.name.1 == node_type);
+    let index = schema.and_then(|schema| {
+        schema.composite_indices.iter().find(|index| {
+            let names = index
+                .fields
+                .iter()
+                .map(|(_, name)| name.as_str())
+                .collect::<Vec<_>>();
+            let covered = exact.len() + range.is_some() as usize;
+            names.len() >= covered
+                && exact
+                    .iter()
+                    .all(|(field, _)| names[..exact.len()].contains(&field.as_str()))
+                && range
+                    .as_ref()
+                    .is_none_or(|(field, _, _)| names[exact.len()] == field.as_str())
+        })
+    });
+    let index = match index {
+        Some(index) => index,
+        None => {
+            let fields = exact
+                .iter()
+                .map(|(field, _)| field.as_str())
+                .chain(range.iter().map(|(field, _, _)| field.as_str()))
+                .collect::<Vec<_>>()
+                .join(", ");
+            generate_error!(
+                ctx,
+                original_query,
+                loc.clone(),
+                E211,
+                [node_type, &fields],
+                [&fields, node_type]
+            );
+            return None;
+        }
+    };
+
+    let node_fields = ctx.node_fields.get(node_type).cloned().unwrap_or_default();
+    let literals = exact
+        .iter()
+        .map(|(field, value)| (field, value))
+        .chain(range.iter().flat_map(|(field, start, end)| {
+            start
+                .iter()
+                .chain(end.iter())
+                .map(move |bound| match bound {
+                    IndexBound::Included(value) | IndexBound::Excluded(value) => (field, value),
+                })
+        }));
+    for (field, value) in literals {
+        if let ValueType::Literal { value, loc } = value
+            && let Some(field) = node_fields.get(field.as_str())
+            && !field.field_type.eq(value)
+        {
+            generate_error!(
+                ctx,
+                original_query,
+                loc.clone(),
+                E205,
+                &value.to_string(),
+                &field.field_type.to_string(),
+                "node",
+                node_type
+            );
+        }
+    }
+
+    // the exact-match keys make up the key prefix, so they must follow the index field order
+    let mut prefix = Vec::with_capacity(exact.len());
+    for (_, name) in &index.fields {
+        if let Some(pos) = exact.iter().position(|(field, _)| field == name) {
+            prefix.push(exact.swap_remove(pos));
+        }
+    }
+    Some((index.name(), prefix, range))
+}
+
+/// Generates the reference to the key used to look up a secondary index.
+///
+/// Date literals are normalised to RFC 3339 so they match how dates are stored.
+fn gen_index_key<'a>(
+    ctx: &mut Ctx<'a>,
+    original_query: &'a Query,
+    scope: &HashMap<&'a str, Type>,
+    value: ValueType,
+    field_type: Option<&FieldType>,
+) -> GeneratedValue {
+    match value {
+        ValueType::Identifier { value, loc } => {
+            if is_valid_identifier(ctx, original_query, loc.clone(), value.as_str())
+                && !scope.contains_key(value.as_str())
+            {
+                generate_error!(ctx, original_query, loc.clone(), E301, value.as_str());
+            }
+            gen_identifier_or_param(original_query, value.as_str(), true, false)
+        }
+        ValueType::Literal { value, loc } => {
+            GeneratedValue::Primitive(GenRef::Ref(match value {
+                Value::String(s) => match field_type {
+                    Some(FieldType::Date) => match Date::new(&Value::String(s.clone())) {
+                        Ok(date) => write_date(&date),
+                        Err(_) => {
+                            generate_error!(ctx, original_query, loc.clone(), E501, s.as_str());
+                            return GeneratedValue::Unknown;
+                        }
+                    },
+                    _ => format!("\"{s}\".to_string()"),
+                },
+                Value::I8(i) => i.to_string(),
+                Value::I16(i) => i.to_string(),
+                Value::I32(i) => i.to_string(),
+                Value::I64(i) => i.to_string(),
+                Value::U8(i) => i.to_string(),
+                Value::U16(i) => i.to_string(),
+                Value::U32(i) => i.to_string(),
+                Value::U64(i) => i.to_string(),
+                Value::U128(i) => i.to_string(),
+                Value::F32(i) => format!("{i:?}"),
+                Value::F64(i) => format!("{i:?}"),
+                Value::Boolean(b) => b.to_string(),
+                _ => unreachable!(),
+            }))
+        }
+        _ => unreachable!(),
+    }
+}
@@ -222,7 +222,7 @@ impl From<DefaultValue> for GeneratedValue {
             DefaultValue::U128(i) => GeneratedValue::Primitive(GenRef::Std(i.to_string())),
             DefaultValue::Boolean(b) => GeneratedValue::Primitive(GenRef::Std(b.to_string())),
             DefaultValue::Now => GeneratedValue::Primitive(GenRef::Std(
-                "chrono::Utc::now().to_rfc3339()".to_string(),
+                "chrono::Utc::now()".to_string(),
             )),
             DefaultValue::Empty => GeneratedValue::Unknown,
         }
@@ -240,6 +240,8 @@ pub(crate) enum Type {
     Vectors(Option<String>),
     Scalar(FieldType),
     Object(HashMap<String, Type>),
+    /// The aggregates of each group of a `GROUP_BY`, keyed by the value the group was made for
+    Groups(HashMap<String, Type>),
     Anonymous(Box<Type>),
     Boolean,
     Unknown,
@@ -256,6 +258,7 @@ impl Type {
             Type::Vectors(_) => "vector",
             Type::Scalar(_) => "scalar",
             Type::Object(_) => "object",
+            Type::Groups(_) => "groups",
             Type::Boolean => "boolean",
             Type::Unknown => "unknown",
             Type::Anonymous(ty) => ty.kind_str(),
@@ -278,10 +281,27 @@ impl Type {
                 let field_names = fields.keys().cloned().collect::<Vec<_>>();
                 format!("object({})", field_names.join(", "))
             }
+            Type::Groups(fields) => {
+                let field_names = fields.keys().cloned().collect::<Vec<_>>();
+                format!("groups({})", field_names.join(", "))
+            }
             _ => unreachable!(),
         }
     }
 
+    /// The schema type of nodes, edges or vectors, `None` for untyped items and other types
+    pub fn item_type(&self) -> Option<&str> {
+        match self {
+            Type::Node(Some(name))
+            | Type::Nodes(Some(name))
+            | Type::Edge(Some(name))
+            | Type::Edges(Some(name))
+            | Type::Vector(Some(name))
+            | Type::Vectors(Some(name)) => Some(name.as_str()),
+            _ => None,
+        }
+    }
+
     /// Recursively strip <code>Anonymous</code> layers and return the base type.
     pub fn base(&self) -> &Type {
         match self {
@@ -30,6 +30,39 @@ pub(super) fn is_valid_identifier(
     }
 }
 
+/// Checks a vector literal against the dimensions declared on its vector type, if any.
+pub(super) fn check_vector_dimensions(
+    ctx: &mut Ctx,
+    original_query: &Query,
+    loc: Loc,
+    vector_type: &str,
+    vector: &[f64],
+) {
+    let dimensions = ctx
+        .src
+        .get_latest_schema()
+        .vector_schemas
+        .iter()
+        .find(|schema| schema.name == vector_type)
+        .and_then(|schema| schema.dimensions);
+    if let Some(dimensions) = dimensions
+        && dimensions != vector.len()
+    {
+        generate_error!(
+            ctx,
+            original_query,
+            loc,
+            E212,
+            [
+                &vector.len().to_string(),
+                &dimensions.to_string(),
+                vector_type
+            ],
+            [&dimensions.to_string(), vector_type]
+        );
+    }
+}
+
 pub(super) fn is_param<'a>(q: &'a Query, name: &str) -> Option<&'a Parameter> {
     q.parameters.iter().find(|p| p.name.1 == *name)
 }
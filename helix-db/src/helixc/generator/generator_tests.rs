@@ -1,9 +1,15 @@
 //! parse -> analyze -> generate -> compile
 
-use crate::helixc::{
-    analyzer::analyzer::analyze,
-    generator::Source as GeneratedSource,
-    parser::helix_parser::{Content, HelixParser, HxFile, Source as ParsedSource},
+use crate::{
+    helix_engine::{
+        bm25::analyzer::AnalyzerKind,
+        vector_core::{vector::VectorPrecision, vector_distance::DistanceMetric},
+    },
+    helixc::{
+        analyzer::{analyzer::analyze, error_codes::ErrorCode},
+        generator::Source as GeneratedSource,
+        parser::helix_parser::{Content, HelixParser, HxFile, Source as ParsedSource},
+    },
 };
 
 use std::{fmt::Write, fs, path::PathBuf, process::Command};
@@ -219,6 +225,169 @@ fn generator_test_2() {
     };
 }
 
+#[test]
+fn generator_test_index_range() {
+    let input = r#"
+        N::User {
+            INDEX age: I64,
+            INDEX created_at: Date,
+            name: String
+        }
+
+        QUERY adults() =>
+            users <- N<User>({age: GTE(18)})
+            RETURN users
+
+        QUERY users_between(min: I64, max: I64) =>
+            users <- N<User>({age: BETWEEN(min, max)})
+            RETURN users
+
+        QUERY users_since(since: Date) =>
+            users <- N<User>({created_at: GT(since)})
+            RETURN users
+
+        QUERY users_before() =>
+            users <- N<User>({created_at: LT("2024-01-01")})
+            RETURN users
+    "#;
+
+    let generated = generate(input.to_string()).unwrap().to_string();
+    assert!(generated.contains(
+        r#"n_from_index_range("User", "age", Bound::Included(&18), Bound::Unbounded)?"#
+    ));
+    assert!(generated.contains(
+        r#"n_from_index_range("User", "age", Bound::Included(&data.min), Bound::Included(&data.max))?"#
+    ));
+    assert!(generated.contains(
+        r#"n_from_index_range("User", "created_at", Bound::Excluded(&data.since), Bound::Unbounded)?"#
+    ));
+    assert!(generated.contains(
+        r#"n_from_index_range("User", "created_at", Bound::Unbounded, Bound::Excluded(&"2024-01-01T00:00:00+00:00".parse::<DateTime<Utc>>().unwrap()))?"#
+    ));
+}
+
+#[test]
+fn generator_test_unique() {
+    let input = r#"
+        N::User {
+            UNIQUE INDEX email: String,
+            UNIQUE handle: String,
+            name: String
+        }
+
+        QUERY create_user(email: String, handle: String, name: String) =>
+            user <- AddN<User>({email: email, handle: handle, name: name})
+            RETURN user
+    "#;
+
+    let mut content = generate_content(input.to_string());
+    content.source = parse_content(&content).unwrap();
+    let fields = &content.source.get_latest_schema().node_schemas[0].fields;
+    assert!(fields[0].is_unique() && fields[0].is_indexed());
+    assert!(fields[1].is_unique() && fields[1].is_indexed());
+    assert!(!fields[2].is_unique());
+
+    // a duplicate key must abort the handler before the transaction is committed
+    let generated = analyze_source(content.source).unwrap().to_string();
+    assert!(generated.contains(r#"Some(&["email", "handle"])).try_collect_to_obj()?"#));
+}
+
+#[test]
+fn generator_test_unique_on_edge() {
+    let input = r#"
+        N::User {
+            name: String
+        }
+
+        E::Follows {
+            From: User,
+            To: User,
+            Properties: {
+                UNIQUE since: String
+            }
+        }
+    "#;
+
+    let mut content = generate_content(input.to_string());
+    content.source = parse_content(&content).unwrap();
+    let (diagnostics, _) = analyze(&content.source);
+    assert!(
+        diagnostics
+            .iter()
+            .any(|d| matches!(d.error_code, ErrorCode::E210))
+    );
+}
+
+#[test]
+fn generator_test_composite_index() {
+    let input = r#"
+        N::Post {
+            user_id: I64,
+            created_at: Date,
+            title: String,
+            INDEX(user_id, created_at)
+        }
+
+        N::Account {
+            tenant_id: String,
+            external_id: String,
+            INDEX(tenant_id, external_id),
+        }
+
+        QUERY posts_since(user_id: I64, since: Date) =>
+            posts <- N<Post>({user_id: user_id, created_at: GTE(since)})
+            RETURN posts
+
+        QUERY account(external_id: String) =>
+            accounts <- N<Account>({external_id: external_id, tenant_id: "acme"})
+            RETURN accounts
+    "#;
+
+    let mut content = generate_content(input.to_string());
+    content.source = parse_content(&content).unwrap();
+    let schema = content.source.get_latest_schema();
+    assert_eq!(schema.node_schemas[0].composite_indices[0].name(), "user_id,created_at");
+    assert_eq!(schema.node_schemas[0].fields.len(), 3);
+
+    let generated = analyze_source(content.source).unwrap().to_string();
+    assert!(generated.contains(
+        r#"n_from_composite_index("Post", "user_id,created_at", &[Value::from(&data.user_id)], Bound::Included(Value::from(&data.since)), Bound::Unbounded)?"#
+    ));
+    // exact-match keys are reordered to follow the index
+    assert!(generated.contains(
+        r#"n_from_composite_index("Account", "tenant_id,external_id", &[Value::from(&"acme".to_string()), Value::from(&data.external_id)], Bound::Unbounded, Bound::Unbounded)?"#
+    ));
+}
+
+#[test]
+fn generator_test_composite_index_errors() {
+    let input = r#"
+        N::Post {
+            user_id: I64,
+            created_at: Date,
+            INDEX(user_id, missing)
+        }
+
+        QUERY posts_since(since: Date) =>
+            posts <- N<Post>({user_id: 1, created_at: GTE(since)})
+            RETURN posts
+    "#;
+
+    let mut content = generate_content(input.to_string());
+    content.source = parse_content(&content).unwrap();
+    let (diagnostics, _) = analyze(&content.source);
+    assert!(
+        diagnostics
+            .iter()
+            .any(|d| matches!(d.error_code, ErrorCode::E202))
+    );
+    assert!(
+        diagnostics
+            .iter()
+            .any(|d| matches!(d.error_code, ErrorCode::E211))
+    );
+}
+
 /*
 #[test]
 fn generator_test_3() {
@@ -234,3 +403,643 @@ fn generator_test_3() {
     };
 }
 */
+
+#[test]
+fn generator_test_edge_index() {
+    let input = r#"
+        N::User {
+            name: String,
+        }
+
+        E::Follows {
+            From: User,
+            To: User,
+            Properties: {
+                INDEX external_ref: String,
+                since: I64,
+            }
+        }
+
+        QUERY follow_by_ref(external_ref: String) =>
+            follow <- E<Follows>({external_ref: external_ref})
+            RETURN follow
+
+        QUERY follows_since(external_ref: String) =>
+            follows <- E<Follows>({external_ref: GTE(external_ref)})
+            RETURN follows
+
+        QUERY follows_by_since(since: I64) =>
+            follows <- E<Follows>({since: since})
+            RETURN follows
+    "#;
+
+    let mut content = generate_content(input.to_string());
+    content.source = parse_content(&content).unwrap();
+    let (diagnostics, source) = analyze(&content.source);
+    // `since` is not indexed
+    assert_eq!(diagnostics.len(), 1);
+    assert!(matches!(diagnostics[0].error_code, ErrorCode::E208));
+
+    let generated = source.to_string();
+    assert!(generated.contains(r#"e_from_index("Follows", "external_ref", &data.external_ref)?"#));
+    assert!(generated.contains(
+        r#"e_from_index_range("Follows", "external_ref", Bound::Included(&data.external_ref), Bound::Unbounded)?"#
+    ));
+}
+
+#[test]
+fn generator_test_vector_dimensions_and_metric() {
+    let input = r#"
+        V::Image(dimensions: 3, metric: L2) {
+            url: String,
+        }
+
+        V::Ranking(metric: DotProduct)
+
+        QUERY add_image() =>
+            image <- AddV<Image>([1.0, 2.0, 3.0], {url: "a.png"})
+            RETURN image
+
+        QUERY search_images() =>
+            images <- SearchV<Image>([1.0, 2.0], 10)
+            RETURN images
+    "#;
+
+    let mut content = generate_content(input.to_string());
+    content.source = parse_content(&content).unwrap();
+    let schema = content.source.get_latest_schema();
+    assert_eq!(schema.vector_schemas[0].dimensions, Some(3));
+    assert_eq!(schema.vector_schemas[0].metric, DistanceMetric::Euclidean);
+    assert_eq!(schema.vector_schemas[1].dimensions, None);
+    assert_eq!(schema.vector_schemas[1].metric, DistanceMetric::DotProduct);
+
+    let (diagnostics, _) = analyze(&content.source);
+    assert_eq!(diagnostics.len(), 1);
+    assert!(matches!(diagnostics[0].error_code, ErrorCode::E212));
+}
+
+#[test]
+fn generator_test_vector_precision() {
+    let input = r#"
+        V::Embedding(dimensions: 4, precision: Int8, rerank: true)
+
+        V::Half(precision: F32)
+
+        QUERY search_embeddings() =>
+            embeddings <- SearchV<Embedding>([0.1, 0.2, 0.3, 0.4], 10)
+            RETURN embeddings
+    "#;
+
+    let mut content = generate_content(input.to_string());
+    content.source = parse_content(&content).unwrap();
+    let schema = content.source.get_latest_schema();
+    assert_eq!(schema.vector_schemas[0].precision, VectorPrecision::Int8);
+    assert!(schema.vector_schemas[0].rerank);
+    assert_eq!(schema.vector_schemas[1].precision, VectorPrecision::F32);
+    assert!(!schema.vector_schemas[1].rerank);
+
+    let (diagnostics, _) = analyze(&content.source);
+    assert!(diagnostics.is_empty());
+
+    let content = generate_content("V::Twice(precision: F32, precision: Int8)".to_string());
+    assert!(parse_content(&content).is_err());
+}
+
+#[test]
+fn generator_test_searchable() {
+    let input = r#"
+        N::Doc {
+            SEARCHABLE title: String,
+            INDEX SEARCHABLE slug: String,
+            views: I32
+        }
+
+        N::Tag {
+            name: String
+        }
+
+        E::Cites {
+            From: Doc,
+            To: Doc,
+            Properties: {
+                SEARCHABLE weight: F64
+            }
+        }
+
+        QUERY search_docs(text: String) =>
+            docs <- SearchBM25<Doc>(text, 10)
+            RETURN docs
+
+        QUERY search_tags(text: String) =>
+            tags <- SearchBM25<Tag>(text, 10)
+            RETURN tags
+    "#;
+
+    let mut content = generate_content(input.to_string());
+    content.source = parse_content(&content).unwrap();
+    let schema = content.source.get_latest_schema();
+    let doc_fields = &schema.node_schemas[0].fields;
+    assert!(doc_fields[0].searchable && !doc_fields[0].is_indexed());
+    assert!(doc_fields[1].searchable && doc_fields[1].is_indexed());
+    assert!(!doc_fields[2].searchable);
+
+    let (diagnostics, _) = analyze(&content.source);
+    assert_eq!(diagnostics.len(), 2);
+    assert!(
+        diagnostics
+            .iter()
+            .any(|d| matches!(d.error_code, ErrorCode::E213))
+    );
+    assert!(
+        diagnostics
+            .iter()
+            .any(|d| matches!(d.error_code, ErrorCode::W102))
+    );
+}
+
+#[test]
+fn generator_test_searchable_analyzer() {
+    let input = r#"
+        N::Article {
+            SEARCHABLE(English) title: String,
+            INDEX SEARCHABLE(Simple) slug: String,
+            SEARCHABLE body: String
+        }
+    "#;
+
+    let mut content = generate_content(input.to_string());
+    content.source = parse_content(&content).unwrap();
+    let schema = content.source.get_latest_schema();
+    let fields = &schema.node_schemas[0].fields;
+    assert_eq!(fields[0].analyzer, Some(AnalyzerKind::English));
+    assert_eq!(fields[1].analyzer, Some(AnalyzerKind::Simple));
+    assert!(fields[1].is_indexed());
+    assert!(fields[2].searchable && fields[2].analyzer.is_none());
+
+    let (diagnostics, _) = analyze(&content.source);
+    assert!(diagnostics.is_empty());
+
+    let content = generate_content("N::Bad { SEARCHABLE(Klingon) name: String }".to_string());
+    assert!(parse_content(&content).is_err());
+}
+
+#[test]
+fn generator_test_search_vector_prefilter() {
+    let input = r#"
+        V::Doc {
+            tenant_id: String,
+            published: Boolean,
+        }
+
+        QUERY search_tenant_docs(vec: [F64], tenant_id: String) =>
+            docs <- SearchV<Doc>(vec, 10)::PREFILTER(_::{tenant_id}::EQ(tenant_id))
+            RETURN docs
+
+        QUERY search_published_docs(vec: [F64], tenant_id: String) =>
+            docs <- SearchV<Doc>(vec, 10)::PREFILTER(AND(_::{tenant_id}::EQ(tenant_id), _::{published}::EQ(true)))
+            RETURN docs
+    "#;
+
+    let source = generate(input.to_string()).unwrap();
+    let generated = source.to_string();
+    assert!(generated.contains(
+        r#"search_v::<_, _>(&data.vec, 10, "Doc", Some(&[|v: &HVector, txn: &RoTxn| { let val = TraversalVal::Vector(v.clone()); Exist::exists(&mut G::new_from(Arc::clone(&db), &txn, vec![val.clone()])"#
+    ));
+    assert!(generated.contains(r#".check_property("published")"#));
+
+    let input = r#"
+        V::Doc {
+            tenant_id: String,
+        }
+
+        QUERY search_docs(vec: [F64], owner: String) =>
+            docs <- SearchV<Doc>(vec, 10)::PREFILTER(_::{owner}::EQ(owner))
+            RETURN docs
+    "#;
+    let mut content = generate_content(input.to_string());
+    content.source = parse_content(&content).unwrap();
+    let (diagnostics, _) = analyze(&content.source);
+    assert!(!diagnostics.is_empty());
+    assert!(
+        diagnostics
+            .iter()
+            .all(|d| matches!(d.error_code, ErrorCode::E202))
+    );
+}
+
+#[test]
+fn generator_test_search_hybrid() {
+    let input = r#"
+        V::Doc {
+            SEARCHABLE text: String,
+        }
+
+        QUERY hybrid_docs(query: String, vec: [F64], k: I64) =>
+            docs <- SearchHybrid<Doc>(query, vec, k)
+            RETURN docs
+
+        QUERY hybrid_docs_weighted(query: String) =>
+            docs <- SearchHybrid<Doc>(query, Embed(query), 10)::ALPHA(0.7)
+            RETURN docs
+
+        QUERY hybrid_docs_rrf(query: String) =>
+            docs <- SearchHybrid<Doc>("graph databases", [0.1, 0.2], 10)::RRF(20)
+            RETURN docs
+    "#;
+
+    let source = generate(input.to_string()).unwrap();
+    let generated = source.to_string();
+    assert!(generated.contains(
+        r#"search_hybrid("Doc", &data.query, &data.vec, data.k.clone(), HybridFusion::default())?"#
+    ));
+    assert!(generated.contains(
+        r#"search_hybrid("Doc", &data.query, &embed!(db, &data.query), 10, HybridFusion::Alpha(0.7 as f32))?"#
+    ));
+    assert!(generated.contains(
+        r#"search_hybrid("Doc", "graph databases", &[0.1,0.2], 10, HybridFusion::Rrf(20 as f32))?"#
+    ));
+
+    let input = r#"
+        V::Doc {
+            text: String,
+        }
+
+        N::User {
+            name: String,
+        }
+
+        QUERY hybrid_docs(query: String, vec: [F64]) =>
+            docs <- SearchHybrid<Doc>(query, vec, 10)
+            users <- SearchHybrid<User>(query, vec, 10)
+            RETURN docs, users
+    "#;
+    let mut content = generate_content(input.to_string());
+    content.source = parse_content(&content).unwrap();
+    let (diagnostics, _) = analyze(&content.source);
+    assert_eq!(diagnostics.len(), 2);
+    assert!(
+        diagnostics
+            .iter()
+            .any(|d| matches!(d.error_code, ErrorCode::W102))
+    );
+    assert!(
+        diagnostics
+            .iter()
+            .any(|d| matches!(d.error_code, ErrorCode::E103))
+    );
+}
+
+const AGGREGATE_BY_QUERIES: &str = r#"
+    N::User {
+        name: String,
+        age: U32,
+        country: String,
+    }
+
+    QUERY user_stats() =>
+        users <- N<User>
+        stats <- users::GROUP_BY(country)::AGGREGATE_BY(COUNT, SUM(age), AVG(age), MIN(name), MAX(age))
+        RETURN stats
+
+    QUERY users_per_country() =>
+        counts <- N<User>::GROUP_BY(country)
+        RETURN counts
+
+    QUERY total_age() =>
+        total <- N<User>::AGGREGATE_BY(SUM(age), COUNT(name))
+        RETURN total
+"#;
+
+#[test]
+fn generator_test_aggregate_by() {
+    let source = generate(AGGREGATE_BY_QUERIES.to_string()).unwrap();
+    let generated = source.to_string();
+    assert!(generated.contains(
+        r#"aggregate_by(Some("country"), &[Aggregation::Count(None), Aggregation::Sum("age"), Aggregation::Avg("age"), Aggregation::Min("name"), Aggregation::Max("age")])"#
+    ));
+    assert!(generated.contains(r#"aggregate_by(Some("country"), &[Aggregation::Count(None)])"#));
+    assert!(generated.contains(
+        r#"aggregate_by(None, &[Aggregation::Sum("age"), Aggregation::Count(Some("name"))])"#
+    ));
+
+    let input = r#"
+        N::User {
+            name: String,
+            age: U32,
+            country: String,
+        }
+
+        QUERY bad_stats() =>
+            sums <- N<User>::AGGREGATE_BY(SUM(name), AVG)
+            ages <- N<User>::GROUP_BY(country)::COUNT
+            missing <- N<User>::GROUP_BY(city)
+            groups <- N<User>::GROUP_BY(country)
+            counts <- groups::COUNT
+            RETURN sums, ages, missing, counts
+    "#;
+    let mut content = generate_content(input.to_string());
+    content.source = parse_content(&content).unwrap();
+    let (diagnostics, _) = analyze(&content.source);
+    assert!(
+        diagnostics
+            .iter()
+            .any(|d| matches!(d.error_code, ErrorCode::E661))
+    );
+    assert!(
+        diagnostics
+            .iter()
+            .any(|d| matches!(d.error_code, ErrorCode::E662))
+    );
+    assert!(
+        diagnostics
+            .iter()
+            .any(|d| matches!(d.error_code, ErrorCode::E663))
+    );
+    assert!(
+        diagnostics
+            .iter()
+            .any(|d| matches!(d.error_code, ErrorCode::E202))
+    );
+    assert!(
+        diagnostics
+            .iter()
+            .any(|d| matches!(d.error_code, ErrorCode::E664))
+    );
+}
+
+#[test]
+fn generator_test_aggregate_by_compiles() {
+    match compile(generate(AGGREGATE_BY_QUERIES.to_string()).unwrap()) {
+        Ok(_) => {}
+        Err(e) => {
+            panic!("error: {e:?}");
+        }
+    };
+}
+
+const UPSERT_QUERIES: &str = r#"
+    N::User {
+        UNIQUE INDEX email: String,
+        name: String,
+        plan: String DEFAULT "free",
+    }
+
+    E::Follows {
+        From: User,
+        To: User,
+        Properties: {
+            since: Date,
+        }
+    }
+
+    QUERY upsert_user(email: String, name: String) =>
+        user <- UpsertN<User>({email: email, name: name})
+        RETURN user
+
+    QUERY follow(from: ID, to: ID, since: Date) =>
+        follows <- UpsertE<Follows>({since: since})::From(from)::To(to)
+        RETURN follows
+"#;
+
+#[test]
+fn generator_test_upsert() {
+    let source = generate(UPSERT_QUERIES.to_string()).unwrap();
+    let generated = source.to_string();
+    assert!(generated.contains(r#"upsert_n("User", "email", Some(props! {"#));
+    assert!(generated.contains(r#"Some(props! { "plan" => "free" }), Some(&["email"]))"#));
+    assert!(generated.contains(
+        r#"upsert_e("Follows", Some(props! { "since" => data.since.clone() }), *data.from, *data.to)"#
+    ));
+
+    let input = r#"
+        N::User {
+            INDEX email: String,
+            name: String,
+        }
+
+        QUERY upsert_user(email: String, name: String) =>
+            user <- UpsertN<User>({email: email, name: name})
+            RETURN user
+    "#;
+    let mut content = generate_content(input.to_string());
+    content.source = parse_content(&content).unwrap();
+    let (diagnostics, _) = analyze(&content.source);
+    assert!(
+        diagnostics
+            .iter()
+            .any(|d| matches!(d.error_code, ErrorCode::E613))
+    );
+}
+
+#[test]
+fn generator_test_upsert_compiles() {
+    match compile(generate(UPSERT_QUERIES.to_string()).unwrap()) {
+        Ok(_) => {}
+        Err(e) => {
+            panic!("error: {e:?}");
+        }
+    };
+}
+
+const IF_ELSE_QUERIES: &str = r#"
+    N::User {
+        name: String,
+        age: U32,
+    }
+
+    E::Follows {
+        From: User,
+        To: User,
+    }
+
+    QUERY follow_or_create(user_id: ID, other_id: ID, name: String, adult: Boolean) =>
+        user <- N<User>(user_id)
+        IF EXISTS(N<User>(user_id)::Out<Follows>::WHERE(_::{name}::EQ(name))) {
+            followed <- N<User>(user_id)::Out<Follows>
+            RETURN followed
+        } ELSE IF N<User>(other_id)::{age}::GT(17) {
+            edge <- AddE<Follows>::From(user_id)::To(other_id)
+        } ELSE IF adult {
+            other <- AddN<User>({name: name, age: 18})
+            RETURN other
+        } ELSE {
+            created <- AddN<User>({name: name, age: 0})
+        }
+        RETURN user
+
+    QUERY has_followers(user_id: ID) =>
+        user <- N<User>(user_id)
+        IF EXISTS(user::In<Follows>) {
+            followers <- user::In<Follows>
+            RETURN followers
+        }
+        IF true {
+            none <- N<User>(user_id)::Out<Follows>
+        }
+        RETURN user
+"#;
+
+#[test]
+fn generator_test_if_else() {
+    let source = generate(IF_ELSE_QUERIES.to_string()).unwrap();
+    let generated = source.to_string();
+    assert!(generated.contains("if Exist::exists(&mut G::new(Arc::clone(&db), &txn)"));
+    assert!(generated.contains("} else if G::new(Arc::clone(&db), &txn)"));
+    assert!(generated.contains(".map_value_or(false, |v| *v > 17)? {"));
+    assert!(generated.contains("} else if data.adult.clone() {"));
+    assert!(
+        generated
+            .contains("if Exist::exists(&mut G::new_from(Arc::clone(&db), &txn, user.clone())")
+    );
+    assert!(generated.contains("if true {"));
+    assert_eq!(
+        generated
+            .matches("return Ok(input.request.out_fmt.create_response(&return_vals));")
+            .count(),
+        3
+    );
+
+    let input = r#"
+        N::User {
+            name: String,
+            age: U32,
+        }
+
+        QUERY scoped(user_id: ID) =>
+            IF N<User>(user_id)::{age}::GT(17) {
+                user <- N<User>(user_id)
+            }
+            RETURN user
+
+        QUERY not_a_bool(user_id: ID) =>
+            IF N<User>(user_id) {
+                user <- N<User>(user_id)
+            }
+            RETURN user_id
+
+        #[mcp]
+        QUERY early(user_id: ID) =>
+            user <- N<User>(user_id)
+            IF N<User>(user_id)::{age}::GT(17) {
+                RETURN user
+            }
+            RETURN user
+    "#;
+    let mut content = generate_content(input.to_string());
+    content.source = parse_content(&content).unwrap();
+    let (diagnostics, _) = analyze(&content.source);
+    assert!(
+        diagnostics
+            .iter()
+            .any(|d| matches!(d.error_code, ErrorCode::E301))
+    );
+    assert!(
+        diagnostics
+            .iter()
+            .any(|d| matches!(d.error_code, ErrorCode::E671))
+    );
+    assert!(
+        diagnostics
+            .iter()
+            .any(|d| matches!(d.error_code, ErrorCode::E402))
+    );
+}
+
+#[test]
+fn generator_test_if_else_compiles() {
+    match compile(generate(IF_ELSE_QUERIES.to_string()).unwrap()) {
+        Ok(_) => {}
+        Err(e) => {
+            panic!("error: {e:?}");
+        }
+    };
+}
+
+#[test]
+fn generator_test_fallible_collect() {
+    let input = r#"
+        N::User {
+            name: String,
+        }
+
+        QUERY get_user(user_id: ID) =>
+            user <- N<User>(user_id)
+            users <- N<User>
+            IF EXISTS(N<User>(user_id)) {
+                RETURN user
+            }
+            RETURN users
+    "#;
+
+    let generated = generate(input.to_string()).unwrap().to_string();
+    assert!(generated.contains(".n_from_id(&data.user_id).try_collect_to_obj()?;"));
+    assert!(generated.contains(r#".n_from_type("User").try_collect_to::<Vec<_>>()?;"#));
+    assert!(!generated.contains(".collect_to::<Vec<_>>()"));
+    assert!(!generated.contains(".collect_to_obj()"));
+    assert!(generated.contains("txn.commit()?;"));
+    assert!(!generated.contains("unwrap()"));
+}
+
+#[test]
+fn generator_test_variable_length_traversal() {
+    let input = r#"
+        N::User {
+            name: String,
+        }
+
+        E::Follows {
+            From: User,
+            To: User,
+        }
+
+        QUERY reach(user_id: ID) =>
+            friends <- N<User>(user_id)::Out*<Follows>(1..3)
+            followers <- N<User>(user_id)::In*<Follows>(2)::WHERE(_::{name}::EQ("a"))
+            RETURN friends, followers
+    "#;
+
+    let generated = generate(input.to_string()).unwrap().to_string();
+    assert!(generated.contains(r#".out_hops("Follows", 1, 3).try_collect_to::<Vec<_>>()?;"#));
+    assert!(generated.contains(r#".in_hops("Follows", 2, 2)"#));
+
+    let input = r#"
+        N::User {
+            name: String,
+        }
+
+        N::Post {
+            title: String,
+        }
+
+        E::Follows {
+            From: User,
+            To: User,
+        }
+
+        E::Wrote {
+            From: User,
+            To: Post,
+        }
+
+        QUERY not_repeatable(user_id: ID) =>
+            posts <- N<User>(user_id)::Out*<Wrote>(1..2)
+            RETURN posts
+
+        QUERY bad_range(user_id: ID) =>
+            users <- N<User>(user_id)::Out*<Follows>(3..1)
+            RETURN users
+    "#;
+    let mut content = generate_content(input.to_string());
+    content.source = parse_content(&content).unwrap();
+    let (diagnostics, _) = analyze(&content.source);
+    assert!(
+        diagnostics
+            .iter()
+            .any(|d| matches!(d.error_code, ErrorCode::E627))
+    );
+    assert!(
+        diagnostics
+            .iter()
+            .any(|d| matches!(d.error_code, ErrorCode::E632))
+    );
+}
@@ -4,6 +4,7 @@ use std::fmt::Display;
 use crate::helixc::generator::{traversal_steps::Traversal, utils::GeneratedValue};
 
 
+#[derive(Clone)]
 pub struct ReturnValue {
     pub value: ReturnValueExpr,
     pub return_type: ReturnType,
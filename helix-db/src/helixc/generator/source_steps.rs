@@ -14,13 +14,20 @@ pub enum SourceStep {
     AddN(AddN),
     AddE(AddE),
     AddV(AddV),
+    UpsertN(UpsertN),
+    UpsertE(UpsertE),
     NFromID(NFromID),
     NFromIndex(NFromIndex),
+    NFromIndexRange(NFromIndexRange),
+    NFromCompositeIndex(NFromCompositeIndex),
     NFromType(NFromType),
     EFromID(EFromID),
     EFromType(EFromType),
+    EFromIndex(EFromIndex),
+    EFromIndexRange(EFromIndexRange),
     SearchVector(SearchVector),
     SearchBM25(SearchBM25),
+    SearchHybrid(SearchHybrid),
     Anonymous,
     Empty,
 }
@@ -63,6 +70,50 @@ impl Display for AddE {
         )
     }
 }
+#[derive(Clone)]
+pub struct UpsertN {
+    pub label: GenRef<String>,
+    /// The unique field the node to update is looked up by
+    pub key: GenRef<String>,
+    pub properties: Option<Vec<(String, GeneratedValue)>>,
+    /// Schema defaults, only written when the node is added
+    pub defaults: Option<Vec<(String, GeneratedValue)>>,
+    pub secondary_indices: Option<Vec<String>>,
+}
+impl Display for UpsertN {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "upsert_n({}, {}, {}, {}, {})",
+            self.label,
+            self.key,
+            write_properties(&self.properties),
+            write_properties(&self.defaults),
+            write_secondary_indices(&self.secondary_indices)
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct UpsertE {
+    pub label: GenRef<String>,
+    pub properties: Option<Vec<(String, GeneratedValue)>>,
+    pub from: GeneratedValue,
+    pub to: GeneratedValue,
+}
+impl Display for UpsertE {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "upsert_e({}, {}, {}, {})",
+            self.label,
+            write_properties(&self.properties),
+            self.from,
+            self.to
+        )
+    }
+}
+
 #[derive(Clone)]
 pub struct AddV {
     pub vec: VecData,
@@ -138,6 +189,39 @@ impl Display for SearchBM25 {
     }
 }
 
+#[derive(Clone)]
+pub struct SearchHybrid {
+    pub label: GenRef<String>,
+    pub query: GeneratedValue,
+    pub vec: VecData,
+    pub k: GeneratedValue,
+    pub fusion: HybridFusion,
+}
+impl Display for SearchHybrid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "search_hybrid({}, {}, {}, {}, {})?",
+            self.label, self.query, self.vec, self.k, self.fusion
+        )
+    }
+}
+
+#[derive(Clone)]
+pub enum HybridFusion {
+    Rrf(Option<GeneratedValue>),
+    Alpha(GeneratedValue),
+}
+impl Display for HybridFusion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HybridFusion::Rrf(Some(k)) => write!(f, "HybridFusion::Rrf({k} as f32)"),
+            HybridFusion::Rrf(None) => write!(f, "HybridFusion::default()"),
+            HybridFusion::Alpha(alpha) => write!(f, "HybridFusion::Alpha({alpha} as f32)"),
+        }
+    }
+}
+
 impl Display for SourceStep {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -145,13 +229,22 @@ impl Display for SourceStep {
             SourceStep::AddN(add_n) => write!(f, "{add_n}"),
             SourceStep::AddE(add_e) => write!(f, "{add_e}"),
             SourceStep::AddV(add_v) => write!(f, "{add_v}"),
+            SourceStep::UpsertN(upsert_n) => write!(f, "{upsert_n}"),
+            SourceStep::UpsertE(upsert_e) => write!(f, "{upsert_e}"),
             SourceStep::NFromID(n_from_id) => write!(f, "{n_from_id}"),
             SourceStep::NFromIndex(n_from_index) => write!(f, "{n_from_index}"),
+            SourceStep::NFromIndexRange(n_from_index_range) => write!(f, "{n_from_index_range}"),
+            SourceStep::NFromCompositeIndex(n_from_composite_index) => {
+                write!(f, "{n_from_composite_index}")
+            }
             SourceStep::NFromType(n_from_type) => write!(f, "{n_from_type}"),
             SourceStep::EFromID(e_from_id) => write!(f, "{e_from_id}"),
             SourceStep::EFromType(e_from_type) => write!(f, "{e_from_type}"),
+            SourceStep::EFromIndex(e_from_index) => write!(f, "{e_from_index}"),
+            SourceStep::EFromIndexRange(e_from_index_range) => write!(f, "{e_from_index_range}"),
             SourceStep::SearchVector(search_vector) => write!(f, "{search_vector}"),
             SourceStep::SearchBM25(search_bm25) => write!(f, "{search_bm25}"),
+            SourceStep::SearchHybrid(search_hybrid) => write!(f, "{search_hybrid}"),
             SourceStep::Anonymous => write!(f, ""),
             SourceStep::Empty => panic!("Should not be empty"),
         }
@@ -171,15 +264,15 @@ impl Display for SearchVector {
         match &self.pre_filter {
             Some(pre_filter) => write!(
                 f,
-                "search_v::<fn(&HVector, &RoTxn) -> bool, _>({}, {}, {}, Some(&[{}]))",
+                "search_v::<_, _>({}, {}, {}, Some(&[|v: &HVector, txn: &RoTxn| {{ let val = TraversalVal::Vector(v.clone()); {} }}]))",
                 self.vec,
                 self.k,
                 self.label,
                 pre_filter
                     .iter()
-                    .map(|f| format!("|v: &HVector, txn: &RoTxn| {f}"))
+                    .map(|f| f.to_string())
                     .collect::<Vec<_>>()
-                    .join(", ")
+                    .join(" && ")
             ),
             None => write!(
                 f,
@@ -201,7 +294,115 @@ pub struct NFromIndex {
 
 impl Display for NFromIndex {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "n_from_index({}, {}, {})", self.label, self.index, self.key)
+        write!(
+            f,
+            "n_from_index({}, {}, {})?",
+            self.label, self.index, self.key
+        )
+    }
+}
+
+
+#[derive(Clone)]
+pub enum IndexRangeBound {
+    Included(GeneratedValue),
+    Excluded(GeneratedValue),
+    Unbounded,
+}
+
+impl Display for IndexRangeBound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndexRangeBound::Included(key) => write!(f, "Bound::Included({key})"),
+            IndexRangeBound::Excluded(key) => write!(f, "Bound::Excluded({key})"),
+            IndexRangeBound::Unbounded => write!(f, "Bound::Unbounded"),
+        }
     }
 }
 
+#[derive(Clone)]
+pub struct NFromIndexRange {
+    pub index: GenRef<String>,
+    pub start: IndexRangeBound,
+    pub end: IndexRangeBound,
+    pub label: GenRef<String>,
+}
+
+impl Display for NFromIndexRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "n_from_index_range({}, {}, {}, {})?",
+            self.label, self.index, self.start, self.end
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct NFromCompositeIndex {
+    pub index: GenRef<String>,
+    /// Keys of the leading fields, in index field order
+    pub prefix: Vec<GeneratedValue>,
+    pub start: IndexRangeBound,
+    pub end: IndexRangeBound,
+    pub label: GenRef<String>,
+}
+
+impl Display for NFromCompositeIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // keys of different fields have different types so they are passed as values
+        let to_value = |bound: &IndexRangeBound| match bound {
+            IndexRangeBound::Included(key) => format!("Bound::Included(Value::from({key}))"),
+            IndexRangeBound::Excluded(key) => format!("Bound::Excluded(Value::from({key}))"),
+            IndexRangeBound::Unbounded => "Bound::Unbounded".to_string(),
+        };
+        write!(
+            f,
+            "n_from_composite_index({}, {}, &[{}], {}, {})?",
+            self.label,
+            self.index,
+            self.prefix
+                .iter()
+                .map(|key| format!("Value::from({key})"))
+                .collect::<Vec<_>>()
+                .join(", "),
+            to_value(&self.start),
+            to_value(&self.end)
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct EFromIndex {
+    pub index: GenRef<String>,
+    pub key: GeneratedValue,
+    pub label: GenRef<String>,
+}
+
+impl Display for EFromIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "e_from_index({}, {}, {})?",
+            self.label, self.index, self.key
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct EFromIndexRange {
+    pub index: GenRef<String>,
+    pub start: IndexRangeBound,
+    pub end: IndexRangeBound,
+    pub label: GenRef<String>,
+}
+
+impl Display for EFromIndexRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "e_from_index_range({}, {}, {}, {})?",
+            self.label, self.index, self.start, self.end
+        )
+    }
+}
@@ -1,7 +1,9 @@
 use core::fmt;
 use std::fmt::Display;
 
-use crate::helixc::generator::{bool_op::BoExp, traversal_steps::Traversal, utils::GenRef};
+use crate::helixc::generator::{
+    bool_op::BoExp, return_values::ReturnValue, traversal_steps::Traversal, utils::GenRef,
+};
 
 
 
@@ -11,6 +13,7 @@ pub enum Statement {
     Drop(Drop),
     Traversal(Traversal),
     ForEach(ForEach),
+    If(If),
     Literal(GenRef<String>),
     Identifier(GenRef<String>),
     BoExp(BoExp),
@@ -23,6 +26,7 @@ impl Display for Statement {
             Statement::Drop(drop) => write!(f, "{drop}"),
             Statement::Traversal(traversal) => write!(f, "{traversal}"),
             Statement::ForEach(foreach) => write!(f, "{foreach}"),
+            Statement::If(if_stmt) => write!(f, "{if_stmt}"),
             Statement::Literal(literal) => write!(f, "{literal}"),
             Statement::Identifier(identifier) => write!(f, "{identifier}"),
             Statement::BoExp(bo) => write!(f, "{bo}"),
@@ -118,6 +122,59 @@ impl Display for ForLoopInVariable {
         }
     }
 }
+#[derive(Clone)]
+pub struct If {
+    pub condition: Box<Statement>,
+    pub then_block: IfBlock,
+    pub else_block: Option<IfBlock>,
+}
+impl Display for If {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "if {} {}", self.condition, self.then_block)?;
+        match &self.else_block {
+            // an `ELSE IF` is an else block holding a single if
+            Some(IfBlock {
+                statements,
+                return_values: None,
+            }) if matches!(statements.as_slice(), [Statement::If(_)]) => {
+                write!(f, " else {}", statements[0])
+            }
+            Some(else_block) => write!(f, " else {else_block}"),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct IfBlock {
+    pub statements: Vec<Statement>,
+    /// Set when the block ends the query early, the values are returned from the handler
+    pub return_values: Option<Vec<ReturnValue>>,
+}
+impl Display for IfBlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{{")?;
+        for statement in &self.statements {
+            writeln!(f, "    {statement};")?;
+        }
+        if let Some(return_values) = &self.return_values {
+            writeln!(
+                f,
+                "let mut return_vals: HashMap<String, ReturnValue> = HashMap::new();"
+            )?;
+            for return_value in return_values {
+                writeln!(f, "    {return_value}")?;
+            }
+            writeln!(f, "    txn.commit()?;")?;
+            writeln!(
+                f,
+                "    return Ok(input.request.out_fmt.create_response(&return_vals));"
+            )?;
+        }
+        write!(f, "}}")
+    }
+}
+
 #[derive(Clone)]
 pub struct Drop {
     pub expression: Traversal,
@@ -56,8 +56,9 @@ pub enum ShouldCollect {
 impl Display for ShouldCollect {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            ShouldCollect::ToVec => write!(f, ".collect_to::<Vec<_>>()"),
-            ShouldCollect::ToVal => write!(f, ".collect_to_obj()"),
+            // errors of any item fail the query instead of being dropped from its results
+            ShouldCollect::ToVec => write!(f, ".try_collect_to::<Vec<_>>()?"),
+            ShouldCollect::ToVal => write!(f, ".try_collect_to_obj()?"),
             ShouldCollect::Try => write!(f, "?"),
             ShouldCollect::No => write!(f, ""),
         }
@@ -122,7 +123,7 @@ impl Display for Traversal {
                 for step in &self.steps {
                     write!(f, "\n{step}")?;
                 }
-                write!(f, "\n    .collect_to::<Vec<_>>();")?;
+                write!(f, "\n    .try_collect_to::<Vec<_>>()?;")?;
                 write!(
                     f,
                     "G::new_mut_from(Arc::clone(&db), &mut txn, update_tr)", // TODO: make
@@ -130,7 +131,7 @@ impl Display for Traversal {
                                                                              // scrappy
                 )?;
                 write!(f, "\n    .update({})", write_properties(properties))?;
-                write!(f, "\n    .collect_to_obj()")?;
+                write!(f, "\n    .try_collect_to_obj()?")?;
                 write!(f, "}}")?;
             }
         }
@@ -154,6 +155,8 @@ pub enum Step {
     In(In),
     OutE(OutE),
     InE(InE),
+    OutHops(Hops),
+    InHops(Hops),
     FromN,
     ToN,
     FromV,
@@ -165,6 +168,7 @@ pub enum Step {
     Range(Range),
     OrderBy(OrderBy),
     Dedup,
+    AggregateBy(AggregateBy),
 
     // bool ops
     BoolOp(BoolOp),
@@ -199,9 +203,12 @@ impl Display for Step {
             Step::In(in_) => write!(f, "{in_}"),
             Step::OutE(out_e) => write!(f, "{out_e}"),
             Step::InE(in_e) => write!(f, "{in_e}"),
+            Step::OutHops(hops) => write!(f, "out_hops({hops})"),
+            Step::InHops(hops) => write!(f, "in_hops({hops})"),
             Step::Where(where_) => write!(f, "{where_}"),
             Step::Range(range) => write!(f, "{range}"),
             Step::OrderBy(order_by) => write!(f, "{order_by}"),
+            Step::AggregateBy(aggregate_by) => write!(f, "{aggregate_by}"),
             Step::BoolOp(bool_op) => write!(f, "{bool_op}"),
             Step::Remapping(remapping) => write!(f, "{remapping}"),
             Step::ShortestPath(shortest_path) => write!(f, "{shortest_path}"),
@@ -223,9 +230,12 @@ impl Debug for Step {
             Step::In(_) => write!(f, "In"),
             Step::OutE(_) => write!(f, "OutE"),
             Step::InE(_) => write!(f, "InE"),
+            Step::OutHops(_) => write!(f, "OutHops"),
+            Step::InHops(_) => write!(f, "InHops"),
             Step::Where(_) => write!(f, "Where"),
             Step::Range(_) => write!(f, "Range"),
             Step::OrderBy(_) => write!(f, "OrderBy"),
+            Step::AggregateBy(_) => write!(f, "AggregateBy"),
             Step::BoolOp(_) => write!(f, "Bool"),
             Step::Remapping(_) => write!(f, "Remapping"),
             Step::ShortestPath(_) => write!(f, "ShortestPath"),
@@ -276,6 +286,19 @@ impl Display for InE {
     }
 }
 
+/// The arguments of `out_hops` and `in_hops`, the depths are inclusive
+#[derive(Clone)]
+pub struct Hops {
+    pub label: GenRef<String>,
+    pub min_depth: usize,
+    pub max_depth: usize,
+}
+impl Display for Hops {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}, {}, {}", self.label, self.min_depth, self.max_depth)
+    }
+}
+
 #[derive(Clone)]
 pub enum Where {
     Ref(WhereRef),
@@ -345,6 +368,51 @@ impl Display for OrderBy {
     }
 }
 
+#[derive(Clone)]
+pub enum Aggregation {
+    Count(Option<GenRef<String>>),
+    Sum(GenRef<String>),
+    Avg(GenRef<String>),
+    Min(GenRef<String>),
+    Max(GenRef<String>),
+}
+impl Display for Aggregation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Aggregation::Count(None) => write!(f, "Aggregation::Count(None)"),
+            Aggregation::Count(Some(property)) => {
+                write!(f, "Aggregation::Count(Some({property}))")
+            }
+            Aggregation::Sum(property) => write!(f, "Aggregation::Sum({property})"),
+            Aggregation::Avg(property) => write!(f, "Aggregation::Avg({property})"),
+            Aggregation::Min(property) => write!(f, "Aggregation::Min({property})"),
+            Aggregation::Max(property) => write!(f, "Aggregation::Max({property})"),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AggregateBy {
+    pub group_by: Option<GenRef<String>>,
+    pub aggregations: Vec<Aggregation>,
+}
+impl Display for AggregateBy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "aggregate_by({}, &[{}])",
+            self.group_by
+                .clone()
+                .map_or("None".to_string(), |property| format!("Some({property})")),
+            self.aggregations
+                .iter()
+                .map(|aggregation| aggregation.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+
 #[derive(Clone)]
 pub struct ShortestPath {
     pub label: Option<GenRef<String>>,
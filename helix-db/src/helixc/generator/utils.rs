@@ -1,6 +1,6 @@
 use std::fmt::{self, Debug, Display};
 
-use crate::helixc::parser::helix_parser::IdType;
+use crate::{helixc::parser::helix_parser::IdType, protocol::date::Date};
 
 #[derive(Clone)]
 pub enum GenRef<T>
@@ -174,6 +174,15 @@ pub fn write_secondary_indices(secondary_indices: &Option<Vec<String>>) -> Strin
     }
 }
 
+/// Writes a date literal as a `DateTime<Utc>`, so it is stored and indexed as a `Value::Date`
+/// rather than as its string.
+pub fn write_date(date: &Date) -> String {
+    format!(
+        "\"{}\".parse::<DateTime<Utc>>().unwrap()",
+        date.to_rfc3339()
+    )
+}
+
 #[derive(Clone)]
 pub enum GeneratedValue {
     // needed?
@@ -344,26 +353,35 @@ use heed3::RoTxn;
 use helix_macros::{handler, tool_call, mcp_handler, migration};
 use helix_db::{
     helix_engine::{
+        bm25::{analyzer::{AnalyzerConfig, AnalyzerKind}, bm25::HybridFusion},
         graph_core::{
-            config::{Config, GraphConfig, VectorConfig},
+            config::{BM25IndexConfig, Config, GraphConfig, VectorConfig, VectorTypeConfig},
             ops::{
-                bm25::search_bm25::SearchBM25Adapter,
+                bm25::{hybrid_search_bm25::SearchHybridAdapter, search_bm25::SearchBM25Adapter},
                 g::G,
-                in_::{in_::InAdapter, in_e::InEdgesAdapter, to_n::ToNAdapter, to_v::ToVAdapter},
+                in_::{
+                    in_::InAdapter, in_e::InEdgesAdapter, in_hops::InHopsAdapter, to_n::ToNAdapter,
+                    to_v::ToVAdapter,
+                },
                 out::{
                     from_n::FromNAdapter, from_v::FromVAdapter, out::OutAdapter, out_e::OutEdgesAdapter,
+                    out_hops::OutHopsAdapter,
                 },
                 source::{
                     add_e::{AddEAdapter, EdgeType},
                     add_n::AddNAdapter,
                     e_from_id::EFromIdAdapter,
+                    e_from_index::EFromIndexAdapter,
                     e_from_type::EFromTypeAdapter,
                     n_from_id::NFromIdAdapter,
-                    n_from_index::NFromIndexAdapter,
+                    n_from_index::{NFromCompositeIndexAdapter, NFromIndexAdapter},
                     n_from_type::NFromTypeAdapter,
+                    upsert_e::UpsertEAdapter,
+                    upsert_n::UpsertNAdapter,
                 },
                 tr_val::{Traversable, TraversalVal},
                 util::{
+                    aggregate::{AggregateAdapter, Aggregation},
                     dedup::DedupAdapter, drop::Drop, exist::Exist, filter_mut::FilterMut,
                     filter_ref::FilterRefAdapter, map::MapAdapter, paths::ShortestPathAdapter,
                     props::PropsAdapter, range::RangeAdapter, update::UpdateAdapter, order::OrderByAdapter,
@@ -375,7 +393,7 @@ use helix_db::{
             }
         },
         types::GraphError,
-        vector_core::vector::HVector,
+        vector_core::{vector::{HVector, VectorPrecision}, vector_distance::DistanceMetric},
     },
     helix_gateway::{
         embedding_providers::embedding_providers::{EmbeddingModel, get_embedding_model},
@@ -402,6 +420,7 @@ use helix_db::{
 };
 use sonic_rs::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
+use std::ops::Bound;
 use std::sync::Arc;
 use std::time::Instant;
 use chrono::{DateTime, Utc};
@@ -2,7 +2,14 @@ use super::{
     location::{HasLoc, Loc},
     parser_methods::ParserError,
 };
-use crate::protocol::value::Value;
+use crate::{
+    helix_engine::{
+        storage_core::index_key::composite_index_name,
+        bm25::analyzer::AnalyzerKind,
+        vector_core::{vector::VectorPrecision, vector_distance::DistanceMetric},
+    },
+    protocol::value::Value,
+};
 use chrono::{DateTime, NaiveDate, Utc};
 use itertools::Itertools;
 use pest::{
@@ -91,13 +98,39 @@ pub struct Schema {
 pub struct NodeSchema {
     pub name: (Loc, String),
     pub fields: Vec<Field>,
+    pub composite_indices: Vec<CompositeIndex>,
     pub loc: Loc,
 }
 
+/// A secondary index over several fields, e.g. `INDEX(tenant_id, external_id)`
+#[derive(Debug, Clone)]
+pub struct CompositeIndex {
+    pub fields: Vec<(Loc, String)>,
+    pub loc: Loc,
+}
+
+impl CompositeIndex {
+    pub fn name(&self) -> String {
+        composite_index_name(
+            &self
+                .fields
+                .iter()
+                .map(|(_, field)| field.as_str())
+                .collect::<Vec<_>>(),
+        )
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct VectorSchema {
     pub name: String,
     pub fields: Vec<Field>,
+    /// Length every vector of this type must have, if declared
+    pub dimensions: Option<usize>,
+    pub metric: DistanceMetric,
+    pub precision: VectorPrecision,
+    /// Whether compact vectors keep a full precision copy to rerank search results with
+    pub rerank: bool,
     pub loc: Loc,
 }
 
@@ -172,6 +205,10 @@ pub struct ValueCast {
 #[derive(Debug, Clone)]
 pub struct Field {
     pub prefix: FieldPrefix,
+    /// Full-text indexed into the BM25 index of the item's label
+    pub searchable: bool,
+    /// Analyzer chosen with `SEARCHABLE(English)`, the standard analyzer when not set
+    pub analyzer: Option<AnalyzerKind>,
     pub defaults: Option<DefaultValue>,
     pub name: String,
     pub field_type: FieldType,
@@ -181,6 +218,10 @@ impl Field {
     pub fn is_indexed(&self) -> bool {
         self.prefix.is_indexed()
     }
+
+    pub fn is_unique(&self) -> bool {
+        self.prefix.is_unique()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -205,12 +246,18 @@ pub enum DefaultValue {
 #[derive(Debug, Clone)]
 pub enum FieldPrefix {
     Index,
+    Unique,
     Optional,
     Empty,
 }
 impl FieldPrefix {
+    /// Unique fields are always backed by a secondary index
     pub fn is_indexed(&self) -> bool {
-        matches!(self, FieldPrefix::Index)
+        matches!(self, FieldPrefix::Index | FieldPrefix::Unique)
+    }
+
+    pub fn is_unique(&self) -> bool {
+        matches!(self, FieldPrefix::Unique)
     }
 }
 
@@ -432,6 +479,7 @@ pub enum StatementType {
     Expression(Expression),
     Drop(Expression),
     ForLoop(ForLoop),
+    If(IfStatement),
 }
 
 #[derive(Debug, Clone)]
@@ -449,6 +497,23 @@ pub struct ForLoop {
     pub loc: Loc,
 }
 
+#[derive(Debug, Clone)]
+pub struct IfStatement {
+    pub condition: Expression,
+    pub then_block: IfBlock,
+    /// `ELSE IF` is parsed as an `ELSE` block holding a single `IF`
+    pub else_block: Option<IfBlock>,
+    pub loc: Loc,
+}
+
+#[derive(Debug, Clone)]
+pub struct IfBlock {
+    pub statements: Vec<Statement>,
+    /// Values of a `RETURN` that ends the query when the block runs
+    pub return_values: Option<Vec<Expression>>,
+    pub loc: Loc,
+}
+
 #[derive(Debug, Clone)]
 pub enum ForLoopVars {
     Identifier {
@@ -485,10 +550,15 @@ pub enum ExpressionType {
     AddVector(AddVector),
     AddNode(AddNode),
     AddEdge(AddEdge),
+    /// `UpsertN`, an `AddN` that updates the node holding the same unique key instead
+    UpsertNode(AddNode),
+    /// `UpsertE`, an `AddE` that updates the edge between the same nodes instead
+    UpsertEdge(AddEdge),
     And(Vec<Expression>),
     Or(Vec<Expression>),
     SearchVector(SearchVector),
     BM25Search(BM25Search),
+    SearchHybrid(SearchHybrid),
     Empty,
 }
 impl Debug for ExpressionType {
@@ -505,10 +575,13 @@ impl Debug for ExpressionType {
             ExpressionType::AddVector(av) => write!(f, "AddVector({av:?})"),
             ExpressionType::AddNode(an) => write!(f, "AddNode({an:?})"),
             ExpressionType::AddEdge(ae) => write!(f, "AddEdge({ae:?})"),
+            ExpressionType::UpsertNode(un) => write!(f, "UpsertNode({un:?})"),
+            ExpressionType::UpsertEdge(ue) => write!(f, "UpsertEdge({ue:?})"),
             ExpressionType::And(and) => write!(f, "And({and:?})"),
             ExpressionType::Or(or) => write!(f, "Or({or:?})"),
             ExpressionType::SearchVector(sv) => write!(f, "SearchVector({sv:?})"),
             ExpressionType::BM25Search(bm25) => write!(f, "BM25Search({bm25:?})"),
+            ExpressionType::SearchHybrid(sh) => write!(f, "SearchHybrid({sh:?})"),
             ExpressionType::Empty => write!(f, "Empty"),
         }
     }
@@ -527,10 +600,13 @@ impl Display for ExpressionType {
             ExpressionType::AddVector(av) => write!(f, "AddVector({av:?})"),
             ExpressionType::AddNode(an) => write!(f, "AddNode({an:?})"),
             ExpressionType::AddEdge(ae) => write!(f, "AddEdge({ae:?})"),
+            ExpressionType::UpsertNode(un) => write!(f, "UpsertNode({un:?})"),
+            ExpressionType::UpsertEdge(ue) => write!(f, "UpsertEdge({ue:?})"),
             ExpressionType::And(and) => write!(f, "And({and:?})"),
             ExpressionType::Or(or) => write!(f, "Or({or:?})"),
             ExpressionType::SearchVector(sv) => write!(f, "SearchVector({sv:?})"),
             ExpressionType::BM25Search(bm25) => write!(f, "BM25Search({bm25:?})"),
+            ExpressionType::SearchHybrid(sh) => write!(f, "SearchHybrid({sh:?})"),
             ExpressionType::Empty => write!(f, "Empty"),
         }
     }
@@ -598,6 +674,8 @@ pub enum StepType {
     Closure(Closure),
     Range((Expression, Expression)),
     OrderBy(OrderBy),
+    GroupBy(GroupBy),
+    AggregateBy(AggregateBy),
     AddEdge(AddEdge),
 }
 impl PartialEq<StepType> for StepType {
@@ -618,6 +696,8 @@ impl PartialEq<StepType> for StepType {
                 | (&StepType::Closure(_), &StepType::Closure(_))
                 | (&StepType::Range(_), &StepType::Range(_))
                 | (&StepType::OrderBy(_), &StepType::OrderBy(_))
+                | (&StepType::GroupBy(_), &StepType::GroupBy(_))
+                | (&StepType::AggregateBy(_), &StepType::AggregateBy(_))
                 | (&StepType::AddEdge(_), &StepType::AddEdge(_))
         )
     }
@@ -664,6 +744,9 @@ pub enum GraphStepType {
     OutE(String),
     InE(String),
 
+    OutRepeat(RepeatStep),
+    InRepeat(RepeatStep),
+
     ShortestPath(ShortestPath),
     SearchVector(SearchVector),
 }
@@ -674,12 +757,23 @@ impl GraphStep {
             GraphStepType::In(s) => Some(s.clone()),
             GraphStepType::OutE(s) => Some(s.clone()),
             GraphStepType::InE(s) => Some(s.clone()),
+            GraphStepType::OutRepeat(s) | GraphStepType::InRepeat(s) => Some(s.edge_type.clone()),
             GraphStepType::SearchVector(s) => Some(s.vector_type.clone().unwrap()),
             _ => None,
         }
     }
 }
 
+/// `Out*<Edge>(min..max)` or `In*<Edge>(min..max)`, the depths are inclusive and
+/// `Out*<Edge>(n)` is exactly `n` hops
+#[derive(Debug, Clone)]
+pub struct RepeatStep {
+    pub loc: Loc,
+    pub edge_type: String,
+    pub min_depth: usize,
+    pub max_depth: usize,
+}
+
 #[derive(Debug, Clone)]
 pub struct ShortestPath {
     pub loc: Loc,
@@ -742,6 +836,22 @@ pub struct BM25Search {
     pub k: Option<EvaluatesToNumber>,
 }
 
+#[derive(Debug, Clone)]
+pub struct SearchHybrid {
+    pub loc: Loc,
+    pub type_arg: Option<String>,
+    pub query: Option<ValueType>,
+    pub data: Option<VectorData>,
+    pub k: Option<EvaluatesToNumber>,
+    pub fusion: Option<HybridFusion>,
+}
+
+#[derive(Debug, Clone)]
+pub enum HybridFusion {
+    Rrf(Option<EvaluatesToNumber>),
+    Alpha(EvaluatesToNumber),
+}
+
 #[derive(Debug, Clone)]
 pub struct EvaluatesToNumber {
     pub loc: Loc,
@@ -810,6 +920,16 @@ pub enum IdType {
         value: Box<ValueType>,
         loc: Loc,
     },
+    ByIndexRange {
+        index: Box<IdType>,
+        start: Option<IndexBound>,
+        end: Option<IndexBound>,
+        loc: Loc,
+    },
+    ByCompositeIndex {
+        fields: Vec<(IdType, IndexLookup)>,
+        loc: Loc,
+    },
 }
 impl Display for IdType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -821,10 +941,38 @@ impl Display for IdType {
                 value: _,
                 loc: _,
             } => write!(f, "{index}"),
+            IdType::ByIndexRange { index, .. } => write!(f, "{index}"),
+            IdType::ByCompositeIndex { fields, .. } => write!(
+                f,
+                "{}",
+                composite_index_name(
+                    &fields
+                        .iter()
+                        .map(|(field, _)| field.to_string())
+                        .collect::<Vec<_>>()
+                )
+            ),
         }
     }
 }
 
+/// A bound of a range scan over a secondary index, e.g. `GTE(18)` in `N<User>({age: GTE(18)})`
+#[derive(Debug, Clone)]
+pub enum IndexBound {
+    Included(ValueType),
+    Excluded(ValueType),
+}
+
+/// How one field of a composite index is matched, e.g. `user_id: id` or `created_at: GTE(since)`
+#[derive(Debug, Clone)]
+pub enum IndexLookup {
+    Eq(ValueType),
+    Range {
+        start: Option<IndexBound>,
+        end: Option<IndexBound>,
+    },
+}
+
 #[derive(Debug, Clone)]
 pub enum ValueType {
     Literal {
@@ -900,6 +1048,8 @@ impl From<IdType> for String {
                 value: _,
                 loc: _,
             } => String::from(*index),
+            IdType::ByIndexRange { index, .. } => String::from(*index),
+            by_composite_index @ IdType::ByCompositeIndex { .. } => by_composite_index.to_string(),
         }
     }
 }
@@ -934,6 +1084,45 @@ pub struct Exclude {
     pub loc: Loc,
 }
 
+#[derive(Debug, Clone)]
+pub struct GroupBy {
+    pub loc: Loc,
+    pub property: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AggregateFunction {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+impl Display for AggregateFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AggregateFunction::Count => write!(f, "COUNT"),
+            AggregateFunction::Sum => write!(f, "SUM"),
+            AggregateFunction::Avg => write!(f, "AVG"),
+            AggregateFunction::Min => write!(f, "MIN"),
+            AggregateFunction::Max => write!(f, "MAX"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Aggregate {
+    pub loc: Loc,
+    pub function: AggregateFunction,
+    pub property: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AggregateBy {
+    pub loc: Loc,
+    pub aggregates: Vec<Aggregate>,
+}
+
 #[derive(Debug, Clone)]
 pub struct Closure {
     pub identifier: String,
@@ -1099,10 +1288,24 @@ impl HelixParser {
     ) -> Result<NodeSchema, ParserError> {
         let mut pairs = pair.clone().into_inner();
         let name = pairs.next().unwrap().as_str().to_string();
-        let fields = self.parse_node_body(pairs.next().unwrap())?;
+        let body = pairs.next().unwrap();
+        let fields = self.parse_node_body(body.clone())?;
+        let composite_indices = body
+            .into_inner()
+            .filter(|p| p.as_rule() == Rule::composite_index)
+            .map(|p| CompositeIndex {
+                fields: p
+                    .clone()
+                    .into_inner()
+                    .map(|field| (field.loc(), field.as_str().to_string()))
+                    .collect(),
+                loc: p.loc(),
+            })
+            .collect();
         Ok(NodeSchema {
             name: (pair.loc(), name),
             fields,
+            composite_indices,
             loc: pair.loc_with_filepath(filepath),
         })
     }
@@ -1114,10 +1317,69 @@ impl HelixParser {
     ) -> Result<VectorSchema, ParserError> {
         let mut pairs = pair.clone().into_inner();
         let name = pairs.next().unwrap().as_str().to_string();
-        let fields = self.parse_node_body(pairs.next().unwrap())?;
+        let mut dimensions = None;
+        let mut metric = None;
+        let mut precision = None;
+        let mut rerank = None;
+        let mut fields = Vec::new();
+        for p in pairs {
+            match p.as_rule() {
+                Rule::vector_options => {
+                    for option in p.into_inner() {
+                        let option = option.into_inner().next().unwrap();
+                        let rule = option.as_rule();
+                        let value = option.into_inner().next().unwrap();
+                        let duplicate = match rule {
+                            Rule::dimensions_option => dimensions
+                                .replace(value.as_str().parse::<usize>().map_err(|_| {
+                                    ParserError::from(format!(
+                                        "invalid dimensions `{}` for vector type `{name}`",
+                                        value.as_str()
+                                    ))
+                                })?)
+                                .is_some(),
+                            Rule::metric_option => metric
+                                .replace(match value.as_str() {
+                                    "DotProduct" => DistanceMetric::DotProduct,
+                                    "Euclidean" | "L2" => DistanceMetric::Euclidean,
+                                    _ => DistanceMetric::Cosine,
+                                })
+                                .is_some(),
+                            Rule::precision_option => precision
+                                .replace(match value.as_str() {
+                                    "F32" => VectorPrecision::F32,
+                                    "Int8" | "I8" => VectorPrecision::Int8,
+                                    _ => VectorPrecision::F64,
+                                })
+                                .is_some(),
+                            Rule::rerank_option => {
+                                rerank.replace(value.as_str() == "true").is_some()
+                            }
+                            _ => unreachable!(),
+                        };
+                        if duplicate {
+                            return Err(ParserError::from(format!(
+                                "option declared twice for vector type `{name}`"
+                            )));
+                        }
+                    }
+                }
+                Rule::vector_body => fields = self.parse_node_body(p)?,
+                _ => return Err(ParserError::from("Unexpected rule in vector definition")),
+            }
+        }
+        if dimensions == Some(0) {
+            return Err(ParserError::from(format!(
+                "vector type `{name}` must have at least one dimension"
+            )));
+        }
         Ok(VectorSchema {
             name,
             fields,
+            dimensions,
+            metric: metric.unwrap_or_default(),
+            precision: precision.unwrap_or_default(),
+            rerank: rerank.unwrap_or_default(),
             loc: pair.loc_with_filepath(filepath),
         })
     }
@@ -1486,18 +1748,39 @@ impl HelixParser {
 
     fn parse_field_def(&self, pair: Pair<Rule>) -> Result<Field, ParserError> {
         let mut pairs = pair.clone().into_inner();
-        // structure is index? ~ identifier ~ ":" ~ param_type
+        // structure is (unique ~ index? | index)? ~ searchable? ~ identifier ~ ":" ~ param_type
         let prefix: FieldPrefix = match pairs.clone().next().unwrap().as_rule() {
             Rule::index => {
                 pairs.next().unwrap();
                 FieldPrefix::Index
             }
+            Rule::unique => {
+                pairs.next().unwrap();
+                if pairs.clone().next().unwrap().as_rule() == Rule::index {
+                    pairs.next().unwrap();
+                }
+                FieldPrefix::Unique
+            }
             // Rule::optional => {
             //     pairs.next().unwrap();
             //     FieldPrefix::Optional
             // }
             _ => FieldPrefix::Empty,
         };
+        let searchable = pairs.clone().next().unwrap().as_rule() == Rule::searchable;
+        let analyzer = match searchable {
+            true => pairs
+                .next()
+                .unwrap()
+                .into_inner()
+                .next()
+                .map(|kind| match kind.as_str() {
+                    "English" => AnalyzerKind::English,
+                    "Simple" => AnalyzerKind::Simple,
+                    _ => AnalyzerKind::Standard,
+                }),
+            false => None,
+        };
         let name = pairs.next().unwrap().as_str().to_string();
 
         let field_type = self.parse_field_type(
@@ -1509,6 +1792,8 @@ impl HelixParser {
 
         Ok(Field {
             prefix,
+            searchable,
+            analyzer,
             defaults,
             name,
             field_type,
@@ -1676,6 +1961,10 @@ impl HelixParser {
                     loc: p.loc(),
                     statement: StatementType::ForLoop(self.parse_for_loop(p)?),
                 }),
+                Rule::if_stmt => Ok(Statement {
+                    loc: p.loc(),
+                    statement: StatementType::If(self.parse_if_statement(p)?),
+                }),
                 _ => Err(ParserError::from(format!(
                     "Unexpected statement type in query body: {:?}",
                     p.as_rule()
@@ -1783,6 +2072,58 @@ impl HelixParser {
         })
     }
 
+    fn parse_if_statement(&self, pair: Pair<Rule>) -> Result<IfStatement, ParserError> {
+        let mut pairs = pair.clone().into_inner();
+        let condition = self.parse_boolean_expression(
+            pairs
+                .next()
+                .ok_or_else(|| ParserError::from("Missing IF condition"))?,
+        )?;
+        let then_block = self.parse_if_block(
+            pairs
+                .next()
+                .ok_or_else(|| ParserError::from("Missing IF block"))?,
+        )?;
+        let else_block = match pairs.next() {
+            Some(p) if p.as_rule() == Rule::if_stmt => Some(IfBlock {
+                loc: p.loc(),
+                statements: vec![Statement {
+                    loc: p.loc(),
+                    statement: StatementType::If(self.parse_if_statement(p)?),
+                }],
+                return_values: None,
+            }),
+            Some(p) => Some(self.parse_if_block(p)?),
+            None => None,
+        };
+
+        Ok(IfStatement {
+            condition,
+            then_block,
+            else_block,
+            loc: pair.loc(),
+        })
+    }
+
+    fn parse_if_block(&self, pair: Pair<Rule>) -> Result<IfBlock, ParserError> {
+        let mut pairs = pair.clone().into_inner();
+        let statements = self.parse_query_body(
+            pairs
+                .next()
+                .ok_or_else(|| ParserError::from("Missing IF block body"))?,
+        )?;
+        let return_values = pairs
+            .next()
+            .map(|p| self.parse_return_statement(p))
+            .transpose()?;
+
+        Ok(IfBlock {
+            statements,
+            return_values,
+            loc: pair.loc(),
+        })
+    }
+
     fn parse_batch_add_vector(&self, pair: Pair<Rule>) -> Result<BatchAddVector, ParserError> {
         let mut vector_type = None;
         let mut vec_identifier = None;
@@ -1827,59 +2168,9 @@ impl HelixParser {
                 Rule::identifier_upper => {
                     vector_type = Some(p.as_str().to_string());
                 }
-                Rule::vector_data => match p.clone().into_inner().next() {
-                    Some(vector_data) => match vector_data.as_rule() {
-                        Rule::identifier => {
-                            data = Some(VectorData::Identifier(p.as_str().to_string()));
-                        }
-                        Rule::vec_literal => {
-                            data = Some(VectorData::Vector(self.parse_vec_literal(p)?));
-                        }
-                        Rule::embed_method => {
-                            data = Some(VectorData::Embed(Embed {
-                                loc: vector_data.loc(),
-                                value: match vector_data.clone().into_inner().next() {
-                                    Some(inner) => match inner.as_rule() {
-                                        Rule::identifier => EvaluatesToString::Identifier(
-                                            inner.as_str().to_string(),
-                                        ),
-                                        Rule::string_literal => EvaluatesToString::StringLiteral(
-                                            inner.as_str().to_string(),
-                                        ),
-                                        _ => {
-                                            return Err(ParserError::from(format!(
-                                                "Unexpected rule in AddV: {:?} => {:?}",
-                                                inner.as_rule(),
-                                                inner,
-                                            )));
-                                        }
-                                    },
-                                    None => {
-                                        return Err(ParserError::from(format!(
-                                            "Unexpected rule in AddV: {:?} => {:?}",
-                                            p.as_rule(),
-                                            p,
-                                        )));
-                                    }
-                                },
-                            }));
-                        }
-                        _ => {
-                            return Err(ParserError::from(format!(
-                                "Unexpected rule in AddV: {:?} => {:?}",
-                                vector_data.as_rule(),
-                                vector_data,
-                            )));
-                        }
-                    },
-                    None => {
-                        return Err(ParserError::from(format!(
-                            "Unexpected rule in AddV: {:?} => {:?}",
-                            p.as_rule(),
-                            p,
-                        )));
-                    }
-                },
+                Rule::vector_data => {
+                    data = Some(self.parse_vector_data(p)?);
+                }
                 Rule::create_field => {
                     fields = Some(self.parse_property_assignments(p)?);
                 }
@@ -1911,59 +2202,9 @@ impl HelixParser {
                 Rule::identifier_upper => {
                     vector_type = Some(p.as_str().to_string());
                 }
-                Rule::vector_data => match p.clone().into_inner().next() {
-                    Some(vector_data) => match vector_data.as_rule() {
-                        Rule::identifier => {
-                            data = Some(VectorData::Identifier(p.as_str().to_string()));
-                        }
-                        Rule::vec_literal => {
-                            data = Some(VectorData::Vector(self.parse_vec_literal(p)?));
-                        }
-                        Rule::embed_method => {
-                            data = Some(VectorData::Embed(Embed {
-                                loc: vector_data.loc(),
-                                value: match vector_data.clone().into_inner().next() {
-                                    Some(inner) => match inner.as_rule() {
-                                        Rule::identifier => EvaluatesToString::Identifier(
-                                            inner.as_str().to_string(),
-                                        ),
-                                        Rule::string_literal => EvaluatesToString::StringLiteral(
-                                            inner.as_str().to_string(),
-                                        ),
-                                        _ => {
-                                            return Err(ParserError::from(format!(
-                                                "Unexpected rule in SearchV: {:?} => {:?}",
-                                                inner.as_rule(),
-                                                inner,
-                                            )));
-                                        }
-                                    },
-                                    None => {
-                                        return Err(ParserError::from(format!(
-                                            "Unexpected rule in SearchV: {:?} => {:?}",
-                                            p.as_rule(),
-                                            p,
-                                        )));
-                                    }
-                                },
-                            }));
-                        }
-                        _ => {
-                            return Err(ParserError::from(format!(
-                                "Unexpected rule in SearchV: {:?} => {:?}",
-                                vector_data.as_rule(),
-                                vector_data,
-                            )));
-                        }
-                    },
-                    None => {
-                        return Err(ParserError::from(format!(
-                            "Unexpected rule in SearchV: {:?} => {:?}",
-                            p.as_rule(),
-                            p,
-                        )));
-                    }
-                },
+                Rule::vector_data => {
+                    data = Some(self.parse_vector_data(p)?);
+                }
                 Rule::integer => {
                     k = Some(EvaluatesToNumber {
                         loc: p.loc(),
@@ -2003,6 +2244,137 @@ impl HelixParser {
         })
     }
 
+    fn parse_vector_data(&self, p: Pair<Rule>) -> Result<VectorData, ParserError> {
+        match p.clone().into_inner().next() {
+            Some(vector_data) => match vector_data.as_rule() {
+                Rule::identifier => Ok(VectorData::Identifier(p.as_str().to_string())),
+                Rule::vec_literal => Ok(VectorData::Vector(self.parse_vec_literal(vector_data)?)),
+                Rule::embed_method => Ok(VectorData::Embed(Embed {
+                    loc: vector_data.loc(),
+                    value: match vector_data.clone().into_inner().next() {
+                        Some(inner) => match inner.as_rule() {
+                            Rule::identifier => {
+                                EvaluatesToString::Identifier(inner.as_str().to_string())
+                            }
+                            Rule::string_literal => {
+                                EvaluatesToString::StringLiteral(inner.as_str().to_string())
+                            }
+                            _ => {
+                                return Err(ParserError::from(format!(
+                                    "Unexpected rule in vector data: {:?} => {:?}",
+                                    inner.as_rule(),
+                                    inner,
+                                )));
+                            }
+                        },
+                        None => {
+                            return Err(ParserError::from(format!(
+                                "Unexpected rule in vector data: {:?} => {:?}",
+                                p.as_rule(),
+                                p,
+                            )));
+                        }
+                    },
+                })),
+                _ => Err(ParserError::from(format!(
+                    "Unexpected rule in vector data: {:?} => {:?}",
+                    vector_data.as_rule(),
+                    vector_data,
+                ))),
+            },
+            None => Err(ParserError::from(format!(
+                "Unexpected rule in vector data: {:?} => {:?}",
+                p.as_rule(),
+                p,
+            ))),
+        }
+    }
+
+    fn parse_search_hybrid(&self, pair: Pair<Rule>) -> Result<SearchHybrid, ParserError> {
+        let mut type_arg = None;
+        let mut query = None;
+        let mut data = None;
+        let mut k = None;
+        let mut fusion = None;
+        for p in pair.clone().into_inner() {
+            match p.as_rule() {
+                Rule::identifier_upper => {
+                    type_arg = Some(p.as_str().to_string());
+                }
+                // the query comes before the vector and k after it
+                Rule::string_literal if data.is_none() => {
+                    query = Some(ValueType::Literal {
+                        value: Value::String(p.as_str().to_string()),
+                        loc: p.loc(),
+                    });
+                }
+                Rule::identifier if data.is_none() => {
+                    query = Some(ValueType::Identifier {
+                        value: p.as_str().to_string(),
+                        loc: p.loc(),
+                    });
+                }
+                Rule::vector_data => {
+                    data = Some(self.parse_vector_data(p)?);
+                }
+                Rule::integer | Rule::identifier => {
+                    k = Some(self.parse_evaluates_to_number(p)?);
+                }
+                Rule::hybrid_fusion => {
+                    let inner = p.into_inner().next().unwrap();
+                    fusion = Some(match inner.as_rule() {
+                        Rule::rrf_fusion => HybridFusion::Rrf(
+                            inner
+                                .into_inner()
+                                .next()
+                                .map(|k| self.parse_evaluates_to_number(k))
+                                .transpose()?,
+                        ),
+                        _ => HybridFusion::Alpha(
+                            self.parse_evaluates_to_number(inner.into_inner().next().unwrap())?,
+                        ),
+                    });
+                }
+                _ => {
+                    return Err(ParserError::from(format!(
+                        "Unexpected rule in SearchHybrid: {:?} => {:?}",
+                        p.as_rule(),
+                        p,
+                    )));
+                }
+            }
+        }
+
+        Ok(SearchHybrid {
+            loc: pair.loc(),
+            type_arg,
+            query,
+            data,
+            k,
+            fusion,
+        })
+    }
+
+    fn parse_evaluates_to_number(&self, p: Pair<Rule>) -> Result<EvaluatesToNumber, ParserError> {
+        let value = match p.as_rule() {
+            Rule::integer => EvaluatesToNumberType::I32(
+                p.as_str()
+                    .parse::<i32>()
+                    .map_err(|_| ParserError::from("Invalid integer value"))?,
+            ),
+            Rule::float => EvaluatesToNumberType::F64(
+                p.as_str()
+                    .parse::<f64>()
+                    .map_err(|_| ParserError::from("Invalid float value"))?,
+            ),
+            _ => EvaluatesToNumberType::Identifier(p.as_str().to_string()),
+        };
+        Ok(EvaluatesToNumber {
+            loc: p.loc(),
+            value,
+        })
+    }
+
     fn parse_vec_literal(&self, pair: Pair<Rule>) -> Result<Vec<f64>, ParserError> {
         let pairs = pair.into_inner();
         let mut vec = Vec::new();
@@ -2251,14 +2623,27 @@ impl HelixParser {
                 loc: expression.loc(),
                 expr: ExpressionType::BooleanLiteral(expression.as_str() == "true"),
             }),
-            Rule::exists => Ok(Expression {
-                loc: expression.loc(),
-                expr: ExpressionType::Exists(Box::new(Expression {
+            Rule::exists => {
+                let traversal = expression.clone().into_inner().next().unwrap();
+                Ok(Expression {
                     loc: expression.loc(),
-                    expr: ExpressionType::Traversal(Box::new(
-                        self.parse_anon_traversal(expression.into_inner().next().unwrap())?,
-                    )),
-                })),
+                    expr: ExpressionType::Exists(Box::new(Expression {
+                        loc: expression.loc(),
+                        expr: ExpressionType::Traversal(Box::new(match traversal.as_rule() {
+                            Rule::anonymous_traversal => self.parse_anon_traversal(traversal)?,
+                            // outside of a `WHERE`, e.g. the condition of an `IF`
+                            _ => self.parse_traversal(traversal)?,
+                        })),
+                    })),
+                })
+            }
+            Rule::identifier => Ok(Expression {
+                loc: expression.loc(),
+                expr: ExpressionType::Identifier(expression.as_str().to_string()),
+            }),
+            Rule::traversal | Rule::id_traversal => Ok(Expression {
+                loc: expression.loc(),
+                expr: ExpressionType::Traversal(Box::new(self.parse_traversal(expression)?)),
             }),
 
             _ => unreachable!(),
@@ -2347,6 +2732,14 @@ impl HelixParser {
                 loc: pair.loc(),
                 expr: ExpressionType::AddEdge(self.parse_add_edge(pair, false)?),
             }),
+            Rule::UpsertN => Ok(Expression {
+                loc: pair.loc(),
+                expr: ExpressionType::UpsertNode(self.parse_add_node(pair)?),
+            }),
+            Rule::UpsertE => Ok(Expression {
+                loc: pair.loc(),
+                expr: ExpressionType::UpsertEdge(self.parse_add_edge(pair, false)?),
+            }),
             Rule::search_vector => Ok(Expression {
                 loc: pair.loc(),
                 expr: ExpressionType::SearchVector(self.parse_search_vector(pair)?),
@@ -2359,6 +2752,10 @@ impl HelixParser {
                 loc: pair.loc(),
                 expr: ExpressionType::BM25Search(self.parse_bm25_search(pair)?),
             }),
+            Rule::search_hybrid => Ok(Expression {
+                loc: pair.loc(),
+                expr: ExpressionType::SearchHybrid(self.parse_search_hybrid(pair)?),
+            }),
             _ => Err(ParserError::from(format!(
                 "Unexpected expression type: {:?}",
                 pair.as_rule()
@@ -2439,68 +2836,11 @@ impl HelixParser {
                                     .collect::<Vec<_>>(),
                             );
                         }
+                        Rule::by_composite_index => {
+                            ids = Some(vec![self.parse_composite_index_lookup(p)?]);
+                        }
                         Rule::by_index => {
-                            ids = Some({
-                                let mut pairs: Pairs<'_, Rule> = p.clone().into_inner();
-                                let index = match pairs.next().unwrap().clone().into_inner().next()
-                                {
-                                    Some(id) => match id.as_rule() {
-                                        Rule::identifier => IdType::Identifier {
-                                            value: id.as_str().to_string(),
-                                            loc: id.loc(),
-                                        },
-                                        Rule::string_literal => IdType::Literal {
-                                            value: id.as_str().to_string(),
-                                            loc: id.loc(),
-                                        },
-                                        other => {
-                                            panic!(
-                                                "Should be identifier or string literal: {other:?}"
-                                            )
-                                        }
-                                    },
-                                    None => return Err(ParserError::from("Missing index")),
-                                };
-                                let value = match pairs.next().unwrap().into_inner().next() {
-                                    Some(val) => match val.as_rule() {
-                                        Rule::identifier => ValueType::Identifier {
-                                            value: val.as_str().to_string(),
-                                            loc: val.loc(),
-                                        },
-                                        Rule::string_literal => ValueType::Literal {
-                                            value: Value::from(val.as_str()),
-                                            loc: val.loc(),
-                                        },
-                                        Rule::integer => ValueType::Literal {
-                                            value: Value::from(
-                                                val.as_str().parse::<i64>().unwrap(),
-                                            ),
-                                            loc: val.loc(),
-                                        },
-                                        Rule::float => ValueType::Literal {
-                                            value: Value::from(
-                                                val.as_str().parse::<f64>().unwrap(),
-                                            ),
-                                            loc: val.loc(),
-                                        },
-                                        Rule::boolean => ValueType::Literal {
-                                            value: Value::from(
-                                                val.as_str().parse::<bool>().unwrap(),
-                                            ),
-                                            loc: val.loc(),
-                                        },
-                                        _ => {
-                                            panic!("Should be identifier or string literal")
-                                        }
-                                    },
-                                    _ => unreachable!(),
-                                };
-                                vec![IdType::ByIndex {
-                                    index: Box::new(index),
-                                    value: Box::new(value),
-                                    loc: p.loc(),
-                                }]
-                            })
+                            ids = Some(vec![self.parse_by_index(p)?]);
                         }
                         _ => unreachable!(),
                     }
@@ -2539,6 +2879,9 @@ impl HelixParser {
                                     .collect::<Vec<_>>(),
                             );
                         }
+                        Rule::by_index => {
+                            ids = Some(vec![self.parse_by_index(p)?]);
+                        }
                         _ => unreachable!(),
                     }
                 }
@@ -2550,6 +2893,148 @@ impl HelixParser {
         }
     }
 
+    fn parse_index_range(
+        &self,
+        pair: Pair<Rule>,
+    ) -> Result<(Option<IndexBound>, Option<IndexBound>), ParserError> {
+        let range = pair.into_inner().next().unwrap();
+        let rule = range.as_rule();
+        let mut values = range
+            .into_inner()
+            .map(|p| self.parse_index_value(p.into_inner().next().unwrap()))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter();
+        let mut next = || {
+            values
+                .next()
+                .ok_or_else(|| ParserError::from("Missing index range value"))
+        };
+        Ok(match rule {
+            Rule::index_gt => (Some(IndexBound::Excluded(next()?)), None),
+            Rule::index_gte => (Some(IndexBound::Included(next()?)), None),
+            Rule::index_lt => (None, Some(IndexBound::Excluded(next()?))),
+            Rule::index_lte => (None, Some(IndexBound::Included(next()?))),
+            Rule::index_between => (
+                Some(IndexBound::Included(next()?)),
+                Some(IndexBound::Included(next()?)),
+            ),
+            other => {
+                return Err(ParserError::from(format!(
+                    "Unexpected index range: {other:?}"
+                )));
+            }
+        })
+    }
+
+    fn parse_by_index(&self, pair: Pair<Rule>) -> Result<IdType, ParserError> {
+        let loc = pair.loc();
+        let mut pairs = pair.into_inner();
+        let index = match pairs.next().unwrap().into_inner().next() {
+            Some(id) => match id.as_rule() {
+                Rule::identifier => IdType::Identifier {
+                    value: id.as_str().to_string(),
+                    loc: id.loc(),
+                },
+                Rule::string_literal => IdType::Literal {
+                    value: id.as_str().to_string(),
+                    loc: id.loc(),
+                },
+                other => {
+                    panic!("Should be identifier or string literal: {other:?}")
+                }
+            },
+            None => return Err(ParserError::from("Missing index")),
+        };
+        let value_pair = pairs.next().unwrap();
+        Ok(match value_pair.as_rule() {
+            Rule::index_range => {
+                let (start, end) = self.parse_index_range(value_pair)?;
+                IdType::ByIndexRange {
+                    index: Box::new(index),
+                    start,
+                    end,
+                    loc,
+                }
+            }
+            _ => {
+                let value = match value_pair.into_inner().next() {
+                    Some(val) => self.parse_index_value(val)?,
+                    _ => unreachable!(),
+                };
+                IdType::ByIndex {
+                    index: Box::new(index),
+                    value: Box::new(value),
+                    loc,
+                }
+            }
+        })
+    }
+
+    fn parse_composite_index_lookup(&self, pair: Pair<Rule>) -> Result<IdType, ParserError> {
+        let loc = pair.loc();
+        let args = pair.into_inner().collect::<Vec<_>>();
+        let last = args.len() - 1;
+        let fields = args
+            .into_iter()
+            .enumerate()
+            .map(|(i, arg)| {
+                let mut pairs = arg.into_inner();
+                let field = pairs.next().unwrap().into_inner().next().unwrap();
+                let field = IdType::Identifier {
+                    value: field.as_str().to_string(),
+                    loc: field.loc(),
+                };
+                let value_pair = pairs.next().unwrap();
+                let lookup = match value_pair.as_rule() {
+                    Rule::index_range if i == last => {
+                        let (start, end) = self.parse_index_range(value_pair)?;
+                        IndexLookup::Range { start, end }
+                    }
+                    Rule::index_range => {
+                        return Err(ParserError::from(format!(
+                            "Only the last field of a composite index lookup can be a range: {field}"
+                        )));
+                    }
+                    _ => IndexLookup::Eq(
+                        self.parse_index_value(value_pair.into_inner().next().unwrap())?,
+                    ),
+                };
+                Ok((field, lookup))
+            })
+            .collect::<Result<Vec<_>, ParserError>>()?;
+        Ok(IdType::ByCompositeIndex { fields, loc })
+    }
+
+    fn parse_index_value(&self, val: Pair<Rule>) -> Result<ValueType, ParserError> {
+        Ok(match val.as_rule() {
+            Rule::identifier => ValueType::Identifier {
+                value: val.as_str().to_string(),
+                loc: val.loc(),
+            },
+            Rule::string_literal => ValueType::Literal {
+                value: Value::from(val.clone().into_inner().next().unwrap().as_str()),
+                loc: val.loc(),
+            },
+            Rule::integer => ValueType::Literal {
+                value: Value::from(val.as_str().parse::<i64>().unwrap()),
+                loc: val.loc(),
+            },
+            Rule::float => ValueType::Literal {
+                value: Value::from(val.as_str().parse::<f64>().unwrap()),
+                loc: val.loc(),
+            },
+            Rule::boolean => ValueType::Literal {
+                value: Value::from(val.as_str().parse::<bool>().unwrap()),
+                loc: val.loc(),
+            },
+            other => {
+                return Err(ParserError::from(format!(
+                    "Should be identifier or literal: {other:?}"
+                )));
+            }
+        })
+    }
+
     fn parse_step(&self, pair: Pair<Rule>) -> Result<Step, ParserError> {
         let inner = pair.clone().into_inner().next().unwrap();
         match inner.as_rule() {
@@ -2613,6 +3098,23 @@ impl HelixParser {
                 loc: inner.loc(),
                 step: StepType::OrderBy(self.parse_order_by(inner)?),
             }),
+            Rule::group_by => Ok(Step {
+                loc: inner.loc(),
+                step: StepType::GroupBy(GroupBy {
+                    loc: inner.loc(),
+                    property: inner
+                        .clone()
+                        .into_inner()
+                        .next()
+                        .unwrap()
+                        .as_str()
+                        .to_string(),
+                }),
+            }),
+            Rule::aggregate_by => Ok(Step {
+                loc: inner.loc(),
+                step: StepType::AggregateBy(self.parse_aggregate_by(inner)?),
+            }),
             _ => Err(ParserError::from(format!(
                 "Unexpected step type: {:?}",
                 inner.as_rule()
@@ -2635,6 +3137,37 @@ impl HelixParser {
         })
     }
 
+    fn parse_aggregate_by(&self, pair: Pair<Rule>) -> Result<AggregateBy, ParserError> {
+        let aggregates = pair
+            .clone()
+            .into_inner()
+            .map(|p| {
+                let mut inner = p.clone().into_inner();
+                let function = match inner.next().unwrap().as_str() {
+                    "COUNT" => AggregateFunction::Count,
+                    "SUM" => AggregateFunction::Sum,
+                    "AVG" => AggregateFunction::Avg,
+                    "MIN" => AggregateFunction::Min,
+                    "MAX" => AggregateFunction::Max,
+                    other => {
+                        return Err(ParserError::from(format!(
+                            "Unexpected aggregate function: {other}"
+                        )));
+                    }
+                };
+                Ok(Aggregate {
+                    loc: p.loc(),
+                    function,
+                    property: inner.next().map(|p| p.as_str().to_string()),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(AggregateBy {
+            loc: pair.loc(),
+            aggregates,
+        })
+    }
+
     fn parse_range(&self, pair: Pair<Rule>) -> Result<(Expression, Expression), ParserError> {
         let mut inner = pair.into_inner().next().unwrap().into_inner();
         // println!("inner: {:?}", inner);
@@ -2644,6 +3177,25 @@ impl HelixParser {
         Ok((start, end))
     }
 
+    fn parse_repeat_step(&self, pair: Pair<Rule>) -> RepeatStep {
+        let loc = pair.loc();
+        let mut inner = pair.into_inner();
+        let edge_type = inner.next().unwrap().as_str().to_string();
+        let mut depths = inner
+            .next()
+            .unwrap()
+            .into_inner()
+            .map(|p| p.as_str().parse::<usize>().unwrap());
+        let min_depth = depths.next().unwrap();
+        let max_depth = depths.next().unwrap_or(min_depth);
+        RepeatStep {
+            loc,
+            edge_type,
+            min_depth,
+            max_depth,
+        }
+    }
+
     fn parse_graph_step(&self, pair: Pair<Rule>) -> GraphStep {
         let types = |pair: &Pair<Rule>| {
             pair.clone()
@@ -2727,6 +3279,14 @@ impl HelixParser {
                     step: GraphStepType::In(types),
                 }
             }
+            Rule::out_repeat => GraphStep {
+                loc: pair.loc(),
+                step: GraphStepType::OutRepeat(self.parse_repeat_step(pair)),
+            },
+            Rule::in_repeat => GraphStep {
+                loc: pair.loc(),
+                step: GraphStepType::InRepeat(self.parse_repeat_step(pair)),
+            },
             Rule::shortest_path => {
                 let (type_arg, from, to) = pair.clone().into_inner().fold(
                     (None, None, None),
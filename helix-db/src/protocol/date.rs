@@ -75,6 +75,12 @@ impl Date {
     }
 }
 
+impl From<DateTime<Utc>> for Date {
+    fn from(date: DateTime<Utc>) -> Self {
+        Date(date)
+    }
+}
+
 struct DateVisitor;
 
 impl<'de> Visitor<'de> for DateVisitor {
@@ -136,7 +142,12 @@ impl<'de> Deserialize<'de> for Date {
     where
         D: Deserializer<'de>,
     {
-        deserializer.deserialize_any(DateVisitor)
+        // binary formats like bincode can't be driven by `deserialize_any`,
+        // but always hold the RFC3339 string written by `serialize`
+        match deserializer.is_human_readable() {
+            true => deserializer.deserialize_any(DateVisitor),
+            false => deserializer.deserialize_str(DateVisitor),
+        }
     }
 }
 
@@ -1,4 +1,5 @@
-use axum::{body::Body, response::IntoResponse};
+use axum::{body::Body, http::header::CONTENT_TYPE, response::IntoResponse};
+use sonic_rs::json;
 use thiserror::Error;
 
 use crate::{
@@ -16,16 +17,46 @@ pub enum HelixError {
     NotFound { ty: RequestType, name: String },
 }
 
+impl HelixError {
+    /// The HTTP status of the error, storage and other internal errors are `500`
+    pub fn status_code(&self) -> u16 {
+        match self {
+            HelixError::NotFound { .. }
+            | HelixError::Graph(GraphError::NodeNotFound | GraphError::EdgeNotFound) => 404,
+            HelixError::Graph(GraphError::ParamNotFound(_)) => 400,
+            HelixError::Graph(GraphError::DuplicateKey(_)) => 409,
+            HelixError::Graph(_) | HelixError::Vector(_) => 500,
+        }
+    }
+
+    /// A stable name for the kind of error, so clients don't have to match on the message
+    pub fn code(&self) -> &'static str {
+        match self {
+            HelixError::NotFound { .. } => "QUERY_NOT_FOUND",
+            HelixError::Graph(GraphError::NodeNotFound) => "NODE_NOT_FOUND",
+            HelixError::Graph(GraphError::EdgeNotFound) => "EDGE_NOT_FOUND",
+            HelixError::Graph(GraphError::ParamNotFound(_)) => "PARAM_NOT_FOUND",
+            HelixError::Graph(GraphError::DuplicateKey(_)) => "DUPLICATE_KEY",
+            HelixError::Graph(GraphError::DecodeError(_)) => "DECODE_ERROR",
+            HelixError::Graph(GraphError::ConversionError(_)) => "CONVERSION_ERROR",
+            HelixError::Graph(GraphError::StorageError(_) | GraphError::Io(_)) => "STORAGE_ERROR",
+            HelixError::Graph(_) => "GRAPH_ERROR",
+            HelixError::Vector(_) => "VECTOR_ERROR",
+        }
+    }
+}
+
 impl IntoResponse for HelixError {
     fn into_response(self) -> axum::response::Response {
-        let body = self.to_string();
-        let code = match &self {
-            HelixError::Graph(_) | HelixError::Vector(_) => 500,
-            HelixError::NotFound { .. } => 404,
-        };
+        let body = sonic_rs::to_vec(&json!({
+            "error": self.to_string(),
+            "code": self.code(),
+        }))
+        .unwrap_or_else(|_| self.to_string().into_bytes());
 
         axum::response::Response::builder()
-            .status(code)
+            .status(self.status_code())
+            .header(CONTENT_TYPE, "application/json")
             .body(Body::from(body))
             .unwrap_or_else(|_| panic!("Should be able to turn HelixError into Response: {self}"))
     }
@@ -34,6 +34,28 @@ impl ReturnValue {
         )
     }
 
+    /// The aggregates of a traversal as an object keyed by their names
+    #[inline]
+    pub fn from_aggregates(aggregates: HashMap<String, Value>) -> Self {
+        ReturnValue::Object(
+            aggregates
+                .into_iter()
+                .map(|(k, v)| (k, ReturnValue::Value(v)))
+                .collect(),
+        )
+    }
+
+    /// Grouped aggregates as an object keyed by the value of each group
+    #[inline]
+    pub fn from_groups(groups: HashMap<String, HashMap<String, Value>>) -> Self {
+        ReturnValue::Object(
+            groups
+                .into_iter()
+                .map(|(k, aggregates)| (k, ReturnValue::from_aggregates(aggregates)))
+                .collect(),
+        )
+    }
+
     #[inline(always)]
     fn process_items_with_mixin<T>(
         item: T,
@@ -74,6 +96,8 @@ impl ReturnValue {
                     TraversalVal::Count(count) => ReturnValue::from(count),
                     TraversalVal::Empty => ReturnValue::Empty,
                     TraversalVal::Value(value) => ReturnValue::from(value),
+                    TraversalVal::Aggregate(aggregates) => ReturnValue::from_aggregates(aggregates),
+                    TraversalVal::Group(groups) => ReturnValue::from_groups(groups),
                     TraversalVal::Path((nodes, edges)) => {
                         let mut properties = HashMap::with_capacity(2);
                         properties.insert(
@@ -108,6 +132,8 @@ impl ReturnValue {
             TraversalVal::Count(count) => ReturnValue::from(count),
             TraversalVal::Empty => ReturnValue::Empty,
             TraversalVal::Value(value) => ReturnValue::from(value),
+            TraversalVal::Aggregate(aggregates) => ReturnValue::from_aggregates(aggregates),
+            TraversalVal::Group(groups) => ReturnValue::from_groups(groups),
             TraversalVal::Path((nodes, edges)) => {
                 let mut properties = HashMap::with_capacity(2);
                 properties.insert(
@@ -253,7 +279,7 @@ where
                 let score = item.score();
 
                 let mut properties = HashMap::with_capacity(2 + length);
-                properties.insert("data".to_string(), ReturnValue::from(data));
+                properties.insert("data".to_string(), ReturnValue::from(data.as_ref()));
                 properties.insert("score".to_string(), ReturnValue::from(score));
                 properties
             }
@@ -344,6 +370,8 @@ impl From<TraversalVal> for ReturnValue {
             TraversalVal::Vector(vector) => ReturnValue::from(vector),
             TraversalVal::Count(count) => ReturnValue::from(count),
             TraversalVal::Value(value) => ReturnValue::from(value),
+            TraversalVal::Aggregate(aggregates) => ReturnValue::from_aggregates(aggregates),
+            TraversalVal::Group(groups) => ReturnValue::from_groups(groups),
             TraversalVal::Empty => ReturnValue::Empty,
             _ => unreachable!(),
         }
@@ -984,7 +984,7 @@ where
 impl From<chrono::DateTime<Utc>> for Value {
     #[inline]
     fn from(dt: chrono::DateTime<Utc>) -> Self {
-        Value::String(dt.to_rfc3339())
+        Value::Date(Date::from(dt))
     }
 }
 
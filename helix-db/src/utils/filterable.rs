@@ -39,7 +39,7 @@ pub trait Filterable {
 
     fn properties(self) -> Option<HashMap<String, Value>>;
 
-    fn vector_data(&self) -> &[f64];
+    fn vector_data(&self) -> Cow<'_, [f64]>;
     fn score(&self) -> f64;
 
     fn properties_mut(&mut self) -> &mut Option<HashMap<String, Value>>;
@@ -98,7 +98,7 @@ impl Filterable for Node {
     }
 
     #[inline(always)]
-    fn vector_data(&self) -> &[f64] {
+    fn vector_data(&self) -> Cow<'_, [f64]> {
         unreachable!()
     }
 
@@ -202,7 +202,7 @@ impl Filterable for Edge {
     }
 
     #[inline(always)]
-    fn vector_data(&self) -> &[f64] {
+    fn vector_data(&self) -> Cow<'_, [f64]> {
         unreachable!()
     }
 
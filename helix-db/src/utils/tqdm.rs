@@ -68,9 +68,10 @@ impl<T: Iterator> Iterator for tqdm<T> {
 
     /// Advances the iterator and updates the progress bar
     fn next(&mut self) -> Option<Self::Item> {
+        let item = self.iter.next()?;
         self.current += 1;
         self.render();
-        self.iter.next()
+        Some(item)
     }
 }
 
@@ -128,7 +129,7 @@ mod tests {
         let iter: Vec<u32> = vec![];
         let mut tqdm = tqdm::new(iter.into_iter(), 0, None, None);
         assert_eq!(tqdm.next(), None);
-        assert_eq!(tqdm.current, 1);
+        assert_eq!(tqdm.current, 0);
     }
 }
 
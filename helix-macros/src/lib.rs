@@ -44,8 +44,8 @@ pub fn handler(args: TokenStream, item: TokenStream) -> TokenStream {
     };
 
     let txn_type = match args.txn_type.to_string().as_str() {
-        "with_read" => quote! { let txn = db.graph_env.read_txn().unwrap(); },
-        "with_write" => quote! { let mut txn = db.graph_env.write_txn().unwrap(); },
+        "with_read" => quote! { let txn = db.graph_env.read_txn()?; },
+        "with_write" => quote! { let mut txn = db.graph_env.write_txn()?; },
         _ => panic!("Invalid transaction type: expected 'with_read' or 'with_write'"),
     };
 
@@ -61,7 +61,7 @@ pub fn handler(args: TokenStream, item: TokenStream) -> TokenStream {
 
             #(#query_stmts)*
 
-            txn.commit().unwrap();
+            txn.commit()?;
 
             Ok(input.request.out_fmt.create_response(&return_vals))
         }
@@ -294,8 +294,8 @@ pub fn tool_call(args: TokenStream, input: TokenStream) -> TokenStream {
 
     let name = args.name;
     let txn_type = match args.txn_type.to_string().as_str() {
-        "with_read" => quote! { let txn = db.graph_env.read_txn().unwrap(); },
-        "with_write" => quote! { let mut txn = db.graph_env.write_txn().unwrap(); },
+        "with_read" => quote! { let txn = db.graph_env.read_txn()?; },
+        "with_write" => quote! { let mut txn = db.graph_env.write_txn()?; },
         _ => panic!("Invalid transaction type: expected 'with_read' or 'with_write'"),
     };
 
@@ -319,7 +319,7 @@ pub fn tool_call(args: TokenStream, input: TokenStream) -> TokenStream {
             #txn_type
             let data: #struct_name = data.data;
             #(#query_stmts)*
-            txn.commit().unwrap();
+            txn.commit()?;
             #name.into_iter()
         }
     };